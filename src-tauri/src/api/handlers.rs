@@ -282,7 +282,7 @@ async fn fetch_usage_for_account(
         .ok_or_else(|| format!("Provider not found: {}", account.provider))?;
 
     provider
-        .fetch_usage(&account.credentials)
+        .fetch_usage_with_headers(&account.credentials, &account.extra_headers)
         .await
         .map(|mut data| {
             data.account_id = account.id.clone();
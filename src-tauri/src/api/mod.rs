@@ -1,10 +1,12 @@
 //! Local API server for CLI and IDE integrations.
 //!
 //! This module provides a REST API server that runs on localhost, allowing
-//! external tools like the CLI and VS Code extension to query usage data.
+//! external tools like the CLI and VS Code extension to query usage data, plus an
+//! optional WebSocket server that pushes usage updates as they happen.
 
 mod handlers;
 mod routes;
+mod websocket;
 
 use std::net::SocketAddr;
 use std::sync::Arc;
@@ -14,6 +16,8 @@ use tauri::AppHandle;
 
 use crate::services::SchedulerState;
 
+pub use websocket::start_websocket_server;
+
 /// Shared state for the API server
 #[derive(Clone)]
 pub struct ApiState {
@@ -0,0 +1,142 @@
+//! Local WebSocket server that pushes usage updates to external dashboards.
+//!
+//! Clients connect to `ws://127.0.0.1:<port>/usage?token=...` and receive one
+//! JSON text frame per usage update. Browsers are turned away by their `Origin`
+//! header, so a web page can't subscribe even if it guesses the port.
+
+use std::net::SocketAddr;
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    http::{header::ORIGIN, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use serde::Deserialize;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::services::{secrets_match, UsageStream};
+
+#[derive(Clone)]
+struct WsState {
+    stream: UsageStream,
+    token: String,
+}
+
+#[derive(Deserialize)]
+struct ConnectQuery {
+    token: Option<String>,
+}
+
+/// Start the WebSocket server on the specified port
+///
+/// Like the API server, it runs until the app exits.
+pub fn start_websocket_server(stream: UsageStream, port: u16, token: String) {
+    tauri::async_runtime::spawn(async move {
+        let app = Router::new()
+            .route("/usage", get(connect))
+            .with_state(WsState { stream, token });
+        let addr = SocketAddr::from(([127, 0, 0, 1], port));
+
+        log::info!("Starting WebSocket server on ws://{}/usage", addr);
+
+        let listener = match tokio::net::TcpListener::bind(addr).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                log::error!("Failed to bind WebSocket server to {}: {}", addr, e);
+                return;
+            }
+        };
+
+        if let Err(e) = axum::serve(listener, app).await {
+            log::error!("WebSocket server error: {}", e);
+        }
+    });
+}
+
+/// Whether a connection may subscribe: the token must match, compared in constant time
+pub fn token_allowed(expected: &str, given: Option<&str>) -> bool {
+    !expected.is_empty()
+        && given.is_some_and(|given| secrets_match(given.as_bytes(), expected.as_bytes()))
+}
+
+/// Whether the upgrade request came from outside a browser
+///
+/// Browsers always send `Origin` on WebSocket handshakes; dashboards and
+/// scripts connecting directly don't.
+pub fn origin_allowed(origin: Option<&str>) -> bool {
+    origin.is_none()
+}
+
+async fn connect(
+    ws: WebSocketUpgrade,
+    headers: HeaderMap,
+    State(state): State<WsState>,
+    Query(query): Query<ConnectQuery>,
+) -> Response {
+    let origin = headers.get(ORIGIN).and_then(|v| v.to_str().ok());
+    if !origin_allowed(origin) {
+        log::warn!("Rejected WebSocket connection from origin {:?}", origin);
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    if !token_allowed(&state.token, query.token.as_deref()) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    // Subscribe before upgrading so no update between the two is missed
+    let updates = state.stream.subscribe();
+    ws.on_upgrade(move |socket| forward_updates(socket, updates))
+}
+
+/// Relay published updates to one client until either side goes away
+async fn forward_updates(mut socket: WebSocket, mut updates: broadcast::Receiver<String>) {
+    loop {
+        tokio::select! {
+            update = updates.recv() => match update {
+                Ok(text) => {
+                    if socket.send(Message::Text(text)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    log::debug!("WebSocket client fell behind, skipped {} updates", skipped);
+                }
+                Err(RecvError::Closed) => break,
+            },
+            incoming = socket.recv() => match incoming {
+                // Clients only listen; anything other than a close is ignored
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_check() {
+        assert!(token_allowed("secret", Some("secret")));
+        assert!(!token_allowed("secret", Some("wrong")));
+        assert!(!token_allowed("secret", Some("secre")));
+        assert!(!token_allowed("secret", None));
+
+        // An empty expected token never lets anyone in
+        assert!(!token_allowed("", Some("")));
+        assert!(!token_allowed("", None));
+    }
+
+    #[test]
+    fn browser_origins_are_rejected() {
+        assert!(origin_allowed(None));
+        assert!(!origin_allowed(Some("https://example.com")));
+        assert!(!origin_allowed(Some("http://127.0.0.1:31416")));
+        assert!(!origin_allowed(Some("null")));
+    }
+}
@@ -1,11 +1,42 @@
-use crate::error::AppError;
-use crate::models::Account;
-use crate::providers::ProviderRegistry;
-use crate::services::CredentialService;
-use tauri::AppHandle;
+use crate::error::{AppError, ProviderError};
+use crate::models::{
+    Account, CryptoBenchResult, CryptoInfo, FieldEncryptionStatus, ImportFormat, ImportResult,
+    KeyRecoveryReport, MigrationPlan, PermissionReport, UsageData,
+};
+use crate::providers::{ProviderRegistry, UsageProvider};
+use crate::services::{
+    benchmark_round_trips, AccountImportService, CredentialService, DebugGate,
+    FilePermissionService, KeyMaterial, SchedulerState, SettingsService,
+};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, State};
+use tokio::sync::Semaphore;
+use tokio::time::{timeout_at, Instant};
 
 use super::usage::{map_provider_error_to_result, TestConnectionResult};
 
+/// Accounts `validate_all_accounts` checks at the same time, to stay clear of rate limits
+const MAX_CONCURRENT_VALIDATIONS: usize = 3;
+
+/// Time allowed for all of `validate_all_accounts`'s checks together
+const VALIDATION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Most round trips `benchmark_crypto` runs in one call
+const MAX_BENCHMARK_ITERATIONS: usize = 10_000;
+
+/// Clean up a pasted session key before it is tested or saved
+#[tauri::command]
+pub fn normalize_session_key(raw: String) -> String {
+    CredentialService::normalize_session_key(&raw)
+}
+
+/// Whether a session key has the shape of a Claude key
+#[tauri::command]
+pub fn looks_like_claude_key(key: String) -> bool {
+    CredentialService::looks_like_claude_key(&key)
+}
+
 /// List all accounts for a provider
 #[tauri::command]
 pub async fn list_accounts(app: AppHandle, provider: String) -> Result<Vec<Account>, AppError> {
@@ -34,11 +65,196 @@ pub async fn save_account(app: AppHandle, account: Account) -> Result<(), AppErr
     CredentialService::save_account(&app, &account)
 }
 
+/// Bulk-create accounts from a JSON array or CSV of name/provider/org_id/session_key rows
+#[tauri::command]
+pub async fn import_accounts(
+    app: AppHandle,
+    data: String,
+    format: ImportFormat,
+) -> Result<ImportResult, AppError> {
+    log::info!("Importing accounts from {:?}", format);
+    AccountImportService::import(&app, &data, format)
+}
+
+/// Duplicate an account's credentials into a new account
+#[tauri::command]
+pub async fn duplicate_account(
+    app: AppHandle,
+    account_id: String,
+    new_name: String,
+) -> Result<Account, AppError> {
+    log::info!("Duplicating account: {} as {}", account_id, new_name);
+    CredentialService::duplicate_account(&app, &account_id, &new_name)
+}
+
+/// Update an account's name and/or tags without resending credentials
+#[tauri::command]
+pub async fn update_account_fields(
+    app: AppHandle,
+    state: State<'_, Arc<SchedulerState>>,
+    account_id: String,
+    name: Option<String>,
+    tags: Option<Vec<String>>,
+) -> Result<(), AppError> {
+    log::info!("Updating fields for account: {}", account_id);
+    CredentialService::update_account_fields(&app, &account_id, name, tags)?;
+    state.forget_account_info(&account_id).await;
+    Ok(())
+}
+
+/// Set or clear the nickname shown in this account's notifications
+#[tauri::command]
+pub async fn set_account_display_name(
+    app: AppHandle,
+    account_id: String,
+    display_name: Option<String>,
+) -> Result<(), AppError> {
+    log::info!("Setting display name for account: {}", account_id);
+    CredentialService::set_display_name(&app, &account_id, display_name)
+}
+
 /// Delete an account by ID
 #[tauri::command]
-pub async fn delete_account(app: AppHandle, account_id: String) -> Result<(), AppError> {
+pub async fn delete_account(
+    app: AppHandle,
+    state: State<'_, Arc<SchedulerState>>,
+    account_id: String,
+) -> Result<(), AppError> {
     log::info!("Deleting account: {}", account_id);
-    CredentialService::delete_account(&app, &account_id)
+    CredentialService::delete_account(&app, &account_id)?;
+    state.forget_account(&account_id).await;
+    Ok(())
+}
+
+/// Delete every account matching the filters in one write, returning how many were removed
+///
+/// At least one filter is required unless `all` is true.
+#[tauri::command]
+pub async fn delete_accounts_where(
+    app: AppHandle,
+    state: State<'_, Arc<SchedulerState>>,
+    provider: Option<String>,
+    tag: Option<String>,
+    archived_only: bool,
+    all: Option<bool>,
+) -> Result<usize, AppError> {
+    log::info!(
+        "Deleting accounts where provider={:?}, tag={:?}, archived_only={}",
+        provider,
+        tag,
+        archived_only
+    );
+    let removed = CredentialService::delete_accounts_where(
+        &app,
+        provider.as_deref(),
+        tag.as_deref(),
+        archived_only,
+        all.unwrap_or(false),
+    )?;
+    for account_id in &removed {
+        state.forget_account(account_id).await;
+    }
+    Ok(removed.len())
+}
+
+/// Accept a credentials store that fails its integrity check by signing its current contents
+///
+/// Only for changes the user made themselves; `confirm` must be true. Returns how
+/// many accounts were signed.
+#[tauri::command]
+pub async fn resign_credentials_store(app: AppHandle, confirm: bool) -> Result<usize, AppError> {
+    log::info!(
+        "Re-signing credentials store requested (confirm: {})",
+        confirm
+    );
+
+    if !confirm {
+        return Err(AppError::InvalidInput(
+            "resign_credentials_store must be called with confirm = true".to_string(),
+        ));
+    }
+
+    CredentialService::resign_accounts(&app)
+}
+
+/// Fold a secondary account's history into a primary account and delete the secondary
+///
+/// Returns how many history snapshots were moved to the primary.
+#[tauri::command]
+pub async fn merge_accounts(
+    app: AppHandle,
+    state: State<'_, Arc<SchedulerState>>,
+    primary_id: String,
+    secondary_id: String,
+) -> Result<usize, AppError> {
+    log::info!("Merging account {} into {}", secondary_id, primary_id);
+    let moved = CredentialService::merge_accounts(&app, &primary_id, &secondary_id)?;
+    state.forget_account(&secondary_id).await;
+    Ok(moved)
+}
+
+/// Make an account the primary one (shown by the tray), or clear it with None
+#[tauri::command]
+pub async fn set_primary_account(
+    app: AppHandle,
+    account_id: Option<String>,
+) -> Result<(), AppError> {
+    log::info!("Setting primary account: {:?}", account_id);
+
+    if let Some(id) = &account_id {
+        if CredentialService::get_account(&app, id)?.is_none() {
+            return Err(AppError::AccountNotFound(id.clone()));
+        }
+    }
+
+    SettingsService::set_primary_account(&app, account_id)
+}
+
+/// Get the primary account, if one is set and still exists
+#[tauri::command]
+pub async fn get_primary_account(app: AppHandle) -> Result<Option<Account>, AppError> {
+    log::info!("Getting primary account");
+
+    match SettingsService::get(&app)?.primary_account_id {
+        Some(id) => CredentialService::get_account(&app, &id),
+        None => Ok(None),
+    }
+}
+
+/// Set an account's monthly budget in percentage points consumed, or clear it with None
+#[tauri::command]
+pub async fn set_monthly_budget(
+    app: AppHandle,
+    account_id: String,
+    budget: Option<f64>,
+) -> Result<(), AppError> {
+    log::info!(
+        "Setting monthly budget for account {}: {:?}",
+        account_id,
+        budget
+    );
+
+    if let Some(budget) = budget {
+        if !budget.is_finite() || budget <= 0.0 {
+            return Err(AppError::InvalidInput(format!(
+                "Monthly budget must be a positive number, got {}",
+                budget
+            )));
+        }
+    }
+
+    if CredentialService::get_account(&app, &account_id)?.is_none() {
+        return Err(AppError::AccountNotFound(account_id));
+    }
+
+    SettingsService::set_monthly_budget(&app, &account_id, budget)
+}
+
+/// Preview pending credentials store migrations without applying them
+#[tauri::command]
+pub async fn preview_migration(app: AppHandle) -> Result<MigrationPlan, AppError> {
+    log::info!("Previewing credentials migration");
+    CredentialService::preview_migration(&app)
 }
 
 /// Test connection for an account
@@ -48,36 +264,505 @@ pub async fn test_account_connection(account: Account) -> Result<TestConnectionR
 
     let registry = ProviderRegistry::new()?;
 
-    let provider_impl = match registry.get(&account.provider) {
-        Some(p) => p,
-        None => {
-            return Ok(TestConnectionResult {
-                success: false,
-                error_code: Some("PROVIDER_UNAVAILABLE".to_string()),
-                error_message: Some(format!("Provider '{}' is not available", account.provider)),
-                hint: Some("This provider is currently blocked or not supported.".to_string()),
-            });
+    Ok(check_account_connection(registry.get(&account.provider), &account).await)
+}
+
+/// Test every stored account's connection at once, e.g. for a health check screen
+///
+/// At most `MAX_CONCURRENT_VALIDATIONS` accounts are checked at a time, and all
+/// checks share one `VALIDATION_TIMEOUT`, so a slow account can't hold up the
+/// rest past it. Results are ordered by account name.
+#[tauri::command]
+pub async fn validate_all_accounts(
+    app: AppHandle,
+) -> Result<Vec<AccountValidationResult>, AppError> {
+    log::info!("Validating all accounts");
+
+    let registry = ProviderRegistry::new()?;
+
+    let mut checks = Vec::new();
+    for provider_id in registry.provider_ids() {
+        for account in CredentialService::list_accounts(&app, provider_id)? {
+            checks.push((account, registry.get(provider_id)));
+        }
+    }
+    checks.sort_by(|(a, _), (b, _)| a.name.cmp(&b.name));
+
+    Ok(validate_accounts(checks, MAX_CONCURRENT_VALIDATIONS, VALIDATION_TIMEOUT).await)
+}
+
+/// Connection test result for one account from `validate_all_accounts`
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountValidationResult {
+    pub account_id: String,
+    pub account_name: String,
+    pub provider: String,
+    pub result: TestConnectionResult,
+}
+
+/// Check accounts concurrently, `max_concurrent` at a time, all against one deadline
+async fn validate_accounts(
+    checks: Vec<(Account, Option<Arc<dyn UsageProvider>>)>,
+    max_concurrent: usize,
+    timeout: Duration,
+) -> Vec<AccountValidationResult> {
+    let semaphore = Arc::new(Semaphore::new(max_concurrent.max(1)));
+    let deadline = Instant::now() + timeout;
+
+    let handles: Vec<_> = checks
+        .into_iter()
+        .map(|(account, provider_impl)| {
+            let semaphore = semaphore.clone();
+            tauri::async_runtime::spawn(async move {
+                // Waiting for a slot counts against the deadline too
+                let check = async {
+                    let _permit = semaphore.acquire().await;
+                    check_account_connection(provider_impl, &account).await
+                };
+
+                let result = timeout_at(deadline, check).await.unwrap_or_else(|_| {
+                    log::warn!("Validation timed out for account {}", account.name);
+                    TestConnectionResult {
+                        success: false,
+                        error_code: Some("TIMEOUT".to_string()),
+                        error_message: Some("The connection test timed out".to_string()),
+                        hint: Some("Try testing this account again on its own.".to_string()),
+                    }
+                });
+
+                AccountValidationResult {
+                    account_id: account.id,
+                    account_name: account.name,
+                    provider: account.provider,
+                    result,
+                }
+            })
+        })
+        .collect();
+
+    let mut results = Vec::with_capacity(handles.len());
+    for handle in handles {
+        match handle.await {
+            Ok(result) => results.push(result),
+            Err(e) => log::error!("Account validation task failed: {}", e),
         }
+    }
+    results
+}
+
+/// Validate the credentials' format, then fetch usage with them
+async fn check_account_connection(
+    provider_impl: Option<Arc<dyn UsageProvider>>,
+    account: &Account,
+) -> TestConnectionResult {
+    let Some(provider_impl) = provider_impl else {
+        return TestConnectionResult {
+            success: false,
+            error_code: Some("PROVIDER_UNAVAILABLE".to_string()),
+            error_message: Some(format!("Provider '{}' is not available", account.provider)),
+            hint: Some("This provider is currently blocked or not supported.".to_string()),
+        };
     };
 
     // First validate format
     if !provider_impl.validate_credentials(&account.credentials) {
-        return Ok(TestConnectionResult {
+        return TestConnectionResult {
             success: false,
             error_code: Some("INVALID_FORMAT".to_string()),
             error_message: Some("Credentials format is invalid".to_string()),
             hint: Some("Please ensure both Organization ID and Session Key are provided.".to_string()),
-        });
+        };
     }
 
     // Try to fetch usage
-    match provider_impl.fetch_usage(&account.credentials).await {
-        Ok(_) => Ok(TestConnectionResult {
+    match provider_impl
+        .fetch_usage_with_headers(&account.credentials, &account.extra_headers)
+        .await
+    {
+        Ok(_) => TestConnectionResult {
             success: true,
             error_code: None,
             error_message: None,
             hint: None,
-        }),
-        Err(e) => Ok(map_provider_error_to_result(e)),
+        },
+        Err(e) => map_provider_error_to_result(e),
+    }
+}
+
+/// Re-check a stored account's credentials against the live API
+///
+/// Unlike `test_account_connection`, this takes the stored account by ID and
+/// reports which organization the key actually belongs to when it no longer
+/// matches. Nothing is saved: not the usage, the history or the account.
+#[tauri::command]
+pub async fn recheck_account(
+    app: AppHandle,
+    account_id: String,
+) -> Result<RecheckResult, AppError> {
+    log::info!("Rechecking account {}", account_id);
+
+    let account = CredentialService::get_account(&app, &account_id)?
+        .ok_or_else(|| AppError::AccountNotFound(account_id.clone()))?;
+    let registry = ProviderRegistry::new()?;
+    let provider_impl = registry
+        .get(&account.provider)
+        .ok_or_else(|| AppError::WithHint {
+            message: format!("Provider '{}' is not available", account.provider),
+            hint: "This provider is currently blocked or not supported.".to_string(),
+        })?;
+
+    let outcome = provider_impl
+        .fetch_usage_with_headers(&account.credentials, &account.extra_headers)
+        .await;
+    Ok(classify_recheck(&account, outcome))
+}
+
+/// How an account's stored credentials fared in `recheck_account`
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecheckStatus {
+    /// The credentials fetched usage for the stored organization
+    Valid,
+    /// The session key expired or was rejected
+    Expired,
+    /// The key works, but for a different organization than the stored one
+    Mismatched,
+    /// The check failed for another reason (network, rate limit, ...)
+    Error,
+}
+
+/// Result of `recheck_account`
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecheckResult {
+    pub account_id: String,
+    pub status: RecheckStatus,
+    /// Organization ID stored with the account
+    pub stored_org_id: Option<String>,
+    /// Organization the key belongs to, when the API reported a different one
+    pub actual_org_id: Option<String>,
+    pub message: Option<String>,
+}
+
+/// Classify a live fetch made with an account's stored credentials
+fn classify_recheck(account: &Account, outcome: Result<UsageData, ProviderError>) -> RecheckResult {
+    let stored_org_id = account.credentials.claude().and_then(|c| c.org_id.clone());
+
+    let (status, actual_org_id, message) = match outcome {
+        Ok(_) => (RecheckStatus::Valid, None, None),
+        Err(e @ (ProviderError::SessionExpired | ProviderError::InvalidCredentials(_))) => {
+            (RecheckStatus::Expired, None, Some(e.to_string()))
+        }
+        Err(ProviderError::OrgMismatch { expected, actual }) => (
+            RecheckStatus::Mismatched,
+            Some(actual.clone()),
+            Some(format!(
+                "Session key belongs to organization {}, not {}",
+                actual, expected
+            )),
+        ),
+        Err(e) => (RecheckStatus::Error, None, Some(e.to_string())),
+    };
+
+    RecheckResult {
+        account_id: account.id.clone(),
+        status,
+        stored_org_id,
+        actual_org_id,
+        message,
+    }
+}
+
+/// Recover stored secrets after the OS username or home directory changed
+///
+/// Derives the old key from the supplied username and home directory, and
+/// re-encrypts everything that decrypts with it under the current key.
+#[tauri::command]
+pub fn reencrypt_with_override_key_material(
+    app: AppHandle,
+    old_username: String,
+    old_home: String,
+) -> Result<KeyRecoveryReport, AppError> {
+    log::info!("Re-encrypting stored secrets from previous key material");
+
+    let old = KeyMaterial {
+        username: old_username,
+        home: old_home,
+    };
+
+    let (recovered_accounts, skipped_accounts) =
+        CredentialService::reencrypt_with_key_material(&app, &old)?;
+    let recovered_settings = SettingsService::reencrypt_with_key_material(&app, &old)?;
+
+    log::info!(
+        "Recovered {} account(s) and {} setting(s); {} account(s) skipped",
+        recovered_accounts.len(),
+        recovered_settings,
+        skipped_accounts.len()
+    );
+
+    Ok(KeyRecoveryReport {
+        recovered_accounts,
+        skipped_accounts,
+        recovered_settings,
+    })
+}
+
+/// Report, per stored session key, whether it is actually encrypted at rest
+#[tauri::command]
+pub fn audit_encryption(app: AppHandle) -> Result<Vec<FieldEncryptionStatus>, AppError> {
+    log::info!("Auditing credential encryption");
+    CredentialService::audit_encryption(&app)
+}
+
+/// Report the encryption version, KDF and keychain use, and each account's stored versions
+#[tauri::command]
+pub fn get_crypto_info(app: AppHandle) -> Result<CryptoInfo, AppError> {
+    log::info!("Getting encryption info");
+    CredentialService::crypto_info(&app)
+}
+
+/// Check whether the credentials file can be read by other users (Unix modes only)
+#[tauri::command]
+pub fn check_credential_file_permissions(app: AppHandle) -> Result<PermissionReport, AppError> {
+    log::info!("Checking credentials file permissions");
+    FilePermissionService::check_credentials(&app)
+}
+
+/// Restrict the credentials file to its owner (mode 600); a no-op on Windows
+#[tauri::command]
+pub fn tighten_permissions(app: AppHandle) -> Result<PermissionReport, AppError> {
+    log::info!("Tightening credentials file permissions");
+    FilePermissionService::tighten_credentials(&app)
+}
+
+/// Time encrypt+decrypt round trips so slow-encryption reports come with numbers
+///
+/// A debug command: open in debug builds, otherwise only once unlocked.
+#[tauri::command]
+pub async fn benchmark_crypto(
+    gate: State<'_, DebugGate>,
+    iterations: usize,
+) -> Result<CryptoBenchResult, AppError> {
+    log::info!("Benchmarking encryption over {} round trips", iterations);
+
+    gate.check("benchmark_crypto")?;
+    if iterations == 0 || iterations > MAX_BENCHMARK_ITERATIONS {
+        return Err(AppError::InvalidInput(format!(
+            "iterations must be between 1 and {}",
+            MAX_BENCHMARK_ITERATIONS
+        )));
+    }
+
+    // Key derivation is CPU-bound; keep it off the async runtime
+    tauri::async_runtime::spawn_blocking(move || benchmark_round_trips(iterations))
+        .await?
+        .map_err(AppError::Store)
+}
+
+/// Encrypt any session keys the audit found stored as plaintext
+#[tauri::command]
+pub fn reencrypt_plaintext_fields(app: AppHandle) -> Result<Vec<FieldEncryptionStatus>, AppError> {
+    log::info!("Encrypting plaintext credential fields");
+
+    let fixed = CredentialService::reencrypt_plaintext_fields(&app)?;
+    log::info!("Encrypted {} plaintext field(s)", fixed.len());
+
+    Ok(fixed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ProviderError;
+    use crate::models::{ClaudeCredentials, Credentials, UsageData};
+    use chrono::Utc;
+    use std::collections::BTreeMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Provider that takes `delay` to answer and records the peak number of fetches in flight
+    struct SlowProvider {
+        delay: Duration,
+        expired: bool,
+        in_flight: AtomicUsize,
+        peak_in_flight: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl UsageProvider for SlowProvider {
+        fn id(&self) -> &'static str {
+            "mock"
+        }
+
+        fn name(&self) -> &'static str {
+            "Mock"
+        }
+
+        async fn fetch_usage(
+            &self,
+            _credentials: &Credentials,
+        ) -> Result<UsageData, ProviderError> {
+            let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak_in_flight.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(self.delay).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+            if self.expired {
+                return Err(ProviderError::SessionExpired);
+            }
+            Ok(UsageData {
+                provider: "mock".to_string(),
+                account_id: String::new(),
+                account_name: String::new(),
+                timestamp: Utc::now(),
+                limits: Vec::new(),
+                raw: None,
+                rate_limit: None,
+            })
+        }
+
+        fn validate_credentials(&self, credentials: &Credentials) -> bool {
+            credentials
+                .claude()
+                .is_some_and(|c| c.session_key.is_some())
+        }
+    }
+
+    fn provider(delay_ms: u64, expired: bool) -> Arc<SlowProvider> {
+        Arc::new(SlowProvider {
+            delay: Duration::from_millis(delay_ms),
+            expired,
+            in_flight: AtomicUsize::new(0),
+            peak_in_flight: AtomicUsize::new(0),
+        })
+    }
+
+    fn account(id: &str, session_key: Option<&str>) -> Account {
+        Account {
+            id: id.to_string(),
+            name: id.to_string(),
+            provider: "mock".to_string(),
+            credentials: Credentials::Claude(ClaudeCredentials {
+                org_id: Some("org-123".to_string()),
+                session_key: session_key.map(str::to_string),
+                session_keys: Vec::new(),
+                oauth: None,
+            }),
+            created_at: Utc::now(),
+            tags: Vec::new(),
+            display_name: None,
+            extra_headers: BTreeMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn validate_accounts_reports_mixed_results_with_bounded_concurrency() {
+        let fast = provider(50, false);
+        let expired = provider(50, true);
+
+        let mut checks: Vec<(Account, Option<Arc<dyn UsageProvider>>)> = (0..6)
+            .map(|i| {
+                let provider: Arc<dyn UsageProvider> = fast.clone();
+                (
+                    account(&format!("ok-{}", i), Some("sk-ant-test")),
+                    Some(provider),
+                )
+            })
+            .collect();
+        checks.push((account("no-key", None), Some(expired.clone())));
+        checks.push((
+            account("expired", Some("sk-ant-old")),
+            Some(expired.clone()),
+        ));
+        checks.push((account("unknown-provider", Some("sk-ant-test")), None));
+
+        let results = validate_accounts(checks, 3, Duration::from_secs(10)).await;
+
+        let codes: Vec<(&str, Option<&str>)> = results
+            .iter()
+            .map(|r| (r.account_id.as_str(), r.result.error_code.as_deref()))
+            .collect();
+        assert_eq!(
+            codes,
+            vec![
+                ("ok-0", None),
+                ("ok-1", None),
+                ("ok-2", None),
+                ("ok-3", None),
+                ("ok-4", None),
+                ("ok-5", None),
+                ("no-key", Some("INVALID_FORMAT")),
+                ("expired", Some("SESSION_EXPIRED")),
+                ("unknown-provider", Some("PROVIDER_UNAVAILABLE")),
+            ]
+        );
+        assert!(results[..6].iter().all(|r| r.result.success));
+
+        assert!(fast.peak_in_flight.load(Ordering::SeqCst) <= 3);
+        assert!(
+            fast.peak_in_flight.load(Ordering::SeqCst) >= 2,
+            "checks did not overlap"
+        );
+    }
+
+    #[tokio::test]
+    async fn slow_account_times_out_without_blocking_others() {
+        let fast = provider(10, false);
+        let stuck = provider(60_000, false);
+
+        let checks: Vec<(Account, Option<Arc<dyn UsageProvider>>)> = vec![
+            (account("stuck", Some("sk-ant-test")), Some(stuck)),
+            (account("fast-1", Some("sk-ant-test")), Some(fast.clone())),
+            (account("fast-2", Some("sk-ant-test")), Some(fast)),
+        ];
+
+        let started = std::time::Instant::now();
+        let results = validate_accounts(checks, 2, Duration::from_millis(300)).await;
+
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(results[0].result.error_code.as_deref(), Some("TIMEOUT"));
+        assert!(results[1].result.success);
+        assert!(results[2].result.success);
+    }
+
+    #[test]
+    fn recheck_classifies_valid_expired_and_mismatched() {
+        let account = account("work", Some("sk-ant-test"));
+        let usage = UsageData {
+            provider: "mock".to_string(),
+            account_id: String::new(),
+            account_name: String::new(),
+            timestamp: Utc::now(),
+            limits: Vec::new(),
+            raw: None,
+            rate_limit: None,
+        };
+
+        let valid = classify_recheck(&account, Ok(usage));
+        assert_eq!(valid.status, RecheckStatus::Valid);
+        assert_eq!(valid.stored_org_id.as_deref(), Some("org-123"));
+        assert_eq!(valid.actual_org_id, None);
+
+        let expired = classify_recheck(&account, Err(ProviderError::SessionExpired));
+        assert_eq!(expired.status, RecheckStatus::Expired);
+        let rejected = classify_recheck(
+            &account,
+            Err(ProviderError::InvalidCredentials("bad key".to_string())),
+        );
+        assert_eq!(rejected.status, RecheckStatus::Expired);
+
+        let mismatched = classify_recheck(
+            &account,
+            Err(ProviderError::OrgMismatch {
+                expected: "org-123".to_string(),
+                actual: "org-456".to_string(),
+            }),
+        );
+        assert_eq!(mismatched.status, RecheckStatus::Mismatched);
+        assert_eq!(mismatched.actual_org_id.as_deref(), Some("org-456"));
+
+        let offline = classify_recheck(&account, Err(ProviderError::RateLimited));
+        assert_eq!(offline.status, RecheckStatus::Error);
     }
 }
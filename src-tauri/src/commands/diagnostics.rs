@@ -0,0 +1,36 @@
+use crate::error::AppError;
+use crate::models::DiagnosticsReport;
+use crate::services::{DebugGate, DiagnosticsService, SchedulerState};
+use std::sync::Arc;
+use tauri::{AppHandle, State};
+
+/// Run self-checks (stores, encryption, notifications, scheduler, network) for bug reports
+#[tauri::command]
+pub async fn run_diagnostics(
+    app: AppHandle,
+    state: State<'_, Arc<SchedulerState>>,
+) -> Result<DiagnosticsReport, AppError> {
+    log::info!("Running diagnostics");
+    Ok(DiagnosticsService::run(&app, &state).await)
+}
+
+/// Open debug commands (raw fetches, simulated errors, benchmarks) for a support session
+///
+/// Release builds only accept the passphrase whose digest they were built with,
+/// and throttle repeated failures; the unlock lasts until `lock_debug_commands`
+/// or the app quits.
+#[tauri::command]
+pub fn unlock_debug_commands(
+    gate: State<'_, DebugGate>,
+    passphrase: String,
+) -> Result<(), AppError> {
+    log::info!("Unlocking debug commands");
+    gate.unlock(&passphrase)
+}
+
+/// Close debug commands again
+#[tauri::command]
+pub fn lock_debug_commands(gate: State<'_, DebugGate>) {
+    log::info!("Locking debug commands");
+    gate.lock();
+}
@@ -1,6 +1,10 @@
-use crate::models::{HistoryMetadata, HistoryQuery, RetentionPolicy, UsageHistoryEntry, UsageStats};
-use crate::services::HistoryService;
-use tauri::AppHandle;
+use crate::models::{
+    AccountUsageStats, BudgetStatus, DayBucket, HistoryAnnotation, HistoryMetadata, HistoryQuery,
+    RecoveryForecast, ResetSchedule, RetentionPolicy, SpendEstimate, SpendPeriod, UnseenChange,
+    UsageHistoryEntry, UsageStats,
+};
+use crate::services::{CredentialService, DebugGate, HistoryService, SettingsService};
+use tauri::{AppHandle, State};
 
 /// Query history entries with optional filters
 #[tauri::command]
@@ -29,6 +33,30 @@ pub async fn get_history_metadata(app: AppHandle) -> Result<HistoryMetadata, Str
     HistoryService::get_metadata(&app).map_err(|e| e.to_string())
 }
 
+/// Events marked on history charts (e.g. quota changes), oldest first
+#[tauri::command]
+pub async fn get_history_annotations(
+    app: AppHandle,
+    account_id: Option<String>,
+) -> Result<Vec<HistoryAnnotation>, String> {
+    log::info!("Getting history annotations for {:?}", account_id);
+    HistoryService::get_annotations(&app, account_id.as_deref()).map_err(|e| e.to_string())
+}
+
+/// Entries recorded by `fetch_usage_with`, oldest first; never part of real history
+///
+/// Gated like `fetch_usage_with`, since the entries come from credentials that
+/// were never saved.
+#[tauri::command]
+pub async fn get_scratch_history(
+    app: AppHandle,
+    gate: State<'_, DebugGate>,
+) -> Result<Vec<UsageHistoryEntry>, String> {
+    log::info!("Getting scratch history");
+    gate.check("get_scratch_history").map_err(|e| e.to_string())?;
+    HistoryService::get_scratch_entries(&app).map_err(|e| e.to_string())
+}
+
 /// Get retention policy
 #[tauri::command]
 pub async fn get_retention_policy(app: AppHandle) -> Result<RetentionPolicy, String> {
@@ -50,6 +78,21 @@ pub async fn cleanup_history(app: AppHandle) -> Result<usize, String> {
     HistoryService::cleanup(&app).map_err(|e| e.to_string())
 }
 
+/// Downsample history older than a cutoff into fixed-size buckets
+#[tauri::command]
+pub async fn compact_history(
+    app: AppHandle,
+    older_than_days: u32,
+    bucket_minutes: u32,
+) -> Result<usize, String> {
+    log::info!(
+        "Compacting history older than {} days into {}-minute buckets",
+        older_than_days,
+        bucket_minutes
+    );
+    HistoryService::compact(&app, older_than_days, bucket_minutes).map_err(|e| e.to_string())
+}
+
 /// Get usage statistics for a time period
 #[tauri::command]
 pub async fn get_usage_stats(
@@ -79,6 +122,17 @@ pub async fn get_usage_stats(
         .map_err(|e| e.to_string())
 }
 
+/// Get per-limit statistics for an account over the last `days` days
+#[tauri::command]
+pub async fn get_account_usage_stats(
+    app: AppHandle,
+    account_id: String,
+    days: u32,
+) -> Result<AccountUsageStats, String> {
+    log::info!("Getting usage stats for account {} over {} days", account_id, days);
+    HistoryService::get_account_stats(&app, &account_id, days).map_err(|e| e.to_string())
+}
+
 /// Export history to JSON
 #[tauri::command]
 pub async fn export_history_json(
@@ -89,6 +143,13 @@ pub async fn export_history_json(
     HistoryService::export_json(&app, query.as_ref()).map_err(|e| e.to_string())
 }
 
+/// Export recent history as a shareable report with accounts and limit IDs anonymized
+#[tauri::command]
+pub async fn export_anonymized_report(app: AppHandle, days: u32) -> Result<String, String> {
+    log::info!("Exporting anonymized report for the last {} days", days);
+    HistoryService::export_anonymized(&app, days).map_err(|e| e.to_string())
+}
+
 /// Export history to CSV
 #[tauri::command]
 pub async fn export_history_csv(
@@ -105,3 +166,126 @@ pub async fn clear_history(app: AppHandle) -> Result<(), String> {
     log::info!("Clearing all history");
     HistoryService::clear_all(&app).map_err(|e| e.to_string())
 }
+
+/// Record that the user has viewed an account's current usage
+#[tauri::command]
+pub async fn mark_account_viewed(app: AppHandle, account_id: String) -> Result<(), String> {
+    log::info!("Marking account {} as viewed", account_id);
+    HistoryService::mark_viewed(&app, &account_id).map_err(|e| e.to_string())
+}
+
+/// Get accounts whose usage crossed a threshold since they were last viewed
+#[tauri::command]
+pub async fn get_unseen_changes(app: AppHandle) -> Result<Vec<UnseenChange>, String> {
+    log::info!("Getting unseen usage changes");
+    let settings = SettingsService::get(&app).map_err(|e| e.to_string())?;
+    HistoryService::get_unseen_changes(&app, &settings.notifications.thresholds)
+        .map_err(|e| e.to_string())
+}
+
+/// Estimate spend for an account from history and the configured pricing
+#[tauri::command]
+pub async fn estimate_spend(
+    app: AppHandle,
+    account_id: String,
+    period: SpendPeriod,
+) -> Result<SpendEstimate, String> {
+    log::info!("Estimating {:?} spend for account {}", period, account_id);
+
+    let account = CredentialService::get_account(&app, &account_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Account not found: {}", account_id))?;
+
+    let settings = SettingsService::get(&app).map_err(|e| e.to_string())?;
+    let pricing = settings
+        .pricing
+        .get(&account.provider)
+        .cloned()
+        .unwrap_or_default();
+
+    HistoryService::estimate_spend(&app, &account_id, &account.provider, period, &pricing)
+        .map_err(|e| e.to_string())
+}
+
+/// Peak utilization per local day for a heatmap, oldest first
+#[tauri::command]
+pub async fn get_daily_heatmap(
+    app: AppHandle,
+    account_id: String,
+    limit_id: String,
+    days: u32,
+) -> Result<Vec<DayBucket>, String> {
+    log::info!(
+        "Getting {}-day heatmap for {} on account {}",
+        days,
+        limit_id,
+        account_id
+    );
+    HistoryService::get_daily_heatmap(&app, &account_id, &limit_id, days).map_err(|e| e.to_string())
+}
+
+/// Infer a limit's recurring reset interval from history and predict the next resets
+#[tauri::command]
+pub async fn get_reset_schedule(
+    app: AppHandle,
+    limit_id: String,
+    account_id: String,
+) -> Result<ResetSchedule, String> {
+    log::info!(
+        "Getting reset schedule for {} on account {}",
+        limit_id,
+        account_id
+    );
+    HistoryService::get_reset_schedule(&app, &account_id, &limit_id).map_err(|e| e.to_string())
+}
+
+/// Estimate whether a limit will cap again soon after its next reset, given recent burn rate
+#[tauri::command]
+pub async fn forecast_recovery(
+    app: AppHandle,
+    account_id: String,
+    limit_id: String,
+) -> Result<RecoveryForecast, String> {
+    log::info!(
+        "Forecasting recovery for {} on account {}",
+        limit_id,
+        account_id
+    );
+    HistoryService::get_recovery_forecast(&app, &account_id, &limit_id).map_err(|e| e.to_string())
+}
+
+/// Render a limit's recent utilization as a Unicode block sparkline (e.g. for a tooltip)
+#[tauri::command]
+pub async fn get_usage_sparkline(
+    app: AppHandle,
+    account_id: String,
+    limit_id: String,
+    points: usize,
+) -> Result<String, String> {
+    log::info!(
+        "Getting {}-point sparkline for {} on account {}",
+        points,
+        limit_id,
+        account_id
+    );
+    HistoryService::get_usage_sparkline(&app, &account_id, &limit_id, points)
+        .map_err(|e| e.to_string())
+}
+
+/// Month-to-date consumption against an account's monthly budget, or None if it has none
+#[tauri::command]
+pub async fn get_budget_status(
+    app: AppHandle,
+    account_id: String,
+) -> Result<Option<BudgetStatus>, String> {
+    log::info!("Getting monthly budget status for account {}", account_id);
+
+    let settings = SettingsService::get(&app).map_err(|e| e.to_string())?;
+    let Some(&budget) = settings.monthly_budgets.get(&account_id) else {
+        return Ok(None);
+    };
+
+    HistoryService::get_budget_status(&app, &account_id, budget, settings.budget_warning_percent)
+        .map(Some)
+        .map_err(|e| e.to_string())
+}
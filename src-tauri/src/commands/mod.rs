@@ -1,10 +1,12 @@
 mod accounts;
+mod diagnostics;
 mod history;
 mod scheduler;
 mod settings;
 mod usage;
 
 pub use accounts::*;
+pub use diagnostics::*;
 pub use history::*;
 pub use scheduler::*;
 pub use settings::*;
@@ -1,5 +1,11 @@
 use crate::error::AppError;
-use crate::services::{SchedulerService, SchedulerState, SettingsService};
+use crate::models::{AccountHealth, ReconcileReport};
+use crate::services::{
+    DebugGate, RetryResult, ScheduledFetch, ScheduledFetches, SchedulerService, SchedulerState,
+    SettingsService,
+};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
 use std::sync::Arc;
 use tauri::{AppHandle, State};
 
@@ -10,6 +16,7 @@ pub fn get_scheduler_status(state: State<'_, Arc<SchedulerState>>) -> SchedulerS
         running: state.is_running(),
         interval_secs: state.get_interval(),
         last_fetch: state.get_last_fetch(),
+        next_fetch: state.next_fetch_at(Utc::now().timestamp_millis() as u64),
     }
 }
 
@@ -32,15 +39,46 @@ pub fn set_refresh_interval(
     state: State<'_, Arc<SchedulerState>>,
     interval_secs: u64,
 ) {
+    let settings = SettingsService::get(&app);
+
+    // A team config's bounds win over the requested interval
+    let interval_secs = match &settings {
+        Ok(settings) => {
+            let range = SettingsService::refresh_interval_range(settings);
+            interval_secs.clamp(*range.start() as u64, *range.end() as u64)
+        }
+        Err(_) => interval_secs,
+    };
     SchedulerService::set_interval(&app, &state, interval_secs);
 
     // Also update settings
-    if let Ok(mut settings) = SettingsService::get(&app) {
+    if let Ok(mut settings) = settings {
         settings.refresh_interval = interval_secs as u32;
         let _ = SettingsService::save(&app, &settings);
     }
 }
 
+/// Re-apply the settings' refresh interval to the scheduler if it has drifted
+#[tauri::command]
+pub fn reconcile_scheduler(
+    app: AppHandle,
+    state: State<'_, Arc<SchedulerState>>,
+) -> Result<ReconcileReport, AppError> {
+    log::info!("Reconciling scheduler with settings");
+    SchedulerService::reconcile(&app, state.inner())
+}
+
+/// Recommend a refresh interval for an account based on its reset cadence and recent usage
+#[tauri::command]
+pub fn suggest_refresh_interval(
+    app: AppHandle,
+    state: State<'_, Arc<SchedulerState>>,
+    account_id: String,
+) -> Result<u64, AppError> {
+    log::info!("Suggesting refresh interval for account {}", account_id);
+    SchedulerService::suggest_refresh_interval(&app, &account_id, state.get_interval())
+}
+
 /// Force an immediate refresh
 #[tauri::command]
 pub async fn force_refresh(
@@ -64,6 +102,83 @@ pub async fn resume_scheduler(
     SchedulerService::force_refresh(&app, &state).await
 }
 
+/// Count fake session errors against an account to exercise the pause/resume flow
+///
+/// A debug command: release builds reject it until debug commands are unlocked.
+#[tauri::command]
+pub async fn simulate_session_error(
+    app: AppHandle,
+    gate: State<'_, DebugGate>,
+    state: State<'_, Arc<SchedulerState>>,
+    account_id: String,
+    count: u64,
+) -> Result<(), AppError> {
+    log::info!(
+        "Simulating {} session errors for account {}",
+        count,
+        account_id
+    );
+
+    gate.check("simulate_session_error")?;
+
+    SchedulerService::simulate_session_errors(&app, &state, &account_id, count).await
+}
+
+/// Let threshold and reset-warning notifications fire again for one limit, or all with None
+///
+/// Returns how many sent-notification entries were cleared.
+#[tauri::command]
+pub fn reset_notification_state(
+    state: State<'_, Arc<SchedulerState>>,
+    limit_id: Option<String>,
+) -> usize {
+    log::info!("Resetting notification state for limit {:?}", limit_id);
+    state.reset_notification_state(limit_id.as_deref())
+}
+
+/// Retry only the accounts that failed in the previous cycle
+#[tauri::command]
+pub async fn retry_last_failure(
+    app: AppHandle,
+    state: State<'_, Arc<SchedulerState>>,
+) -> Result<HashMap<String, RetryResult>, AppError> {
+    log::info!("Retrying accounts that failed last cycle");
+    Ok(SchedulerService::retry_last_failure(&app, &state).await)
+}
+
+/// Schedule a one-shot fetch for an account at a specific time, independent of the scheduler
+#[tauri::command]
+pub fn schedule_fetch_at(
+    app: AppHandle,
+    state: State<'_, Arc<SchedulerState>>,
+    fetches: State<'_, ScheduledFetches>,
+    account_id: String,
+    at: DateTime<Utc>,
+) -> Result<ScheduledFetch, AppError> {
+    log::info!("Scheduling fetch for account {} at {}", account_id, at);
+
+    let state = state.inner().clone();
+    let target = account_id.clone();
+    Ok(fetches.schedule(&account_id, at, move || async move {
+        if let Err(e) = SchedulerService::fetch_single_account(&app, &state, &target).await {
+            log::warn!("Scheduled fetch for account {} failed: {}", target, e);
+        }
+    }))
+}
+
+/// Cancel a pending one-shot fetch, returning false if it already ran or is unknown
+#[tauri::command]
+pub fn cancel_scheduled_fetch(fetches: State<'_, ScheduledFetches>, id: String) -> bool {
+    log::info!("Cancelling scheduled fetch {}", id);
+    fetches.cancel(&id)
+}
+
+/// List one-shot fetches that have not run yet
+#[tauri::command]
+pub fn list_scheduled_fetches(fetches: State<'_, ScheduledFetches>) -> Vec<ScheduledFetch> {
+    fetches.list()
+}
+
 /// Get the current session status (aggregate across all accounts)
 #[tauri::command]
 pub async fn get_session_status(state: State<'_, Arc<SchedulerState>>) -> Result<SessionStatusResponse, AppError> {
@@ -75,12 +190,24 @@ pub async fn get_session_status(state: State<'_, Arc<SchedulerState>>) -> Result
     })
 }
 
+/// How reliably an account's recent fetches have succeeded
+#[tauri::command]
+pub async fn get_account_health(
+    state: State<'_, Arc<SchedulerState>>,
+    account_id: String,
+) -> Result<AccountHealth, AppError> {
+    log::info!("Getting health for account: {}", account_id);
+    Ok(state.account_health(&account_id).await)
+}
+
 #[derive(serde::Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SchedulerStatusResponse {
     pub running: bool,
     pub interval_secs: u64,
     pub last_fetch: u64,
+    /// When the scheduler will next fetch (unix seconds, offline backoff included); 0 while stopped
+    pub next_fetch: u64,
 }
 
 #[derive(serde::Serialize)]
@@ -1,8 +1,19 @@
 use crate::error::AppError;
-use crate::models::AppSettings;
-use crate::services::SettingsService;
-use tauri::AppHandle;
-use tauri_plugin_notification::NotificationExt;
+use crate::models::{
+    AppSettings, IssueSeverity, NotificationRecord, SettingsIssue, SimulatedNotification,
+    SnapshotId, SnapshotInfo, SnapshotReason, TeamConfigReport, UsageData,
+};
+use crate::providers::RequestLimiter;
+use crate::services::{
+    apply_window_action, window_changes, CredentialService, DataDir, DataDirExt, DisplayTimezone,
+    HistoryService, NotificationLogService, NotificationService, ResponseCacheService,
+    SchedulerService, SchedulerState, SettingsService, ShortcutService, SnapshotService,
+    WindowAction,
+};
+use chrono::{DateTime, Datelike, Local, NaiveTime, Utc, Weekday};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, State};
+use tauri_plugin_notification::{NotificationExt, PermissionState};
 
 #[tauri::command]
 pub async fn get_settings(app: AppHandle) -> Result<AppSettings, AppError> {
@@ -10,10 +21,83 @@ pub async fn get_settings(app: AppHandle) -> Result<AppSettings, AppError> {
     SettingsService::get(&app)
 }
 
+/// Save app settings
+///
+/// With `reject_invalid`, settings that `validate_settings` reports errors for
+/// are not saved. Neither are settings whose global shortcut can't be bound.
+/// Refresh interval bounds come from `apply_team_config`, so the stored ones
+/// are kept whatever `settings` says.
 #[tauri::command]
-pub async fn save_settings(app: AppHandle, settings: AppSettings) -> Result<(), AppError> {
+pub async fn save_settings(
+    app: AppHandle,
+    mut settings: AppSettings,
+    reject_invalid: Option<bool>,
+) -> Result<(), AppError> {
     log::info!("Saving app settings");
-    SettingsService::save(&app, &settings)
+    let previous = SettingsService::get(&app)?;
+    settings.refresh_interval_bounds = previous.refresh_interval_bounds;
+
+    if reject_invalid.unwrap_or(false) {
+        let errors: Vec<String> = SettingsService::validate(&settings)
+            .into_iter()
+            .filter(|issue| issue.severity == IssueSeverity::Error)
+            .map(|issue| format!("{}: {}", issue.field, issue.message))
+            .collect();
+        if !errors.is_empty() {
+            return Err(AppError::InvalidInput(format!(
+                "Settings not saved: {}",
+                errors.join("; ")
+            )));
+        }
+    }
+
+    // Bind first so a shortcut that can't be registered is never saved
+    ShortcutService::apply(&app, settings.global_shortcut.as_deref())?;
+    if let Err(e) = SettingsService::save(&app, &settings) {
+        let _ = ShortcutService::apply(&app, previous.global_shortcut.as_deref());
+        return Err(e);
+    }
+    RequestLimiter::global().set_max_concurrent(settings.max_concurrent_requests);
+    for action in window_changes(&previous, &settings) {
+        apply_window_action(&app, action)?;
+    }
+    Ok(())
+}
+
+/// Apply a shared team config (thresholds, reset warnings, disabled limits, refresh bounds)
+///
+/// Personal settings and credentials are left alone; the report lists which
+/// keys were applied and which were rejected.
+#[tauri::command]
+pub async fn apply_team_config(
+    app: AppHandle,
+    state: State<'_, Arc<SchedulerState>>,
+    json: String,
+) -> Result<TeamConfigReport, AppError> {
+    log::info!("Applying team config");
+
+    let report = SettingsService::apply_team_config(&app, &json)?;
+    if report
+        .applied
+        .iter()
+        .any(|key| key == "refreshIntervalBounds")
+    {
+        // The interval may have been clamped; the running scheduler has to follow
+        SchedulerService::reconcile(&app, state.inner())?;
+    }
+    log::info!(
+        "Team config: {} field(s) applied, {} rejected",
+        report.applied.len(),
+        report.rejected.len()
+    );
+    Ok(report)
+}
+
+/// Check settings for invalid values without saving them
+#[tauri::command]
+pub async fn validate_settings(settings: AppSettings) -> Vec<SettingsIssue> {
+    log::info!("Validating app settings");
+    SettingsService::validate(&settings)
 }
 
 #[tauri::command]
@@ -27,3 +111,247 @@ pub async fn send_test_notification(app: AppHandle) -> Result<(), AppError> {
         .map_err(|e| AppError::Notification(e.to_string()))?;
     Ok(())
 }
+
+/// Preview which notifications a usage trajectory would trigger with the given thresholds
+///
+/// Nothing is sent; current settings are used apart from the thresholds.
+#[tauri::command]
+pub async fn simulate_notifications(
+    app: AppHandle,
+    snapshots: Vec<UsageData>,
+    thresholds: Vec<u32>,
+) -> Result<Vec<SimulatedNotification>, AppError> {
+    log::info!(
+        "Simulating notifications over {} snapshot(s) with thresholds {:?}",
+        snapshots.len(),
+        thresholds
+    );
+
+    let mut settings = SettingsService::get(&app)?;
+    settings.notifications.thresholds = thresholds;
+
+    Ok(NotificationService::simulate(&snapshots, &settings))
+}
+
+/// Whether the configured DND schedule would mute notifications at `time` (HH:MM)
+///
+/// `weekday` (e.g. "sat") picks which day's window to check; defaults to today.
+#[tauri::command]
+pub async fn is_dnd_active_at(
+    app: AppHandle,
+    time: String,
+    weekday: Option<String>,
+) -> Result<bool, AppError> {
+    log::info!("Checking DND at {} on {:?}", time, weekday);
+
+    let time = NaiveTime::parse_from_str(time.trim(), "%H:%M")
+        .map_err(|_| AppError::InvalidInput(format!("expected a time as HH:MM, got '{}'", time)))?;
+    let weekday = match weekday {
+        Some(day) => day
+            .trim()
+            .parse::<Weekday>()
+            .map_err(|_| AppError::InvalidInput(format!("unknown weekday '{}'", day)))?,
+        None => Local::now().weekday(),
+    };
+    let settings = SettingsService::get(&app)?;
+
+    Ok(NotificationService::dnd_active_for(
+        &settings.notifications,
+        weekday,
+        time,
+    ))
+}
+
+/// Current notification permission ("granted", "denied", "prompt", ...), re-checked on each call
+#[tauri::command]
+pub async fn get_notification_permission_status(
+    app: AppHandle,
+) -> Result<PermissionState, AppError> {
+    log::info!("Getting notification permission status");
+    NotificationService::check_permission(&app)
+}
+
+/// Most recent sent notifications, newest first; `limit` of None returns the whole log
+#[tauri::command]
+pub async fn get_notification_log(
+    app: AppHandle,
+    limit: Option<usize>,
+) -> Result<Vec<NotificationRecord>, AppError> {
+    log::info!("Getting notification log (limit: {:?})", limit);
+    NotificationLogService::get(&app, limit)
+}
+
+/// Delete the notification log, returning how many records were removed
+#[tauri::command]
+pub async fn clear_notification_log(app: AppHandle) -> Result<usize, AppError> {
+    log::info!("Clearing notification log");
+    NotificationLogService::clear(&app)
+}
+
+/// Alert when a limit has this many units (e.g. messages) left, for limits that report a total
+///
+/// An empty list removes the limit's absolute thresholds.
+#[tauri::command]
+pub async fn set_absolute_thresholds(
+    app: AppHandle,
+    limit_id: String,
+    units_left: Vec<f64>,
+) -> Result<(), AppError> {
+    log::info!(
+        "Setting absolute thresholds for {}: {:?}",
+        limit_id,
+        units_left
+    );
+    SettingsService::set_absolute_thresholds(&app, &limit_id, units_left)
+}
+
+/// Stop sending notifications for a limit
+#[tauri::command]
+pub async fn disable_limit_notifications(app: AppHandle, limit_id: String) -> Result<(), AppError> {
+    log::info!("Disabling notifications for limit: {}", limit_id);
+    SettingsService::set_limit_notifications_enabled(&app, &limit_id, false)
+}
+
+/// Resume notifications for a previously disabled limit
+#[tauri::command]
+pub async fn enable_limit_notifications(app: AppHandle, limit_id: String) -> Result<(), AppError> {
+    log::info!("Enabling notifications for limit: {}", limit_id);
+    SettingsService::set_limit_notifications_enabled(&app, &limit_id, true)
+}
+
+/// Show human-readable times in "local", "UTC" or a named IANA zone (e.g. "Europe/Berlin")
+///
+/// Returns the zone's canonical name as saved.
+#[tauri::command]
+pub async fn set_display_timezone(app: AppHandle, zone: String) -> Result<String, AppError> {
+    log::info!("Setting display time zone: {}", zone);
+    Ok(SettingsService::set_display_timezone(&app, &zone)?.name())
+}
+
+/// Format a UTC time (e.g. a limit's `resets_at`) in the display time zone
+#[tauri::command]
+pub async fn format_reset_time(
+    app: AppHandle,
+    resets_at: DateTime<Utc>,
+) -> Result<String, AppError> {
+    log::info!("Formatting reset time {}", resets_at);
+    let settings = SettingsService::get(&app)?;
+    Ok(DisplayTimezone::from_setting(&settings.display_timezone).format(resets_at))
+}
+
+/// Keep the main window above other windows, saved for the next launch
+#[tauri::command]
+pub async fn set_always_on_top(app: AppHandle, enabled: bool) -> Result<(), AppError> {
+    log::info!("Setting always on top: {}", enabled);
+    let mut settings = SettingsService::get(&app)?;
+    settings.always_on_top = enabled;
+    SettingsService::save(&app, &settings)?;
+    apply_window_action(&app, WindowAction::SetAlwaysOnTop(enabled))
+}
+
+/// Switch between the compact usage-only window and the full dashboard
+///
+/// Saved for the next launch; `mini-mode-changed` is emitted with the new state.
+#[tauri::command]
+pub async fn set_mini_mode(app: AppHandle, enabled: bool) -> Result<(), AppError> {
+    log::info!("Setting mini mode: {}", enabled);
+    let mut settings = SettingsService::get(&app)?;
+    settings.mini_mode = enabled;
+    SettingsService::save(&app, &settings)?;
+
+    let action = if enabled {
+        WindowAction::EnterMiniMode
+    } else {
+        WindowAction::ExitMiniMode
+    };
+    apply_window_action(&app, action)
+}
+
+/// Limit IDs pinned to the top of the dashboard, in display order
+#[tauri::command]
+pub async fn get_limit_order(app: AppHandle) -> Result<Vec<String>, AppError> {
+    log::info!("Getting limit order");
+    Ok(SettingsService::get(&app)?.limit_order)
+}
+
+/// Pin limits to the top of the dashboard in the given order; an empty list unpins all
+#[tauri::command]
+pub async fn set_limit_order(app: AppHandle, limit_ids: Vec<String>) -> Result<(), AppError> {
+    log::info!("Setting limit order: {:?}", limit_ids);
+    SettingsService::set_limit_order(&app, limit_ids)
+}
+
+/// Directory the stores are read from for this session
+#[tauri::command]
+pub async fn get_data_dir(app: AppHandle) -> Result<String, AppError> {
+    log::info!("Getting data directory");
+    Ok(app.data_path("")?.to_string_lossy().into_owned())
+}
+
+/// Move the stores to `dir` (or with None, back to the default) from the next launch
+///
+/// `AI_PULSE_DATA_DIR` still takes precedence when set. Existing data is not copied.
+#[tauri::command]
+pub async fn set_data_dir(app: AppHandle, dir: Option<String>) -> Result<(), AppError> {
+    log::info!("Setting data directory override: {:?}", dir);
+    DataDir::set_saved_override(&app, dir.as_deref())
+}
+
+/// Delete all accounts, settings, history, cached responses and notification state
+#[tauri::command]
+pub async fn factory_reset(
+    app: AppHandle,
+    state: State<'_, Arc<SchedulerState>>,
+    confirm: bool,
+) -> Result<(), AppError> {
+    log::info!("Factory reset requested (confirm: {})", confirm);
+
+    if !confirm {
+        return Err(AppError::InvalidInput(
+            "factory reset must be called with confirm = true".to_string(),
+        ));
+    }
+
+    SchedulerService::stop(&app, &state);
+
+    CredentialService::reset_all(&app)?;
+    SettingsService::reset(&app)?;
+    ShortcutService::apply(&app, None)?;
+    HistoryService::wipe(&app)?;
+    NotificationLogService::clear(&app)?;
+    ResponseCacheService::clear(&app)?;
+    SnapshotService::wipe(&app)?;
+    state.clear_all().await;
+
+    log::warn!("Factory reset complete - all data removed");
+    let _ = app.emit("factory-reset", ());
+    Ok(())
+}
+
+/// Save the current accounts, settings, history and notification state
+#[tauri::command]
+pub async fn create_snapshot(app: AppHandle) -> Result<SnapshotId, AppError> {
+    log::info!("Creating snapshot");
+    Ok(SnapshotService::create(&app, SnapshotReason::Manual)?.id)
+}
+
+#[tauri::command]
+pub async fn list_snapshots(app: AppHandle) -> Result<Vec<SnapshotInfo>, AppError> {
+    log::info!("Listing snapshots");
+    SnapshotService::list(&app)
+}
+
+/// Replace the current state with a saved snapshot
+///
+/// The state being replaced is kept as a `pre-restore` snapshot.
+#[tauri::command]
+pub async fn restore_snapshot(app: AppHandle, id: SnapshotId) -> Result<SnapshotInfo, AppError> {
+    log::info!("Restoring snapshot {}", id);
+    let info = SnapshotService::restore(&app, &id)?;
+
+    // The restore stands even if the snapshot's shortcut is now taken; the
+    // failure is reported through `global-shortcut-failed`
+    let settings = SettingsService::get(&app)?;
+    let _ = ShortcutService::apply(&app, settings.global_shortcut.as_deref());
+    Ok(info)
+}
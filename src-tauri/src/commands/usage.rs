@@ -1,8 +1,30 @@
 use crate::error::{AppError, ProviderError};
-use crate::models::{Credentials, UsageData};
-use crate::providers::{ProviderMetadata, ProviderRegistry};
-use crate::services::CredentialService;
-use tauri::AppHandle;
+use crate::models::{
+    AccountInfo, AccountUsageRow, BadgeData, CachedResponse, CappedLimitsResponse, Credentials,
+    ProceedCheck, RateLimitInfo, UsageData, UsageSummary,
+};
+use crate::providers::{
+    CredentialField, ProviderCapabilities, ProviderMetadata, ProviderRegistry, UsageProvider,
+};
+use crate::services::{
+    CredentialService, DebugGate, HistoryService, InFlightFetches, ResponseCacheService,
+    SchedulerState, SettingsService, UsageStream,
+};
+use std::sync::Arc;
+use tauri::{AppHandle, State};
+
+/// Allowance on top of the refresh intervals for a fetch that is still in flight
+const FETCH_SLACK_SECS: u64 = 60;
+
+/// Age past which cached usage is too old for `check_proceed` to trust
+///
+/// A single missed cycle (rate limited, a slow fetch) shouldn't make usage
+/// stale, so two intervals pass before it does.
+fn max_usage_age_secs(interval_secs: u64) -> u64 {
+    interval_secs
+        .saturating_mul(2)
+        .saturating_add(FETCH_SLACK_SECS)
+}
 
 /// Map a ProviderError to a TestConnectionResult
 pub fn map_provider_error_to_result(error: ProviderError) -> TestConnectionResult {
@@ -31,6 +53,15 @@ pub fn map_provider_error_to_result(error: ProviderError) -> TestConnectionResul
             error_message: Some(format!("Missing required field: {}", field)),
             hint: Some("Please provide all required credentials.".to_string()),
         },
+        ProviderError::OrgMismatch { expected, actual } => TestConnectionResult {
+            success: false,
+            error_code: Some("ORG_MISMATCH".to_string()),
+            error_message: Some("Session key belongs to a different organization".to_string()),
+            hint: Some(format!(
+                "Your session key belongs to org {}, but this account is configured for org {}. Update the Organization ID or use a session key from org {}.",
+                actual, expected, expected
+            )),
+        },
         ProviderError::InvalidCredentials(msg) => TestConnectionResult {
             success: false,
             error_code: Some("INVALID_CREDENTIALS".to_string()),
@@ -59,6 +90,21 @@ pub fn map_provider_error_to_result(error: ProviderError) -> TestConnectionResul
             error_message: Some("Failed to parse API response".to_string()),
             hint: Some(format!("The API response format was unexpected: {}", msg)),
         },
+        ProviderError::Parse { message, snippet } => TestConnectionResult {
+            success: false,
+            error_code: Some("INCOMPLETE_RESPONSE".to_string()),
+            error_message: Some(format!("Incomplete API response ({})", message)),
+            hint: Some(format!(
+                "The response was cut off or malformed near \"{}\". This is usually a flaky connection or proxy - please try again.",
+                snippet
+            )),
+        },
+        ProviderError::Canceled => TestConnectionResult {
+            success: false,
+            error_code: Some("CANCELED".to_string()),
+            error_message: Some("The request was canceled".to_string()),
+            hint: None,
+        },
     }
 }
 
@@ -69,9 +115,42 @@ pub fn list_providers() -> Result<Vec<ProviderMetadata>, AppError> {
     Ok(registry.all_metadata())
 }
 
+/// Get the capability flags for a provider
+#[tauri::command]
+pub fn get_provider_capabilities(provider: String) -> Result<ProviderCapabilities, AppError> {
+    log::info!("Getting capabilities for provider: {}", provider);
+
+    let registry = ProviderRegistry::new()?;
+    let provider_impl = registry
+        .get(&provider)
+        .ok_or_else(|| ProviderError::HttpError(format!("Unknown or unavailable provider: {}", provider)))?;
+
+    Ok(provider_impl.capabilities())
+}
+
+/// Fields the account form should ask for with this provider
+#[tauri::command]
+pub fn get_credential_schema(provider: String) -> Result<Vec<CredentialField>, AppError> {
+    log::info!("Getting credential schema for provider: {}", provider);
+
+    let registry = ProviderRegistry::new()?;
+    let provider_impl = registry.get(&provider).ok_or_else(|| {
+        ProviderError::HttpError(format!("Unknown or unavailable provider: {}", provider))
+    })?;
+
+    Ok(provider_impl.credential_schema())
+}
+
 /// Fetch usage data for a specific account
+///
+/// Passing a `request_id` lets the frontend abort the fetch with `cancel_fetch`.
 #[tauri::command]
-pub async fn fetch_usage_for_account(app: AppHandle, account_id: String) -> Result<UsageData, AppError> {
+pub async fn fetch_usage_for_account(
+    app: AppHandle,
+    in_flight: State<'_, InFlightFetches>,
+    account_id: String,
+    request_id: Option<String>,
+) -> Result<UsageData, AppError> {
     log::info!("Fetching usage for account: {}", account_id);
 
     // Get the account
@@ -84,24 +163,411 @@ pub async fn fetch_usage_for_account(app: AppHandle, account_id: String) -> Resu
         .get(&account.provider)
         .ok_or_else(|| ProviderError::HttpError(format!("Unknown or unavailable provider: {}", account.provider)))?;
 
-    // Validate credentials
-    if !provider_impl.validate_credentials(&account.credentials) {
+    // Validate credentials (stored, or from the environment if allowed)
+    let mut credentials = CredentialService::resolve_for_fetch(&app, &account)?;
+    if !provider_impl.validate_credentials(&credentials) {
         return Err(ProviderError::InvalidCredentials(
             format!("Invalid credentials for account {}", account.name),
         )
         .into());
     }
 
+    // Swap an expiring OAuth access token for a fresh one first
+    CredentialService::refresh_oauth_for_fetch(&app, &account, &mut credentials).await?;
+
     // Fetch usage
-    let mut usage = provider_impl.fetch_usage(&account.credentials).await?;
+    let mut usage = in_flight
+        .run(
+            request_id.as_deref(),
+            provider_impl.fetch_usage_with_headers(&credentials, &account.extra_headers),
+        )
+        .await?;
 
     // Add account info to usage data
     usage.account_id = account.id;
     usage.account_name = account.name;
 
+    if let Ok(settings) = SettingsService::get(&app) {
+        usage.order_limits(&settings.limit_order);
+    }
+
     Ok(usage)
 }
 
+/// Abort an in-flight `fetch_usage_for_account` started with this request ID
+#[tauri::command]
+pub fn cancel_fetch(in_flight: State<'_, InFlightFetches>, request_id: String) -> bool {
+    log::info!("Canceling fetch: {}", request_id);
+    in_flight.cancel(&request_id)
+}
+
+/// Request-rate budget from the account's latest fetch, if the provider reported one
+#[tauri::command]
+pub async fn get_rate_limit_info(
+    state: State<'_, Arc<SchedulerState>>,
+    account_id: String,
+) -> Result<Option<RateLimitInfo>, AppError> {
+    log::info!("Getting rate limit info for account: {}", account_id);
+
+    Ok(state
+        .get_previous_usage(&account_id)
+        .await
+        .and_then(|usage| usage.rate_limit))
+}
+
+/// Where external dashboards can subscribe to usage updates over WebSocket
+///
+/// `url` is None while the server is disabled in settings. Setting changes
+/// take effect on the next launch.
+#[tauri::command]
+pub async fn get_websocket_info(
+    app: AppHandle,
+    stream: State<'_, UsageStream>,
+) -> Result<WebSocketInfo, AppError> {
+    log::info!("Getting WebSocket info");
+
+    let settings = SettingsService::get(&app)?;
+
+    Ok(WebSocketInfo {
+        enabled: settings.websocket_enabled,
+        url: settings
+            .websocket_enabled
+            .then(|| format!("ws://127.0.0.1:{}/usage", settings.websocket_port)),
+        requires_token: true,
+        token: settings.websocket_token,
+        subscribers: stream.subscriber_count(),
+    })
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebSocketInfo {
+    pub enabled: bool,
+    pub url: Option<String>,
+    /// Whether clients must append `?token=...` to the URL (always, now)
+    pub requires_token: bool,
+    /// Token to append; generated on the first launch with the server enabled
+    pub token: Option<String>,
+    pub subscribers: usize,
+}
+
+/// List limits that are at their cap for an account, based on the latest cached usage
+#[tauri::command]
+pub async fn get_capped_limits(
+    app: AppHandle,
+    state: State<'_, Arc<SchedulerState>>,
+    account_id: String,
+) -> Result<CappedLimitsResponse, AppError> {
+    log::info!("Getting capped limits for account: {}", account_id);
+
+    let cap_threshold = SettingsService::get(&app)?.cap_threshold;
+    let limits = state
+        .get_previous_usage(&account_id)
+        .await
+        .map(|usage| usage.capped_limits(cap_threshold))
+        .unwrap_or_default();
+
+    Ok(CappedLimitsResponse {
+        account_id,
+        is_any_capped: !limits.is_empty(),
+        limits,
+    })
+}
+
+/// Recent successful responses cached for an account, newest first
+///
+/// Lets the UI show the last known-good data while offline and step back
+/// through recent raw states.
+#[tauri::command]
+pub async fn get_cached_responses(
+    app: AppHandle,
+    account_id: String,
+) -> Result<Vec<CachedResponse>, AppError> {
+    log::info!("Getting cached responses for account: {}", account_id);
+    ResponseCacheService::get(&app, &account_id)
+}
+
+/// Plan and seat details for an account, or None if its provider doesn't report them
+///
+/// Served from a day-long cache unless `refresh` is set.
+#[tauri::command]
+pub async fn get_account_info(
+    app: AppHandle,
+    state: State<'_, Arc<SchedulerState>>,
+    account_id: String,
+    refresh: Option<bool>,
+) -> Result<Option<AccountInfo>, AppError> {
+    log::info!("Getting account info for account: {}", account_id);
+
+    let now = chrono::Utc::now();
+    if !refresh.unwrap_or(false) {
+        if let Some(info) = state.get_account_info(&account_id, now).await {
+            return Ok(Some(info));
+        }
+    }
+
+    let account = CredentialService::get_account(&app, &account_id)?
+        .ok_or_else(|| AppError::AccountNotFound(account_id.clone()))?;
+    let registry = ProviderRegistry::new()?;
+    let provider_impl = registry.get(&account.provider).ok_or_else(|| {
+        ProviderError::HttpError(format!(
+            "Unknown or unavailable provider: {}",
+            account.provider
+        ))
+    })?;
+
+    let credentials = CredentialService::resolve_for_fetch(&app, &account)?;
+    let info = provider_impl.fetch_account_info(&credentials).await?;
+    if let Some(info) = &info {
+        state.set_account_info(&account_id, info.clone()).await;
+    }
+
+    Ok(info)
+}
+
+/// Badge for one of an account's limits, in the shields.io endpoint format
+///
+/// Built from the latest cached usage; a grey "no data" badge is returned
+/// until the account has been fetched.
+#[tauri::command]
+pub async fn get_badge_data(
+    state: State<'_, Arc<SchedulerState>>,
+    account_id: String,
+    limit_id: String,
+) -> Result<BadgeData, AppError> {
+    log::info!(
+        "Getting badge data for {} on account: {}",
+        limit_id,
+        account_id
+    );
+
+    let Some(usage) = state.get_previous_usage(&account_id).await else {
+        return Ok(BadgeData::no_data(&limit_id));
+    };
+
+    usage
+        .limits
+        .iter()
+        .find(|l| l.id == limit_id)
+        .map(BadgeData::for_limit)
+        .ok_or_else(|| {
+            AppError::InvalidInput(format!(
+                "Account {} has no limit '{}'",
+                account_id, limit_id
+            ))
+        })
+}
+
+/// IDs of the account's limits that will reset within the next `duration_secs`
+///
+/// Based on `resets_at` from the latest cached usage; empty if there is none yet.
+#[tauri::command]
+pub async fn will_reset_within(
+    state: State<'_, Arc<SchedulerState>>,
+    account_id: String,
+    duration_secs: u64,
+) -> Result<Vec<String>, AppError> {
+    log::info!(
+        "Checking limits resetting within {}s for account: {}",
+        duration_secs,
+        account_id
+    );
+
+    let now = chrono::Utc::now();
+    let until = i64::try_from(duration_secs)
+        .ok()
+        .and_then(chrono::Duration::try_seconds)
+        .and_then(|window| now.checked_add_signed(window))
+        .ok_or_else(|| {
+            AppError::InvalidInput(format!("Duration of {}s is too long", duration_secs))
+        })?;
+
+    Ok(state
+        .get_previous_usage(&account_id)
+        .await
+        .map(|usage| usage.limits_resetting_within(now, until))
+        .unwrap_or_default())
+}
+
+/// Whether every limit has at least `required_headroom_percent` left below its cap
+///
+/// Reads cached usage only. An account with no cached usage yet, or whose usage has
+/// missed more than one refresh, can't proceed, since its headroom is unknown.
+#[tauri::command]
+pub async fn check_proceed(
+    app: AppHandle,
+    state: State<'_, Arc<SchedulerState>>,
+    account_id: String,
+    required_headroom_percent: f64,
+) -> Result<ProceedCheck, AppError> {
+    log::info!(
+        "Checking {}% headroom for account: {}",
+        required_headroom_percent,
+        account_id
+    );
+
+    if !required_headroom_percent.is_finite() || required_headroom_percent < 0.0 {
+        return Err(AppError::InvalidInput(format!(
+            "Required headroom must be a non-negative percentage, got {}",
+            required_headroom_percent
+        )));
+    }
+
+    let cap_threshold = SettingsService::get(&app)?.cap_threshold;
+    Ok(match state.get_previous_usage(&account_id).await {
+        Some(usage) => ProceedCheck {
+            account_id,
+            ..usage.proceed_check(
+                cap_threshold,
+                required_headroom_percent,
+                max_usage_age_secs(state.get_interval()),
+                chrono::Utc::now(),
+            )
+        },
+        None => ProceedCheck {
+            account_id,
+            can_proceed: false,
+            blocking_limit_id: None,
+            min_headroom: None,
+            data_age_secs: None,
+            stale: false,
+        },
+    })
+}
+
+/// Shorthand for `check_proceed(...).can_proceed`, for scripts gating heavy jobs
+#[tauri::command]
+pub async fn can_proceed(
+    app: AppHandle,
+    state: State<'_, Arc<SchedulerState>>,
+    account_id: String,
+    required_headroom_percent: f64,
+) -> Result<bool, AppError> {
+    Ok(
+        check_proceed(app, state, account_id, required_headroom_percent)
+            .await?
+            .can_proceed,
+    )
+}
+
+/// Fetch usage for credentials that aren't saved, e.g. to preview during onboarding
+///
+/// Takes no `AppHandle`, so it cannot read or write any store. The credentials
+/// are never logged.
+#[tauri::command]
+pub async fn preview_usage(provider: String, credentials: Credentials) -> Result<UsageData, AppError> {
+    log::info!("Previewing usage for provider: {}", provider);
+
+    let registry = ProviderRegistry::new()?;
+    let provider_impl = registry.get(&provider).ok_or_else(|| AppError::WithHint {
+        message: format!("Provider '{}' is not available", provider),
+        hint: "This provider is currently blocked or not supported.".to_string(),
+    })?;
+
+    preview_usage_with(provider_impl.as_ref(), &credentials).await
+}
+
+/// Fetch with caller-supplied credentials, bypassing stored accounts (debug command)
+///
+/// Like `preview_usage`, the credentials are never saved to an account. With
+/// `record`, the result is also kept in the scratch history bucket, labeled
+/// "scratch" and kept apart from real accounts' history.
+#[tauri::command]
+pub async fn fetch_usage_with(
+    app: AppHandle,
+    gate: State<'_, DebugGate>,
+    provider: String,
+    credentials: Credentials,
+    record: Option<bool>,
+) -> Result<UsageData, AppError> {
+    log::info!(
+        "Fetching usage with supplied credentials for provider: {}",
+        provider
+    );
+
+    gate.check("fetch_usage_with")?;
+
+    let registry = ProviderRegistry::new()?;
+    let provider_impl = registry.get(&provider).ok_or_else(|| AppError::WithHint {
+        message: format!("Provider '{}' is not available", provider),
+        hint: "This provider is currently blocked or not supported.".to_string(),
+    })?;
+
+    let usage = preview_usage_with(provider_impl.as_ref(), &credentials).await?;
+    if record.unwrap_or(false) {
+        HistoryService::add_scratch_entry(&app, &usage)?;
+    }
+
+    Ok(usage)
+}
+
+/// Validate then fetch with the given provider, turning failures into hinted errors
+async fn preview_usage_with(
+    provider: &dyn UsageProvider,
+    credentials: &Credentials,
+) -> Result<UsageData, AppError> {
+    if !provider.validate_credentials(credentials) {
+        return Err(AppError::WithHint {
+            message: "Credentials format is invalid".to_string(),
+            hint: "Please ensure both Organization ID and Session Key are provided.".to_string(),
+        });
+    }
+
+    provider.fetch_usage(credentials).await.map_err(|e| {
+        let result = map_provider_error_to_result(e);
+        AppError::WithHint {
+            message: result.error_message.unwrap_or_default(),
+            hint: result.hint.unwrap_or_default(),
+        }
+    })
+}
+
+/// Compare cached usage for several accounts, aligned by limit
+#[tauri::command]
+pub async fn compare_accounts(
+    state: State<'_, Arc<SchedulerState>>,
+    account_ids: Vec<String>,
+) -> Result<Vec<AccountUsageRow>, AppError> {
+    log::info!("Comparing usage for {} accounts", account_ids.len());
+
+    let mut usages = Vec::with_capacity(account_ids.len());
+    for account_id in account_ids {
+        let usage = state.get_previous_usage(&account_id).await;
+        usages.push((account_id, usage));
+    }
+
+    Ok(AccountUsageRow::align(&usages))
+}
+
+/// Summarize cached usage for one account
+///
+/// Without an explicit account this uses the primary account, or the most-used
+/// account when no primary is set. Returns None if that account hasn't been fetched yet.
+#[tauri::command]
+pub async fn get_usage_summary(
+    app: AppHandle,
+    state: State<'_, Arc<SchedulerState>>,
+    account_id: Option<String>,
+) -> Result<Option<UsageSummary>, AppError> {
+    log::info!("Getting usage summary for account: {:?}", account_id);
+
+    let account_id = match account_id {
+        Some(id) => Some(id),
+        None => SettingsService::get(&app)?.primary_account_id,
+    };
+
+    let usage = match account_id {
+        Some(id) => state.get_previous_usage(&id).await,
+        None => state
+            .get_all_previous_usage()
+            .await
+            .into_iter()
+            .max_by(|a, b| a.max_utilization().total_cmp(&b.max_utilization())),
+    };
+
+    Ok(usage.map(|usage| usage.summary()))
+}
+
 /// Validate credentials for a specific provider
 #[tauri::command]
 pub async fn validate_credentials(
@@ -174,3 +640,151 @@ pub async fn test_connection(
         Err(e) => Ok(map_provider_error_to_result(e)),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ClaudeCredentials;
+
+    #[test]
+    fn provider_capabilities_for_claude() {
+        let capabilities = get_provider_capabilities("claude".to_string()).unwrap();
+
+        assert!(capabilities.supports_expiry);
+        assert!(!capabilities.supports_spend);
+        assert_eq!(capabilities.limit_kinds, vec!["five_hour", "weekly"]);
+    }
+
+    #[test]
+    fn org_mismatch_maps_to_actionable_hint() {
+        let result = map_provider_error_to_result(ProviderError::OrgMismatch {
+            expected: "org-configured".to_string(),
+            actual: "org-from-key".to_string(),
+        });
+
+        assert!(!result.success);
+        assert_eq!(result.error_code.as_deref(), Some("ORG_MISMATCH"));
+        assert!(result.hint.unwrap().starts_with(
+            "Your session key belongs to org org-from-key, but this account is configured for org org-configured."
+        ));
+    }
+
+    #[test]
+    fn one_missed_refresh_does_not_make_usage_stale() {
+        let interval = 300;
+        let usage = UsageData {
+            provider: "claude".to_string(),
+            account_id: "acc-1".to_string(),
+            account_name: "Personal".to_string(),
+            timestamp: chrono::Utc::now(),
+            limits: Vec::new(),
+            raw: None,
+            rate_limit: None,
+        };
+        let check = |age_secs: i64| {
+            let now = usage.timestamp + chrono::Duration::seconds(age_secs);
+            usage
+                .proceed_check(100.0, 0.0, max_usage_age_secs(interval), now)
+                .stale
+        };
+
+        assert!(!check(interval as i64 + 30));
+        assert!(!check(2 * interval as i64 + 30));
+        assert!(check(2 * interval as i64 + FETCH_SLACK_SECS as i64 + 1));
+        assert_eq!(max_usage_age_secs(u64::MAX), u64::MAX);
+    }
+
+    #[test]
+    fn provider_capabilities_unknown_provider() {
+        assert!(get_provider_capabilities("unknown".to_string()).is_err());
+    }
+
+    /// Provider that returns a canned result and counts fetches
+    struct MockProvider {
+        result: fn() -> Result<UsageData, ProviderError>,
+        fetches: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl UsageProvider for MockProvider {
+        fn id(&self) -> &'static str {
+            "mock"
+        }
+
+        fn name(&self) -> &'static str {
+            "Mock"
+        }
+
+        async fn fetch_usage(&self, _credentials: &Credentials) -> Result<UsageData, ProviderError> {
+            self.fetches.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            (self.result)()
+        }
+
+        fn validate_credentials(&self, credentials: &Credentials) -> bool {
+            credentials
+                .claude()
+                .is_some_and(|c| c.session_key.is_some())
+        }
+    }
+
+    fn mock(result: fn() -> Result<UsageData, ProviderError>) -> MockProvider {
+        MockProvider {
+            result,
+            fetches: std::sync::atomic::AtomicUsize::new(0),
+        }
+    }
+
+    fn credentials(session_key: Option<&str>) -> Credentials {
+        Credentials::Claude(ClaudeCredentials {
+            org_id: Some("org-123".to_string()),
+            session_key: session_key.map(str::to_string),
+            session_keys: Vec::new(),
+            oauth: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn preview_usage_returns_fetched_data_without_store() {
+        let provider = mock(|| {
+            Ok(UsageData {
+                provider: "mock".to_string(),
+                account_id: String::new(),
+                account_name: String::new(),
+                timestamp: chrono::Utc::now(),
+                limits: Vec::new(),
+                raw: None,
+                rate_limit: None,
+            })
+        });
+
+        // No AppHandle or store is involved - the preview only talks to the provider
+        let usage = preview_usage_with(&provider, &credentials(Some("sk-ant-test")))
+            .await
+            .unwrap();
+
+        assert_eq!(usage.provider, "mock");
+        assert!(usage.account_id.is_empty());
+        assert_eq!(provider.fetches.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn preview_usage_rejects_bad_format_before_fetching() {
+        let provider = mock(|| Err(ProviderError::SessionExpired));
+
+        let err = preview_usage_with(&provider, &credentials(None)).await.unwrap_err();
+
+        assert!(matches!(err, AppError::WithHint { .. }));
+        assert_eq!(provider.fetches.load(std::sync::atomic::Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn preview_usage_maps_provider_errors_to_hints() {
+        let provider = mock(|| Err(ProviderError::SessionExpired));
+
+        let err = preview_usage_with(&provider, &credentials(Some("sk-ant-test")))
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().starts_with("Your session has expired - "));
+    }
+}
@@ -20,6 +20,18 @@ pub enum AppError {
 
     #[error("Notification error: {0}")]
     Notification(String),
+
+    #[error("Account not found: {0}")]
+    AccountNotFound(String),
+
+    #[error("Integrity check failed: {0}")]
+    IntegrityViolation(String),
+
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    #[error("{message} - {hint}")]
+    WithHint { message: String, hint: String },
 }
 
 #[derive(Debug, Error)]
@@ -39,11 +51,35 @@ pub enum ProviderError {
     #[error("Invalid response format: {0}")]
     ParseError(String),
 
+    /// A successful response whose body didn't parse, e.g. cut short by a proxy
+    #[error("Incomplete or malformed response ({message}) near: {snippet} - this is usually temporary, try again shortly")]
+    Parse { message: String, snippet: String },
+
     #[error("Missing credentials for provider: {0}")]
     MissingCredentials(String),
 
     #[error("Invalid credentials: {0}")]
     InvalidCredentials(String),
+
+    #[error("Session key belongs to organization {actual}, not {expected}")]
+    OrgMismatch { expected: String, actual: String },
+
+    #[error("Request was canceled")]
+    Canceled,
+}
+
+/// Store plugin errors (opening, loading, or saving a store file)
+impl From<tauri_plugin_store::Error> for AppError {
+    fn from(err: tauri_plugin_store::Error) -> Self {
+        AppError::Store(err.to_string())
+    }
+}
+
+/// Transport-level failures from the HTTP client
+impl From<reqwest::Error> for ProviderError {
+    fn from(err: reqwest::Error) -> Self {
+        ProviderError::HttpError(err.to_string())
+    }
 }
 
 // Make errors serializable for Tauri commands
@@ -97,6 +133,15 @@ mod tests {
         assert_eq!(err.to_string(), "Missing credentials for provider: claude");
     }
 
+    #[test]
+    fn provider_error_org_mismatch() {
+        let err = ProviderError::OrgMismatch {
+            expected: "org-a".to_string(),
+            actual: "org-b".to_string(),
+        };
+        assert_eq!(err.to_string(), "Session key belongs to organization org-b, not org-a");
+    }
+
     #[test]
     fn app_error_from_provider_error() {
         let provider_err = ProviderError::SessionExpired;
@@ -104,6 +149,81 @@ mod tests {
         assert!(app_err.to_string().contains("Session expired"));
     }
 
+    #[test]
+    fn app_error_account_not_found() {
+        let err = AppError::AccountNotFound("abc-123".to_string());
+        assert_eq!(err.to_string(), "Account not found: abc-123");
+    }
+
+    #[test]
+    fn app_error_invalid_input() {
+        let err = AppError::InvalidInput("confirm required".to_string());
+        assert_eq!(err.to_string(), "Invalid input: confirm required");
+    }
+
+    #[test]
+    fn app_error_with_hint() {
+        let err = AppError::WithHint {
+            message: "Your session has expired".to_string(),
+            hint: "Get a fresh session key".to_string(),
+        };
+        assert_eq!(err.to_string(), "Your session has expired - Get a fresh session key");
+    }
+
+    #[test]
+    fn provider_error_parse_and_credentials() {
+        assert_eq!(
+            ProviderError::ParseError("missing field".to_string()).to_string(),
+            "Invalid response format: missing field"
+        );
+        assert_eq!(
+            ProviderError::InvalidCredentials("bad key".to_string()).to_string(),
+            "Invalid credentials: bad key"
+        );
+    }
+
+    #[test]
+    fn app_error_messages() {
+        assert_eq!(
+            AppError::Store("disk full".to_string()).to_string(),
+            "Store error: disk full"
+        );
+        assert_eq!(
+            AppError::RateLimit("wait".to_string()).to_string(),
+            "Rate limited: wait"
+        );
+        assert_eq!(
+            AppError::Notification("denied".to_string()).to_string(),
+            "Notification error: denied"
+        );
+        assert_eq!(
+            AppError::IntegrityViolation("tampered".to_string()).to_string(),
+            "Integrity check failed: tampered"
+        );
+    }
+
+    #[test]
+    fn app_error_from_serde_json_error() {
+        let json_err = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        let app_err: AppError = json_err.into();
+        assert!(matches!(app_err, AppError::Serialization(_)));
+        assert!(app_err.to_string().starts_with("Serialization error: "));
+    }
+
+    #[test]
+    fn app_error_from_store_error() {
+        let io_err = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "read-only");
+        let app_err: AppError = tauri_plugin_store::Error::Io(io_err).into();
+        assert!(matches!(app_err, AppError::Store(_)));
+        assert!(app_err.to_string().contains("read-only"));
+    }
+
+    #[test]
+    fn app_error_serializes_as_message() {
+        let err = AppError::AccountNotFound("abc-123".to_string());
+        assert_eq!(serde_json::to_string(&err).unwrap(), "\"Account not found: abc-123\"");
+    }
+
     #[test]
     fn provider_error_serialization() {
         let err = ProviderError::SessionExpired;
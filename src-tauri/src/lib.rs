@@ -1,8 +1,10 @@
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use tauri::{
     menu::{Menu, MenuItem, PredefinedMenuItem, Submenu},
     tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
-    Emitter, Manager,
+    Emitter, Manager, RunEvent, WindowEvent,
 };
 use tauri_plugin_autostart::MacosLauncher;
 use tauri_plugin_notification::NotificationExt;
@@ -16,14 +18,42 @@ mod providers;
 mod services;
 
 use commands::{
-    clear_history, cleanup_history, delete_account, export_history_csv, export_history_json,
-    fetch_usage_for_account, force_refresh, get_account, get_history_metadata, get_retention_policy,
-    get_scheduler_status, get_session_status, get_settings, get_usage_stats, has_accounts,
-    list_accounts, list_providers, query_history, resume_scheduler, save_account, save_settings,
-    send_test_notification, set_refresh_interval, set_retention_policy, start_scheduler,
-    stop_scheduler, test_account_connection, test_connection, validate_credentials,
+    apply_team_config, audit_encryption, benchmark_crypto, can_proceed, cancel_fetch,
+    cancel_scheduled_fetch, check_credential_file_permissions, check_proceed, cleanup_history,
+    clear_history, clear_notification_log, compact_history, compare_accounts, create_snapshot,
+    delete_account, delete_accounts_where, disable_limit_notifications, duplicate_account,
+    enable_limit_notifications, estimate_spend, export_anonymized_report, export_history_csv,
+    export_history_json, factory_reset, fetch_usage_for_account, fetch_usage_with, force_refresh,
+    forecast_recovery, format_reset_time, get_account, get_account_health, get_account_info,
+    get_account_usage_stats, get_badge_data, get_budget_status, get_cached_responses,
+    get_capped_limits, get_credential_schema, get_crypto_info, get_daily_heatmap, get_data_dir,
+    get_history_annotations, get_history_metadata, get_limit_order, get_notification_log,
+    get_notification_permission_status, get_primary_account, get_provider_capabilities,
+    get_rate_limit_info, get_reset_schedule, get_retention_policy, get_scheduler_status,
+    get_scratch_history, get_session_status, get_settings, get_unseen_changes, get_usage_sparkline,
+    get_usage_stats, get_usage_summary, get_websocket_info, has_accounts, import_accounts,
+    is_dnd_active_at, list_accounts, list_providers, list_scheduled_fetches, list_snapshots,
+    lock_debug_commands, looks_like_claude_key, mark_account_viewed, merge_accounts,
+    normalize_session_key, preview_migration, preview_usage, query_history, recheck_account,
+    reconcile_scheduler, reencrypt_plaintext_fields, reencrypt_with_override_key_material,
+    reset_notification_state, resign_credentials_store, restore_snapshot, resume_scheduler,
+    retry_last_failure, run_diagnostics, save_account, save_settings, schedule_fetch_at,
+    send_test_notification, set_absolute_thresholds, set_account_display_name, set_always_on_top,
+    set_data_dir, set_display_timezone, set_limit_order, set_mini_mode, set_monthly_budget,
+    set_primary_account, set_refresh_interval, set_retention_policy, simulate_notifications,
+    simulate_session_error, start_scheduler, stop_scheduler, suggest_refresh_interval,
+    test_account_connection, test_connection, tighten_permissions, unlock_debug_commands,
+    update_account_fields, validate_all_accounts, validate_credentials, validate_settings,
+    will_reset_within,
+};
+use models::AppSettings;
+use providers::RequestLimiter;
+use services::{
+    apply_window_action, startup_actions, window_actions, DataDir, DataDirExt, DebugGate,
+    GlobalShortcutState, HistoryService, InFlightFetches, NotificationPermission,
+    NotificationService, OAuthRefreshLocks, ScheduledFetches, SchedulerService, SchedulerState,
+    SettingsService, ShortcutService, StartupAction, StoreLocks, UsageStream,
 };
-use services::{HistoryService, SchedulerService, SchedulerState, SettingsService};
 
 /// Helper to show the main window and optionally emit an event
 fn show_window_and_emit<T: serde::Serialize + Clone>(
@@ -39,6 +69,37 @@ fn show_window_and_emit<T: serde::Serialize + Clone>(
     }
 }
 
+/// Longest a quit waits for an in-flight fetch and the final store flush
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Set once a quit has started, so repeated quit requests don't start another
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+
+/// Stop the scheduler and flush stores in the background, then exit
+///
+/// Every quit path ends up here. A fetch that hangs past `SHUTDOWN_TIMEOUT`
+/// doesn't hold up the exit.
+fn shutdown_and_exit(app: &tauri::AppHandle) {
+    if SHUTTING_DOWN.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<Arc<SchedulerState>>().inner().clone();
+        if tokio::time::timeout(SHUTDOWN_TIMEOUT, SchedulerService::shutdown(&app, &state))
+            .await
+            .is_err()
+        {
+            log::warn!(
+                "Shutdown didn't finish within {:?}, exiting anyway",
+                SHUTDOWN_TIMEOUT
+            );
+        }
+        app.exit(0);
+    });
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -55,42 +116,144 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
         .manage(Arc::new(SchedulerState::new()))
+        .manage(StoreLocks::new())
+        .manage(OAuthRefreshLocks::new())
+        .manage(ScheduledFetches::new())
+        .manage(InFlightFetches::new())
+        .manage(UsageStream::new())
+        .manage(NotificationPermission::new())
+        .manage(GlobalShortcutState::new())
+        .manage(DebugGate::default())
         .invoke_handler(tauri::generate_handler![
             // Account commands (multi-account)
             list_accounts,
             has_accounts,
             get_account,
             save_account,
+            import_accounts,
+            duplicate_account,
+            update_account_fields,
+            set_account_display_name,
             delete_account,
+            delete_accounts_where,
+            merge_accounts,
+            resign_credentials_store,
+            set_primary_account,
+            get_primary_account,
+            set_monthly_budget,
             test_account_connection,
+            recheck_account,
+            validate_all_accounts,
+            preview_migration,
+            reencrypt_with_override_key_material,
+            audit_encryption,
+            get_crypto_info,
+            check_credential_file_permissions,
+            tighten_permissions,
+            benchmark_crypto,
+            reencrypt_plaintext_fields,
+            normalize_session_key,
+            looks_like_claude_key,
             // Settings commands
             get_settings,
             save_settings,
+            validate_settings,
+            apply_team_config,
             send_test_notification,
+            simulate_notifications,
+            is_dnd_active_at,
+            get_notification_permission_status,
+            get_notification_log,
+            clear_notification_log,
+            run_diagnostics,
+            unlock_debug_commands,
+            lock_debug_commands,
+            disable_limit_notifications,
+            enable_limit_notifications,
+            set_absolute_thresholds,
+            get_limit_order,
+            set_limit_order,
+            set_always_on_top,
+            set_mini_mode,
+            set_display_timezone,
+            format_reset_time,
+            factory_reset,
+            get_data_dir,
+            set_data_dir,
+            create_snapshot,
+            list_snapshots,
+            restore_snapshot,
             // Usage commands
             fetch_usage_for_account,
+            cancel_fetch,
+            preview_usage,
+            fetch_usage_with,
+            get_capped_limits,
+            check_proceed,
+            can_proceed,
+            get_badge_data,
+            will_reset_within,
+            get_rate_limit_info,
+            get_cached_responses,
+            get_account_info,
+            get_websocket_info,
+            compare_accounts,
+            get_usage_summary,
             validate_credentials,
             test_connection,
             list_providers,
+            get_provider_capabilities,
+            get_credential_schema,
             // Scheduler commands
             get_scheduler_status,
             get_session_status,
+            get_account_health,
             start_scheduler,
             stop_scheduler,
             set_refresh_interval,
+            suggest_refresh_interval,
+            reconcile_scheduler,
             force_refresh,
             resume_scheduler,
+            simulate_session_error,
+            retry_last_failure,
+            reset_notification_state,
+            schedule_fetch_at,
+            cancel_scheduled_fetch,
+            list_scheduled_fetches,
             // History commands
             query_history,
             get_history_metadata,
+            get_history_annotations,
+            get_scratch_history,
             get_retention_policy,
             set_retention_policy,
             cleanup_history,
+            compact_history,
             get_usage_stats,
+            get_account_usage_stats,
+            mark_account_viewed,
+            get_unseen_changes,
+            estimate_spend,
+            get_daily_heatmap,
+            get_reset_schedule,
+            forecast_recovery,
+            get_usage_sparkline,
+            get_budget_status,
             export_history_json,
+            export_anonymized_report,
             export_history_csv,
             clear_history,
         ])
+        .on_window_event(|window, event| {
+            // Closing the main window quits the app - save everything first
+            if let WindowEvent::CloseRequested { api, .. } = event {
+                if window.label() == "main" {
+                    api.prevent_close();
+                    shutdown_and_exit(window.app_handle());
+                }
+            }
+        })
         .setup(|app| {
             // Set up logging in debug mode
             if cfg!(debug_assertions) {
@@ -101,10 +264,13 @@ pub fn run() {
                 )?;
             }
 
+            // Resolve the data directory before anything opens a store
+            let data_dir = DataDir::from_app(app.handle())?;
+            app.manage(data_dir);
+
             // Send welcome notification on first launch (also triggers macOS permission prompt)
             {
-                use tauri_plugin_store::StoreExt;
-                let store = app.store("settings.json")?;
+                let store = app.data_store("settings.json")?;
                 let welcome_shown = store.get("welcome_shown").and_then(|v| v.as_bool()).unwrap_or(false);
 
                 if !welcome_shown {
@@ -120,6 +286,12 @@ pub fn run() {
                 }
             }
 
+            // Remember the notification permission so a denial can be explained later
+            match NotificationService::check_permission(app.handle()) {
+                Ok(state) => log::info!("Notification permission: {}", state),
+                Err(e) => log::warn!("Failed to check notification permission: {}", e),
+            }
+
             // Set up native application menu (macOS menu bar, Windows/Linux window menu)
             #[cfg(target_os = "macos")]
             {
@@ -136,7 +308,8 @@ pub fn run() {
                         &PredefinedMenuItem::hide_others(app, Some("Hide Others"))?,
                         &PredefinedMenuItem::show_all(app, Some("Show All"))?,
                         &PredefinedMenuItem::separator(app)?,
-                        &PredefinedMenuItem::quit(app, Some("Quit AI Pulse"))?,
+                        // Not the predefined item, so quitting goes through the same flush as the tray
+                        &MenuItem::with_id(app, "menu-quit", "Quit AI Pulse", true, Some("CmdOrCtrl+Q"))?,
                     ],
                 )?;
 
@@ -228,7 +401,7 @@ pub fn run() {
 
             let _tray = tray_builder
                 .on_menu_event(|app, event| match event.id.as_ref() {
-                    "quit" => app.exit(0),
+                    "quit" => shutdown_and_exit(app),
                     "show" => show_window_and_emit(app, None::<(&str, ())>),
                     "refresh" => {
                         if let Some(window) = app.get_webview_window("main") {
@@ -267,6 +440,7 @@ pub fn run() {
                         }
                         "menu-usage" => show_window_and_emit(app, Some(("menu-usage", ()))),
                         "menu-analytics" => show_window_and_emit(app, Some(("menu-analytics", ()))),
+                        "menu-quit" => shutdown_and_exit(app),
                         _ => {}
                     }
                 });
@@ -287,11 +461,58 @@ pub fn run() {
                 }
             }
 
-            // Start the background scheduler
+            // Apply startup preferences (scheduler, initial fetch, window visibility)
             let scheduler_state = app.state::<Arc<SchedulerState>>();
-            SchedulerService::start(app.handle().clone(), scheduler_state.inner().clone());
+            SchedulerService::watch_power(app.handle().clone(), scheduler_state.inner().clone());
+            let startup_settings = SettingsService::get(app.handle()).unwrap_or_else(|e| {
+                log::warn!("Failed to load settings for startup, using defaults: {}", e);
+                AppSettings::default()
+            });
+            RequestLimiter::global().set_max_concurrent(startup_settings.max_concurrent_requests);
+            scheduler_state.seed_budget_levels(app.handle(), &startup_settings);
+            for action in startup_actions(&startup_settings) {
+                log::info!("Startup action: {:?}", action);
+                match action {
+                    StartupAction::StartScheduler => {
+                        SchedulerService::start(
+                            app.handle().clone(),
+                            scheduler_state.inner().clone(),
+                        );
+                    }
+                    StartupAction::FetchOnce => {
+                        let app = app.handle().clone();
+                        let state = scheduler_state.inner().clone();
+                        tauri::async_runtime::spawn(async move {
+                            if let Err(e) = SchedulerService::force_refresh(&app, &state).await {
+                                log::warn!("Startup fetch failed: {}", e);
+                            }
+                        });
+                    }
+                    StartupAction::HideMainWindow => {
+                        if let Some(window) = app.get_webview_window("main") {
+                            let _ = window.hide();
+                        }
+                    }
+                }
+            }
+
+            // Settings are migrated by now; catch anything the startup actions left out of step
+            if let Err(e) = SchedulerService::reconcile(app.handle(), scheduler_state.inner()) {
+                log::warn!("Failed to reconcile scheduler with settings: {}", e);
+            }
 
-            // Start the API server if enabled
+            // Restore always-on-top and mini mode
+            for action in window_actions(&startup_settings) {
+                if let Err(e) = apply_window_action(app.handle(), action) {
+                    log::warn!("Failed to apply {:?}: {}", action, e);
+                }
+            }
+
+            // Bind the show/hide shortcut; save_settings re-binds it on change. A
+            // failure is already reported through `global-shortcut-failed`
+            let _ = ShortcutService::apply(app.handle(), startup_settings.global_shortcut.as_deref());
+
+            // Start the API and WebSocket servers if enabled
             match SettingsService::get(app.handle()) {
                 Ok(settings) => {
                     log::info!(
@@ -314,14 +535,35 @@ pub fn run() {
                             settings.api_server_port
                         );
                     }
+
+                    if settings.websocket_enabled {
+                        match SettingsService::ensure_websocket_token(app.handle()) {
+                            Ok(token) => api::start_websocket_server(
+                                app.state::<UsageStream>().inner().clone(),
+                                settings.websocket_port,
+                                token,
+                            ),
+                            Err(e) => {
+                                log::error!("Not starting WebSocket server without a token: {}", e);
+                            }
+                        }
+                    }
                 }
                 Err(e) => {
-                    log::warn!("Failed to load settings for API server: {}", e);
+                    log::warn!("Failed to load settings for API servers: {}", e);
                 }
             }
 
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // Quits from outside the app (the Dock, logging out) flush first too;
+            // `app.exit` at the end of the shutdown carries a code and goes through
+            if let RunEvent::ExitRequested { code: None, api, .. } = event {
+                api.prevent_exit();
+                shutdown_and_exit(app);
+            }
+        });
 }
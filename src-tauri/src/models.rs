@@ -1,16 +1,172 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc, Weekday};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
-/// Credentials for a provider
+/// Credentials for an account, shaped by its provider
+///
+/// Serialized with a `type` tag. Objects without one (credentials stores before
+/// v6, or a frontend that predates the tag) are read as `Claude`, the only
+/// shape that existed then.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Credentials {
+    Claude(ClaudeCredentials),
+    Codex(CodexCredentials),
+    Gemini(GeminiCredentials),
+}
+
+impl Default for Credentials {
+    fn default() -> Self {
+        Credentials::Claude(ClaudeCredentials::default())
+    }
+}
+
+impl<'de> Deserialize<'de> for Credentials {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(tag = "type", rename_all = "lowercase")]
+        enum Tagged {
+            Claude(ClaudeCredentials),
+            Codex(CodexCredentials),
+            Gemini(GeminiCredentials),
+        }
+
+        let value = serde_json::Value::deserialize(deserializer)?;
+        let parsed = if value.get("type").is_some() {
+            serde_json::from_value(value).map(|tagged| match tagged {
+                Tagged::Claude(c) => Credentials::Claude(c),
+                Tagged::Codex(c) => Credentials::Codex(c),
+                Tagged::Gemini(c) => Credentials::Gemini(c),
+            })
+        } else {
+            serde_json::from_value(value).map(Credentials::Claude)
+        };
+        parsed.map_err(serde::de::Error::custom)
+    }
+}
+
+impl Credentials {
+    /// Provider ID these credentials are for
+    pub fn provider(&self) -> &'static str {
+        match self {
+            Credentials::Claude(_) => "claude",
+            Credentials::Codex(_) => "codex",
+            Credentials::Gemini(_) => "gemini",
+        }
+    }
+
+    pub fn claude(&self) -> Option<&ClaudeCredentials> {
+        match self {
+            Credentials::Claude(c) => Some(c),
+            _ => None,
+        }
+    }
+
+    pub fn claude_mut(&mut self) -> Option<&mut ClaudeCredentials> {
+        match self {
+            Credentials::Claude(c) => Some(c),
+            _ => None,
+        }
+    }
+}
+
+/// Claude credentials: a session key (or several) for an organization, or OAuth tokens
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct Credentials {
-    /// Claude: organization ID
+pub struct ClaudeCredentials {
+    /// Organization ID
     #[serde(skip_serializing_if = "Option::is_none")]
     pub org_id: Option<String>,
 
-    /// Claude: session key
+    /// Session key (mirrors the first entry of `session_keys` when that is set)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub session_key: Option<String>,
+
+    /// Session keys in failover order, tried until one is accepted
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub session_keys: Vec<String>,
+
+    /// OAuth tokens, used instead of the session key when present
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oauth: Option<OAuthCredentials>,
+}
+
+/// Codex (OpenAI) credentials
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CodexCredentials {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+}
+
+/// Gemini credentials: an API key and the Google Cloud project it bills to
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct GeminiCredentials {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<String>,
+}
+
+/// OAuth token pair; the access token is swapped for a fresh one before it expires
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OAuthCredentials {
+    pub access_token: String,
+    pub refresh_token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl OAuthCredentials {
+    /// Whether the access token expires within `margin` of `now` (or already has)
+    pub fn needs_refresh(&self, now: DateTime<Utc>, margin: chrono::Duration) -> bool {
+        self.expires_at - now <= margin
+    }
+}
+
+impl ClaudeCredentials {
+    /// Session keys to try, in order: `session_key` first, then the rest of `session_keys`
+    ///
+    /// For stored (synced) credentials this is exactly `session_keys`.
+    pub fn session_key_candidates(&self) -> Vec<&String> {
+        let mut candidates: Vec<&String> =
+            self.session_key.iter().filter(|k| !k.is_empty()).collect();
+        for key in &self.session_keys {
+            if !candidates.contains(&key) {
+                candidates.push(key);
+            }
+        }
+        candidates
+    }
+
+    /// Make `session_key` the head of `session_keys`
+    ///
+    /// The UI edits `session_key` directly, so a newly entered key moves to the
+    /// front of the list; a cleared `session_key` is refilled from the list.
+    pub fn sync_session_keys(&mut self) {
+        match self.session_key.as_ref().filter(|k| !k.is_empty()) {
+            Some(key) => {
+                if self.session_keys.is_empty() {
+                    return;
+                }
+                let key = key.clone();
+                self.session_keys.retain(|k| k != &key);
+                self.session_keys.insert(0, key);
+            }
+            None => self.session_key = self.session_keys.first().cloned(),
+        }
+    }
+
+    /// Move the key at `index` to the front after it worked where earlier keys failed
+    ///
+    /// The keys that failed are considered stale and move to the back of the list.
+    pub fn promote_session_key(&mut self, index: usize) {
+        if index == 0 || index >= self.session_keys.len() {
+            return;
+        }
+
+        let stale: Vec<String> = self.session_keys.drain(..index).collect();
+        self.session_keys.extend(stale);
+        self.session_key = self.session_keys.first().cloned();
+    }
 }
 
 /// Account for multi-account support
@@ -27,6 +183,188 @@ pub struct Account {
     pub credentials: Credentials,
     /// When the account was created
     pub created_at: DateTime<Utc>,
+    /// User-defined labels for grouping and filtering accounts
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Optional nickname shown in notifications instead of `name`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    /// Extra HTTP headers sent with this account's API requests (e.g. for a proxy gateway)
+    ///
+    /// A BTreeMap so the accounts store serializes (and is signed) deterministically.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra_headers: BTreeMap<String, String>,
+}
+
+impl Account {
+    /// Name to prefix notifications with: the nickname if set, otherwise the account name
+    pub fn notification_label(&self) -> &str {
+        self.display_name
+            .as_deref()
+            .map(str::trim)
+            .filter(|nickname| !nickname.is_empty())
+            .unwrap_or(&self.name)
+    }
+}
+
+/// File format accepted by `import_accounts`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportFormat {
+    Json,
+    Csv,
+}
+
+/// One account row from an import file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportRow {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub provider: String,
+    #[serde(default, alias = "orgId")]
+    pub org_id: Option<String>,
+    #[serde(default, alias = "sessionKey")]
+    pub session_key: Option<String>,
+}
+
+/// A row that could not be imported
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportFailure {
+    /// 1-based data row (the CSV header doesn't count)
+    pub row: usize,
+    /// Account name from the row, if it had one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub error: String,
+}
+
+/// Outcome of a bulk account import
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportResult {
+    /// IDs of the accounts created
+    pub imported: Vec<String>,
+    pub failures: Vec<ImportFailure>,
+}
+
+/// A single pending credentials store migration step
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationStep {
+    pub from_version: u32,
+    pub to_version: u32,
+    /// Human-readable summary of what the step would change
+    pub actions: Vec<String>,
+}
+
+/// Dry-run summary of the migrations `ensure_migrated` would apply
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationPlan {
+    pub current_version: u32,
+    pub target_version: u32,
+    pub steps: Vec<MigrationStep>,
+}
+
+/// Whether one stored credential field is encrypted at rest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldEncryptionStatus {
+    pub account_id: String,
+    /// Field path, e.g. `sessionKey` or `sessionKeys[1]`
+    pub field: String,
+    pub encrypted: bool,
+    /// Encryption scheme prefix (e.g. `enc:v1`), None for plaintext
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scheme: Option<String>,
+    /// Version number from the scheme prefix, None for plaintext
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
+}
+
+/// Timings from `benchmark_crypto`, in microseconds per encrypt+decrypt round trip
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CryptoBenchResult {
+    pub iterations: usize,
+    /// Key derivation being measured, as in `CryptoInfo.kdf`
+    pub kdf: String,
+    pub average_micros: f64,
+    pub median_micros: f64,
+    pub min_micros: f64,
+    pub max_micros: f64,
+}
+
+/// Encryption scheme in use, and which versions each account's secrets are stored under
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CryptoInfo {
+    /// Version new values are encrypted under (the N in `enc:vN:`)
+    pub current_version: u32,
+    /// Key derivation for the current version
+    pub kdf: String,
+    pub cipher: String,
+    /// Whether keys come from the OS keychain rather than machine-derived material
+    pub keychain_active: bool,
+    pub accounts: Vec<AccountCryptoInfo>,
+}
+
+/// Encryption versions found in one account's stored secrets
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountCryptoInfo {
+    pub account_id: String,
+    /// Distinct versions its encrypted fields use, ascending
+    pub versions: Vec<u32>,
+    /// Secret fields stored in plaintext
+    pub plaintext_fields: usize,
+    /// Whether any field is plaintext or under an older version than the current one
+    pub needs_reencryption: bool,
+    /// Whether any field is under a newer version than this build can decrypt
+    pub unsupported: bool,
+}
+
+/// Who besides the owner can access a file, from its Unix mode bits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FileAccess {
+    /// Only the owner has any access
+    OwnerOnly,
+    /// The file's group can read or write it
+    GroupReadable,
+    /// Any user on the machine can read or write it
+    WorldReadable,
+    /// The file doesn't exist yet
+    Missing,
+    /// The platform has no Unix modes to check (Windows)
+    Unsupported,
+}
+
+/// Result of `check_credential_file_permissions` and `tighten_permissions`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionReport {
+    pub path: String,
+    /// Permission bits in octal, e.g. "644"; None if missing or unsupported
+    pub mode: Option<String>,
+    pub access: FileAccess,
+    /// Whether others can get at the file, so it should be tightened
+    pub warning: bool,
+    pub message: String,
+}
+
+/// Outcome of re-encrypting stored secrets after the key material changed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyRecoveryReport {
+    /// Accounts whose credentials were re-encrypted under the current key
+    pub recovered_accounts: Vec<String>,
+    /// Accounts left untouched because they didn't decrypt with the old material
+    pub skipped_accounts: Vec<String>,
+    /// Sensitive settings fields re-encrypted under the current key
+    pub recovered_settings: usize,
 }
 
 /// Usage data returned to frontend
@@ -44,6 +382,24 @@ pub struct UsageData {
     pub limits: Vec<UsageLimit>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub raw: Option<serde_json::Value>,
+    /// Request rate-limit budget reported with the response, if the provider sent one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<RateLimitInfo>,
+}
+
+/// Request-rate budget from the provider's rate-limit headers (separate from usage limits)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitInfo {
+    /// Requests allowed in the current window
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+    /// Requests left in the current window
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remaining: Option<u64>,
+    /// When the request budget refills
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reset_at: Option<DateTime<Utc>>,
 }
 
 /// Individual usage limit
@@ -56,6 +412,351 @@ pub struct UsageLimit {
     pub resets_at: DateTime<Utc>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub category: Option<String>,
+    /// What the limit covers; data saved before kinds existed reads back as `Unknown`
+    #[serde(default)]
+    pub kind: LimitKind,
+    /// Size of the limit in its native units (e.g. messages), when the provider reports it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total: Option<f64>,
+}
+
+/// Type of usage limit, so callers don't have to pattern-match on limit IDs
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum LimitKind {
+    /// Rolling 5-hour session window
+    FiveHour,
+    /// Rolling 7-day window across all models
+    Weekly,
+    /// 7-day window for a single model family (e.g. "opus")
+    ModelSpecific { model: String },
+    /// Anything a provider reports that we don't recognize yet
+    #[default]
+    #[serde(other)]
+    Unknown,
+}
+
+/// A limit that is at or over its cap and cannot be used until it resets
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CappedLimit {
+    pub limit_id: String,
+    pub label: String,
+    pub utilization: f64,
+    pub resets_at: DateTime<Utc>,
+}
+
+/// Capped limits for an account, computed from the latest cached usage
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CappedLimitsResponse {
+    pub account_id: String,
+    pub is_any_capped: bool,
+    pub limits: Vec<CappedLimit>,
+}
+
+/// Utilization band, matching the bands and colors of the tray icon (`src/lib/tray.ts`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UsageLevel {
+    /// Below 50%
+    Low,
+    /// 50% up to 75%
+    Medium,
+    /// 75% up to 90%
+    High,
+    /// 90% and above
+    Critical,
+}
+
+impl UsageLevel {
+    pub fn from_utilization(utilization: f64) -> Self {
+        if utilization < 50.0 {
+            Self::Low
+        } else if utilization < 75.0 {
+            Self::Medium
+        } else if utilization < 90.0 {
+            Self::High
+        } else {
+            Self::Critical
+        }
+    }
+
+    /// Hex color without the `#`, as accepted by shields.io
+    pub fn color(&self) -> &'static str {
+        match self {
+            Self::Low => "22c55e",
+            Self::Medium => "eab308",
+            Self::High => "f97316",
+            Self::Critical => "ef4444",
+        }
+    }
+}
+
+/// Badge description in the shields.io endpoint format
+///
+/// See <https://shields.io/badges/endpoint-badge>.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BadgeData {
+    /// Always 1
+    pub schema_version: u32,
+    pub label: String,
+    pub message: String,
+    pub color: String,
+}
+
+impl BadgeData {
+    /// Badge showing a limit's utilization, colored by its `UsageLevel`
+    pub fn for_limit(limit: &UsageLimit) -> Self {
+        Self {
+            schema_version: 1,
+            label: limit.label.clone(),
+            message: format!("{:.0}%", limit.utilization),
+            color: UsageLevel::from_utilization(limit.utilization)
+                .color()
+                .to_string(),
+        }
+    }
+
+    /// Grey badge for a limit with no cached usage yet
+    pub fn no_data(label: &str) -> Self {
+        Self {
+            schema_version: 1,
+            label: label.to_string(),
+            message: "no data".to_string(),
+            color: "lightgrey".to_string(),
+        }
+    }
+}
+
+/// Whether an account has enough headroom below its cap to start more work
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProceedCheck {
+    pub account_id: String,
+    pub can_proceed: bool,
+    /// Limit with the least headroom, when that is less than required
+    pub blocking_limit_id: Option<String>,
+    /// Smallest headroom (cap minus utilization) across all limits; None without cached usage
+    pub min_headroom: Option<f64>,
+    /// Seconds since the cached usage was fetched; None without cached usage
+    pub data_age_secs: Option<u64>,
+    /// The cached usage has missed more than one refresh, so the answer can't be trusted
+    pub stale: bool,
+}
+
+impl UsageData {
+    /// Check every limit has at least `required_headroom` percentage points left below `cap_threshold`
+    ///
+    /// Usage fetched more than `max_age_secs` before `now` is stale and never allows proceeding.
+    pub fn proceed_check(
+        &self,
+        cap_threshold: f64,
+        required_headroom: f64,
+        max_age_secs: u64,
+        now: DateTime<Utc>,
+    ) -> ProceedCheck {
+        let tightest = self
+            .limits
+            .iter()
+            .map(|l| (l, cap_threshold - l.utilization))
+            .min_by(|(_, a), (_, b)| a.total_cmp(b));
+
+        let blocking = tightest
+            .filter(|(_, headroom)| *headroom < required_headroom)
+            .map(|(l, _)| l.id.clone());
+        let age = now
+            .signed_duration_since(self.timestamp)
+            .num_seconds()
+            .max(0) as u64;
+        let stale = age > max_age_secs;
+
+        ProceedCheck {
+            account_id: self.account_id.clone(),
+            can_proceed: blocking.is_none() && !stale,
+            blocking_limit_id: blocking,
+            min_headroom: tightest.map(|(_, headroom)| headroom),
+            data_age_secs: Some(age),
+            stale,
+        }
+    }
+
+    /// Move the limits listed in `order` to the front, in that order
+    ///
+    /// Unlisted limits keep their relative order after them. IDs with no
+    /// matching limit (e.g. a limit the API stopped reporting) are ignored.
+    pub fn order_limits(&mut self, order: &[String]) {
+        if order.is_empty() {
+            return;
+        }
+
+        let rank = |limit: &UsageLimit| {
+            order
+                .iter()
+                .position(|id| *id == limit.id)
+                .unwrap_or(order.len())
+        };
+        self.limits.sort_by_key(rank);
+    }
+
+    /// Limits whose utilization has reached `cap_threshold` (percent)
+    pub fn capped_limits(&self, cap_threshold: f64) -> Vec<CappedLimit> {
+        self.limits
+            .iter()
+            .filter(|l| l.utilization >= cap_threshold)
+            .map(|l| CappedLimit {
+                limit_id: l.id.clone(),
+                label: l.label.clone(),
+                utilization: l.utilization,
+                resets_at: l.resets_at,
+            })
+            .collect()
+    }
+
+    /// IDs of limits that reset after `now` and no later than `until`
+    pub fn limits_resetting_within(&self, now: DateTime<Utc>, until: DateTime<Utc>) -> Vec<String> {
+        self.limits
+            .iter()
+            .filter(|l| l.resets_at > now && l.resets_at <= until)
+            .map(|l| l.id.clone())
+            .collect()
+    }
+
+    /// Limits whose reported `total` differs from `previous`, e.g. after a plan change
+    ///
+    /// Limits without a total in either snapshot are skipped.
+    pub fn quota_changes(&self, previous: &UsageData) -> Vec<QuotaChange> {
+        self.limits
+            .iter()
+            .filter_map(|limit| {
+                let new_total = limit.total?;
+                let old_total = previous.limits.iter().find(|l| l.id == limit.id)?.total?;
+                (old_total != new_total).then(|| QuotaChange {
+                    account_id: self.account_id.clone(),
+                    limit_id: limit.id.clone(),
+                    old_total,
+                    new_total,
+                    detected_at: self.timestamp,
+                })
+            })
+            .collect()
+    }
+
+    /// Highest utilization across all limits (0 when there are none)
+    pub fn max_utilization(&self) -> f64 {
+        self.limits
+            .iter()
+            .map(|l| l.utilization)
+            .fold(0.0, f64::max)
+    }
+
+    pub fn summary(&self) -> UsageSummary {
+        let highest_limit = self
+            .limits
+            .iter()
+            .max_by(|a, b| a.utilization.total_cmp(&b.utilization))
+            .cloned();
+
+        UsageSummary {
+            account_id: self.account_id.clone(),
+            account_name: self.account_name.clone(),
+            timestamp: self.timestamp,
+            highest_limit,
+            limits: self.limits.clone(),
+        }
+    }
+}
+
+/// At-a-glance usage for one account, as shown by the tray and single-account views
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageSummary {
+    pub account_id: String,
+    pub account_name: String,
+    pub timestamp: DateTime<Utc>,
+    /// Most-used limit, if the account reported any
+    pub highest_limit: Option<UsageLimit>,
+    pub limits: Vec<UsageLimit>,
+}
+
+/// How reliably an account's recent fetches have succeeded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Healthy,
+    Degraded,
+    Failing,
+    /// No fetch has been attempted this session
+    Unknown,
+}
+
+/// Fetch reliability for one account over its most recent attempts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountHealth {
+    pub account_id: String,
+    /// Attempts considered (at most the tracked window)
+    pub attempts: usize,
+    pub successes: usize,
+    /// Fraction of attempts that succeeded (0-1), None with no attempts
+    pub success_rate: Option<f64>,
+    pub last_error: Option<String>,
+    pub last_error_at: Option<DateTime<Utc>>,
+    pub status: HealthStatus,
+}
+
+/// One account's utilization for a limit in a comparison table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LimitComparison {
+    pub limit_id: String,
+    /// None when the account has no such limit (distinct from 0% used)
+    pub utilization: Option<f64>,
+}
+
+/// A row in the account comparison table; every row lists the same limits in the same order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountUsageRow {
+    pub account_id: String,
+    /// Name from the cached usage, if any has been fetched yet
+    pub account_name: Option<String>,
+    pub fetched_at: Option<DateTime<Utc>>,
+    pub limits: Vec<LimitComparison>,
+}
+
+impl AccountUsageRow {
+    /// Align cached usage for several accounts onto the union of their limits
+    ///
+    /// Limit columns follow first-seen order across the given accounts.
+    pub fn align(usages: &[(String, Option<UsageData>)]) -> Vec<AccountUsageRow> {
+        let mut limit_ids: Vec<&str> = Vec::new();
+        for limit in usages.iter().filter_map(|(_, u)| u.as_ref()).flat_map(|u| &u.limits) {
+            if !limit_ids.contains(&limit.id.as_str()) {
+                limit_ids.push(&limit.id);
+            }
+        }
+
+        usages
+            .iter()
+            .map(|(account_id, usage)| AccountUsageRow {
+                account_id: account_id.clone(),
+                account_name: usage.as_ref().map(|u| u.account_name.clone()),
+                fetched_at: usage.as_ref().map(|u| u.timestamp),
+                limits: limit_ids
+                    .iter()
+                    .map(|&id| LimitComparison {
+                        limit_id: id.to_string(),
+                        utilization: usage
+                            .as_ref()
+                            .and_then(|u| u.limits.iter().find(|l| l.id == id))
+                            .map(|l| l.utilization),
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
 }
 
 /// Claude API response structures
@@ -78,15 +779,71 @@ pub struct LimitUsage {
     pub resets_at: Option<String>,
 }
 
+/// Claude organization details, as returned by `/organizations/{org_id}`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClaudeOrganization {
+    pub uuid: String,
+    pub name: Option<String>,
+    /// Feature flags, e.g. "chat", "claude_pro", "claude_max"
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    pub rate_limit_tier: Option<String>,
+    /// Organization type for shared plans, e.g. "team" or "enterprise"
+    pub raven_type: Option<String>,
+    pub seat_tier: Option<String>,
+}
+
+/// Plan and seat details for an account; these rarely change
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountInfo {
+    /// Plan name for display, e.g. "Pro", "Max" or "Team"; None if it couldn't be told
+    pub plan: Option<String>,
+    pub organization_name: Option<String>,
+    /// Provider's own tier ID, e.g. "default_claude_max_20x"
+    pub rate_limit_tier: Option<String>,
+    /// Seat type on shared plans, e.g. "team_standard"
+    pub seat_tier: Option<String>,
+    pub fetched_at: DateTime<Utc>,
+}
+
+/// Claude OAuth token endpoint response
+#[derive(Debug, Deserialize)]
+pub struct OAuthTokenResponse {
+    pub access_token: String,
+    /// Absent when the server keeps the old refresh token valid
+    pub refresh_token: Option<String>,
+    /// Lifetime of the access token in seconds
+    pub expires_in: i64,
+}
+
 /// App settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AppSettings {
     pub theme: String,
     pub language: String,
+    /// Locale for notification text (e.g. "en", "de"); unknown locales fall back to English
+    #[serde(default = "default_locale")]
+    pub locale: String,
     pub launch_at_startup: bool,
     pub refresh_mode: String,
     pub refresh_interval: u32,
+    /// Fetch once as soon as the scheduler starts instead of waiting a full interval
+    #[serde(default = "default_true")]
+    pub immediate_fetch_on_start: bool,
+    /// Start the background scheduler when the app launches
+    #[serde(default = "default_true")]
+    pub autostart_scheduler: bool,
+    /// Launch with the main window hidden, leaving only the tray icon
+    #[serde(default)]
+    pub start_minimized_to_tray: bool,
+    /// Utilization (percent) at which a limit counts as capped
+    #[serde(default = "default_cap_threshold")]
+    pub cap_threshold: f64,
+    /// Window (ms) over which per-account fetches are randomly spread each cycle (0 disables)
+    #[serde(default = "default_fetch_stagger_ms")]
+    pub fetch_stagger_ms: u64,
     /// Which limit to display in the menu bar: "highest", "five_hour", or "seven_day"
     #[serde(default = "default_tray_display_limit")]
     pub tray_display_limit: String,
@@ -97,39 +854,380 @@ pub struct AppSettings {
     pub providers: Vec<ProviderConfig>,
     /// Enable the local API server for CLI and IDE integrations
     #[serde(default)]
-    pub api_server_enabled: bool,
-    /// Port for the local API server (default: 31415)
-    #[serde(default = "default_api_server_port")]
-    pub api_server_port: u16,
-    /// Optional authentication token for the API server
+    pub api_server_enabled: bool,
+    /// Port for the local API server (default: 31415)
+    #[serde(default = "default_api_server_port")]
+    pub api_server_port: u16,
+    /// Optional authentication token for the API server
+    #[serde(default)]
+    pub api_server_token: Option<String>,
+    /// Push usage updates to local WebSocket clients (e.g. external dashboards)
+    #[serde(default)]
+    pub websocket_enabled: bool,
+    /// Port for the WebSocket server (default: 31416)
+    #[serde(default = "default_websocket_port")]
+    pub websocket_port: u16,
+    /// Optional token clients must pass as `?token=` when connecting
+    #[serde(default)]
+    pub websocket_token: Option<String>,
+    /// Fall back to ANTHROPIC_ORG_ID / ANTHROPIC_SESSION_KEY when an account has no stored value
+    #[serde(default)]
+    pub allow_env_credentials: bool,
+    /// Unit costs keyed by provider, then limit ID (cost per percentage point consumed)
+    #[serde(default)]
+    pub pricing: HashMap<String, HashMap<String, f64>>,
+    /// EMA alpha (0-1] for smoothing the utilization shown in the UI; None shows raw values
+    #[serde(default)]
+    pub smoothing_factor: Option<f64>,
+    /// Account the tray and single-account views show; None falls back to the worst case
+    #[serde(default)]
+    pub primary_account_id: Option<String>,
+    /// Keep history entries gzip-compressed in history.json.gz instead of inline in history.json
+    #[serde(default)]
+    pub compress_history: bool,
+    /// Monthly consumption cap per account ID, in percentage points consumed across all limits
+    #[serde(default)]
+    pub monthly_budgets: HashMap<String, f64>,
+    /// Share of a monthly budget (percent) at which `budget-warning` fires
+    #[serde(default = "default_budget_warning_percent")]
+    pub budget_warning_percent: u32,
+    /// Limit IDs to show first, in this order; other limits follow in API order
+    #[serde(default)]
+    pub limit_order: Vec<String>,
+    /// When the API host is unreachable, back off and emit one `offline` event instead of errors
+    #[serde(default)]
+    pub quiet_when_offline: bool,
+    /// Zone for human-readable times: "local", "UTC", or an IANA name like "Europe/Berlin"
+    #[serde(default = "default_display_timezone")]
+    pub display_timezone: String,
+    /// Most provider requests in flight at once, across all accounts and features
+    #[serde(default = "default_max_concurrent_requests")]
+    pub max_concurrent_requests: usize,
+    /// Recent responses kept per account for offline viewing; 0 turns the cache off
+    #[serde(default = "default_cached_responses_per_account")]
+    pub cached_responses_per_account: usize,
+    /// Keep the main window above other windows
+    #[serde(default)]
+    pub always_on_top: bool,
+    /// Show the compact usage-only window instead of the full dashboard
+    #[serde(default)]
+    pub mini_mode: bool,
+    /// Refresh interval range set by a team config; None allows the full range
+    #[serde(default)]
+    pub refresh_interval_bounds: Option<RefreshIntervalBounds>,
+}
+
+impl AppSettings {
+    /// JSON pointers to serialized fields that are encrypted at rest in the settings store
+    pub const SENSITIVE_FIELDS: &'static [&'static str] = &[
+        "/apiServerToken",
+        "/websocketToken",
+        "/notifications/email/password",
+    ];
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_locale() -> String {
+    "en".to_string()
+}
+
+fn default_display_timezone() -> String {
+    "local".to_string()
+}
+
+fn default_max_concurrent_requests() -> usize {
+    crate::providers::DEFAULT_MAX_CONCURRENT_REQUESTS
+}
+
+fn default_cached_responses_per_account() -> usize {
+    10
+}
+
+fn default_cap_threshold() -> f64 {
+    100.0
+}
+
+fn default_fetch_stagger_ms() -> u64 {
+    2000
+}
+
+fn default_api_server_port() -> u16 {
+    31415
+}
+
+fn default_websocket_port() -> u16 {
+    31416
+}
+
+fn default_budget_warning_percent() -> u32 {
+    80
+}
+
+fn default_tray_display_limit() -> String {
+    "highest".to_string()
+}
+
+/// Refresh interval range, in seconds, that a team config allows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct RefreshIntervalBounds {
+    pub min_secs: u32,
+    pub max_secs: u32,
+}
+
+/// How serious a settings problem is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IssueSeverity {
+    /// The setting can't work as given; `save_settings` can reject it
+    Error,
+    /// Works, but probably not as intended
+    Warning,
+}
+
+/// A problem found by `validate_settings`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsIssue {
+    /// Path to the field as serialized, e.g. "notifications.dndStartTime"
+    pub field: String,
+    pub severity: IssueSeverity,
+    pub message: String,
+}
+
+/// Outcome of `apply_team_config`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TeamConfigReport {
+    /// Team config keys that were applied, e.g. "thresholds"
+    pub applied: Vec<String>,
+    /// Keys that were left out, each with the reason
+    pub rejected: Vec<SettingsIssue>,
+}
+
+/// Outcome of `reconcile_scheduler`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReconcileReport {
+    /// Interval the scheduler uses after reconciling
+    pub interval_secs: u64,
+    /// Whether the scheduler is running; reconciling never starts or stops it
+    pub running: bool,
+    /// What was out of step and corrected, e.g. "interval 42s -> 300s"; empty if nothing was
+    pub fixed: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationSettings {
+    pub enabled: bool,
+    pub thresholds: Vec<u32>,
+    pub notify_on_reset: bool,
+    pub notify_on_expiry: bool,
+    /// Do Not Disturb mode - when enabled, notifications are muted during the scheduled time
+    #[serde(default)]
+    pub dnd_enabled: bool,
+    /// Start time for DND in HH:MM format (e.g., "22:00")
+    #[serde(default)]
+    pub dnd_start_time: Option<String>,
+    /// End time for DND in HH:MM format (e.g., "08:00")
+    #[serde(default)]
+    pub dnd_end_time: Option<String>,
+    /// Per-weekday DND windows; days without one use `dnd_start_time`/`dnd_end_time`
+    #[serde(default)]
+    pub dnd_schedule: DndSchedule,
+    /// Limit IDs that never trigger threshold or reset notifications
+    #[serde(default)]
+    pub disabled_limit_ids: HashSet<String>,
+    /// Thresholds at or above this percentage are sent as critical alerts
+    #[serde(default = "default_critical_threshold")]
+    pub critical_threshold: u32,
+    /// Sound played for critical alerts where the platform supports it (None for silent)
+    #[serde(default = "default_critical_sound")]
+    pub critical_sound: Option<String>,
+    /// Also email critical threshold and reset alerts; None leaves email off
+    #[serde(default)]
+    pub email: Option<EmailSettings>,
+    /// Record notifications held back by DND in the notification log
+    #[serde(default)]
+    pub log_suppressed: bool,
+    /// Extra thresholds per limit ID, as units left (e.g. "10 messages left")
+    ///
+    /// Converted to percentages with the limit's `total`; ignored for limits that don't report one.
+    #[serde(default)]
+    pub absolute_thresholds: HashMap<String, Vec<f64>>,
+    /// How many minutes before a reset the "reset soon" alert may fire
+    #[serde(default = "default_reset_warning_lead_minutes")]
+    pub reset_warning_lead_minutes: u32,
+    /// Utilization (percent) a limit must be at for the "reset soon" alert
+    #[serde(default = "default_reset_warning_min_percent")]
+    pub reset_warning_min_percent: u32,
+    /// Per-limit overrides of the reset warning lead time and percent, keyed by limit ID
+    #[serde(default)]
+    pub reset_warning_overrides: HashMap<String, ResetWarningOverride>,
+    /// Collect a poll cycle's threshold crossings into one summary notification
+    #[serde(default)]
+    pub digest_mode: bool,
+}
+
+/// Reset warning settings for one limit; unset fields use the global values
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResetWarningOverride {
+    #[serde(default)]
+    pub lead_minutes: Option<u32>,
+    #[serde(default)]
+    pub min_percent: Option<u32>,
+}
+
+/// SMTP server and addresses for emailed alerts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmailSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    pub smtp_host: String,
+    /// 465 connects with implicit TLS; any other port upgrades with STARTTLS
+    #[serde(default = "default_smtp_port")]
+    pub smtp_port: u16,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Sender address; defaults to `username`, then to the recipient
     #[serde(default)]
-    pub api_server_token: Option<String>,
+    pub from: Option<String>,
+    pub recipient: String,
 }
 
-fn default_api_server_port() -> u16 {
-    31415
+/// DND window for one day, in HH:MM; an end before the start runs into the next morning
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DndWindow {
+    pub start: String,
+    pub end: String,
 }
 
-fn default_tray_display_limit() -> String {
-    "highest".to_string()
+/// Optional DND window per weekday
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DndSchedule {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mon: Option<DndWindow>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tue: Option<DndWindow>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wed: Option<DndWindow>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thu: Option<DndWindow>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fri: Option<DndWindow>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sat: Option<DndWindow>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sun: Option<DndWindow>,
+}
+
+impl DndSchedule {
+    pub fn for_day(&self, day: Weekday) -> Option<&DndWindow> {
+        match day {
+            Weekday::Mon => self.mon.as_ref(),
+            Weekday::Tue => self.tue.as_ref(),
+            Weekday::Wed => self.wed.as_ref(),
+            Weekday::Thu => self.thu.as_ref(),
+            Weekday::Fri => self.fri.as_ref(),
+            Weekday::Sat => self.sat.as_ref(),
+            Weekday::Sun => self.sun.as_ref(),
+        }
+    }
+}
+
+/// How prominently a notification should be presented
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationUrgency {
+    Normal,
+    Critical,
+}
+
+/// Kind of notification reported by `simulate_notifications`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SimulatedNotificationKind {
+    Threshold,
+    Reset,
+}
+
+/// What a sent notification was about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    Threshold,
+    Reset,
+    ResetSoon,
+    Budget,
+    SessionExpiry,
 }
 
+/// A successful fetch kept in the response cache, raw provider response included
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct NotificationSettings {
-    pub enabled: bool,
-    pub thresholds: Vec<u32>,
-    pub notify_on_reset: bool,
-    pub notify_on_expiry: bool,
-    /// Do Not Disturb mode - when enabled, notifications are muted during the scheduled time
-    #[serde(default)]
-    pub dnd_enabled: bool,
-    /// Start time for DND in HH:MM format (e.g., "22:00")
-    #[serde(default)]
-    pub dnd_start_time: Option<String>,
-    /// End time for DND in HH:MM format (e.g., "08:00")
+pub struct CachedResponse {
+    pub fetched_at: DateTime<Utc>,
+    pub usage: UsageData,
+}
+
+/// Entry in the notification log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationRecord {
+    pub timestamp: DateTime<Utc>,
+    pub title: String,
+    pub body: String,
+    pub kind: NotificationKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit_id: Option<String>,
+    /// Held back (e.g. by DND) instead of shown
     #[serde(default)]
-    pub dnd_end_time: Option<String>,
+    pub suppressed: bool,
+}
+
+/// A notification that would have fired while replaying a usage trajectory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulatedNotification {
+    /// Timestamp of the snapshot that triggered it
+    pub at: DateTime<Utc>,
+    pub limit_id: String,
+    pub kind: SimulatedNotificationKind,
+    /// Threshold crossed (threshold alerts only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub threshold: Option<u32>,
+    pub urgency: NotificationUrgency,
+    pub title: String,
+    pub body: String,
+}
+
+fn default_critical_threshold() -> u32 {
+    90
+}
+
+fn default_reset_warning_lead_minutes() -> u32 {
+    60
+}
+
+fn default_reset_warning_min_percent() -> u32 {
+    75
+}
+
+fn default_critical_sound() -> Option<String> {
+    Some("default".to_string())
+}
+
+fn default_smtp_port() -> u16 {
+    587
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -169,8 +1267,50 @@ pub struct UsageHistoryEntry {
 #[serde(rename_all = "camelCase")]
 pub struct UsageLimitSnapshot {
     pub id: String,
+    /// Utilization at this time, or the bucket average for compacted entries
     pub utilization: f64,
     pub resets_at: DateTime<Utc>,
+    /// Lowest utilization in the bucket (compacted entries only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_utilization: Option<f64>,
+    /// Highest utilization in the bucket (compacted entries only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_utilization: Option<f64>,
+}
+
+/// A limit's total changing between fetches, so percentages before and after aren't comparable
+///
+/// Payload of `quota-changed`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuotaChange {
+    pub account_id: String,
+    pub limit_id: String,
+    pub old_total: f64,
+    pub new_total: f64,
+    pub detected_at: DateTime<Utc>,
+}
+
+/// An event kept alongside history so charts can mark it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum HistoryAnnotation {
+    /// A limit's total changed (plan upgrade or downgrade)
+    QuotaChanged(QuotaChange),
+}
+
+impl HistoryAnnotation {
+    pub fn account_id(&self) -> &str {
+        match self {
+            Self::QuotaChanged(change) => &change.account_id,
+        }
+    }
+
+    pub fn at(&self) -> DateTime<Utc> {
+        match self {
+            Self::QuotaChanged(change) => change.detected_at,
+        }
+    }
 }
 
 /// History storage metadata
@@ -221,6 +1361,246 @@ pub struct UsageStats {
     pub sample_count: usize,
 }
 
+/// Statistics for a single limit over an account's history window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LimitUsageStats {
+    pub limit_id: String,
+    pub avg_utilization: f64,
+    pub max_utilization: f64,
+    pub min_utilization: f64,
+    /// Hour of day (0-23, UTC) with the highest average utilization
+    pub peak_hour: Option<u32>,
+    /// Number of resets observed within the window
+    pub reset_count: usize,
+    /// Number of snapshots that included this limit
+    pub sample_count: usize,
+}
+
+/// Aggregated usage statistics for one account over a window of days
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountUsageStats {
+    pub account_id: String,
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    /// Number of history snapshots found in the window (may be sparse)
+    pub sample_count: usize,
+    pub limits: Vec<LimitUsageStats>,
+}
+
+/// A limit that crossed a notification threshold since the account was last viewed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThresholdCrossing {
+    pub limit_id: String,
+    /// Highest threshold crossed
+    pub threshold: u32,
+    pub utilization: f64,
+}
+
+/// Usage changes for an account that the user has not seen yet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnseenChange {
+    pub account_id: String,
+    pub account_name: String,
+    /// Timestamp of the newest history snapshot
+    pub latest_snapshot_at: DateTime<Utc>,
+    /// When the account was last viewed (None if never)
+    pub last_viewed_at: Option<DateTime<Utc>>,
+    pub crossings: Vec<ThresholdCrossing>,
+}
+
+/// Window over which spend is estimated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpendPeriod {
+    Day,
+    Week,
+    Month,
+}
+
+impl SpendPeriod {
+    pub fn days(self) -> i64 {
+        match self {
+            SpendPeriod::Day => 1,
+            SpendPeriod::Week => 7,
+            SpendPeriod::Month => 30,
+        }
+    }
+}
+
+/// Estimated spend for a single limit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LimitSpend {
+    pub limit_id: String,
+    /// Percentage points consumed over the period, counting usage across resets
+    pub consumed_units: f64,
+    /// Configured cost per unit (None when unpriced)
+    pub unit_cost: Option<f64>,
+    /// Estimated cost (None when unpriced)
+    pub cost: Option<f64>,
+}
+
+/// Spend estimate for one account over a period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpendEstimate {
+    pub account_id: String,
+    pub provider: String,
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    pub limits: Vec<LimitSpend>,
+    /// Sum over priced limits only
+    pub total_cost: f64,
+    /// Limits with no configured pricing
+    pub unpriced_limits: Vec<String>,
+}
+
+/// Peak utilization of one limit on one local calendar day
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DayBucket {
+    pub date: NaiveDate,
+    /// Highest utilization seen that day; None when there is no history for the day
+    pub peak_utilization: Option<f64>,
+    pub sample_count: usize,
+}
+
+/// How consistent the observed intervals between resets are
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScheduleConfidence {
+    High,
+    Medium,
+    Low,
+    /// Too few resets in history to infer a cadence
+    Unknown,
+}
+
+/// Recurring reset cadence of one limit, inferred from history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResetSchedule {
+    pub account_id: String,
+    pub limit_id: String,
+    /// Distinct reset times seen in history, oldest first
+    pub observed_resets: Vec<DateTime<Utc>>,
+    /// Median time between consecutive resets (None when Unknown)
+    pub interval_secs: Option<i64>,
+    /// Upcoming reset times, starting with the next one (empty when Unknown)
+    pub predicted_resets: Vec<DateTime<Utc>>,
+    /// `predicted_resets` formatted in the display time zone
+    #[serde(default)]
+    pub predicted_resets_display: Vec<String>,
+    pub confidence: ScheduleConfidence,
+}
+
+/// Estimate of how long a limit stays usable after its next reset, from recent burn rate
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveryForecast {
+    pub account_id: String,
+    pub limit_id: String,
+    /// Too little recent history to estimate a burn rate; the estimates below are None
+    pub insufficient_data: bool,
+    /// Next reset, from the latest snapshot
+    pub resets_at: Option<DateTime<Utc>>,
+    /// Average consumption over the lookback, in percentage points per hour
+    pub burn_rate_per_hour: Option<f64>,
+    /// Time from the reset until the cap is reached again (None if usage isn't growing)
+    pub headroom_secs: Option<i64>,
+    pub recaps_at: Option<DateTime<Utc>>,
+    /// Whether the cap is reached again before the window starting at the reset ends
+    pub recaps_before_next_reset: Option<bool>,
+}
+
+/// How far an account is into its monthly budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BudgetLevel {
+    Under,
+    Warning,
+    Exceeded,
+}
+
+/// Consumption so far this calendar month against an account's budget
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetStatus {
+    pub account_id: String,
+    /// Local midnight on the first of the month, as UTC
+    pub month_start: DateTime<Utc>,
+    /// Percentage points consumed across all limits since `month_start`
+    pub consumed: f64,
+    pub budget: f64,
+    pub warning_percent: u32,
+    pub level: BudgetLevel,
+}
+
+/// Identifier of a saved app snapshot (its creation time, e.g. `20250101-120000-000`)
+pub type SnapshotId = String;
+
+/// Why a snapshot was taken
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SnapshotReason {
+    /// Requested by the user; never pruned
+    Manual,
+    /// Taken automatically before a credentials store migration
+    PreMigration,
+    /// Taken automatically before restoring another snapshot
+    PreRestore,
+    /// Taken automatically before re-signing a credentials store that failed its integrity check
+    PreResign,
+}
+
+impl SnapshotReason {
+    pub fn is_automatic(self) -> bool {
+        self != SnapshotReason::Manual
+    }
+}
+
+/// Summary of a saved snapshot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotInfo {
+    pub id: SnapshotId,
+    pub created_at: DateTime<Utc>,
+    pub reason: SnapshotReason,
+    pub account_count: usize,
+    pub history_entry_count: usize,
+}
+
+/// Shareable usage report with account names, IDs and limit IDs anonymized
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnonymizedReport {
+    pub generated_at: DateTime<Utc>,
+    pub days: u32,
+    pub accounts: Vec<AnonymizedAccount>,
+}
+
+/// One account in an anonymized report, labelled "Account N"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnonymizedAccount {
+    pub label: String,
+    pub provider: String,
+    pub points: Vec<AnonymizedPoint>,
+}
+
+/// Utilization of each (hashed) limit at one point in time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnonymizedPoint {
+    pub timestamp: DateTime<Utc>,
+    /// Hashed limit ID -> utilization
+    pub limits: BTreeMap<String, f64>,
+}
+
 /// Data retention policy
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -245,9 +1625,15 @@ impl Default for AppSettings {
         Self {
             theme: "dark".to_string(),
             language: "en".to_string(),
+            locale: "en".to_string(),
             launch_at_startup: false,
             refresh_mode: "adaptive".to_string(),
             refresh_interval: 300,
+            immediate_fetch_on_start: true,
+            autostart_scheduler: true,
+            start_minimized_to_tray: false,
+            fetch_stagger_ms: 2000,
+            cap_threshold: 100.0,
             tray_display_limit: "highest".to_string(),
             global_shortcut: None,
             notifications: NotificationSettings {
@@ -258,6 +1644,17 @@ impl Default for AppSettings {
                 dnd_enabled: false,
                 dnd_start_time: Some("22:00".to_string()),
                 dnd_end_time: Some("08:00".to_string()),
+                dnd_schedule: DndSchedule::default(),
+                disabled_limit_ids: HashSet::new(),
+                critical_threshold: 90,
+                critical_sound: Some("default".to_string()),
+                email: None,
+                log_suppressed: false,
+                absolute_thresholds: HashMap::new(),
+                reset_warning_lead_minutes: default_reset_warning_lead_minutes(),
+                reset_warning_min_percent: default_reset_warning_min_percent(),
+                reset_warning_overrides: HashMap::new(),
+                digest_mode: false,
             },
             providers: vec![ProviderConfig {
                 id: "claude".to_string(),
@@ -267,38 +1664,437 @@ impl Default for AppSettings {
             api_server_enabled: false,
             api_server_port: 31415,
             api_server_token: None,
+            websocket_enabled: false,
+            websocket_port: 31416,
+            websocket_token: None,
+            allow_env_credentials: false,
+            pricing: HashMap::new(),
+            smoothing_factor: None,
+            primary_account_id: None,
+            compress_history: false,
+            monthly_budgets: HashMap::new(),
+            budget_warning_percent: default_budget_warning_percent(),
+            limit_order: Vec::new(),
+            quiet_when_offline: false,
+            display_timezone: default_display_timezone(),
+            max_concurrent_requests: default_max_concurrent_requests(),
+            cached_responses_per_account: default_cached_responses_per_account(),
+            always_on_top: false,
+            mini_mode: false,
+            refresh_interval_bounds: None,
         }
     }
 }
 
+// ============================================================================
+// Diagnostics Models
+// ============================================================================
+
+/// Outcome of a single diagnostic check, ordered from best to worst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagnosticStatus {
+    Ok,
+    Warn,
+    Fail,
+}
+
+/// One check in a diagnostics report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticCheck {
+    pub name: String,
+    pub status: DiagnosticStatus,
+    pub message: String,
+}
+
+/// Result of `run_diagnostics`; contains no credentials or tokens
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticsReport {
+    pub generated_at: DateTime<Utc>,
+    pub app_version: String,
+    /// Worst status across all checks
+    pub overall: DiagnosticStatus,
+    pub checks: Vec<DiagnosticCheck>,
+    /// Plain-text rendering for pasting into bug reports
+    pub summary: String,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Usage for `acc-1` with `(id, utilization, total)` limits, all resetting as it is fetched
+    fn usage_with_limits(limits: &[(&str, f64, Option<f64>)]) -> UsageData {
+        let now = Utc::now();
+        UsageData {
+            provider: "claude".to_string(),
+            account_id: "acc-1".to_string(),
+            account_name: "Personal".to_string(),
+            timestamp: now,
+            limits: limits
+                .iter()
+                .map(|(id, utilization, total)| UsageLimit {
+                    id: id.to_string(),
+                    label: id.to_string(),
+                    utilization: *utilization,
+                    resets_at: now,
+                    category: None,
+                    kind: LimitKind::Unknown,
+                    total: *total,
+                })
+                .collect(),
+            raw: None,
+            rate_limit: None,
+        }
+    }
+
     #[test]
     fn credentials_default() {
         let creds = Credentials::default();
-        assert!(creds.org_id.is_none());
-        assert!(creds.session_key.is_none());
+        let claude = creds.claude().unwrap();
+        assert!(claude.org_id.is_none());
+        assert!(claude.session_key.is_none());
     }
 
     #[test]
     fn credentials_serialization() {
-        let creds = Credentials {
+        let creds = Credentials::Claude(ClaudeCredentials {
             org_id: Some("org-123".to_string()),
             session_key: Some("sk-xxx".to_string()),
-        };
+            session_keys: Vec::new(),
+            oauth: None,
+        });
         let json = serde_json::to_string(&creds).unwrap();
+        assert!(json.starts_with(r#"{"type":"claude","#));
         assert!(json.contains("org_id"));
         assert!(json.contains("session_key"));
     }
 
     #[test]
     fn credentials_deserialization() {
+        let json = r#"{"type":"claude","org_id":"org-123","session_key":"sk-xxx"}"#;
+        let creds: Credentials = serde_json::from_str(json).unwrap();
+        let claude = creds.claude().unwrap();
+        assert_eq!(claude.org_id, Some("org-123".to_string()));
+        assert_eq!(claude.session_key, Some("sk-xxx".to_string()));
+    }
+
+    #[test]
+    fn untagged_credentials_read_as_claude() {
         let json = r#"{"org_id":"org-123","session_key":"sk-xxx"}"#;
         let creds: Credentials = serde_json::from_str(json).unwrap();
-        assert_eq!(creds.org_id, Some("org-123".to_string()));
-        assert_eq!(creds.session_key, Some("sk-xxx".to_string()));
+        assert_eq!(creds.provider(), "claude");
+        assert_eq!(creds.claude().unwrap().org_id.as_deref(), Some("org-123"));
+    }
+
+    #[test]
+    fn each_credentials_variant_round_trips() {
+        let variants = [
+            Credentials::Claude(ClaudeCredentials {
+                org_id: Some("org-123".to_string()),
+                session_key: Some("sk-a".to_string()),
+                session_keys: vec!["sk-a".to_string(), "sk-b".to_string()],
+                oauth: None,
+            }),
+            Credentials::Codex(CodexCredentials {
+                api_key: Some("sk-proj-abc".to_string()),
+            }),
+            Credentials::Gemini(GeminiCredentials {
+                api_key: Some("AIza-abc".to_string()),
+                project_id: Some("my-project".to_string()),
+            }),
+        ];
+
+        for creds in variants {
+            let json = serde_json::to_value(&creds).unwrap();
+            assert_eq!(json["type"], creds.provider());
+
+            let parsed: Credentials = serde_json::from_value(json.clone()).unwrap();
+            assert_eq!(parsed.provider(), creds.provider());
+            assert_eq!(serde_json::to_value(&parsed).unwrap(), json);
+        }
+
+        let unknown = serde_json::json!({ "type": "mistral", "api_key": "x" });
+        assert!(serde_json::from_value::<Credentials>(unknown).is_err());
+    }
+
+    #[test]
+    fn session_key_candidates_fall_back_to_single_key() {
+        let creds = ClaudeCredentials {
+            org_id: None,
+            session_key: Some("sk-only".to_string()),
+            session_keys: Vec::new(),
+            oauth: None,
+        };
+        assert_eq!(creds.session_key_candidates(), vec!["sk-only"]);
+    }
+
+    #[test]
+    fn sync_session_keys_moves_edited_key_to_front() {
+        let mut creds = ClaudeCredentials {
+            org_id: None,
+            session_key: Some("sk-b".to_string()),
+            session_keys: vec!["sk-a".to_string(), "sk-b".to_string(), "sk-c".to_string()],
+            oauth: None,
+        };
+        creds.sync_session_keys();
+        assert_eq!(creds.session_keys, vec!["sk-b", "sk-a", "sk-c"]);
+    }
+
+    #[test]
+    fn promote_session_key_demotes_stale_keys() {
+        let mut creds = ClaudeCredentials {
+            org_id: None,
+            session_key: Some("sk-a".to_string()),
+            session_keys: vec!["sk-a".to_string(), "sk-b".to_string(), "sk-c".to_string()],
+            oauth: None,
+        };
+        creds.promote_session_key(1);
+        assert_eq!(creds.session_keys, vec!["sk-b", "sk-c", "sk-a"]);
+        assert_eq!(creds.session_key.as_deref(), Some("sk-b"));
+    }
+
+    #[test]
+    fn oauth_token_near_expiry_needs_refresh() {
+        let now = Utc::now();
+        let margin = chrono::Duration::minutes(5);
+        let oauth = |expires_in: chrono::Duration| OAuthCredentials {
+            access_token: "access".to_string(),
+            refresh_token: "refresh".to_string(),
+            expires_at: now + expires_in,
+        };
+
+        assert!(!oauth(chrono::Duration::hours(1)).needs_refresh(now, margin));
+        assert!(oauth(chrono::Duration::minutes(4)).needs_refresh(now, margin));
+        assert!(oauth(chrono::Duration::minutes(-10)).needs_refresh(now, margin));
+    }
+
+    #[test]
+    fn capped_limits_mixed() {
+        let usage = usage_with_limits(&[
+            ("five_hour", 100.0, None),
+            ("seven_day", 64.0, None),
+            ("seven_day_opus", 103.5, None),
+        ]);
+
+        let capped = usage.capped_limits(100.0);
+        let ids: Vec<&str> = capped.iter().map(|l| l.limit_id.as_str()).collect();
+        assert_eq!(ids, vec!["five_hour", "seven_day_opus"]);
+
+        // A lower cap threshold catches limits that are close to full
+        assert_eq!(usage.capped_limits(60.0).len(), 3);
+        assert!(usage.capped_limits(110.0).is_empty());
+    }
+
+    #[test]
+    fn limits_resetting_within_window() {
+        let mut usage = usage_with_limits(&[
+            ("five_hour", 50.0, None),
+            ("seven_day", 50.0, None),
+            ("seven_day_opus", 50.0, None),
+            ("stale", 50.0, None),
+        ]);
+        let now = usage.timestamp;
+        let resets_in = [
+            chrono::Duration::hours(3),
+            chrono::Duration::days(4),
+            chrono::Duration::hours(8),
+            // Already past: the cached value is stale, not an upcoming reset
+            chrono::Duration::minutes(-5),
+        ];
+        for (limit, resets_in) in usage.limits.iter_mut().zip(resets_in) {
+            limit.resets_at = now + resets_in;
+        }
+
+        let overnight = usage.limits_resetting_within(now, now + chrono::Duration::hours(8));
+        assert_eq!(overnight, vec!["five_hour", "seven_day_opus"]);
+        assert_eq!(
+            usage.limits_resetting_within(now, now + chrono::Duration::hours(1)),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            usage
+                .limits_resetting_within(now, now + chrono::Duration::days(7))
+                .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn quota_changes_detect_increase_and_decrease() {
+        let before = usage_with_limits(&[
+            ("five_hour", 40.0, Some(45.0)),
+            ("seven_day", 40.0, Some(1000.0)),
+            ("seven_day_opus", 40.0, None),
+        ]);
+
+        // Upgrade: five_hour grows, seven_day unchanged, opus starts reporting a total
+        let upgraded = usage_with_limits(&[
+            ("five_hour", 40.0, Some(225.0)),
+            ("seven_day", 40.0, Some(1000.0)),
+            ("seven_day_opus", 40.0, Some(50.0)),
+        ]);
+        assert_eq!(
+            upgraded.quota_changes(&before),
+            vec![QuotaChange {
+                account_id: "acc-1".to_string(),
+                limit_id: "five_hour".to_string(),
+                old_total: 45.0,
+                new_total: 225.0,
+                detected_at: upgraded.timestamp,
+            }]
+        );
+
+        // Downgrade
+        let downgraded = usage_with_limits(&[
+            ("five_hour", 40.0, Some(45.0)),
+            ("seven_day", 40.0, Some(500.0)),
+        ]);
+        let changes = downgraded.quota_changes(&upgraded);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[1].limit_id, "seven_day");
+        assert_eq!(changes[1].old_total, 1000.0);
+        assert_eq!(changes[1].new_total, 500.0);
+
+        assert!(before.quota_changes(&before).is_empty());
+    }
+
+    #[test]
+    fn badge_color_follows_usage_bands() {
+        let badge = |utilization: f64| {
+            BadgeData::for_limit(&UsageLimit {
+                id: "five_hour".to_string(),
+                label: "Session".to_string(),
+                utilization,
+                resets_at: Utc::now(),
+                category: None,
+                kind: LimitKind::Unknown,
+                total: None,
+            })
+        };
+
+        assert_eq!(badge(0.0).color, "22c55e");
+        assert_eq!(badge(49.9).color, "22c55e");
+        assert_eq!(badge(50.0).color, "eab308");
+        assert_eq!(badge(74.9).color, "eab308");
+        assert_eq!(badge(75.0).color, "f97316");
+        assert_eq!(badge(89.9).color, "f97316");
+        assert_eq!(badge(90.0).color, "ef4444");
+        assert_eq!(badge(104.0).color, "ef4444");
+
+        let json = serde_json::to_value(badge(62.4)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "schemaVersion": 1,
+                "label": "Session",
+                "message": "62%",
+                "color": "eab308",
+            })
+        );
+    }
+
+    #[test]
+    fn proceed_check_reports_tightest_limit() {
+        let usage = usage_with_limits(&[("five_hour", 40.0, None), ("seven_day", 75.0, None)]);
+
+        let now = usage.timestamp + chrono::Duration::seconds(60);
+
+        // 25 points left on the weekly limit
+        let sufficient = usage.proceed_check(100.0, 20.0, 300, now);
+        assert!(sufficient.can_proceed);
+        assert_eq!(sufficient.blocking_limit_id, None);
+        assert_eq!(sufficient.min_headroom, Some(25.0));
+        assert_eq!(sufficient.data_age_secs, Some(60));
+        assert!(!sufficient.stale);
+
+        let insufficient = usage.proceed_check(100.0, 30.0, 300, now);
+        assert!(!insufficient.can_proceed);
+        assert_eq!(insufficient.blocking_limit_id.as_deref(), Some("seven_day"));
+
+        // A lower cap leaves less room
+        assert!(!usage.proceed_check(90.0, 20.0, 300, now).can_proceed);
+
+        // Usage older than the refresh interval can't be trusted, whatever the headroom
+        let stale = usage.proceed_check(100.0, 20.0, 30, now);
+        assert!(stale.stale);
+        assert!(!stale.can_proceed);
+        assert_eq!(stale.blocking_limit_id, None);
+    }
+
+    #[test]
+    fn order_limits_pins_listed_ids_first() {
+        let mut usage = usage_with_limits(&[
+            ("five_hour", 10.0, None),
+            ("seven_day", 10.0, None),
+            ("seven_day_opus", 10.0, None),
+            ("seven_day_sonnet", 10.0, None),
+        ]);
+
+        // "retired_limit" no longer exists and is skipped
+        usage.order_limits(&[
+            "seven_day_opus".to_string(),
+            "retired_limit".to_string(),
+            "five_hour".to_string(),
+        ]);
+
+        let ids: Vec<&str> = usage.limits.iter().map(|l| l.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "seven_day_opus",
+                "five_hour",
+                "seven_day",
+                "seven_day_sonnet"
+            ]
+        );
+    }
+
+    #[test]
+    fn align_accounts_with_overlapping_and_disjoint_limits() {
+        let usage = |account_id: &str, limits: &[(&str, f64, Option<f64>)]| UsageData {
+            account_id: account_id.to_string(),
+            account_name: account_id.to_uppercase(),
+            ..usage_with_limits(limits)
+        };
+
+        let rows = AccountUsageRow::align(&[
+            (
+                "a".to_string(),
+                Some(usage(
+                    "a",
+                    &[("five_hour", 40.0, None), ("seven_day_opus", 0.0, None)],
+                )),
+            ),
+            (
+                "b".to_string(),
+                Some(usage(
+                    "b",
+                    &[("five_hour", 75.0, None), ("seven_day", 20.0, None)],
+                )),
+            ),
+            ("c".to_string(), None),
+        ]);
+
+        assert_eq!(rows.len(), 3);
+        for row in &rows {
+            let ids: Vec<&str> = row.limits.iter().map(|l| l.limit_id.as_str()).collect();
+            assert_eq!(ids, vec!["five_hour", "seven_day_opus", "seven_day"]);
+        }
+
+        let values = |row: &AccountUsageRow| -> Vec<Option<f64>> {
+            row.limits.iter().map(|l| l.utilization).collect()
+        };
+        // A real 0% stays distinct from a limit the account doesn't have
+        assert_eq!(values(&rows[0]), vec![Some(40.0), Some(0.0), None]);
+        assert_eq!(values(&rows[1]), vec![Some(75.0), None, Some(20.0)]);
+        assert_eq!(values(&rows[2]), vec![None, None, None]);
+
+        assert_eq!(rows[1].account_name.as_deref(), Some("B"));
+        assert!(rows[2].account_name.is_none());
+        assert!(rows[2].fetched_at.is_none());
     }
 
     #[test]
@@ -313,17 +2109,30 @@ mod tests {
         let settings = AppSettings::default();
         assert_eq!(settings.theme, "dark");
         assert_eq!(settings.language, "en");
+        assert_eq!(settings.locale, "en");
         assert!(!settings.launch_at_startup);
         assert_eq!(settings.refresh_mode, "adaptive");
         assert_eq!(settings.refresh_interval, 300);
+        assert!(settings.immediate_fetch_on_start);
+        assert_eq!(settings.fetch_stagger_ms, 2000);
+        assert!(!settings.quiet_when_offline);
+        assert_eq!(settings.display_timezone, "local");
+        assert_eq!(settings.max_concurrent_requests, 4);
+        assert_eq!(settings.cached_responses_per_account, 10);
+        assert_eq!(settings.cap_threshold, 100.0);
         assert_eq!(settings.tray_display_limit, "highest");
         assert!(settings.notifications.enabled);
         assert_eq!(settings.notifications.thresholds, vec![50, 75, 90]);
+        assert_eq!(settings.notifications.critical_threshold, 90);
         assert_eq!(settings.providers.len(), 1);
         // API server settings
         assert!(!settings.api_server_enabled);
         assert_eq!(settings.api_server_port, 31415);
         assert!(settings.api_server_token.is_none());
+        assert!(!settings.websocket_enabled);
+        assert_eq!(settings.websocket_port, 31416);
+        assert!(!settings.allow_env_credentials);
+        assert!(settings.pricing.is_empty());
     }
 
     #[test]
@@ -1,32 +1,182 @@
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use reqwest::header::{HeaderMap, HeaderValue, COOKIE, ORIGIN, REFERER, USER_AGENT};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, COOKIE, ORIGIN, REFERER, USER_AGENT};
+use std::collections::BTreeMap;
+use std::sync::Arc;
 
 use crate::error::ProviderError;
-use crate::models::{ClaudeUsageResponse, Credentials, UsageData, UsageLimit};
-use crate::providers::UsageProvider;
+use crate::models::{
+    AccountInfo, ClaudeCredentials, ClaudeOrganization, ClaudeUsageResponse, Credentials,
+    LimitKind, OAuthCredentials, OAuthTokenResponse, RateLimitInfo, UsageData, UsageLimit,
+};
+use crate::providers::{
+    CredentialField, ProviderCapabilities, ProviderRegistration, UsageProvider,
+};
 
 const CLAUDE_API_BASE: &str = "https://claude.ai/api";
 
+/// Usage and token endpoints for OAuth-authenticated accounts
+const CLAUDE_OAUTH_API_BASE: &str = "https://api.anthropic.com";
+const CLAUDE_OAUTH_TOKEN_URL: &str = "https://console.anthropic.com/v1/oauth/token";
+const CLAUDE_OAUTH_CLIENT_ID: &str = "9d1c250a-e61b-44d9-88ed-5944d1962f5e";
+const CLAUDE_OAUTH_BETA: &str = "oauth-2025-04-20";
+
+/// Access tokens expiring within this many seconds are refreshed before use
+const OAUTH_REFRESH_MARGIN_SECS: i64 = 300;
+
+/// Environment variables consulted when `allow_env_credentials` is on
+pub const ENV_ORG_ID: &str = "ANTHROPIC_ORG_ID";
+pub const ENV_SESSION_KEY: &str = "ANTHROPIC_SESSION_KEY";
+
+/// Rate-limit headers, tried in order: Anthropic's own names, then the common `x-ratelimit-*` ones
+const RATE_LIMIT_LIMIT_HEADERS: [&str; 2] =
+    ["anthropic-ratelimit-requests-limit", "x-ratelimit-limit"];
+const RATE_LIMIT_REMAINING_HEADERS: [&str; 2] = [
+    "anthropic-ratelimit-requests-remaining",
+    "x-ratelimit-remaining",
+];
+const RATE_LIMIT_RESET_HEADERS: [&str; 2] =
+    ["anthropic-ratelimit-requests-reset", "x-ratelimit-reset"];
+
+/// Reset values above this are unix timestamps; smaller ones are seconds from now
+const MIN_RESET_TIMESTAMP: i64 = 1_000_000_000;
+
+/// Bytes of body kept on each side of where parsing stopped
+const PARSE_SNIPPET_CONTEXT: usize = 40;
+
+/// Headers an account's `extra_headers` may not set: the session cookie, headers the
+/// HTTP client manages, and the browser headers Cloudflare checks
+const RESERVED_HEADERS: [&str; 8] = [
+    "cookie",
+    "host",
+    "content-length",
+    "transfer-encoding",
+    "connection",
+    "origin",
+    "referer",
+    "user-agent",
+];
+
+#[derive(Clone)]
 pub struct ClaudeProvider {
     client: reqwest::Client,
     base_url: String,
+    oauth_base_url: String,
+    oauth_token_url: String,
+    /// Per-account headers merged into every request
+    extra_headers: HeaderMap,
+}
+
+inventory::submit! {
+    ProviderRegistration::new(|| Ok(Arc::new(ClaudeProvider::new()?)))
 }
 
 impl ClaudeProvider {
     pub fn new() -> Result<Self, ProviderError> {
-        Self::with_base_url(CLAUDE_API_BASE)
+        let mut provider = Self::with_base_url(CLAUDE_API_BASE)?;
+        provider.oauth_base_url = CLAUDE_OAUTH_API_BASE.to_string();
+        provider.oauth_token_url = CLAUDE_OAUTH_TOKEN_URL.to_string();
+        Ok(provider)
     }
 
     /// Create a provider with a custom base URL (for testing)
+    ///
+    /// OAuth usage and token requests go to the same server.
     pub fn with_base_url(base_url: &str) -> Result<Self, ProviderError> {
-        let client = reqwest::Client::builder()
-            .build()
-            .map_err(|e| ProviderError::HttpError(e.to_string()))?;
+        let client = reqwest::Client::builder().build()?;
 
         Ok(Self {
             client,
             base_url: base_url.to_string(),
+            oauth_base_url: base_url.to_string(),
+            oauth_token_url: format!("{}/v1/oauth/token", base_url),
+            extra_headers: HeaderMap::new(),
+        })
+    }
+
+    /// Send `extra` with every request, dropping reserved or malformed headers
+    pub fn with_extra_headers(mut self, extra: &BTreeMap<String, String>) -> Self {
+        self.extra_headers = Self::sanitize_extra_headers(extra);
+        self
+    }
+
+    fn sanitize_extra_headers(extra: &BTreeMap<String, String>) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+
+        for (name, value) in extra {
+            let Ok(header_name) = HeaderName::from_bytes(name.trim().as_bytes()) else {
+                log::warn!("Ignoring extra header with invalid name: {:?}", name);
+                continue;
+            };
+
+            // HeaderName is always lowercase
+            if RESERVED_HEADERS.contains(&header_name.as_str()) {
+                log::warn!(
+                    "Ignoring extra header {}: it can't be overridden",
+                    header_name
+                );
+                continue;
+            }
+
+            let Ok(header_value) = HeaderValue::from_str(value.trim()) else {
+                log::warn!("Ignoring extra header {}: invalid value", header_name);
+                continue;
+            };
+
+            headers.insert(header_name, header_value);
+        }
+
+        headers
+    }
+
+    /// Fill in missing credential fields from the environment
+    ///
+    /// Each field resolves as: stored value, then `env` (only when `allow_env`
+    /// is set), then a `MissingCredentials` error. `env` is injected so the
+    /// precedence can be tested without touching the process environment.
+    pub fn resolve_credentials(
+        stored: &Credentials,
+        allow_env: bool,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Credentials, ProviderError> {
+        let from_env = |name: &str| {
+            if !allow_env {
+                return None;
+            }
+            env(name).map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+        };
+
+        let mut resolved = Self::claude_credentials(stored)?.clone();
+
+        // OAuth tokens need neither an org ID nor a session key
+        if resolved.oauth.is_some() {
+            return Ok(Credentials::Claude(resolved));
+        }
+
+        if resolved.org_id.as_ref().map_or(true, |s| s.trim().is_empty()) {
+            resolved.org_id = Some(
+                from_env(ENV_ORG_ID)
+                    .ok_or_else(|| ProviderError::MissingCredentials("org_id".to_string()))?,
+            );
+        }
+
+        if resolved.session_key_candidates().is_empty() {
+            resolved.session_key = Some(
+                from_env(ENV_SESSION_KEY)
+                    .ok_or_else(|| ProviderError::MissingCredentials("session_key".to_string()))?,
+            );
+        }
+
+        Ok(Credentials::Claude(resolved))
+    }
+
+    /// The Claude variant of `credentials`, or `InvalidCredentials` for another provider's
+    fn claude_credentials(credentials: &Credentials) -> Result<&ClaudeCredentials, ProviderError> {
+        credentials.claude().ok_or_else(|| {
+            ProviderError::InvalidCredentials(format!(
+                "expected Claude credentials, got {}",
+                credentials.provider()
+            ))
         })
     }
 
@@ -79,9 +229,426 @@ impl ClaudeProvider {
             HeaderValue::from_static("en-US,en;q=0.9"),
         );
 
+        // Per-account headers (already stripped of reserved names)
+        for (name, value) in &self.extra_headers {
+            headers.insert(name.clone(), value.clone());
+        }
+
         headers
     }
 
+    /// Fetch usage, trying each session key in turn until one is accepted
+    ///
+    /// Returns the usage along with the index of the key that worked, so the
+    /// caller can promote it. Only `SessionExpired` moves on to the next key;
+    /// any other error is returned immediately.
+    pub async fn fetch_usage_with_failover(
+        &self,
+        credentials: &Credentials,
+    ) -> Result<(UsageData, usize), ProviderError> {
+        let credentials = Self::claude_credentials(credentials)?;
+
+        if let Some(oauth) = &credentials.oauth {
+            return self
+                .fetch_with_access_token(&oauth.access_token)
+                .await
+                .map(|usage| (usage, 0));
+        }
+
+        let org_id = credentials
+            .org_id
+            .as_ref()
+            .ok_or_else(|| ProviderError::MissingCredentials("org_id".to_string()))?;
+
+        let session_keys = credentials.session_key_candidates();
+        if session_keys.is_empty() {
+            return Err(ProviderError::MissingCredentials("session_key".to_string()));
+        }
+
+        for (index, session_key) in session_keys.iter().enumerate() {
+            match self.fetch_with_session_key(org_id, session_key).await {
+                Ok(usage) => return Ok((usage, index)),
+                Err(ProviderError::SessionExpired) => {
+                    log::warn!(
+                        "Session key {}/{} was rejected, marking it stale",
+                        index + 1,
+                        session_keys.len()
+                    );
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(ProviderError::SessionExpired)
+    }
+
+    /// Exchange the refresh token for a new token pair if the access token is about to expire
+    ///
+    /// Returns None when the current access token is still good. Refresh tokens are
+    /// single-use, so the caller must persist the returned pair.
+    pub async fn refresh_oauth_if_needed(
+        &self,
+        oauth: &OAuthCredentials,
+        now: DateTime<Utc>,
+    ) -> Result<Option<OAuthCredentials>, ProviderError> {
+        if !oauth.needs_refresh(now, chrono::Duration::seconds(OAUTH_REFRESH_MARGIN_SECS)) {
+            return Ok(None);
+        }
+
+        log::info!(
+            "Claude OAuth access token expires at {}, refreshing",
+            oauth.expires_at
+        );
+
+        let response = self
+            .client
+            .post(&self.oauth_token_url)
+            .json(&serde_json::json!({
+                "grant_type": "refresh_token",
+                "refresh_token": oauth.refresh_token,
+                "client_id": CLAUDE_OAUTH_CLIENT_ID,
+            }))
+            .send()
+            .await?;
+
+        let status = response.status();
+        match status.as_u16() {
+            200 => {
+                let body: OAuthTokenResponse = response
+                    .json()
+                    .await
+                    .map_err(|e| ProviderError::ParseError(e.to_string()))?;
+                let expires_at = chrono::Duration::try_seconds(body.expires_in)
+                    .and_then(|lifetime| now.checked_add_signed(lifetime))
+                    .ok_or_else(|| {
+                        ProviderError::ParseError(format!(
+                            "OAuth token expires_in out of range: {}",
+                            body.expires_in
+                        ))
+                    })?;
+
+                Ok(Some(OAuthCredentials {
+                    access_token: body.access_token,
+                    refresh_token: body
+                        .refresh_token
+                        .unwrap_or_else(|| oauth.refresh_token.clone()),
+                    expires_at,
+                }))
+            }
+            // The refresh token was revoked or already used
+            400 | 401 => Err(ProviderError::SessionExpired),
+            429 => Err(ProviderError::RateLimited),
+            _ => {
+                let body = response.text().await.unwrap_or_default();
+                Err(ProviderError::HttpError(format!(
+                    "Unexpected status {} refreshing OAuth token: {}",
+                    status, body
+                )))
+            }
+        }
+    }
+
+    /// Make a single usage request with an OAuth access token
+    async fn fetch_with_access_token(
+        &self,
+        access_token: &str,
+    ) -> Result<UsageData, ProviderError> {
+        let url = format!("{}/api/oauth/usage", self.oauth_base_url);
+
+        log::info!("Fetching Claude usage from: {}", url);
+
+        let response = self
+            .client
+            .get(&url)
+            .headers(self.extra_headers.clone())
+            .bearer_auth(access_token)
+            .header("anthropic-beta", CLAUDE_OAUTH_BETA)
+            .send()
+            .await?;
+
+        let status = response.status();
+        log::info!("Claude API response status: {}", status);
+        let rate_limit = Self::parse_rate_limit(response.headers(), Utc::now());
+
+        match status.as_u16() {
+            200 => {
+                let text = response.text().await?;
+                let body: ClaudeUsageResponse =
+                    serde_json::from_str(&text).map_err(|e| Self::body_parse_error(&text, e))?;
+
+                let mut usage = self.parse_response(body)?;
+                usage.rate_limit = rate_limit;
+                Ok(usage)
+            }
+            401 => Err(ProviderError::SessionExpired),
+            429 => Err(ProviderError::RateLimited),
+            _ => {
+                let body = response.text().await.unwrap_or_default();
+                Err(ProviderError::HttpError(format!(
+                    "Unexpected status {}: {}",
+                    status, body
+                )))
+            }
+        }
+    }
+
+    /// Make a single usage request with one session key
+    async fn fetch_with_session_key(
+        &self,
+        org_id: &str,
+        session_key: &str,
+    ) -> Result<UsageData, ProviderError> {
+        let url = format!("{}/organizations/{}/usage", self.base_url, org_id);
+        let headers = self.build_headers(session_key);
+
+        log::info!("Fetching Claude usage from: {}", url);
+
+        let response = self
+            .client
+            .get(&url)
+            .headers(headers)
+            .send()
+            .await?;
+
+        let status = response.status();
+        log::info!("Claude API response status: {}", status);
+        let rate_limit = Self::parse_rate_limit(response.headers(), Utc::now());
+
+        match status.as_u16() {
+            200 => {
+                let text = response.text().await?;
+
+                log::info!("Claude API raw response: {}", &text[..text.len().min(1000)]);
+
+                let body: ClaudeUsageResponse = serde_json::from_str(&text).map_err(|e| {
+                    log::error!("Failed to parse Claude response: {}. Body: {}", e, text);
+                    Self::body_parse_error(&text, e)
+                })?;
+
+                let mut usage = self.parse_response(body)?;
+                usage.rate_limit = rate_limit;
+                Ok(usage)
+            }
+            401 => Err(ProviderError::SessionExpired),
+            403 | 404 => {
+                let body = response.text().await.unwrap_or_default();
+
+                // A JSON "no access to this org" error means the key works, just for another org
+                if Self::is_org_access_error(&body) {
+                    if let Some(actual) = self.find_session_org(org_id, session_key).await {
+                        return Err(ProviderError::OrgMismatch {
+                            expected: org_id.to_string(),
+                            actual,
+                        });
+                    }
+                }
+
+                if status.as_u16() == 403 {
+                    Err(ProviderError::CloudflareBlocked)
+                } else {
+                    Err(ProviderError::HttpError(format!(
+                        "Unexpected status {}: {}",
+                        status, body
+                    )))
+                }
+            }
+            429 => Err(ProviderError::RateLimited),
+            _ => {
+                let body = response.text().await.unwrap_or_default();
+                Err(ProviderError::HttpError(format!(
+                    "Unexpected status {}: {}",
+                    status, body
+                )))
+            }
+        }
+    }
+
+    /// Read the request-rate budget from response headers, or None if none were sent
+    ///
+    /// The reset may be an RFC 3339 time, a unix timestamp, or a number of seconds
+    /// from `now`. Malformed values are ignored rather than failing the fetch.
+    fn parse_rate_limit(headers: &HeaderMap, now: DateTime<Utc>) -> Option<RateLimitInfo> {
+        let first = |names: &[&str]| {
+            names
+                .iter()
+                .find_map(|name| headers.get(*name)?.to_str().ok())
+                .map(str::trim)
+        };
+
+        let limit = first(&RATE_LIMIT_LIMIT_HEADERS).and_then(|v| v.parse().ok());
+        let remaining = first(&RATE_LIMIT_REMAINING_HEADERS).and_then(|v| v.parse().ok());
+        let reset_at = first(&RATE_LIMIT_RESET_HEADERS).and_then(|v| {
+            if let Ok(at) = DateTime::parse_from_rfc3339(v) {
+                return Some(at.with_timezone(&Utc));
+            }
+            let secs: i64 = v.parse().ok()?;
+            if secs >= MIN_RESET_TIMESTAMP {
+                DateTime::from_timestamp(secs, 0)
+            } else {
+                // Out-of-range values from the server are dropped rather than trusted
+                chrono::Duration::try_seconds(secs).and_then(|d| now.checked_add_signed(d))
+            }
+        });
+
+        if limit.is_none() && remaining.is_none() && reset_at.is_none() {
+            return None;
+        }
+
+        Some(RateLimitInfo {
+            limit,
+            remaining,
+            reset_at,
+        })
+    }
+
+    /// Error for a successful response whose body isn't a valid usage response
+    ///
+    /// Keeps a snippet of the body around where parsing stopped; for a truncated
+    /// body that is its tail.
+    fn body_parse_error(text: &str, error: serde_json::Error) -> ProviderError {
+        let message = if error.is_eof() {
+            "body ends early".to_string()
+        } else {
+            error.to_string()
+        };
+
+        // line and column are 1-based; column counts bytes
+        let offset = if error.is_eof() || error.line() == 0 {
+            text.len()
+        } else {
+            text.split_inclusive('\n')
+                .take(error.line() - 1)
+                .map(str::len)
+                .sum::<usize>()
+                + error.column().saturating_sub(1)
+        };
+
+        let floor = |mut i: usize| {
+            i = i.min(text.len());
+            while !text.is_char_boundary(i) {
+                i -= 1;
+            }
+            i
+        };
+        let start = floor(offset.saturating_sub(PARSE_SNIPPET_CONTEXT));
+        let end = floor(offset.saturating_add(PARSE_SNIPPET_CONTEXT));
+
+        ProviderError::Parse {
+            message,
+            snippet: text[start..end].to_string(),
+        }
+    }
+
+    /// Whether an error body is the API refusing access to the requested organization
+    ///
+    /// Cloudflare blocks come back as HTML, so only JSON errors that mention the
+    /// organization qualify.
+    fn is_org_access_error(body: &str) -> bool {
+        let Ok(json) = serde_json::from_str::<serde_json::Value>(body) else {
+            return false;
+        };
+
+        let error = json.get("error").unwrap_or(&json);
+        let error_type = error.get("type").and_then(|t| t.as_str()).unwrap_or_default();
+        let message = error
+            .get("message")
+            .and_then(|m| m.as_str())
+            .unwrap_or_default()
+            .to_lowercase();
+
+        matches!(error_type, "permission_error" | "not_found_error") && message.contains("organization")
+    }
+
+    /// The organization the session key belongs to, if it isn't `expected_org`
+    async fn find_session_org(&self, expected_org: &str, session_key: &str) -> Option<String> {
+        let url = format!("{}/organizations", self.base_url);
+        let response = self
+            .client
+            .get(&url)
+            .headers(self.build_headers(session_key))
+            .send()
+            .await
+            .ok()?;
+
+        if !response.status().is_success() {
+            return None;
+        }
+
+        let orgs: Vec<serde_json::Value> = response.json().await.ok()?;
+        let uuids: Vec<&str> = orgs
+            .iter()
+            .filter_map(|o| o.get("uuid").and_then(|u| u.as_str()))
+            .collect();
+
+        if uuids.contains(&expected_org) {
+            return None;
+        }
+        uuids.first().map(|u| u.to_string())
+    }
+
+    /// Fetch the organization's details with one session key
+    async fn fetch_organization(
+        &self,
+        org_id: &str,
+        session_key: &str,
+    ) -> Result<AccountInfo, ProviderError> {
+        let url = format!("{}/organizations/{}", self.base_url, org_id);
+
+        log::info!("Fetching Claude organization from: {}", url);
+
+        let response = self
+            .client
+            .get(&url)
+            .headers(self.build_headers(session_key))
+            .send()
+            .await?;
+
+        let status = response.status();
+        match status.as_u16() {
+            200 => {
+                let text = response.text().await?;
+                Self::parse_account_info(&text, Utc::now())
+            }
+            401 => Err(ProviderError::SessionExpired),
+            403 => Err(ProviderError::CloudflareBlocked),
+            429 => Err(ProviderError::RateLimited),
+            _ => {
+                let body = response.text().await.unwrap_or_default();
+                Err(ProviderError::HttpError(format!(
+                    "Unexpected status {}: {}",
+                    status, body
+                )))
+            }
+        }
+    }
+
+    /// Parse an organization response into `AccountInfo`
+    ///
+    /// Shared plans are named by the organization type; personal ones by the
+    /// highest plan capability the organization has.
+    fn parse_account_info(text: &str, now: DateTime<Utc>) -> Result<AccountInfo, ProviderError> {
+        let org: ClaudeOrganization =
+            serde_json::from_str(text).map_err(|e| Self::body_parse_error(text, e))?;
+
+        let has = |capability: &str| org.capabilities.iter().any(|c| c == capability);
+        let plan = match org.raven_type.as_deref() {
+            Some("enterprise") => Some("Enterprise"),
+            Some("team") => Some("Team"),
+            _ if has("claude_max") => Some("Max"),
+            _ if has("claude_pro") => Some("Pro"),
+            _ if has("chat") => Some("Free"),
+            _ => None,
+        };
+
+        Ok(AccountInfo {
+            plan: plan.map(str::to_string),
+            organization_name: org.name,
+            rate_limit_tier: org.rate_limit_tier,
+            seat_tier: org.seat_tier,
+            fetched_at: now,
+        })
+    }
+
     /// Parse API response into UsageData
     fn parse_response(&self, response: ClaudeUsageResponse) -> Result<UsageData, ProviderError> {
         let mut limits = Vec::new();
@@ -141,8 +708,9 @@ impl ClaudeProvider {
             account_id: String::new(),  // Will be set by caller
             account_name: String::new(), // Will be set by caller
             timestamp: Utc::now(),
-            limits,
+            limits: dedup_limits(limits),
             raw: Some(serde_json::to_value(&response).unwrap_or_default()),
+            rate_limit: None,
         })
     }
 
@@ -170,8 +738,24 @@ impl ClaudeProvider {
             utilization: usage.utilization,
             resets_at,
             category: category.map(String::from),
+            kind: Self::limit_kind(id),
+            total: None,
         }))
     }
+
+    /// Map a Claude limit field name to its kind
+    fn limit_kind(id: &str) -> LimitKind {
+        match id {
+            "five_hour" => LimitKind::FiveHour,
+            "seven_day" | "seven_day_oauth_apps" => LimitKind::Weekly,
+            _ => match id.strip_prefix("seven_day_") {
+                Some(model @ ("opus" | "sonnet")) => LimitKind::ModelSpecific {
+                    model: model.to_string(),
+                },
+                _ => LimitKind::Unknown,
+            },
+        }
+    }
 }
 
 impl Default for ClaudeProvider {
@@ -190,64 +774,88 @@ impl UsageProvider for ClaudeProvider {
         "Claude"
     }
 
+    fn credential_schema(&self) -> Vec<CredentialField> {
+        vec![
+            CredentialField {
+                key: "org_id".to_string(),
+                label: "Organization ID".to_string(),
+                placeholder: "xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx".to_string(),
+                is_secret: false,
+                validation: Some(
+                    "^[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}$"
+                        .to_string(),
+                ),
+            },
+            CredentialField {
+                key: "session_key".to_string(),
+                label: "Session Key".to_string(),
+                placeholder: "sk-ant-sid01-...".to_string(),
+                is_secret: true,
+                validation: Some(r"^sk-ant-\S+$".to_string()),
+            },
+        ]
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            supports_expiry: true,
+            // Only utilization percentages are reported, not billable units
+            supports_spend: false,
+            limit_kinds: vec!["five_hour".to_string(), "weekly".to_string()],
+        }
+    }
+
     async fn fetch_usage(&self, credentials: &Credentials) -> Result<UsageData, ProviderError> {
+        self.fetch_usage_with_failover(credentials)
+            .await
+            .map(|(usage, _)| usage)
+    }
+
+    async fn fetch_usage_with_headers(
+        &self,
+        credentials: &Credentials,
+        extra_headers: &BTreeMap<String, String>,
+    ) -> Result<UsageData, ProviderError> {
+        self.clone()
+            .with_extra_headers(extra_headers)
+            .fetch_usage(credentials)
+            .await
+    }
+
+    /// Plan details come from the organization endpoint, which needs a session key
+    ///
+    /// OAuth-only accounts return None.
+    async fn fetch_account_info(
+        &self,
+        credentials: &Credentials,
+    ) -> Result<Option<AccountInfo>, ProviderError> {
+        let credentials = Self::claude_credentials(credentials)?;
+        if credentials.oauth.is_some() {
+            return Ok(None);
+        }
+
         let org_id = credentials
             .org_id
             .as_ref()
             .ok_or_else(|| ProviderError::MissingCredentials("org_id".to_string()))?;
-
         let session_key = credentials
-            .session_key
-            .as_ref()
+            .session_key_candidates()
+            .into_iter()
+            .next()
             .ok_or_else(|| ProviderError::MissingCredentials("session_key".to_string()))?;
 
-        let url = format!("{}/organizations/{}/usage", self.base_url, org_id);
-        let headers = self.build_headers(session_key);
-
-        log::info!("Fetching Claude usage from: {}", url);
-
-        let response = self
-            .client
-            .get(&url)
-            .headers(headers)
-            .send()
-            .await
-            .map_err(|e| ProviderError::HttpError(e.to_string()))?;
-
-        let status = response.status();
-        log::info!("Claude API response status: {}", status);
-
-        match status.as_u16() {
-            200 => {
-                let text = response
-                    .text()
-                    .await
-                    .map_err(|e| ProviderError::HttpError(e.to_string()))?;
-
-                log::info!("Claude API raw response: {}", &text[..text.len().min(1000)]);
+        self.fetch_organization(org_id, session_key).await.map(Some)
+    }
 
-                let body: ClaudeUsageResponse = serde_json::from_str(&text)
-                    .map_err(|e| {
-                        log::error!("Failed to parse Claude response: {}. Body: {}", e, text);
-                        ProviderError::ParseError(format!("{} - Response: {}", e, &text[..text.len().min(500)]))
-                    })?;
+    fn validate_credentials(&self, credentials: &Credentials) -> bool {
+        let Credentials::Claude(credentials) = credentials else {
+            return false;
+        };
 
-                self.parse_response(body)
-            }
-            401 => Err(ProviderError::SessionExpired),
-            403 => Err(ProviderError::CloudflareBlocked),
-            429 => Err(ProviderError::RateLimited),
-            _ => {
-                let body = response.text().await.unwrap_or_default();
-                Err(ProviderError::HttpError(format!(
-                    "Unexpected status {}: {}",
-                    status, body
-                )))
-            }
+        if let Some(oauth) = &credentials.oauth {
+            return !oauth.access_token.is_empty() && !oauth.refresh_token.is_empty();
         }
-    }
 
-    fn validate_credentials(&self, credentials: &Credentials) -> bool {
         credentials.org_id.as_ref().map(|s| !s.is_empty()).unwrap_or(false)
             && credentials
                 .session_key
@@ -257,49 +865,384 @@ impl UsageProvider for ClaudeProvider {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use wiremock::matchers::{header, method, path};
-    use wiremock::{Mock, MockServer, ResponseTemplate};
+/// Merge limits that share an ID, keeping the one with the highest utilization
+///
+/// The API has been seen to report the same limit twice after an account
+/// change, which would otherwise break per-limit notification tracking. The
+/// merged limit takes the position of the first occurrence.
+pub fn dedup_limits(limits: Vec<UsageLimit>) -> Vec<UsageLimit> {
+    let mut merged: Vec<UsageLimit> = Vec::with_capacity(limits.len());
+
+    for limit in limits {
+        match merged.iter_mut().find(|existing| existing.id == limit.id) {
+            Some(existing) => {
+                log::warn!(
+                    "Response has duplicate limit {} ({}% and {}%), keeping the higher",
+                    limit.id,
+                    existing.utilization,
+                    limit.utilization
+                );
+                if limit.utilization > existing.utilization {
+                    *existing = limit;
+                }
+            }
+            None => merged.push(limit),
+        }
+    }
+
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn env_with(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: Vec<(String, String)> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| vars.iter().find(|(k, _)| k == name).map(|(_, v)| v.clone())
+    }
+
+    fn env_set() -> impl Fn(&str) -> Option<String> {
+        env_with(&[(ENV_ORG_ID, "env-org"), (ENV_SESSION_KEY, "sk-env")])
+    }
+
+    #[test]
+    fn resolve_prefers_stored_credentials_over_env() {
+        let resolved = ClaudeProvider::resolve_credentials(&make_credentials(), true, env_set()).unwrap();
+        let resolved = resolved.claude().unwrap();
+        assert_eq!(resolved.org_id.as_deref(), Some("test-org-123"));
+        assert_eq!(resolved.session_key.as_deref(), Some("sk-test-session-key"));
+    }
+
+    #[test]
+    fn resolve_falls_back_to_env_when_allowed() {
+        let stored = Credentials::Claude(ClaudeCredentials {
+            org_id: Some("test-org-123".to_string()),
+            session_key: None,
+            session_keys: Vec::new(),
+            oauth: None,
+        });
+
+        let resolved = ClaudeProvider::resolve_credentials(&stored, true, env_set()).unwrap();
+        let resolved = resolved.claude().unwrap();
+        assert_eq!(resolved.org_id.as_deref(), Some("test-org-123"));
+        assert_eq!(resolved.session_key.as_deref(), Some("sk-env"));
+
+        let resolved = ClaudeProvider::resolve_credentials(&Credentials::default(), true, env_set()).unwrap();
+        let resolved = resolved.claude().unwrap();
+        assert_eq!(resolved.org_id.as_deref(), Some("env-org"));
+        assert_eq!(resolved.session_key.as_deref(), Some("sk-env"));
+    }
+
+    #[test]
+    fn resolve_ignores_env_when_not_allowed() {
+        let result = ClaudeProvider::resolve_credentials(&Credentials::default(), false, env_set());
+        assert!(matches!(result, Err(ProviderError::MissingCredentials(f)) if f == "org_id"));
+    }
+
+    #[test]
+    fn resolve_errors_when_env_unset() {
+        let stored = Credentials::Claude(ClaudeCredentials {
+            org_id: Some("test-org-123".to_string()),
+            session_key: Some(String::new()),
+            session_keys: Vec::new(),
+            oauth: None,
+        });
+
+        let result = ClaudeProvider::resolve_credentials(&stored, true, env_with(&[]));
+        assert!(matches!(result, Err(ProviderError::MissingCredentials(f)) if f == "session_key"));
+
+        // Blank env values count as unset
+        let result = ClaudeProvider::resolve_credentials(&stored, true, env_with(&[(ENV_SESSION_KEY, "  ")]));
+        assert!(matches!(result, Err(ProviderError::MissingCredentials(_))));
+    }
+
+    fn make_credentials() -> Credentials {
+        Credentials::Claude(ClaudeCredentials {
+            org_id: Some("test-org-123".to_string()),
+            session_key: Some("sk-test-session-key".to_string()),
+            session_keys: Vec::new(),
+            oauth: None,
+        })
+    }
+
+    fn make_usage_response() -> serde_json::Value {
+        serde_json::json!({
+            "five_hour": {
+                "utilization": 0.45,
+                "resets_at": "2025-01-15T17:00:00Z"
+            },
+            "seven_day": {
+                "utilization": 0.25,
+                "resets_at": "2025-01-20T00:00:00Z"
+            },
+            "seven_day_opus": null,
+            "seven_day_sonnet": null,
+            "seven_day_oauth_apps": null,
+            "iguana_necktie": null,
+            "extra_usage": null
+        })
+    }
+
+    // ============================================================================
+    // Integration tests with mocked HTTP
+    // ============================================================================
+
+    #[tokio::test]
+    async fn test_fetch_usage_success() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/organizations/test-org-123/usage"))
+            .and(header("cookie", "sessionKey=sk-test-session-key"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(make_usage_response()))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let provider = ClaudeProvider::with_base_url(&mock_server.uri()).unwrap();
+        let credentials = make_credentials();
+
+        let result = provider.fetch_usage(&credentials).await;
+
+        assert!(result.is_ok());
+        let usage = result.unwrap();
+        assert_eq!(usage.provider, "claude");
+        assert_eq!(usage.limits.len(), 2);
+
+        let five_hour = usage.limits.iter().find(|l| l.id == "five_hour").unwrap();
+        assert!((five_hour.utilization - 0.45).abs() < 0.001);
+
+        let seven_day = usage.limits.iter().find(|l| l.id == "seven_day").unwrap();
+        assert!((seven_day.utilization - 0.25).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_credential_schema_lists_org_id_and_secret_session_key() {
+        let provider = ClaudeProvider::new().unwrap();
+        let fields: Vec<_> = provider
+            .credential_schema()
+            .into_iter()
+            .map(|f| (f.key, f.is_secret))
+            .collect();
+
+        assert_eq!(
+            fields,
+            vec![
+                ("org_id".to_string(), false),
+                ("session_key".to_string(), true)
+            ]
+        );
+        assert_eq!(provider.metadata().required_credentials.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_account_info_from_organization() {
+        let now = Utc::now();
+        let body = r#"{
+            "uuid": "test-org-123",
+            "name": "Jane's Organization",
+            "capabilities": ["chat", "claude_max"],
+            "rate_limit_tier": "default_claude_max_20x",
+            "billing_type": "stripe_subscription",
+            "raven_type": null,
+            "seat_tier": null
+        }"#;
+
+        let info = ClaudeProvider::parse_account_info(body, now).unwrap();
+        assert_eq!(info.plan.as_deref(), Some("Max"));
+        assert_eq!(
+            info.organization_name.as_deref(),
+            Some("Jane's Organization")
+        );
+        assert_eq!(
+            info.rate_limit_tier.as_deref(),
+            Some("default_claude_max_20x")
+        );
+        assert_eq!(info.seat_tier, None);
+        assert_eq!(info.fetched_at, now);
+
+        // Shared plans are named by organization type
+        let team = r#"{"uuid": "o", "capabilities": ["chat", "claude_pro"], "raven_type": "team", "seat_tier": "team_standard"}"#;
+        let info = ClaudeProvider::parse_account_info(team, now).unwrap();
+        assert_eq!(info.plan.as_deref(), Some("Team"));
+        assert_eq!(info.seat_tier.as_deref(), Some("team_standard"));
+
+        let unknown = r#"{"uuid": "o", "capabilities": ["api"]}"#;
+        assert_eq!(
+            ClaudeProvider::parse_account_info(unknown, now)
+                .unwrap()
+                .plan,
+            None
+        );
+
+        assert!(matches!(
+            ClaudeProvider::parse_account_info("not json", now),
+            Err(ProviderError::Parse { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_account_info() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/organizations/test-org-123"))
+            .and(header("cookie", "sessionKey=sk-test-session-key"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "uuid": "test-org-123",
+                "name": "Test Org",
+                "capabilities": ["chat", "claude_pro"]
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let provider = ClaudeProvider::with_base_url(&mock_server.uri()).unwrap();
+        let info = provider
+            .fetch_account_info(&make_credentials())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(info.plan.as_deref(), Some("Pro"));
+        assert_eq!(info.organization_name.as_deref(), Some("Test Org"));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_usage_sends_extra_headers() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/organizations/test-org-123/usage"))
+            .and(header("cookie", "sessionKey=sk-test-session-key"))
+            .and(header("x-gateway-token", "gw-secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(make_usage_response()))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let extra = BTreeMap::from([
+            ("X-Gateway-Token".to_string(), "gw-secret".to_string()),
+            ("Cookie".to_string(), "sessionKey=sk-hijacked".to_string()),
+        ]);
+        let provider = ClaudeProvider::with_base_url(&mock_server.uri()).unwrap();
+
+        let result = provider
+            .fetch_usage_with_headers(&make_credentials(), &extra)
+            .await;
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn rate_limit_headers_parse_into_info() {
+        let now = Utc::now();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "anthropic-ratelimit-requests-limit",
+            HeaderValue::from_static("50"),
+        );
+        headers.insert(
+            "anthropic-ratelimit-requests-remaining",
+            HeaderValue::from_static("7"),
+        );
+        headers.insert(
+            "anthropic-ratelimit-requests-reset",
+            HeaderValue::from_static("2025-01-15T12:00:30Z"),
+        );
+
+        let info = ClaudeProvider::parse_rate_limit(&headers, now).unwrap();
+        assert_eq!(info.limit, Some(50));
+        assert_eq!(info.remaining, Some(7));
+        assert_eq!(
+            info.reset_at,
+            Some("2025-01-15T12:00:30Z".parse::<DateTime<Utc>>().unwrap())
+        );
+
+        // Generic headers with a relative reset, and a malformed limit that is skipped
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-limit", HeaderValue::from_static("lots"));
+        headers.insert("x-ratelimit-remaining", HeaderValue::from_static("0"));
+        headers.insert("x-ratelimit-reset", HeaderValue::from_static("60"));
+
+        let info = ClaudeProvider::parse_rate_limit(&headers, now).unwrap();
+        assert_eq!(info.limit, None);
+        assert_eq!(info.remaining, Some(0));
+        assert_eq!(info.reset_at, Some(now + chrono::Duration::seconds(60)));
+
+        // An absurd relative reset is dropped instead of overflowing
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-remaining", HeaderValue::from_static("3"));
+        headers.insert(
+            "x-ratelimit-reset",
+            HeaderValue::from_static("-9223372036854775808"),
+        );
+        let info = ClaudeProvider::parse_rate_limit(&headers, now).unwrap();
+        assert_eq!(info.remaining, Some(3));
+        assert_eq!(info.reset_at, None);
 
-    fn make_credentials() -> Credentials {
-        Credentials {
-            org_id: Some("test-org-123".to_string()),
-            session_key: Some("sk-test-session-key".to_string()),
-        }
+        assert_eq!(
+            ClaudeProvider::parse_rate_limit(&HeaderMap::new(), now),
+            None
+        );
     }
 
-    fn make_usage_response() -> serde_json::Value {
-        serde_json::json!({
-            "five_hour": {
-                "utilization": 0.45,
-                "resets_at": "2025-01-15T17:00:00Z"
-            },
-            "seven_day": {
-                "utilization": 0.25,
-                "resets_at": "2025-01-20T00:00:00Z"
-            },
-            "seven_day_opus": null,
-            "seven_day_sonnet": null,
-            "seven_day_oauth_apps": null,
-            "iguana_necktie": null,
-            "extra_usage": null
-        })
+    #[tokio::test]
+    async fn test_fetch_usage_attaches_rate_limit_info() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/organizations/test-org-123/usage"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(make_usage_response())
+                    .insert_header("anthropic-ratelimit-requests-remaining", "3"),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let provider = ClaudeProvider::with_base_url(&mock_server.uri()).unwrap();
+        let usage = provider.fetch_usage(&make_credentials()).await.unwrap();
+
+        assert_eq!(usage.rate_limit.and_then(|r| r.remaining), Some(3));
     }
 
-    // ============================================================================
-    // Integration tests with mocked HTTP
-    // ============================================================================
+    #[test]
+    fn extra_headers_drop_reserved_and_invalid_names() {
+        let extra = BTreeMap::from([
+            ("X-Gateway-Token".to_string(), "gw-secret".to_string()),
+            ("cookie".to_string(), "sessionKey=other".to_string()),
+            ("Host".to_string(), "evil.example".to_string()),
+            ("User-Agent".to_string(), "curl/8".to_string()),
+            ("bad header".to_string(), "x".to_string()),
+            ("x-bad-value".to_string(), "line\nbreak".to_string()),
+        ]);
+
+        let headers = ClaudeProvider::sanitize_extra_headers(&extra);
+
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers["x-gateway-token"], "gw-secret");
+
+        // The session cookie always comes from the credentials
+        let provider = ClaudeProvider::new().unwrap().with_extra_headers(&extra);
+        let built = provider.build_headers("sk-real");
+        assert_eq!(built[COOKIE], "sessionKey=sk-real");
+        assert_eq!(built["x-gateway-token"], "gw-secret");
+    }
 
     #[tokio::test]
-    async fn test_fetch_usage_success() {
+    async fn test_fetch_usage_401_session_expired() {
         let mock_server = MockServer::start().await;
 
         Mock::given(method("GET"))
             .and(path("/organizations/test-org-123/usage"))
-            .and(header("cookie", "sessionKey=sk-test-session-key"))
-            .respond_with(ResponseTemplate::new(200).set_body_json(make_usage_response()))
+            .respond_with(ResponseTemplate::new(401))
             .expect(1)
             .mount(&mock_server)
             .await;
@@ -309,35 +1252,68 @@ mod tests {
 
         let result = provider.fetch_usage(&credentials).await;
 
-        assert!(result.is_ok());
-        let usage = result.unwrap();
-        assert_eq!(usage.provider, "claude");
-        assert_eq!(usage.limits.len(), 2);
+        assert!(result.is_err());
+        match result.unwrap_err() {
+            ProviderError::SessionExpired => {}
+            err => panic!("Expected SessionExpired, got {:?}", err),
+        }
+    }
 
-        let five_hour = usage.limits.iter().find(|l| l.id == "five_hour").unwrap();
-        assert!((five_hour.utilization - 0.45).abs() < 0.001);
+    #[tokio::test]
+    async fn test_fetch_usage_fails_over_to_next_session_key() {
+        let mock_server = MockServer::start().await;
 
-        let seven_day = usage.limits.iter().find(|l| l.id == "seven_day").unwrap();
-        assert!((seven_day.utilization - 0.25).abs() < 0.001);
+        Mock::given(method("GET"))
+            .and(path("/organizations/test-org-123/usage"))
+            .and(header("cookie", "sessionKey=sk-expired"))
+            .respond_with(ResponseTemplate::new(401))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/organizations/test-org-123/usage"))
+            .and(header("cookie", "sessionKey=sk-fallback"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(make_usage_response()))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let provider = ClaudeProvider::with_base_url(&mock_server.uri()).unwrap();
+        let credentials = Credentials::Claude(ClaudeCredentials {
+            org_id: Some("test-org-123".to_string()),
+            session_key: Some("sk-expired".to_string()),
+            session_keys: vec!["sk-expired".to_string(), "sk-fallback".to_string()],
+            oauth: None,
+        });
+
+        let (usage, index) = provider.fetch_usage_with_failover(&credentials).await.unwrap();
+
+        assert_eq!(index, 1);
+        assert_eq!(usage.limits.len(), 2);
     }
 
     #[tokio::test]
-    async fn test_fetch_usage_401_session_expired() {
+    async fn test_fetch_usage_all_session_keys_exhausted() {
         let mock_server = MockServer::start().await;
 
         Mock::given(method("GET"))
             .and(path("/organizations/test-org-123/usage"))
             .respond_with(ResponseTemplate::new(401))
-            .expect(1)
+            .expect(2)
             .mount(&mock_server)
             .await;
 
         let provider = ClaudeProvider::with_base_url(&mock_server.uri()).unwrap();
-        let credentials = make_credentials();
+        let credentials = Credentials::Claude(ClaudeCredentials {
+            org_id: Some("test-org-123".to_string()),
+            session_key: Some("sk-first".to_string()),
+            session_keys: vec!["sk-first".to_string(), "sk-second".to_string()],
+            oauth: None,
+        });
 
         let result = provider.fetch_usage(&credentials).await;
 
-        assert!(result.is_err());
         match result.unwrap_err() {
             ProviderError::SessionExpired => {}
             err => panic!("Expected SessionExpired, got {:?}", err),
@@ -367,6 +1343,81 @@ mod tests {
         }
     }
 
+    #[test]
+    fn org_access_error_detection() {
+        let mismatch = r#"{"type":"error","error":{"type":"permission_error","message":"Invalid authorization for organization"}}"#;
+        assert!(ClaudeProvider::is_org_access_error(mismatch));
+
+        let not_found = r#"{"error":{"type":"not_found_error","message":"Organization not found"}}"#;
+        assert!(ClaudeProvider::is_org_access_error(not_found));
+
+        // Cloudflare challenge pages and unrelated errors don't count
+        assert!(!ClaudeProvider::is_org_access_error("<html>Just a moment...</html>"));
+        assert!(!ClaudeProvider::is_org_access_error(""));
+        let other = r#"{"error":{"type":"permission_error","message":"Account disabled"}}"#;
+        assert!(!ClaudeProvider::is_org_access_error(other));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_usage_org_mismatch() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/organizations/test-org-123/usage"))
+            .respond_with(ResponseTemplate::new(403).set_body_string(
+                r#"{"type":"error","error":{"type":"permission_error","message":"Invalid authorization for organization"}}"#,
+            ))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/organizations"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([
+                { "uuid": "other-org-456", "name": "Other" }
+            ])))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let provider = ClaudeProvider::with_base_url(&mock_server.uri()).unwrap();
+        let result = provider.fetch_usage(&make_credentials()).await;
+
+        match result.unwrap_err() {
+            ProviderError::OrgMismatch { expected, actual } => {
+                assert_eq!(expected, "test-org-123");
+                assert_eq!(actual, "other-org-456");
+            }
+            err => panic!("Expected OrgMismatch, got {:?}", err),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_usage_org_error_when_key_has_org_is_not_mismatch() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/organizations/test-org-123/usage"))
+            .respond_with(ResponseTemplate::new(403).set_body_string(
+                r#"{"error":{"type":"permission_error","message":"No access to organization usage"}}"#,
+            ))
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/organizations"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([
+                { "uuid": "test-org-123" }
+            ])))
+            .mount(&mock_server)
+            .await;
+
+        let provider = ClaudeProvider::with_base_url(&mock_server.uri()).unwrap();
+        let result = provider.fetch_usage(&make_credentials()).await;
+
+        assert!(matches!(result, Err(ProviderError::CloudflareBlocked)));
+    }
+
     #[tokio::test]
     async fn test_fetch_usage_429_rate_limited() {
         let mock_server = MockServer::start().await;
@@ -433,20 +1484,61 @@ mod tests {
 
         assert!(result.is_err());
         match result.unwrap_err() {
-            ProviderError::ParseError(msg) => {
-                assert!(msg.contains("not valid json"));
+            ProviderError::Parse { snippet, .. } => {
+                assert!(snippet.contains("not valid json"));
+            }
+            err => panic!("Expected Parse, got {:?}", err),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_usage_truncated_json() {
+        let mock_server = MockServer::start().await;
+        let body = r#"{"five_hour": {"utilization": 42.0, "resets_at": "2025-01-01T12:00"#;
+
+        Mock::given(method("GET"))
+            .and(path("/organizations/test-org-123/usage"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(body))
+            .mount(&mock_server)
+            .await;
+
+        let provider = ClaudeProvider::with_base_url(&mock_server.uri()).unwrap();
+        let err = provider.fetch_usage(&make_credentials()).await.unwrap_err();
+
+        match &err {
+            ProviderError::Parse { message, snippet } => {
+                assert_eq!(message, "body ends early");
+                assert!(body.ends_with(snippet.as_str()));
+                assert_eq!(snippet.len(), PARSE_SNIPPET_CONTEXT);
             }
-            err => panic!("Expected ParseError, got {:?}", err),
+            err => panic!("Expected Parse, got {:?}", err),
+        }
+        assert!(err.to_string().contains("try again shortly"));
+    }
+
+    #[test]
+    fn test_parse_error_snippet_on_later_line() {
+        let text = "{\n  \"five_hour\": {\n    \"utilization\": oops\n  }\n}";
+        let error = serde_json::from_str::<ClaudeUsageResponse>(text).unwrap_err();
+
+        match ClaudeProvider::body_parse_error(text, error) {
+            ProviderError::Parse { message, snippet } => {
+                assert!(message.contains("line 3"));
+                assert!(snippet.contains("oops"));
+            }
+            err => panic!("Expected Parse, got {:?}", err),
         }
     }
 
     #[tokio::test]
     async fn test_fetch_usage_missing_org_id() {
         let provider = ClaudeProvider::new().unwrap();
-        let credentials = Credentials {
+        let credentials = Credentials::Claude(ClaudeCredentials {
             org_id: None,
             session_key: Some("sk-test".to_string()),
-        };
+            session_keys: Vec::new(),
+            oauth: None,
+        });
 
         let result = provider.fetch_usage(&credentials).await;
 
@@ -462,10 +1554,12 @@ mod tests {
     #[tokio::test]
     async fn test_fetch_usage_missing_session_key() {
         let provider = ClaudeProvider::new().unwrap();
-        let credentials = Credentials {
+        let credentials = Credentials::Claude(ClaudeCredentials {
             org_id: Some("org-123".to_string()),
             session_key: None,
-        };
+            session_keys: Vec::new(),
+            oauth: None,
+        });
 
         let result = provider.fetch_usage(&credentials).await;
 
@@ -574,6 +1668,70 @@ mod tests {
         assert_eq!(oauth.category, Some("oauth".to_string()));
     }
 
+    #[test]
+    fn test_parse_response_assigns_limit_kinds() {
+        let response: ClaudeUsageResponse = serde_json::from_value(serde_json::json!({
+            "five_hour": { "utilization": 12.0, "resets_at": "2025-01-15T17:00:00Z" },
+            "seven_day": { "utilization": 40.0, "resets_at": "2025-01-20T00:00:00Z" },
+            "seven_day_opus": { "utilization": 75.0, "resets_at": "2025-01-20T00:00:00Z" },
+            "seven_day_sonnet": null,
+            "seven_day_oauth_apps": null,
+            "iguana_necktie": null,
+            "extra_usage": null
+        }))
+        .unwrap();
+
+        let usage = ClaudeProvider::new()
+            .unwrap()
+            .parse_response(response)
+            .unwrap();
+        let kinds: Vec<(&str, &LimitKind)> = usage
+            .limits
+            .iter()
+            .map(|l| (l.id.as_str(), &l.kind))
+            .collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                ("five_hour", &LimitKind::FiveHour),
+                ("seven_day", &LimitKind::Weekly),
+                (
+                    "seven_day_opus",
+                    &LimitKind::ModelSpecific {
+                        model: "opus".to_string()
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_limit_kind_defaults_to_unknown() {
+        let stored = serde_json::json!({
+            "id": "five_hour",
+            "label": "5-Hour Limit",
+            "utilization": 10.0,
+            "resetsAt": "2025-01-15T17:00:00Z"
+        });
+        let limit: UsageLimit = serde_json::from_value(stored.clone()).unwrap();
+        assert_eq!(limit.kind, LimitKind::Unknown);
+
+        let mut newer = stored;
+        newer["kind"] = serde_json::json!({ "type": "dailyBurst" });
+        let limit: UsageLimit = serde_json::from_value(newer).unwrap();
+        assert_eq!(limit.kind, LimitKind::Unknown);
+
+        let opus = serde_json::to_value(LimitKind::ModelSpecific {
+            model: "opus".to_string(),
+        })
+        .unwrap();
+        assert_eq!(
+            opus,
+            serde_json::json!({ "type": "modelSpecific", "model": "opus" })
+        );
+    }
+
     #[tokio::test]
     async fn test_headers_are_browser_like() {
         let mock_server = MockServer::start().await;
@@ -595,6 +1753,136 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    // ============================================================================
+    // OAuth
+    // ============================================================================
+
+    fn make_oauth(expires_in: chrono::Duration) -> OAuthCredentials {
+        OAuthCredentials {
+            access_token: "access-old".to_string(),
+            refresh_token: "refresh-old".to_string(),
+            expires_at: Utc::now() + expires_in,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_oauth_token_near_expiry_is_refreshed() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/v1/oauth/token"))
+            .and(body_partial_json(serde_json::json!({
+                "grant_type": "refresh_token",
+                "refresh_token": "refresh-old"
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "access-new",
+                "refresh_token": "refresh-new",
+                "expires_in": 28800
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let provider = ClaudeProvider::with_base_url(&mock_server.uri()).unwrap();
+        let now = Utc::now();
+        let rotated = provider
+            .refresh_oauth_if_needed(&make_oauth(chrono::Duration::minutes(2)), now)
+            .await
+            .unwrap()
+            .expect("token near expiry should be refreshed");
+
+        assert_eq!(rotated.access_token, "access-new");
+        assert_eq!(rotated.refresh_token, "refresh-new");
+        assert_eq!(rotated.expires_at, now + chrono::Duration::seconds(28800));
+    }
+
+    #[tokio::test]
+    async fn test_oauth_out_of_range_expiry_is_a_parse_error() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/v1/oauth/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "access-new",
+                "expires_in": i64::MAX
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let provider = ClaudeProvider::with_base_url(&mock_server.uri()).unwrap();
+        let result = provider
+            .refresh_oauth_if_needed(&make_oauth(chrono::Duration::zero()), Utc::now())
+            .await;
+
+        assert!(matches!(result, Err(ProviderError::ParseError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_oauth_token_not_near_expiry_is_kept() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/v1/oauth/token"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(0)
+            .mount(&mock_server)
+            .await;
+
+        let provider = ClaudeProvider::with_base_url(&mock_server.uri()).unwrap();
+        let refreshed = provider
+            .refresh_oauth_if_needed(&make_oauth(chrono::Duration::hours(2)), Utc::now())
+            .await
+            .unwrap();
+
+        assert!(refreshed.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_oauth_rejected_refresh_token_is_session_expired() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/v1/oauth/token"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                "error": "invalid_grant"
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let provider = ClaudeProvider::with_base_url(&mock_server.uri()).unwrap();
+        let result = provider
+            .refresh_oauth_if_needed(&make_oauth(chrono::Duration::zero()), Utc::now())
+            .await;
+
+        assert!(matches!(result, Err(ProviderError::SessionExpired)));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_usage_with_oauth_uses_bearer_token() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/api/oauth/usage"))
+            .and(header("authorization", "Bearer access-old"))
+            .and(header("anthropic-beta", CLAUDE_OAUTH_BETA))
+            .respond_with(ResponseTemplate::new(200).set_body_json(make_usage_response()))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let provider = ClaudeProvider::with_base_url(&mock_server.uri()).unwrap();
+        let credentials = Credentials::Claude(ClaudeCredentials {
+            oauth: Some(make_oauth(chrono::Duration::hours(2))),
+            ..ClaudeCredentials::default()
+        });
+
+        assert!(provider.validate_credentials(&credentials));
+        let (usage, key_index) = provider.fetch_usage_with_failover(&credentials).await.unwrap();
+        assert_eq!(key_index, 0);
+        assert_eq!(usage.limits.len(), 2);
+    }
+
     // ============================================================================
     // Unit tests for validate_credentials
     // ============================================================================
@@ -609,31 +1897,77 @@ mod tests {
     #[test]
     fn test_validate_credentials_missing_org_id() {
         let provider = ClaudeProvider::new().unwrap();
-        let credentials = Credentials {
+        let credentials = Credentials::Claude(ClaudeCredentials {
             org_id: None,
             session_key: Some("sk-test".to_string()),
-        };
+            session_keys: Vec::new(),
+            oauth: None,
+        });
         assert!(!provider.validate_credentials(&credentials));
     }
 
     #[test]
     fn test_validate_credentials_missing_session_key() {
         let provider = ClaudeProvider::new().unwrap();
-        let credentials = Credentials {
+        let credentials = Credentials::Claude(ClaudeCredentials {
             org_id: Some("org-123".to_string()),
             session_key: None,
-        };
+            session_keys: Vec::new(),
+            oauth: None,
+        });
         assert!(!provider.validate_credentials(&credentials));
     }
 
     #[test]
     fn test_validate_credentials_empty_strings() {
         let provider = ClaudeProvider::new().unwrap();
-        let credentials = Credentials {
+        let credentials = Credentials::Claude(ClaudeCredentials {
             org_id: Some("".to_string()),
             session_key: Some("".to_string()),
-        };
+            session_keys: Vec::new(),
+            oauth: None,
+        });
+        assert!(!provider.validate_credentials(&credentials));
+    }
+
+    #[test]
+    fn test_validate_credentials_other_provider() {
+        let provider = ClaudeProvider::new().unwrap();
+        let credentials = Credentials::Codex(crate::models::CodexCredentials {
+            api_key: Some("sk-proj-abc".to_string()),
+        });
         assert!(!provider.validate_credentials(&credentials));
+        assert!(matches!(
+            ClaudeProvider::resolve_credentials(&credentials, false, env_set()),
+            Err(ProviderError::InvalidCredentials(_))
+        ));
+    }
+
+    #[test]
+    fn dedup_limits_keeps_highest_utilization() {
+        let resets_at = Utc::now();
+        let limit = |id: &str, utilization: f64| UsageLimit {
+            id: id.to_string(),
+            label: id.to_string(),
+            utilization,
+            resets_at,
+            category: None,
+            kind: ClaudeProvider::limit_kind(id),
+            total: None,
+        };
+
+        let limits = dedup_limits(vec![
+            limit("five_hour", 30.0),
+            limit("seven_day", 50.0),
+            limit("five_hour", 72.5),
+            limit("five_hour", 10.0),
+        ]);
+
+        let merged: Vec<(&str, f64)> = limits
+            .iter()
+            .map(|l| (l.id.as_str(), l.utilization))
+            .collect();
+        assert_eq!(merged, vec![("five_hour", 72.5), ("seven_day", 50.0)]);
     }
 
     #[test]
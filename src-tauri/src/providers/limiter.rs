@@ -0,0 +1,307 @@
+use super::{CredentialField, ProviderCapabilities, ProviderMetadata, UsageProvider};
+use crate::error::ProviderError;
+use crate::models::{AccountInfo, Credentials, UsageData};
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Concurrent provider requests allowed until settings are applied
+pub const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 4;
+
+static GLOBAL: OnceLock<Arc<RequestLimiter>> = OnceLock::new();
+
+/// Bounds how many provider requests are in flight at once
+///
+/// Shared by every `ProviderRegistry` so the scheduler, bulk validation and
+/// manual refreshes draw from the same pool.
+#[derive(Debug)]
+pub struct RequestLimiter {
+    capacity: Arc<Mutex<Capacity>>,
+    semaphore: Arc<Semaphore>,
+}
+
+#[derive(Debug)]
+struct Capacity {
+    limit: usize,
+    /// Held permits to drop instead of release when they come back, after the limit was lowered
+    retiring: usize,
+}
+
+/// A slot from a `RequestLimiter`, released (or retired) when dropped
+#[derive(Debug)]
+pub struct RequestPermit {
+    permit: Option<OwnedSemaphorePermit>,
+    capacity: Arc<Mutex<Capacity>>,
+}
+
+impl Drop for RequestPermit {
+    fn drop(&mut self) {
+        // Release under the lock so a concurrent limit change sees a consistent count
+        let mut capacity = self.capacity.lock().unwrap_or_else(PoisonError::into_inner);
+        let Some(permit) = self.permit.take() else {
+            return;
+        };
+        if capacity.retiring > 0 {
+            capacity.retiring -= 1;
+            permit.forget();
+        } else {
+            drop(permit);
+        }
+    }
+}
+
+impl RequestLimiter {
+    pub fn new(max_concurrent: usize) -> Self {
+        let max_concurrent = max_concurrent.max(1);
+        Self {
+            capacity: Arc::new(Mutex::new(Capacity {
+                limit: max_concurrent,
+                retiring: 0,
+            })),
+            semaphore: Arc::new(Semaphore::new(max_concurrent)),
+        }
+    }
+
+    /// The limiter shared by the whole app
+    pub fn global() -> Arc<Self> {
+        GLOBAL
+            .get_or_init(|| Arc::new(Self::new(DEFAULT_MAX_CONCURRENT_REQUESTS)))
+            .clone()
+    }
+
+    /// Current limit
+    #[cfg(test)]
+    pub fn max_concurrent(&self) -> usize {
+        self.capacity
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .limit
+    }
+
+    /// Change the limit (at least 1)
+    ///
+    /// Raising it frees slots at once. Lowering it removes free slots, and any
+    /// still held are retired as their requests finish, so the requests in
+    /// flight never exceed the new limit once those have drained. Raising it
+    /// again first cancels retirements that haven't happened yet.
+    pub fn set_max_concurrent(&self, max_concurrent: usize) {
+        let max_concurrent = max_concurrent.max(1);
+        let mut capacity = self.capacity.lock().unwrap_or_else(PoisonError::into_inner);
+        if capacity.limit == max_concurrent {
+            return;
+        }
+
+        log::info!("Provider request limit set to {}", max_concurrent);
+        if max_concurrent > capacity.limit {
+            let raise = max_concurrent - capacity.limit;
+            let kept = raise.min(capacity.retiring);
+            capacity.retiring -= kept;
+            self.semaphore.add_permits(raise - kept);
+        } else {
+            let excess = capacity.limit - max_concurrent;
+            capacity.retiring += excess - self.semaphore.forget_permits(excess);
+        }
+        capacity.limit = max_concurrent;
+    }
+
+    /// Wait for a free slot; the request may run while the permit is held
+    pub async fn acquire(&self) -> RequestPermit {
+        let permit = self
+            .semaphore
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("request semaphore is never closed");
+        RequestPermit {
+            permit: Some(permit),
+            capacity: self.capacity.clone(),
+        }
+    }
+}
+
+/// Wraps a provider so every fetch holds a permit from a `RequestLimiter`
+pub(super) struct LimitedProvider {
+    inner: Arc<dyn UsageProvider>,
+    limiter: Arc<RequestLimiter>,
+}
+
+impl LimitedProvider {
+    pub(super) fn new(inner: Arc<dyn UsageProvider>, limiter: Arc<RequestLimiter>) -> Self {
+        Self { inner, limiter }
+    }
+}
+
+#[async_trait]
+impl UsageProvider for LimitedProvider {
+    fn id(&self) -> &'static str {
+        self.inner.id()
+    }
+
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    async fn fetch_usage(&self, credentials: &Credentials) -> Result<UsageData, ProviderError> {
+        let _permit = self.limiter.acquire().await;
+        self.inner.fetch_usage(credentials).await
+    }
+
+    async fn fetch_usage_with_headers(
+        &self,
+        credentials: &Credentials,
+        extra_headers: &BTreeMap<String, String>,
+    ) -> Result<UsageData, ProviderError> {
+        let _permit = self.limiter.acquire().await;
+        self.inner
+            .fetch_usage_with_headers(credentials, extra_headers)
+            .await
+    }
+
+    async fn fetch_account_info(
+        &self,
+        credentials: &Credentials,
+    ) -> Result<Option<AccountInfo>, ProviderError> {
+        let _permit = self.limiter.acquire().await;
+        self.inner.fetch_account_info(credentials).await
+    }
+
+    fn validate_credentials(&self, credentials: &Credentials) -> bool {
+        self.inner.validate_credentials(credentials)
+    }
+
+    fn credential_schema(&self) -> Vec<CredentialField> {
+        self.inner.credential_schema()
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.inner.capabilities()
+    }
+
+    fn metadata(&self) -> ProviderMetadata {
+        self.inner.metadata()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ClaudeCredentials;
+    use chrono::Utc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    /// Provider that sleeps during each fetch and records how many overlap
+    #[derive(Default)]
+    struct CountingProvider {
+        in_flight: AtomicUsize,
+        peak_in_flight: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl UsageProvider for CountingProvider {
+        fn id(&self) -> &'static str {
+            "counting"
+        }
+
+        fn name(&self) -> &'static str {
+            "Counting"
+        }
+
+        async fn fetch_usage(
+            &self,
+            _credentials: &Credentials,
+        ) -> Result<UsageData, ProviderError> {
+            let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak_in_flight.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(30)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+            Ok(UsageData {
+                provider: "counting".to_string(),
+                account_id: String::new(),
+                account_name: String::new(),
+                timestamp: Utc::now(),
+                limits: Vec::new(),
+                raw: None,
+                rate_limit: None,
+            })
+        }
+
+        fn validate_credentials(&self, _credentials: &Credentials) -> bool {
+            true
+        }
+    }
+
+    async fn peak_for(limiter: Arc<RequestLimiter>, fetches: usize) -> usize {
+        let inner = Arc::new(CountingProvider::default());
+        let provider = Arc::new(LimitedProvider::new(inner.clone(), limiter));
+        let credentials = Credentials::Claude(ClaudeCredentials::default());
+
+        let handles: Vec<_> = (0..fetches)
+            .map(|_| {
+                let provider = provider.clone();
+                let credentials = credentials.clone();
+                tokio::spawn(async move { provider.fetch_usage(&credentials).await })
+            })
+            .collect();
+        for handle in handles {
+            handle.await.unwrap().unwrap();
+        }
+
+        inner.peak_in_flight.load(Ordering::SeqCst)
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn fetches_never_exceed_the_limit() {
+        let limiter = Arc::new(RequestLimiter::new(2));
+        assert_eq!(peak_for(limiter.clone(), 8).await, 2);
+
+        limiter.set_max_concurrent(3);
+        assert_eq!(limiter.max_concurrent(), 3);
+        assert_eq!(peak_for(limiter.clone(), 8).await, 3);
+
+        // Zero would block every request, so it is raised to one
+        limiter.set_max_concurrent(0);
+        assert_eq!(peak_for(limiter, 4).await, 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn lowering_the_limit_retires_held_permits() {
+        let limiter = Arc::new(RequestLimiter::new(2));
+        let first = limiter.acquire().await;
+        let second = limiter.acquire().await;
+
+        limiter.set_max_concurrent(1);
+
+        // The first permit back is retired rather than handed to a new request
+        drop(first);
+        let wait = Duration::from_millis(50);
+        assert!(tokio::time::timeout(wait, limiter.acquire()).await.is_err());
+
+        drop(second);
+        let third = tokio::time::timeout(wait, limiter.acquire()).await.unwrap();
+        assert!(tokio::time::timeout(wait, limiter.acquire()).await.is_err());
+        drop(third);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn raising_the_limit_again_cancels_pending_retirements() {
+        let limiter = Arc::new(RequestLimiter::new(2));
+        let first = limiter.acquire().await;
+        let second = limiter.acquire().await;
+
+        // Lowered while both are held, then raised before either comes back
+        limiter.set_max_concurrent(1);
+        limiter.set_max_concurrent(3);
+        drop(first);
+        drop(second);
+
+        let wait = Duration::from_millis(50);
+        let mut held = Vec::new();
+        for _ in 0..3 {
+            held.push(tokio::time::timeout(wait, limiter.acquire()).await.unwrap());
+        }
+        assert!(tokio::time::timeout(wait, limiter.acquire()).await.is_err());
+    }
+}
@@ -1,12 +1,16 @@
 mod claude;
+mod limiter;
 
 pub use claude::ClaudeProvider;
+pub use limiter::{RequestLimiter, DEFAULT_MAX_CONCURRENT_REQUESTS};
+
+use limiter::LimitedProvider;
 
 use crate::error::ProviderError;
-use crate::models::{Credentials, UsageData};
+use crate::models::{AccountInfo, Credentials, UsageData};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 
 /// Trait for usage data providers
@@ -21,16 +25,46 @@ pub trait UsageProvider: Send + Sync {
     /// Fetch current usage data
     async fn fetch_usage(&self, credentials: &Credentials) -> Result<UsageData, ProviderError>;
 
+    /// Fetch usage, sending an account's extra request headers where the provider supports them
+    async fn fetch_usage_with_headers(
+        &self,
+        credentials: &Credentials,
+        extra_headers: &BTreeMap<String, String>,
+    ) -> Result<UsageData, ProviderError> {
+        if !extra_headers.is_empty() {
+            log::warn!("Provider {} ignores extra request headers", self.id());
+        }
+        self.fetch_usage(credentials).await
+    }
+
+    /// Fetch plan and seat details, or None if the provider doesn't report them
+    async fn fetch_account_info(
+        &self,
+        _credentials: &Credentials,
+    ) -> Result<Option<AccountInfo>, ProviderError> {
+        Ok(None)
+    }
+
     /// Validate that credentials have required fields
     fn validate_credentials(&self, credentials: &Credentials) -> bool;
 
+    /// Credential fields the account form should ask for, in display order
+    fn credential_schema(&self) -> Vec<CredentialField> {
+        Vec::new()
+    }
+
+    /// Features and limit kinds this provider supports
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities::default()
+    }
+
     /// Get metadata about this provider
     fn metadata(&self) -> ProviderMetadata {
         ProviderMetadata {
             id: self.id().to_string(),
             name: self.name().to_string(),
             status: ProviderStatus::Available,
-            required_credentials: vec![],
+            required_credentials: self.credential_schema(),
             description: None,
         }
     }
@@ -59,6 +93,18 @@ pub struct ProviderMetadata {
     pub description: Option<String>,
 }
 
+/// Feature flags the UI uses to adapt to a provider
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderCapabilities {
+    /// Provider reports when a session has expired
+    pub supports_expiry: bool,
+    /// Provider reports consumption that can be priced
+    pub supports_spend: bool,
+    /// Kinds of limits reported (e.g., "five_hour", "weekly", "tokens")
+    pub limit_kinds: Vec<String>,
+}
+
 /// Describes a credential field for the UI
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -67,21 +113,68 @@ pub struct CredentialField {
     pub label: String,
     pub placeholder: String,
     pub is_secret: bool,
+    /// Pattern a valid value matches, in JavaScript `RegExp` syntax
+    pub validation: Option<String>,
+}
+
+/// A provider submitted to the registry at build time
+///
+/// Implement `UsageProvider`, then register the provider next to its
+/// implementation so `ProviderRegistry::new` picks it up:
+///
+/// ```ignore
+/// inventory::submit! {
+///     ProviderRegistration::new(|| Ok(Arc::new(GeminiProvider::new()?)))
+/// }
+/// ```
+pub struct ProviderRegistration {
+    build: fn() -> Result<Arc<dyn UsageProvider>, ProviderError>,
 }
 
+impl ProviderRegistration {
+    pub const fn new(build: fn() -> Result<Arc<dyn UsageProvider>, ProviderError>) -> Self {
+        Self { build }
+    }
+}
+
+inventory::collect!(ProviderRegistration);
+
 /// Registry of all available providers
+///
+/// Providers handed out by the registry take a permit from its
+/// `RequestLimiter` for every fetch.
 pub struct ProviderRegistry {
     providers: HashMap<String, Arc<dyn UsageProvider>>,
 }
 
 impl ProviderRegistry {
-    /// Create a new registry with all available providers
+    /// Create a new registry with every registered provider, sharing the global request limit
+    ///
+    /// Fails if any provider can't be built. When two providers share an ID the
+    /// first one registered is kept.
     pub fn new() -> Result<Self, ProviderError> {
+        Self::with_limiter(RequestLimiter::global())
+    }
+
+    /// Create a registry whose providers draw from `limiter`
+    pub fn with_limiter(limiter: Arc<RequestLimiter>) -> Result<Self, ProviderError> {
         let mut providers: HashMap<String, Arc<dyn UsageProvider>> = HashMap::new();
 
-        // Register Claude provider
-        let claude = ClaudeProvider::new()?;
-        providers.insert(claude.id().to_string(), Arc::new(claude));
+        for registration in inventory::iter::<ProviderRegistration> {
+            let provider = (registration.build)()?;
+            let id = provider.id();
+            if providers.contains_key(id) {
+                log::warn!(
+                    "Provider {} is registered more than once; keeping the first",
+                    id
+                );
+                continue;
+            }
+            providers.insert(
+                id.to_string(),
+                Arc::new(LimitedProvider::new(provider, limiter.clone())),
+            );
+        }
 
         Ok(Self { providers })
     }
@@ -102,22 +195,8 @@ impl ProviderRegistry {
             .values()
             .map(|p| {
                 let mut meta = p.metadata();
-                // Add required credentials for Claude
+                // Add a description for Claude
                 if meta.id == "claude" {
-                    meta.required_credentials = vec![
-                        CredentialField {
-                            key: "org_id".to_string(),
-                            label: "Organization ID".to_string(),
-                            placeholder: "xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx".to_string(),
-                            is_secret: false,
-                        },
-                        CredentialField {
-                            key: "session_key".to_string(),
-                            label: "Session Key".to_string(),
-                            placeholder: "sk-ant-sid01-...".to_string(),
-                            is_secret: true,
-                        },
-                    ];
                     meta.description = Some(
                         "Monitor your Claude Pro/Max usage limits. \
                          Get credentials from claude.ai DevTools."
@@ -150,11 +229,75 @@ mod tests {
     #[test]
     fn test_all_metadata() {
         let registry = ProviderRegistry::new().unwrap();
-        let metadata = registry.all_metadata();
+        let mut metadata = registry.all_metadata();
+        metadata.sort_by(|a, b| a.id.cmp(&b.id));
 
-        // Should have Claude
-        assert_eq!(metadata.len(), 1);
+        // Claude, plus the provider registered by these tests
+        assert_eq!(metadata.len(), 2);
         assert_eq!(metadata[0].id, "claude");
         assert_eq!(metadata[0].status, ProviderStatus::Available);
+        assert_eq!(metadata[1].id, "test-only");
+    }
+
+    /// Provider that only exists in test builds
+    struct TestOnlyProvider;
+
+    #[async_trait]
+    impl UsageProvider for TestOnlyProvider {
+        fn id(&self) -> &'static str {
+            "test-only"
+        }
+
+        fn name(&self) -> &'static str {
+            "Test Only"
+        }
+
+        async fn fetch_usage(
+            &self,
+            _credentials: &Credentials,
+        ) -> Result<UsageData, ProviderError> {
+            Err(ProviderError::InvalidCredentials(
+                "test provider".to_string(),
+            ))
+        }
+
+        fn validate_credentials(&self, _credentials: &Credentials) -> bool {
+            false
+        }
+    }
+
+    inventory::submit! {
+        ProviderRegistration::new(|| Ok(Arc::new(TestOnlyProvider)))
+    }
+
+    #[test]
+    fn registered_provider_is_collected() {
+        let registry = ProviderRegistry::new().unwrap();
+
+        let provider = registry.get("test-only").unwrap();
+        assert_eq!(provider.name(), "Test Only");
+        let mut ids: Vec<_> = registry.provider_ids().collect();
+        ids.sort();
+        assert_eq!(ids, vec!["claude", "test-only"]);
+    }
+
+    #[tokio::test]
+    async fn registry_providers_wait_for_a_permit() {
+        let limiter = Arc::new(RequestLimiter::new(1));
+        let registry = ProviderRegistry::with_limiter(limiter.clone()).unwrap();
+        let provider = registry.get("test-only").unwrap();
+        let credentials = Credentials::Claude(Default::default());
+
+        let held = limiter.acquire().await;
+        let blocked = tokio::time::timeout(
+            std::time::Duration::from_millis(50),
+            provider.fetch_usage(&credentials),
+        )
+        .await;
+        assert!(blocked.is_err());
+
+        // The test provider always rejects credentials; getting an answer is enough
+        drop(held);
+        assert!(provider.fetch_usage(&credentials).await.is_err());
     }
 }
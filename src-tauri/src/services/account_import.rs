@@ -0,0 +1,264 @@
+use crate::error::AppError;
+use crate::models::{
+    Account, ClaudeCredentials, Credentials, ImportFailure, ImportFormat, ImportResult, ImportRow,
+};
+use crate::providers::ProviderRegistry;
+use crate::services::CredentialService;
+use chrono::Utc;
+use std::collections::BTreeMap;
+use tauri::AppHandle;
+
+/// Columns an import CSV must have (in any order)
+const CSV_COLUMNS: [&str; 4] = ["name", "provider", "org_id", "session_key"];
+
+pub struct AccountImportService;
+
+impl AccountImportService {
+    /// Import accounts from a JSON array or CSV, saving every valid row
+    ///
+    /// Bad rows are reported in the result instead of aborting the import.
+    pub fn import(
+        app: &AppHandle,
+        data: &str,
+        format: ImportFormat,
+    ) -> Result<ImportResult, AppError> {
+        let rows = Self::parse(data, format)?;
+        let registry = ProviderRegistry::new()?;
+
+        let (accounts, mut failures) = Self::prepare(rows, &registry);
+
+        let mut imported = Vec::new();
+        for (row, account) in accounts {
+            match CredentialService::save_account(app, &account) {
+                Ok(()) => imported.push(account.id),
+                Err(e) => failures.push(ImportFailure {
+                    row,
+                    name: Some(account.name),
+                    error: e.to_string(),
+                }),
+            }
+        }
+
+        failures.sort_by_key(|f| f.row);
+        log::info!(
+            "Imported {} account(s), {} row(s) failed",
+            imported.len(),
+            failures.len()
+        );
+
+        Ok(ImportResult { imported, failures })
+    }
+
+    /// Split an import file into rows; a row that can't be read is kept as its error
+    pub fn parse(
+        data: &str,
+        format: ImportFormat,
+    ) -> Result<Vec<Result<ImportRow, String>>, AppError> {
+        match format {
+            ImportFormat::Json => {
+                let values: Vec<serde_json::Value> = serde_json::from_str(data).map_err(|e| {
+                    AppError::InvalidInput(format!("expected a JSON array of accounts: {}", e))
+                })?;
+
+                Ok(values
+                    .into_iter()
+                    .map(|value| serde_json::from_value(value).map_err(|e| e.to_string()))
+                    .collect())
+            }
+            ImportFormat::Csv => Self::parse_csv(data),
+        }
+    }
+
+    fn parse_csv(data: &str) -> Result<Vec<Result<ImportRow, String>>, AppError> {
+        let mut lines = data.lines().filter(|line| !line.trim().is_empty());
+
+        let header: Vec<String> = lines
+            .next()
+            .map(Self::split_csv_line)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|column| column.trim().to_ascii_lowercase())
+            .collect();
+
+        let mut indices = [0usize; CSV_COLUMNS.len()];
+        for (index, column) in indices.iter_mut().zip(CSV_COLUMNS) {
+            *index = header.iter().position(|h| h == column).ok_or_else(|| {
+                AppError::InvalidInput(format!("CSV header is missing the '{}' column", column))
+            })?;
+        }
+        let [name, provider, org_id, session_key] = indices;
+
+        Ok(lines
+            .map(|line| {
+                let fields = Self::split_csv_line(line);
+                if fields.len() != header.len() {
+                    return Err(format!(
+                        "expected {} columns, found {}",
+                        header.len(),
+                        fields.len()
+                    ));
+                }
+
+                let optional =
+                    |i: usize| Some(fields[i].trim().to_string()).filter(|v| !v.is_empty());
+                Ok(ImportRow {
+                    name: fields[name].trim().to_string(),
+                    provider: fields[provider].trim().to_string(),
+                    org_id: optional(org_id),
+                    session_key: optional(session_key),
+                })
+            })
+            .collect())
+    }
+
+    /// Split one CSV line, honouring double-quoted fields and `""` escapes
+    fn split_csv_line(line: &str) -> Vec<String> {
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        let mut in_quotes = false;
+        let mut chars = line.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '"' if in_quotes && chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = !in_quotes,
+                ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+                _ => field.push(c),
+            }
+        }
+        fields.push(field);
+
+        fields
+    }
+
+    /// Turn parsed rows into new accounts, validating each against its provider
+    ///
+    /// Returns the accounts to save (with their row numbers) and the rows that failed.
+    pub fn prepare(
+        rows: Vec<Result<ImportRow, String>>,
+        registry: &ProviderRegistry,
+    ) -> (Vec<(usize, Account)>, Vec<ImportFailure>) {
+        let mut accounts = Vec::new();
+        let mut failures = Vec::new();
+
+        for (i, row) in rows.into_iter().enumerate() {
+            let row_number = i + 1;
+            let fail = |name: Option<String>, error: String| ImportFailure {
+                row: row_number,
+                name,
+                error,
+            };
+
+            let row = match row {
+                Ok(row) => row,
+                Err(e) => {
+                    failures.push(fail(None, e));
+                    continue;
+                }
+            };
+
+            if row.name.trim().is_empty() {
+                failures.push(fail(None, "name is required".to_string()));
+                continue;
+            }
+
+            let Some(provider) = registry.get(&row.provider) else {
+                failures.push(fail(
+                    Some(row.name),
+                    format!("Unknown or unavailable provider: {}", row.provider),
+                ));
+                continue;
+            };
+
+            let credentials = Credentials::Claude(ClaudeCredentials {
+                org_id: row.org_id.map(|id| id.trim().to_string()),
+                session_key: row
+                    .session_key
+                    .map(|key| CredentialService::normalize_session_key(&key)),
+                session_keys: Vec::new(),
+                oauth: None,
+            });
+
+            if !provider.validate_credentials(&credentials) {
+                failures.push(fail(
+                    Some(row.name),
+                    "missing or invalid credentials".to_string(),
+                ));
+                continue;
+            }
+
+            accounts.push((
+                row_number,
+                Account {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    name: row.name.trim().to_string(),
+                    provider: provider.id().to_string(),
+                    credentials,
+                    created_at: Utc::now(),
+                    tags: Vec::new(),
+                    display_name: None,
+                    extra_headers: BTreeMap::new(),
+                },
+            ));
+        }
+
+        (accounts, failures)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_import_keeps_valid_rows_and_reports_invalid_ones() {
+        let csv = "name,provider,org_id,session_key\n\
+                   \"Work, EU\",claude,org-123,sk-ant-valid-key\n\
+                   Personal,claude,,sk-ant-no-org\n";
+
+        let rows = AccountImportService::parse(csv, ImportFormat::Csv).unwrap();
+        let registry = ProviderRegistry::new().unwrap();
+        let (accounts, failures) = AccountImportService::prepare(rows, &registry);
+
+        assert_eq!(accounts.len(), 1);
+        let (row, account) = &accounts[0];
+        assert_eq!(*row, 1);
+        assert_eq!(account.name, "Work, EU");
+        assert_eq!(
+            account.credentials.claude().unwrap().org_id.as_deref(),
+            Some("org-123")
+        );
+        assert!(uuid::Uuid::parse_str(&account.id).is_ok());
+
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].row, 2);
+        assert_eq!(failures[0].name.as_deref(), Some("Personal"));
+    }
+
+    #[test]
+    fn imported_accounts_get_fresh_ids() {
+        let json = r#"[
+            {"name": "A", "provider": "claude", "org_id": "org-1", "session_key": "sk-ant-a"},
+            {"name": "B", "provider": "claude", "orgId": "org-1", "sessionKey": "sk-ant-b"},
+            {"name": "C", "provider": "gemini", "org_id": "org-1", "session_key": "key"}
+        ]"#;
+
+        let rows = AccountImportService::parse(json, ImportFormat::Json).unwrap();
+        let registry = ProviderRegistry::new().unwrap();
+        let (accounts, failures) = AccountImportService::prepare(rows, &registry);
+
+        assert_eq!(accounts.len(), 2);
+        assert_ne!(accounts[0].1.id, accounts[1].1.id);
+        assert_eq!(failures.len(), 1);
+        assert!(failures[0].error.contains("gemini"));
+    }
+
+    #[test]
+    fn csv_without_required_column_is_rejected() {
+        let result = AccountImportService::parse("name,provider\nA,claude\n", ImportFormat::Csv);
+        assert!(matches!(result, Err(AppError::InvalidInput(_))));
+    }
+}
@@ -1,26 +1,79 @@
 use crate::error::AppError;
-use crate::models::{Account, Credentials};
-use crate::services::crypto;
+use crate::models::{
+    Account, AccountCryptoInfo, ClaudeCredentials, CodexCredentials, Credentials, CryptoInfo,
+    FieldEncryptionStatus, GeminiCredentials, MigrationPlan, MigrationStep, OAuthCredentials,
+    SnapshotReason,
+};
+use crate::providers::{ClaudeProvider, RequestLimiter};
+use crate::services::crypto::{self, KeyMaterial, ENCRYPTED_PREFIX};
+use crate::services::key_value::{persist_unlocked, KeyValueStore};
+use crate::services::{
+    DataDirExt, HistoryService, OAuthRefreshLocks, ResponseCacheService, SettingsService,
+    SnapshotService, StoreLocks,
+};
 use chrono::Utc;
-use std::collections::HashMap;
-use tauri::AppHandle;
-use tauri_plugin_store::StoreExt;
+use serde_json::Value as JsonValue;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, PoisonError};
+use tauri::{AppHandle, Emitter, Manager};
 
-const STORE_FILE: &str = "credentials.json";
+pub(crate) const STORE_FILE: &str = "credentials.json";
 const ACCOUNTS_KEY: &str = "accounts";
+const INTEGRITY_KEY: &str = "accounts_hmac";
 const VERSION_KEY: &str = "version";
-const CURRENT_VERSION: u32 = 3; // v3: encrypted credentials
+const CURRENT_VERSION: u32 = 6; // v6: provider-tagged credentials
 
-/// Prefix to identify encrypted values
-const ENCRYPTED_PREFIX: &str = "enc:v1:";
+/// Keys used by the v1 (flat, per-provider) credentials format
+const LEGACY_KEYS: [&str; 3] = ["claude", "codex", "gemini"];
+
+/// Prefix and suffix of the per-step migration snapshot key (`.pre-vN.bak`)
+const BACKUP_PREFIX: &str = ".pre-v";
+const BACKUP_SUFFIX: &str = ".bak";
+
+/// Prefix every Claude session key starts with
+const CLAUDE_KEY_PREFIX: &str = "sk-ant-";
+
+/// Shortest string plausibly a full Claude session key (real keys are ~100 chars)
+const MIN_CLAUDE_KEY_LEN: usize = 32;
+
+/// Tag that marks an account as archived
+const ARCHIVED_TAG: &str = "archived";
+
+/// Event payload emitted when a credentials migration step fails and is rolled back
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationFailedEvent {
+    pub from_version: u32,
+    pub to_version: u32,
+    pub error: String,
+}
+
+/// Store contents that migrations depend on, read without modifying anything
+struct MigrationInput {
+    version: u32,
+    legacy_keys: Vec<String>,
+    legacy_claude: Option<Credentials>,
+    accounts: HashMap<String, Account>,
+}
 
 pub struct CredentialService;
 
 impl CredentialService {
+    /// Scheme prefix of an encrypted field (`enc:v1`, `enc:v2`, ...), None for plaintext
+    fn encryption_scheme(value: &str) -> Option<&str> {
+        let rest = value.strip_prefix("enc:v")?;
+        let digits = rest.find(':')?;
+        if digits == 0 || !rest[..digits].bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+
+        Some(&value[.."enc:v".len() + digits])
+    }
+
     /// Encrypt a single credential field
     fn encrypt_field(value: Option<&String>) -> Option<String> {
         value.map(|key| {
-            if key.starts_with(ENCRYPTED_PREFIX) {
+            if Self::encryption_scheme(key).is_some() {
                 key.clone()
             } else {
                 match crypto::encrypt(key) {
@@ -53,54 +106,611 @@ impl CredentialService {
 
     /// Encrypt sensitive credential fields
     fn encrypt_credentials(credentials: &Credentials) -> Credentials {
-        Credentials {
-            org_id: credentials.org_id.clone(),
-            session_key: Self::encrypt_field(credentials.session_key.as_ref()),
-        }
+        Self::map_secrets(credentials, |value| Self::encrypt_field(Some(value)))
+            .unwrap_or_else(|| credentials.clone())
     }
 
-    /// Decrypt sensitive credential fields
-    fn decrypt_credentials(credentials: &Credentials) -> Credentials {
-        Credentials {
-            org_id: credentials.org_id.clone(),
-            session_key: Self::decrypt_field(credentials.session_key.as_ref()),
-        }
+    /// Encrypt an account's credentials and extra header values
+    fn encrypt_account(account: &Account) -> Account {
+        Self::map_account_secrets(account, |value| Self::encrypt_field(Some(value)))
+            .unwrap_or_else(|| account.clone())
+    }
+
+    /// Decrypt an account's credentials and extra header values
+    fn decrypt_account(account: &Account) -> Account {
+        Self::map_account_secrets(account, |value| Self::decrypt_field(Some(value)))
+            .unwrap_or_else(|| account.clone())
+    }
+
+    /// Apply `f` to an account's secret credential fields and extra header values
+    ///
+    /// Header values are treated as secrets since they often carry a gateway token.
+    fn map_account_secrets(
+        account: &Account,
+        f: impl Fn(&String) -> Option<String>,
+    ) -> Option<Account> {
+        let credentials = Self::map_secrets(&account.credentials, &f)?;
+        let extra_headers = account
+            .extra_headers
+            .iter()
+            .map(|(name, value)| Some((name.clone(), f(value)?)))
+            .collect::<Option<BTreeMap<_, _>>>()?;
+
+        Some(Account {
+            credentials,
+            extra_headers,
+            ..account.clone()
+        })
+    }
+
+    /// Apply `f` to every secret field, or None if it fails for any
+    ///
+    /// Org and project IDs aren't secret and are copied as-is.
+    fn map_secrets(
+        credentials: &Credentials,
+        f: impl Fn(&String) -> Option<String>,
+    ) -> Option<Credentials> {
+        let map_optional = |value: &Option<String>| match value {
+            Some(value) => f(value).map(Some),
+            None => Some(None),
+        };
+
+        Some(match credentials {
+            Credentials::Claude(claude) => Credentials::Claude(ClaudeCredentials {
+                org_id: claude.org_id.clone(),
+                session_key: map_optional(&claude.session_key)?,
+                session_keys: claude
+                    .session_keys
+                    .iter()
+                    .map(&f)
+                    .collect::<Option<Vec<_>>>()?,
+                oauth: match &claude.oauth {
+                    Some(oauth) => Some(Self::map_oauth_tokens(oauth, &f)?),
+                    None => None,
+                },
+            }),
+            Credentials::Codex(codex) => Credentials::Codex(CodexCredentials {
+                api_key: map_optional(&codex.api_key)?,
+            }),
+            Credentials::Gemini(gemini) => Credentials::Gemini(GeminiCredentials {
+                api_key: map_optional(&gemini.api_key)?,
+                project_id: gemini.project_id.clone(),
+            }),
+        })
+    }
+
+    /// Secret fields, including extra header values, by the name the encryption audit reports them under
+    fn secret_fields(account: &Account) -> Vec<(String, &String)> {
+        let mut fields: Vec<(String, &String)> = match &account.credentials {
+            Credentials::Claude(claude) => claude
+                .session_key
+                .iter()
+                .map(|value| ("sessionKey".to_string(), value))
+                .chain(
+                    claude
+                        .session_keys
+                        .iter()
+                        .enumerate()
+                        .map(|(i, value)| (format!("sessionKeys[{}]", i), value)),
+                )
+                .chain(claude.oauth.iter().flat_map(|oauth| {
+                    [
+                        ("oauth.accessToken".to_string(), &oauth.access_token),
+                        ("oauth.refreshToken".to_string(), &oauth.refresh_token),
+                    ]
+                }))
+                .collect(),
+            Credentials::Codex(CodexCredentials { api_key })
+            | Credentials::Gemini(GeminiCredentials { api_key, .. }) => api_key
+                .iter()
+                .map(|value| ("apiKey".to_string(), value))
+                .collect(),
+        };
+        fields.extend(
+            account
+                .extra_headers
+                .iter()
+                .map(|(name, value)| (format!("extraHeaders.{}", name), value)),
+        );
+        fields
+    }
+
+    /// Apply `f` to both OAuth tokens, or None if it fails for either
+    fn map_oauth_tokens(
+        oauth: &OAuthCredentials,
+        f: impl Fn(&String) -> Option<String>,
+    ) -> Option<OAuthCredentials> {
+        Some(OAuthCredentials {
+            access_token: f(&oauth.access_token)?,
+            refresh_token: f(&oauth.refresh_token)?,
+            expires_at: oauth.expires_at,
+        })
     }
 
     // =========================================================================
     // Account-based API (v2)
     // =========================================================================
 
+    /// Read the stored schema version (v1 stores have no version key)
+    fn stored_version(app: &AppHandle) -> Result<u32, AppError> {
+        let store = app.data_store(STORE_FILE)?;
+
+        Ok(store
+            .get(VERSION_KEY)
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+            .unwrap_or(1))
+    }
+
+    /// Lock guarding read-modify-write sequences on the credentials store
+    fn store_lock(app: &AppHandle) -> Arc<Mutex<()>> {
+        app.state::<StoreLocks>().for_file(STORE_FILE)
+    }
+
     /// Ensure the store is migrated to the latest version
     pub fn ensure_migrated(app: &AppHandle) -> Result<(), AppError> {
-        let store = app
-            .store(STORE_FILE)
-            .map_err(|e| AppError::Store(e.to_string()))?;
+        if Self::stored_version(app)? >= CURRENT_VERSION {
+            return Ok(());
+        }
 
-        // Check current version
-        let version: u32 = store
-            .get(VERSION_KEY)
+        let lock = Self::store_lock(app);
+        let _guard = lock.lock().unwrap_or_else(PoisonError::into_inner);
+
+        // Another caller may have finished migrating while we waited
+        if Self::stored_version(app)? >= CURRENT_VERSION {
+            return Ok(());
+        }
+
+        let store = app.data_store(STORE_FILE)?;
+
+        // A previous launch may have died mid-step; roll back before retrying
+        Self::restore_leftover_backups(&*store)?;
+
+        let plan = Self::plan_migration(&Self::read_migration_input(app)?);
+
+        if !plan.steps.is_empty() {
+            if let Err(e) = SnapshotService::create(app, SnapshotReason::PreMigration) {
+                log::warn!("Failed to snapshot state before migrating: {}", e);
+            }
+        }
+
+        for step in &plan.steps {
+            log::info!(
+                "Migrating credentials from v{} to v{}: {}",
+                step.from_version,
+                step.to_version,
+                step.actions.join(", ")
+            );
+
+            let result = Self::run_migration_step(&*store, step.to_version, || {
+                match step.to_version {
+                    2 => Self::migrate_v1_to_v2(app),
+                    3 => Self::migrate_v2_to_v3(app),
+                    4 => Self::migrate_v3_to_v4(app),
+                    5 => Self::migrate_v4_to_v5(app),
+                    6 => Self::migrate_v5_to_v6(app),
+                    _ => Ok(()),
+                }
+            });
+
+            if let Err(e) = result {
+                let _ = app.emit(
+                    "migration-failed",
+                    MigrationFailedEvent {
+                        from_version: step.from_version,
+                        to_version: step.to_version,
+                        error: e.to_string(),
+                    },
+                );
+                return Err(e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Store key holding the snapshot taken before migrating to `to_version`
+    fn backup_key(to_version: u32) -> String {
+        format!("{}{}{}", BACKUP_PREFIX, to_version, BACKUP_SUFFIX)
+    }
+
+    /// Keys any migration step may modify
+    fn migration_keys() -> impl Iterator<Item = &'static str> {
+        [ACCOUNTS_KEY, INTEGRITY_KEY, VERSION_KEY]
+            .into_iter()
+            .chain(LEGACY_KEYS)
+    }
+
+    /// Snapshot the migration keys before a step (absent keys are recorded as null)
+    fn backup_migration_keys(store: &impl KeyValueStore, to_version: u32) -> Result<(), AppError> {
+        let snapshot: serde_json::Map<String, JsonValue> = Self::migration_keys()
+            .map(|key| (key.to_string(), store.get_value(key).unwrap_or(JsonValue::Null)))
+            .collect();
+
+        store.set_value(&Self::backup_key(to_version), JsonValue::Object(snapshot));
+        store.persist()
+    }
+
+    /// Put the migration keys back from a step's snapshot; returns false if there was none
+    fn restore_migration_backup(
+        store: &impl KeyValueStore,
+        to_version: u32,
+    ) -> Result<bool, AppError> {
+        let backup_key = Self::backup_key(to_version);
+        let Some(JsonValue::Object(snapshot)) = store.get_value(&backup_key) else {
+            return Ok(false);
+        };
+
+        for (key, value) in snapshot {
+            if value.is_null() {
+                store.delete_value(&key);
+            } else {
+                store.set_value(&key, value);
+            }
+        }
+
+        store.delete_value(&backup_key);
+        store.persist()?;
+        Ok(true)
+    }
+
+    /// Restore any snapshot left behind by a step that never completed
+    fn restore_leftover_backups(store: &impl KeyValueStore) -> Result<(), AppError> {
+        let versions: Vec<u32> = store
+            .key_list()
+            .iter()
+            .filter_map(|key| {
+                key.strip_prefix(BACKUP_PREFIX)?
+                    .strip_suffix(BACKUP_SUFFIX)?
+                    .parse()
+                    .ok()
+            })
+            .collect();
+
+        for version in versions {
+            log::warn!("Found incomplete migration to v{}, restoring backup", version);
+            Self::restore_migration_backup(store, version)?;
+        }
+
+        Ok(())
+    }
+
+    /// Run one migration step, restoring the pre-step snapshot if it fails
+    fn run_migration_step(
+        store: &impl KeyValueStore,
+        to_version: u32,
+        step: impl FnOnce() -> Result<(), AppError>,
+    ) -> Result<(), AppError> {
+        Self::backup_migration_keys(store, to_version)?;
+
+        match step() {
+            Ok(()) => {
+                store.delete_value(&Self::backup_key(to_version));
+                store.persist()
+            }
+            Err(e) => {
+                log::error!("Migration to v{} failed, restoring backup: {}", to_version, e);
+                if let Err(restore_err) = Self::restore_migration_backup(store, to_version) {
+                    log::error!("Failed to restore migration backup: {}", restore_err);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Describe pending migrations without writing anything to the store
+    pub fn preview_migration(app: &AppHandle) -> Result<MigrationPlan, AppError> {
+        Ok(Self::plan_migration(&Self::read_migration_input(app)?))
+    }
+
+    /// Snapshot the store contents that migrations read
+    fn read_migration_input(app: &AppHandle) -> Result<MigrationInput, AppError> {
+        let store = app.data_store(STORE_FILE)?;
+
+        let legacy_keys = LEGACY_KEYS
+            .iter()
+            .filter(|k| store.has(k))
+            .map(|k| k.to_string())
+            .collect();
+
+        let legacy_claude = store
+            .get("claude")
+            .and_then(|v| serde_json::from_value::<Credentials>(v.clone()).ok());
+
+        let accounts = store
+            .get(ACCOUNTS_KEY)
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+            .unwrap_or_default();
+
+        Ok(MigrationInput {
+            version: Self::stored_version(app)?,
+            legacy_keys,
+            legacy_claude,
+            accounts,
+        })
+    }
+
+    /// "1 account" or "N accounts", for migration step descriptions
+    fn accounts_label(count: usize) -> String {
+        format!("{} account{}", count, if count == 1 { "" } else { "s" })
+    }
+
+    /// Work out which steps would run and what each would change
+    fn plan_migration(input: &MigrationInput) -> MigrationPlan {
+        let mut steps = Vec::new();
+        let mut legacy_keys = input.legacy_keys.clone();
+
+        // Track the accounts map as it would look after each step
+        let mut account_count = input.accounts.len();
+        let mut plaintext_count = input
+            .accounts
+            .values()
+            .filter(|a| {
+                a.credentials
+                    .claude()
+                    .and_then(|c| c.session_key.as_ref())
+                    .map(|k| !k.starts_with(ENCRYPTED_PREFIX))
+                    .unwrap_or(false)
+            })
+            .count();
+
+        if input.version < 2 {
+            let mut actions = Vec::new();
+            let migrates_claude = input
+                .legacy_claude
+                .as_ref()
+                .map(Self::validate_claude)
+                .unwrap_or(false);
+
+            if migrates_claude {
+                actions.push("convert legacy Claude credentials into 1 account".to_string());
+                account_count = 1;
+                plaintext_count = 1;
+            } else {
+                actions.push("create empty accounts map".to_string());
+                account_count = 0;
+                plaintext_count = 0;
+            }
+
+            if !legacy_keys.is_empty() {
+                actions.push(format!("remove legacy keys ({})", legacy_keys.join(", ")));
+                legacy_keys.clear();
+            }
+
+            steps.push(MigrationStep {
+                from_version: 1,
+                to_version: 2,
+                actions,
+            });
+        }
+
+        if input.version < 3 {
+            let mut actions = vec![format!("encrypt {}", Self::accounts_label(plaintext_count))];
+            if !legacy_keys.is_empty() {
+                actions.push(format!("remove legacy keys ({})", legacy_keys.join(", ")));
+            }
+
+            steps.push(MigrationStep {
+                from_version: 2,
+                to_version: 3,
+                actions,
+            });
+        }
+
+        if input.version < 4 {
+            steps.push(MigrationStep {
+                from_version: 3,
+                to_version: 4,
+                actions: vec![format!(
+                    "sign {} with an integrity HMAC",
+                    Self::accounts_label(account_count)
+                )],
+            });
+        }
+
+        if input.version < 5 {
+            steps.push(MigrationStep {
+                from_version: 4,
+                to_version: 5,
+                actions: vec![format!(
+                    "move session keys into failover lists for {}",
+                    Self::accounts_label(account_count)
+                )],
+            });
+        }
+
+        if input.version < 6 {
+            steps.push(MigrationStep {
+                from_version: 5,
+                to_version: 6,
+                actions: vec![format!(
+                    "tag credentials of {} as Claude",
+                    Self::accounts_label(account_count)
+                )],
+            });
+        }
+
+        MigrationPlan {
+            current_version: input.version,
+            target_version: CURRENT_VERSION,
+            steps,
+        }
+    }
+
+    /// Migrate from v3 (unsigned) to v4 (HMAC over the accounts map)
+    fn migrate_v3_to_v4(app: &AppHandle) -> Result<(), AppError> {
+        let store = app.data_store(STORE_FILE)?;
+
+        let accounts: HashMap<String, Account> = store
+            .get(ACCOUNTS_KEY)
             .and_then(|v| serde_json::from_value(v.clone()).ok())
-            .unwrap_or(1);
+            .unwrap_or_default();
+
+        store.set(VERSION_KEY.to_string(), serde_json::json!(4));
+        Self::write_accounts(app, &accounts)?;
+
+        log::info!("Migration to v4 complete. {} accounts signed.", accounts.len());
+        Ok(())
+    }
 
-        if version < 2 {
-            log::info!("Migrating credentials from v{} to v2", version);
-            Self::migrate_v1_to_v2(app)?;
+    /// Migrate from v4 (single session key) to v5 (session key failover lists)
+    fn migrate_v4_to_v5(app: &AppHandle) -> Result<(), AppError> {
+        let store = app.data_store(STORE_FILE)?;
+
+        let mut accounts = Self::read_accounts(app)?;
+
+        // Keys stay encrypted; the list just starts with the existing key
+        for account in accounts.values_mut() {
+            if let Some(credentials) = account.credentials.claude_mut() {
+                if credentials.session_keys.is_empty() {
+                    credentials.session_keys = credentials.session_key.iter().cloned().collect();
+                }
+            }
         }
 
-        if version < 3 {
-            log::info!("Migrating credentials from v2 to v3 (encrypting)");
-            Self::migrate_v2_to_v3(app)?;
+        store.set(VERSION_KEY.to_string(), serde_json::json!(5));
+        Self::write_accounts(app, &accounts)?;
+
+        log::info!("Migration to v5 complete. {} accounts updated.", accounts.len());
+        Ok(())
+    }
+
+    /// Migrate from v5 (untagged credentials) to v6 (credentials tagged with their provider)
+    ///
+    /// Untagged credentials already read as `Claude`, so this only re-signs the
+    /// accounts in the tagged form.
+    fn migrate_v5_to_v6(app: &AppHandle) -> Result<(), AppError> {
+        let store = app.data_store(STORE_FILE)?;
+
+        let accounts = Self::read_accounts(app)?;
+
+        store.set(VERSION_KEY.to_string(), serde_json::json!(6));
+        Self::write_accounts(app, &accounts)?;
+
+        log::info!(
+            "Migration to v6 complete. {} accounts tagged.",
+            accounts.len()
+        );
+        Ok(())
+    }
+
+    /// Compute the HMAC for an accounts map over a canonical (sorted) serialization
+    fn sign_accounts(accounts: &HashMap<String, Account>) -> Result<String, AppError> {
+        Ok(crypto::sign(&Self::signed_bytes(accounts)?))
+    }
+
+    /// The canonical (sorted) serialization an accounts HMAC covers
+    fn signed_bytes(accounts: &HashMap<String, Account>) -> Result<Vec<u8>, AppError> {
+        let ordered: BTreeMap<&String, &Account> = accounts.iter().collect();
+        Ok(serde_json::to_vec(&ordered)?)
+    }
+
+    /// `signed_bytes` as written before v6, when credentials had no `type` tag
+    ///
+    /// Every account was Claude then, and the tag is serialized first, so
+    /// dropping it reproduces the old bytes exactly. A quote inside a string
+    /// value is escaped, so the pattern can't match there.
+    fn untagged_signed_bytes(bytes: &[u8]) -> Vec<u8> {
+        String::from_utf8_lossy(bytes)
+            .replace(r#""credentials":{"type":"claude","#, r#""credentials":{"#)
+            .replace(r#""credentials":{"type":"claude"}"#, r#""credentials":{}"#)
+            .into_bytes()
+    }
+
+    /// Verify an accounts map against its stored HMAC
+    fn verify_accounts(
+        accounts: &HashMap<String, Account>,
+        signature: Option<&str>,
+    ) -> Result<(), AppError> {
+        let signature = signature.ok_or_else(|| {
+            AppError::IntegrityViolation("accounts store is missing its signature".to_string())
+        })?;
+
+        let bytes = Self::signed_bytes(accounts)?;
+
+        // Stores signed before v6 are verified in their untagged form until migrated
+        if crypto::verify(&bytes, signature)
+            || crypto::verify(&Self::untagged_signed_bytes(&bytes), signature)
+        {
+            Ok(())
+        } else {
+            log::error!("Accounts store failed integrity check - it may have been modified");
+            Err(AppError::IntegrityViolation(
+                "accounts store was modified outside AI Pulse; restore a snapshot, or re-sign \
+                 it with resign_credentials_store if you made the change yourself"
+                    .to_string(),
+            ))
         }
+    }
+
+    /// Check the accounts in a copy of the store's contents parse and match their HMAC
+    ///
+    /// A copy without an accounts key (nothing saved yet) passes.
+    pub(crate) fn verify_store_values(
+        values: &serde_json::Map<String, JsonValue>,
+    ) -> Result<(), AppError> {
+        let Some(accounts) = values.get(ACCOUNTS_KEY) else {
+            return Ok(());
+        };
+
+        let accounts: HashMap<String, Account> = serde_json::from_value(accounts.clone())?;
+        let signature = values.get(INTEGRITY_KEY).and_then(JsonValue::as_str);
+        Self::verify_accounts(&accounts, signature)
+    }
+
+    /// Read the (still encrypted) accounts map, verifying its HMAC
+    fn read_accounts(app: &AppHandle) -> Result<HashMap<String, Account>, AppError> {
+        let store = app.data_store(STORE_FILE)?;
+
+        Self::load_accounts(&*store)
+    }
+
+    /// Load and verify the accounts map from a store
+    pub(crate) fn load_accounts(
+        store: &impl KeyValueStore,
+    ) -> Result<HashMap<String, Account>, AppError> {
+        let accounts: HashMap<String, Account> = store
+            .get_value(ACCOUNTS_KEY)
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default();
+
+        let signature = store
+            .get_value(INTEGRITY_KEY)
+            .and_then(|v| v.as_str().map(String::from));
+
+        Self::verify_accounts(&accounts, signature.as_deref())?;
+        Ok(accounts)
+    }
+
+    /// Write the (already encrypted) accounts map along with a fresh HMAC
+    fn write_accounts(app: &AppHandle, accounts: &HashMap<String, Account>) -> Result<(), AppError> {
+        let store = app.data_store(STORE_FILE)?;
+
+        Self::store_accounts(&*store, accounts)
+    }
+
+    /// Sign and persist the accounts map to a store
+    pub(crate) fn store_accounts(
+        store: &impl KeyValueStore,
+        accounts: &HashMap<String, Account>,
+    ) -> Result<(), AppError> {
+        Self::set_accounts(store, accounts)?;
+        store.persist()
+    }
 
+    /// Sign the accounts map and put it in a store, leaving the save to the caller
+    fn set_accounts(
+        store: &impl KeyValueStore,
+        accounts: &HashMap<String, Account>,
+    ) -> Result<(), AppError> {
+        let signature = Self::sign_accounts(accounts)?;
+        store.set_value(ACCOUNTS_KEY, serde_json::to_value(accounts)?);
+        store.set_value(INTEGRITY_KEY, serde_json::json!(signature));
         Ok(())
     }
 
     /// Migrate from v2 (plaintext) to v3 (encrypted credentials)
     fn migrate_v2_to_v3(app: &AppHandle) -> Result<(), AppError> {
-        let store = app
-            .store(STORE_FILE)
-            .map_err(|e| AppError::Store(e.to_string()))?;
+        let store = app.data_store(STORE_FILE)?;
 
         let mut accounts: HashMap<String, Account> = store
             .get(ACCOUNTS_KEY)
@@ -114,14 +724,14 @@ impl CredentialService {
 
         // Save encrypted accounts
         store.set(ACCOUNTS_KEY.to_string(), serde_json::to_value(&accounts)?);
-        store.set(VERSION_KEY.to_string(), serde_json::to_value(CURRENT_VERSION)?);
+        store.set(VERSION_KEY.to_string(), serde_json::json!(3));
 
         // Clean up any leftover legacy keys (may exist from incomplete v1->v2 migration)
-        store.delete("claude");
-        store.delete("codex");
-        store.delete("gemini");
+        for key in LEGACY_KEYS {
+            store.delete(key);
+        }
 
-        store.save().map_err(|e| AppError::Store(e.to_string()))?;
+        store.persist()?;
 
         log::info!("Migration to v3 complete. {} accounts encrypted.", accounts.len());
         Ok(())
@@ -129,9 +739,7 @@ impl CredentialService {
 
     /// Migrate from v1 (flat provider credentials) to v2 (account-based)
     fn migrate_v1_to_v2(app: &AppHandle) -> Result<(), AppError> {
-        let store = app
-            .store(STORE_FILE)
-            .map_err(|e| AppError::Store(e.to_string()))?;
+        let store = app.data_store(STORE_FILE)?;
 
         let mut accounts: HashMap<String, Account> = HashMap::new();
 
@@ -146,6 +754,9 @@ impl CredentialService {
                         provider: "claude".to_string(),
                         credentials: creds,
                         created_at: Utc::now(),
+                        tags: Vec::new(),
+                        display_name: None,
+                        extra_headers: BTreeMap::new(),
                     };
                     log::info!("Migrating Claude credentials to account: {}", account.id);
                     accounts.insert(account.id.clone(), account);
@@ -155,14 +766,14 @@ impl CredentialService {
 
         // Save new format
         store.set(ACCOUNTS_KEY.to_string(), serde_json::to_value(&accounts)?);
-        store.set(VERSION_KEY.to_string(), serde_json::to_value(CURRENT_VERSION)?);
+        store.set(VERSION_KEY.to_string(), serde_json::json!(2));
 
         // Clean up old format keys
-        store.delete("claude");
-        store.delete("codex");
-        store.delete("gemini");
+        for key in LEGACY_KEYS {
+            store.delete(key);
+        }
 
-        store.save().map_err(|e| AppError::Store(e.to_string()))?;
+        store.persist()?;
         log::info!("Migration complete. {} accounts migrated.", accounts.len());
 
         Ok(())
@@ -172,22 +783,12 @@ impl CredentialService {
     pub fn list_accounts(app: &AppHandle, provider: &str) -> Result<Vec<Account>, AppError> {
         Self::ensure_migrated(app)?;
 
-        let store = app
-            .store(STORE_FILE)
-            .map_err(|e| AppError::Store(e.to_string()))?;
-
-        let accounts: HashMap<String, Account> = store
-            .get(ACCOUNTS_KEY)
-            .and_then(|v| serde_json::from_value(v.clone()).ok())
-            .unwrap_or_default();
+        let accounts = Self::read_accounts(app)?;
 
         let filtered: Vec<Account> = accounts
             .into_values()
             .filter(|a| a.provider == provider)
-            .map(|mut a| {
-                a.credentials = Self::decrypt_credentials(&a.credentials);
-                a
-            })
+            .map(|a| Self::decrypt_account(&a))
             .collect();
 
         Ok(filtered)
@@ -197,149 +798,832 @@ impl CredentialService {
     pub fn get_account(app: &AppHandle, account_id: &str) -> Result<Option<Account>, AppError> {
         Self::ensure_migrated(app)?;
 
-        let store = app
-            .store(STORE_FILE)
-            .map_err(|e| AppError::Store(e.to_string()))?;
-
-        let accounts: HashMap<String, Account> = store
-            .get(ACCOUNTS_KEY)
-            .and_then(|v| serde_json::from_value(v.clone()).ok())
-            .unwrap_or_default();
+        let accounts = Self::read_accounts(app)?;
 
-        Ok(accounts.get(account_id).cloned().map(|mut a| {
-            a.credentials = Self::decrypt_credentials(&a.credentials);
-            a
-        }))
+        Ok(accounts.get(account_id).map(Self::decrypt_account))
     }
 
     /// Save (create or update) an account (encrypts credentials)
     pub fn save_account(app: &AppHandle, account: &Account) -> Result<(), AppError> {
         Self::ensure_migrated(app)?;
 
-        let store = app
-            .store(STORE_FILE)
-            .map_err(|e| AppError::Store(e.to_string()))?;
-
-        let mut accounts: HashMap<String, Account> = store
-            .get(ACCOUNTS_KEY)
-            .and_then(|v| serde_json::from_value(v.clone()).ok())
-            .unwrap_or_default();
-
-        // Encrypt credentials before storing
-        let mut encrypted_account = account.clone();
-        encrypted_account.credentials = Self::encrypt_credentials(&account.credentials);
-        accounts.insert(account.id.clone(), encrypted_account);
+        let lock = Self::store_lock(app);
+        let guard = lock.lock().unwrap_or_else(PoisonError::into_inner);
+        let store = app.data_store(STORE_FILE)?;
 
-        store.set(ACCOUNTS_KEY.to_string(), serde_json::to_value(&accounts)?);
-        store.save().map_err(|e| AppError::Store(e.to_string()))?;
+        Self::insert_account(&*store, account)?;
+        persist_unlocked(&*store, guard)?;
 
         log::info!("Saved account: {} ({})", account.name, account.id);
         Ok(())
     }
 
-    /// Check if any accounts exist for a provider (without decrypting credentials)
-    pub fn has_accounts(app: &AppHandle, provider: &str) -> Result<bool, AppError> {
-        Self::ensure_migrated(app)?;
+    /// Encrypt an account's credentials and add it to a store, replacing any with its ID
+    ///
+    /// The caller holds the store lock and persists the store.
+    pub(crate) fn insert_account(
+        store: &impl KeyValueStore,
+        account: &Account,
+    ) -> Result<(), AppError> {
+        let mut accounts = Self::load_accounts(store)?;
 
-        let store = app
-            .store(STORE_FILE)
-            .map_err(|e| AppError::Store(e.to_string()))?;
+        // Keep the failover list in step with the (possibly edited) primary key
+        let mut credentials = account.credentials.clone();
+        if let Some(claude) = credentials.claude_mut() {
+            claude.sync_session_keys();
+        }
 
-        let accounts: HashMap<String, Account> = store
-            .get(ACCOUNTS_KEY)
-            .and_then(|v| serde_json::from_value(v.clone()).ok())
-            .unwrap_or_default();
+        // Encrypt credentials and header values before storing
+        let encrypted_account = Self::encrypt_account(&Account {
+            credentials,
+            ..account.clone()
+        });
+        accounts.insert(account.id.clone(), encrypted_account);
 
-        Ok(accounts.values().any(|a| a.provider == provider))
+        Self::set_accounts(store, &accounts)
     }
 
-    /// Delete an account by ID
-    pub fn delete_account(app: &AppHandle, account_id: &str) -> Result<(), AppError> {
+    /// Remove an account from a store, returning whether it was there
+    ///
+    /// The caller holds the store lock and persists the store.
+    pub(crate) fn remove_account(
+        store: &impl KeyValueStore,
+        account_id: &str,
+    ) -> Result<bool, AppError> {
+        let mut accounts = Self::load_accounts(store)?;
+        if accounts.remove(account_id).is_none() {
+            return Ok(false);
+        }
+
+        Self::set_accounts(store, &accounts)?;
+        Ok(true)
+    }
+
+    /// Promote the session key at `index` after failover, demoting the keys that failed
+    pub fn promote_session_key(
+        app: &AppHandle,
+        account_id: &str,
+        index: usize,
+    ) -> Result<(), AppError> {
         Self::ensure_migrated(app)?;
 
-        let store = app
-            .store(STORE_FILE)
-            .map_err(|e| AppError::Store(e.to_string()))?;
+        let lock = Self::store_lock(app);
+        let guard = lock.lock().unwrap_or_else(PoisonError::into_inner);
+        let store = app.data_store(STORE_FILE)?;
 
-        let mut accounts: HashMap<String, Account> = store
-            .get(ACCOUNTS_KEY)
-            .and_then(|v| serde_json::from_value(v.clone()).ok())
-            .unwrap_or_default();
+        let mut accounts = Self::read_accounts(app)?;
+        let account = accounts
+            .get_mut(account_id)
+            .ok_or_else(|| AppError::AccountNotFound(account_id.to_string()))?;
 
-        if accounts.remove(account_id).is_some() {
-            store.set(ACCOUNTS_KEY.to_string(), serde_json::to_value(&accounts)?);
-            store.save().map_err(|e| AppError::Store(e.to_string()))?;
-            log::info!("Deleted account: {}", account_id);
+        // Reordering works on the ciphertexts directly; nothing is decrypted
+        if let Some(credentials) = account.credentials.claude_mut() {
+            credentials.promote_session_key(index);
         }
+        Self::set_accounts(&*store, &accounts)?;
+        persist_unlocked(&*store, guard)?;
 
+        log::info!("Promoted session key {} for account {}", index, account_id);
         Ok(())
     }
 
-    /// Validate Claude credentials format
-    pub fn validate_claude(credentials: &Credentials) -> bool {
-        // Claude requires org_id and session_key
-        let has_org_id = credentials
-            .org_id
-            .as_ref()
-            .map(|s| !s.trim().is_empty())
-            .unwrap_or(false);
+    /// Duplicate an existing account under a new name with a fresh ID
+    ///
+    /// Credentials are decrypted from the source and re-encrypted on save.
+    /// History is keyed by account ID, so the copy starts with none.
+    pub fn duplicate_account(
+        app: &AppHandle,
+        account_id: &str,
+        new_name: &str,
+    ) -> Result<Account, AppError> {
+        let source = Self::get_account(app, account_id)?
+            .ok_or_else(|| AppError::AccountNotFound(account_id.to_string()))?;
 
-        let has_session_key = credentials
-            .session_key
-            .as_ref()
-            .map(|s| !s.trim().is_empty())
-            .unwrap_or(false);
+        let copy = Self::copy_account(&source, new_name);
+        Self::save_account(app, &copy)?;
 
-        has_org_id && has_session_key
+        log::info!("Duplicated account {} into {} ({})", account_id, copy.name, copy.id);
+        Ok(copy)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Fold `secondary_id` into `primary_id` and delete the secondary account
+    ///
+    /// The primary keeps its own credentials; only the secondary's history moves
+    /// over. Returns how many history snapshots were reassigned.
+    pub fn merge_accounts(
+        app: &AppHandle,
+        primary_id: &str,
+        secondary_id: &str,
+    ) -> Result<usize, AppError> {
+        let primary = Self::get_account(app, primary_id)?
+            .ok_or_else(|| AppError::AccountNotFound(primary_id.to_string()))?;
+        let secondary = Self::get_account(app, secondary_id)?
+            .ok_or_else(|| AppError::AccountNotFound(secondary_id.to_string()))?;
 
-    #[test]
-    fn validate_claude_with_valid_credentials() {
-        let creds = Credentials {
-            org_id: Some("org-123".to_string()),
-            session_key: Some("sk-ant-xxx".to_string()),
-        };
-        assert!(CredentialService::validate_claude(&creds));
+        Self::check_mergeable(&primary, &secondary)?;
+
+        let moved =
+            HistoryService::reassign_account(app, &secondary.id, &primary.id, &primary.name)?;
+        Self::delete_account(app, &secondary.id)?;
+
+        log::info!(
+            "Merged account {} into {} ({} history entries moved)",
+            secondary.id,
+            primary.id,
+            moved
+        );
+        Ok(moved)
     }
 
-    #[test]
-    fn validate_claude_missing_org_id() {
-        let creds = Credentials {
-            org_id: None,
-            session_key: Some("sk-ant-xxx".to_string()),
-        };
-        assert!(!CredentialService::validate_claude(&creds));
+    /// Reject merges of an account into itself or across providers
+    fn check_mergeable(primary: &Account, secondary: &Account) -> Result<(), AppError> {
+        if primary.id == secondary.id {
+            return Err(AppError::InvalidInput(
+                "Cannot merge an account into itself".to_string(),
+            ));
+        }
+        if primary.provider != secondary.provider {
+            return Err(AppError::InvalidInput(format!(
+                "Cannot merge a {} account into a {} account",
+                secondary.provider, primary.provider
+            )));
+        }
+        Ok(())
     }
 
-    #[test]
-    fn validate_claude_missing_session_key() {
-        let creds = Credentials {
+    /// Build a copy of an account with a new ID, name, and creation time
+    fn copy_account(source: &Account, new_name: &str) -> Account {
+        Account {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: new_name.to_string(),
+            provider: source.provider.clone(),
+            credentials: source.credentials.clone(),
+            created_at: Utc::now(),
+            tags: source.tags.clone(),
+            // The copy gets a new name, so a copied nickname would only mislabel it
+            display_name: None,
+            extra_headers: source.extra_headers.clone(),
+        }
+    }
+
+    /// Update account metadata without touching the stored (encrypted) credentials
+    pub fn update_account_fields(
+        app: &AppHandle,
+        account_id: &str,
+        name: Option<String>,
+        tags: Option<Vec<String>>,
+    ) -> Result<(), AppError> {
+        Self::ensure_migrated(app)?;
+
+        let lock = Self::store_lock(app);
+        let guard = lock.lock().unwrap_or_else(PoisonError::into_inner);
+        let store = app.data_store(STORE_FILE)?;
+
+        let mut accounts = Self::read_accounts(app)?;
+        let account = accounts
+            .get_mut(account_id)
+            .ok_or_else(|| AppError::AccountNotFound(account_id.to_string()))?;
+
+        Self::apply_field_updates(account, name, tags);
+        Self::set_accounts(&*store, &accounts)?;
+        persist_unlocked(&*store, guard)?;
+
+        log::info!("Updated fields for account: {}", account_id);
+        Ok(())
+    }
+
+    /// Apply the provided metadata updates, leaving everything else as-is
+    fn apply_field_updates(account: &mut Account, name: Option<String>, tags: Option<Vec<String>>) {
+        if let Some(name) = name {
+            account.name = name;
+        }
+        if let Some(tags) = tags {
+            account.tags = tags;
+        }
+    }
+
+    /// Set or clear (with None or a blank string) the nickname used in notifications
+    pub fn set_display_name(
+        app: &AppHandle,
+        account_id: &str,
+        display_name: Option<String>,
+    ) -> Result<(), AppError> {
+        Self::ensure_migrated(app)?;
+
+        let lock = Self::store_lock(app);
+        let guard = lock.lock().unwrap_or_else(PoisonError::into_inner);
+        let store = app.data_store(STORE_FILE)?;
+
+        let mut accounts = Self::read_accounts(app)?;
+        let account = accounts
+            .get_mut(account_id)
+            .ok_or_else(|| AppError::AccountNotFound(account_id.to_string()))?;
+
+        account.display_name = display_name
+            .map(|nickname| nickname.trim().to_string())
+            .filter(|nickname| !nickname.is_empty());
+        Self::set_accounts(&*store, &accounts)?;
+        persist_unlocked(&*store, guard)?;
+
+        log::info!("Updated display name for account: {}", account_id);
+        Ok(())
+    }
+
+    /// Check if any accounts exist for a provider (without decrypting credentials)
+    pub fn has_accounts(app: &AppHandle, provider: &str) -> Result<bool, AppError> {
+        Self::ensure_migrated(app)?;
+
+        let accounts = Self::read_accounts(app)?;
+
+        Ok(accounts.values().any(|a| a.provider == provider))
+    }
+
+    /// Delete an account by ID
+    pub fn delete_account(app: &AppHandle, account_id: &str) -> Result<(), AppError> {
+        Self::ensure_migrated(app)?;
+
+        let lock = Self::store_lock(app);
+        let guard = lock.lock().unwrap_or_else(PoisonError::into_inner);
+        let store = app.data_store(STORE_FILE)?;
+
+        if Self::remove_account(&*store, account_id)? {
+            persist_unlocked(&*store, guard)?;
+            log::info!("Deleted account: {}", account_id);
+        }
+
+        // Also drops settings and cached responses left from an account that's already gone
+        SettingsService::forget_account(app, account_id)?;
+        ResponseCacheService::forget(app, account_id)?;
+        app.state::<OAuthRefreshLocks>().forget(account_id);
+
+        Ok(())
+    }
+
+    /// Delete every account matching all the given filters in one store write
+    ///
+    /// `archived_only` matches accounts tagged `archived`. With no filter the
+    /// call is rejected unless `all` is set, so a forgotten argument can't wipe
+    /// every account. Returns the removed accounts' IDs.
+    pub fn delete_accounts_where(
+        app: &AppHandle,
+        provider: Option<&str>,
+        tag: Option<&str>,
+        archived_only: bool,
+        all: bool,
+    ) -> Result<Vec<String>, AppError> {
+        Self::ensure_migrated(app)?;
+
+        let lock = Self::store_lock(app);
+        let guard = lock.lock().unwrap_or_else(PoisonError::into_inner);
+        let store = app.data_store(STORE_FILE)?;
+
+        let removed = Self::delete_matching(&*store, provider, tag, archived_only, all)?;
+        if !removed.is_empty() {
+            persist_unlocked(&*store, guard)?;
+        }
+        for account_id in &removed {
+            SettingsService::forget_account(app, account_id)?;
+            ResponseCacheService::forget(app, account_id)?;
+            app.state::<OAuthRefreshLocks>().forget(account_id);
+        }
+
+        log::info!("Deleted {} accounts by filter", removed.len());
+        Ok(removed)
+    }
+
+    /// Remove matching accounts from a store, returning their IDs
+    ///
+    /// The caller holds the store lock and persists the store if any were removed.
+    fn delete_matching(
+        store: &impl KeyValueStore,
+        provider: Option<&str>,
+        tag: Option<&str>,
+        archived_only: bool,
+        all: bool,
+    ) -> Result<Vec<String>, AppError> {
+        if provider.is_none() && tag.is_none() && !archived_only && !all {
+            return Err(AppError::InvalidInput(
+                "Pass a provider, tag or archived_only filter, or all: true to delete every account"
+                    .to_string(),
+            ));
+        }
+
+        let mut accounts = Self::load_accounts(store)?;
+        let has_tag = |account: &Account, tag: &str| account.tags.iter().any(|t| t == tag);
+        let removed: Vec<String> = accounts
+            .values()
+            .filter(|a| provider.map_or(true, |p| a.provider == p))
+            .filter(|a| tag.map_or(true, |t| has_tag(a, t)))
+            .filter(|a| !archived_only || has_tag(a, ARCHIVED_TAG))
+            .map(|a| a.id.clone())
+            .collect();
+
+        if !removed.is_empty() {
+            for account_id in &removed {
+                accounts.remove(account_id);
+            }
+            Self::set_accounts(store, &accounts)?;
+        }
+        Ok(removed)
+    }
+
+    /// Trust the accounts store as it is now, signing it afresh
+    ///
+    /// The way out of an integrity failure the user caused (editing `credentials.json`
+    /// by hand, moving it between machines). The current contents are snapshotted
+    /// first. Returns how many accounts were signed.
+    pub fn resign_accounts(app: &AppHandle) -> Result<usize, AppError> {
+        SnapshotService::create(app, SnapshotReason::PreResign)?;
+
+        let lock = Self::store_lock(app);
+        let guard = lock.lock().unwrap_or_else(PoisonError::into_inner);
+        let store = app.data_store(STORE_FILE)?;
+
+        let count = Self::resign_store(&*store)?;
+        persist_unlocked(&*store, guard)?;
+        log::warn!(
+            "Re-signed the accounts store ({} accounts) at the user's request",
+            count
+        );
+        Ok(count)
+    }
+
+    /// Sign whatever accounts a store holds, without checking the old signature
+    ///
+    /// Accounts that no longer parse fail the call rather than being dropped. The
+    /// caller persists the store.
+    pub(crate) fn resign_store(store: &impl KeyValueStore) -> Result<usize, AppError> {
+        let accounts: HashMap<String, Account> = match store.get_value(ACCOUNTS_KEY) {
+            Some(value) => serde_json::from_value(value)?,
+            None => HashMap::new(),
+        };
+
+        Self::set_accounts(store, &accounts)?;
+        Ok(accounts.len())
+    }
+
+    /// Delete every account and reset the store to an empty, signed, current-version state
+    ///
+    /// Skips migration on purpose: a store too broken to migrate should still be resettable.
+    pub fn reset_all(app: &AppHandle) -> Result<(), AppError> {
+        let lock = Self::store_lock(app);
+        let guard = lock.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let store = app.data_store(STORE_FILE)?;
+
+        Self::reset_store(&*store)?;
+        persist_unlocked(&*store, guard)?;
+        log::info!("Deleted all accounts");
+        Ok(())
+    }
+
+    /// Clear every key, including legacy data and migration backups, leaving the save to the caller
+    fn reset_store(store: &impl KeyValueStore) -> Result<(), AppError> {
+        for key in store.key_list() {
+            store.delete_value(&key);
+        }
+
+        store.set_value(VERSION_KEY, serde_json::to_value(CURRENT_VERSION)?);
+        Self::set_accounts(store, &HashMap::new())
+    }
+
+    /// Re-encrypt every account under the current key after the key material changed
+    ///
+    /// Only accounts whose encrypted fields all decrypt with `old` are rewritten; the
+    /// rest are stored exactly as before. Returns the recovered and skipped account IDs.
+    pub fn reencrypt_with_key_material(
+        app: &AppHandle,
+        old: &KeyMaterial,
+    ) -> Result<(Vec<String>, Vec<String>), AppError> {
+        let lock = Self::store_lock(app);
+        let guard = lock.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let store = app.data_store(STORE_FILE)?;
+
+        let result = Self::reencrypt_store(&*store, old)?;
+        persist_unlocked(&*store, guard)?;
+        Ok(result)
+    }
+
+    fn reencrypt_store(
+        store: &impl KeyValueStore,
+        old: &KeyMaterial,
+    ) -> Result<(Vec<String>, Vec<String>), AppError> {
+        let mut accounts: HashMap<String, Account> = store
+            .get_value(ACCOUNTS_KEY)
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default();
+
+        let signature = store
+            .get_value(INTEGRITY_KEY)
+            .and_then(|v| v.as_str().map(String::from));
+
+        // The signature was made with whichever key last wrote the store
+        let ordered: BTreeMap<&String, &Account> = accounts.iter().collect();
+        let bytes = serde_json::to_vec(&ordered)?;
+        let signed_with_old = signature
+            .as_deref()
+            .is_some_and(|sig| crypto::verify_with(old, &bytes, sig));
+        if !signed_with_old {
+            Self::verify_accounts(&accounts, signature.as_deref())?;
+        }
+
+        let mut recovered = Vec::new();
+        let mut skipped = Vec::new();
+
+        for (id, account) in accounts.iter_mut() {
+            match Self::reencrypt_account(account, old) {
+                Some(reencrypted) => {
+                    *account = reencrypted;
+                    recovered.push(id.clone());
+                }
+                None => skipped.push(id.clone()),
+            }
+        }
+
+        recovered.sort();
+        skipped.sort();
+
+        Self::set_accounts(store, &accounts)?;
+        Ok((recovered, skipped))
+    }
+
+    /// Report whether each stored secret (session key, token, API key, header value) is actually encrypted
+    ///
+    /// `encrypt_field` keeps the plaintext when encryption fails, so this is the only
+    /// way to spot secrets that ended up on disk in the clear. Org and project IDs
+    /// aren't secret and are never encrypted, so they aren't listed.
+    pub fn audit_encryption(app: &AppHandle) -> Result<Vec<FieldEncryptionStatus>, AppError> {
+        let store = app.data_store(STORE_FILE)?;
+
+        Self::audit_store(&*store)
+    }
+
+    fn audit_store(store: &impl KeyValueStore) -> Result<Vec<FieldEncryptionStatus>, AppError> {
+        let accounts: BTreeMap<String, Account> = Self::load_accounts(store)?.into_iter().collect();
+
+        let mut statuses = Vec::new();
+        for (id, account) in &accounts {
+            for (field, value) in Self::secret_fields(account) {
+                let scheme = Self::encryption_scheme(value);
+                statuses.push(FieldEncryptionStatus {
+                    account_id: id.clone(),
+                    field,
+                    encrypted: scheme.is_some(),
+                    scheme: scheme.map(String::from),
+                    version: crypto::scheme_version(value),
+                });
+            }
+        }
+
+        Ok(statuses)
+    }
+
+    /// Report the encryption scheme in use and the versions each account's secrets are under
+    pub fn crypto_info(app: &AppHandle) -> Result<CryptoInfo, AppError> {
+        Ok(Self::assemble_crypto_info(&Self::audit_encryption(app)?))
+    }
+
+    /// Group audited fields by account into a `CryptoInfo` for the current scheme
+    fn assemble_crypto_info(statuses: &[FieldEncryptionStatus]) -> CryptoInfo {
+        let mut accounts: BTreeMap<&str, AccountCryptoInfo> = BTreeMap::new();
+        for status in statuses {
+            let account = accounts
+                .entry(status.account_id.as_str())
+                .or_insert_with(|| AccountCryptoInfo {
+                    account_id: status.account_id.clone(),
+                    versions: Vec::new(),
+                    plaintext_fields: 0,
+                    needs_reencryption: false,
+                    unsupported: false,
+                });
+
+            match status.version {
+                Some(version) => {
+                    if !account.versions.contains(&version) {
+                        account.versions.push(version);
+                    }
+                    account.needs_reencryption |= version < crypto::ENCRYPTION_VERSION;
+                    account.unsupported |= version > crypto::ENCRYPTION_VERSION;
+                }
+                None => {
+                    account.plaintext_fields += 1;
+                    account.needs_reencryption = true;
+                }
+            }
+        }
+
+        for account in accounts.values_mut() {
+            account.versions.sort_unstable();
+        }
+
+        CryptoInfo {
+            current_version: crypto::ENCRYPTION_VERSION,
+            kdf: crypto::KDF.to_string(),
+            cipher: crypto::CIPHER.to_string(),
+            // No keychain backend yet; keys are always derived from machine info
+            keychain_active: false,
+            accounts: accounts.into_values().collect(),
+        }
+    }
+
+    /// Encrypt any session keys that were stored as plaintext
+    ///
+    /// Returns the fields that are encrypted now but weren't before.
+    pub fn reencrypt_plaintext_fields(
+        app: &AppHandle,
+    ) -> Result<Vec<FieldEncryptionStatus>, AppError> {
+        Self::ensure_migrated(app)?;
+
+        let lock = Self::store_lock(app);
+        let guard = lock.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let store = app.data_store(STORE_FILE)?;
+
+        let encrypted = Self::encrypt_plaintext_in_store(&*store)?;
+        persist_unlocked(&*store, guard)?;
+        Ok(encrypted)
+    }
+
+    fn encrypt_plaintext_in_store(
+        store: &impl KeyValueStore,
+    ) -> Result<Vec<FieldEncryptionStatus>, AppError> {
+        let before = Self::audit_store(store)?;
+        if before.iter().all(|status| status.encrypted) {
+            return Ok(Vec::new());
+        }
+
+        let mut accounts = Self::load_accounts(store)?;
+        for account in accounts.values_mut() {
+            *account = Self::encrypt_account(account);
+        }
+        Self::set_accounts(store, &accounts)?;
+
+        let after = Self::audit_store(store)?;
+        Ok(after
+            .into_iter()
+            .filter(|status| {
+                status.encrypted
+                    && before.iter().any(|b| {
+                        !b.encrypted && b.account_id == status.account_id && b.field == status.field
+                    })
+            })
+            .collect())
+    }
+
+    /// Move encrypted fields from the old key to the current one, or None if any fail to decrypt
+    fn reencrypt_account(account: &Account, old: &KeyMaterial) -> Option<Account> {
+        let reencrypt = |value: &String| -> Option<String> {
+            match value.strip_prefix(ENCRYPTED_PREFIX) {
+                Some(encrypted) => {
+                    let plain = crypto::decrypt_with(old, encrypted).ok()?;
+                    let fresh = crypto::encrypt(&plain).ok()?;
+                    Some(format!("{}{}", ENCRYPTED_PREFIX, fresh))
+                }
+                None => Some(value.clone()),
+            }
+        };
+
+        Self::map_account_secrets(account, reencrypt)
+    }
+
+    /// Credentials to fetch with, filling gaps from the environment when the user allows it
+    pub fn resolve_for_fetch(app: &AppHandle, account: &Account) -> Result<Credentials, AppError> {
+        if account.provider != "claude" {
+            return Ok(account.credentials.clone());
+        }
+
+        let allow_env = SettingsService::get(app)
+            .map(|s| s.allow_env_credentials)
+            .unwrap_or(false);
+
+        Ok(ClaudeProvider::resolve_credentials(
+            &account.credentials,
+            allow_env,
+            |name| std::env::var(name).ok(),
+        )?)
+    }
+
+    /// Refresh an OAuth access token that is about to expire, saving the rotated pair
+    ///
+    /// The old refresh token stops working once exchanged, so refreshes are
+    /// serialized per account: whoever gets the lock second reads the pair the
+    /// first one saved instead of spending the old token again. The new pair is
+    /// saved before `credentials` is updated for the fetch, and a failed save
+    /// is returned rather than leaving the only copy of the token in memory.
+    pub async fn refresh_oauth_for_fetch(
+        app: &AppHandle,
+        account: &Account,
+        credentials: &mut Credentials,
+    ) -> Result<(), AppError> {
+        if credentials
+            .claude()
+            .and_then(|c| c.oauth.as_ref())
+            .is_none()
+        {
+            return Ok(());
+        }
+
+        let lock = app.state::<OAuthRefreshLocks>().for_account(&account.id);
+        let _guard = lock.lock().await;
+
+        // Another fetch may have rotated the tokens while this one waited
+        let stored = Self::get_account(app, &account.id)?.unwrap_or_else(|| account.clone());
+        let Some(oauth) = stored
+            .credentials
+            .claude()
+            .and_then(|c| c.oauth.clone())
+            .or_else(|| credentials.claude().and_then(|c| c.oauth.clone()))
+        else {
+            return Ok(());
+        };
+
+        let claude = ClaudeProvider::new()?;
+        let refreshed = {
+            let _permit = RequestLimiter::global().acquire().await;
+            claude.refresh_oauth_if_needed(&oauth, Utc::now()).await?
+        };
+
+        let current = match refreshed {
+            Some(rotated) => {
+                let mut updated = stored;
+                if let Some(claude) = updated.credentials.claude_mut() {
+                    claude.oauth = Some(rotated.clone());
+                }
+                Self::save_account(app, &updated).map_err(|e| {
+                    log::error!(
+                        "Failed to save refreshed OAuth tokens for {}: {}",
+                        account.name,
+                        e
+                    );
+                    e
+                })?;
+                rotated
+            }
+            None => oauth,
+        };
+
+        if let Some(claude) = credentials.claude_mut() {
+            claude.oauth = Some(current);
+        }
+        Ok(())
+    }
+
+    /// Validate Claude credentials format
+    pub fn validate_claude(credentials: &Credentials) -> bool {
+        let Credentials::Claude(credentials) = credentials else {
+            return false;
+        };
+
+        // Claude requires org_id and session_key
+        let has_org_id = credentials
+            .org_id
+            .as_ref()
+            .map(|s| !s.trim().is_empty())
+            .unwrap_or(false);
+
+        let has_session_key = credentials
+            .session_key
+            .as_ref()
+            .map(|s| !s.trim().is_empty())
+            .unwrap_or(false);
+
+        has_org_id && has_session_key
+    }
+
+    /// Clean up a pasted session key: whitespace, a `Bearer ` prefix, and surrounding quotes
+    pub fn normalize_session_key(raw: &str) -> String {
+        let mut key = raw.trim();
+
+        loop {
+            let before = key;
+
+            if key.get(..7).is_some_and(|p| p.eq_ignore_ascii_case("bearer ")) {
+                key = key[7..].trim_start();
+            }
+            for quote in ['"', '\'', '`'] {
+                if key.len() >= 2 && key.starts_with(quote) && key.ends_with(quote) {
+                    key = key[1..key.len() - 1].trim();
+                }
+            }
+
+            if key == before {
+                return key.to_string();
+            }
+        }
+    }
+
+    /// Heuristic check that a (normalized) key looks like a Claude session key
+    pub fn looks_like_claude_key(key: &str) -> bool {
+        key.starts_with(CLAUDE_KEY_PREFIX)
+            && key.len() >= MIN_CLAUDE_KEY_LEN
+            && !key.chars().any(char::is_whitespace)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::key_value::MemoryStore;
+
+    #[test]
+    fn validate_claude_with_valid_credentials() {
+        let creds = Credentials::Claude(ClaudeCredentials {
+            org_id: Some("org-123".to_string()),
+            session_key: Some("sk-ant-xxx".to_string()),
+            session_keys: Vec::new(),
+            oauth: None,
+        });
+        assert!(CredentialService::validate_claude(&creds));
+    }
+
+    #[test]
+    fn normalize_session_key_trims_whitespace() {
+        assert_eq!(
+            CredentialService::normalize_session_key("  sk-ant-sid01-abc\n\t"),
+            "sk-ant-sid01-abc"
+        );
+    }
+
+    #[test]
+    fn normalize_session_key_strips_bearer_prefix() {
+        assert_eq!(
+            CredentialService::normalize_session_key("Bearer sk-ant-sid01-abc"),
+            "sk-ant-sid01-abc"
+        );
+        assert_eq!(
+            CredentialService::normalize_session_key(" bearer   sk-ant-sid01-abc "),
+            "sk-ant-sid01-abc"
+        );
+    }
+
+    #[test]
+    fn normalize_session_key_strips_quotes() {
+        assert_eq!(
+            CredentialService::normalize_session_key("\"sk-ant-sid01-abc\""),
+            "sk-ant-sid01-abc"
+        );
+        assert_eq!(
+            CredentialService::normalize_session_key("'Bearer \"sk-ant-sid01-abc\"'"),
+            "sk-ant-sid01-abc"
+        );
+        // A lone quote is left alone rather than guessed at
+        assert_eq!(
+            CredentialService::normalize_session_key("\"sk-ant-sid01-abc"),
+            "\"sk-ant-sid01-abc"
+        );
+    }
+
+    #[test]
+    fn looks_like_claude_key_checks_prefix_and_length() {
+        let key = format!("sk-ant-sid01-{}", "a".repeat(40));
+        assert!(CredentialService::looks_like_claude_key(&key));
+        assert!(!CredentialService::looks_like_claude_key("sk-ant-short"));
+        assert!(!CredentialService::looks_like_claude_key(&format!("sk-xyz-{}", "a".repeat(40))));
+        assert!(!CredentialService::looks_like_claude_key(&format!("sk-ant-{} b", "a".repeat(40))));
+    }
+
+    #[test]
+    fn validate_claude_missing_org_id() {
+        let creds = Credentials::Claude(ClaudeCredentials {
+            org_id: None,
+            session_key: Some("sk-ant-xxx".to_string()),
+            session_keys: Vec::new(),
+            oauth: None,
+        });
+        assert!(!CredentialService::validate_claude(&creds));
+    }
+
+    #[test]
+    fn validate_claude_missing_session_key() {
+        let creds = Credentials::Claude(ClaudeCredentials {
             org_id: Some("org-123".to_string()),
             session_key: None,
-        };
+            session_keys: Vec::new(),
+            oauth: None,
+        });
         assert!(!CredentialService::validate_claude(&creds));
     }
 
     #[test]
     fn validate_claude_empty_org_id() {
-        let creds = Credentials {
+        let creds = Credentials::Claude(ClaudeCredentials {
             org_id: Some("".to_string()),
             session_key: Some("sk-ant-xxx".to_string()),
-        };
+            session_keys: Vec::new(),
+            oauth: None,
+        });
         assert!(!CredentialService::validate_claude(&creds));
     }
 
     #[test]
     fn validate_claude_whitespace_only() {
-        let creds = Credentials {
+        let creds = Credentials::Claude(ClaudeCredentials {
             org_id: Some("   ".to_string()),
             session_key: Some("sk-ant-xxx".to_string()),
-        };
+            session_keys: Vec::new(),
+            oauth: None,
+        });
         assert!(!CredentialService::validate_claude(&creds));
     }
 
@@ -348,4 +1632,668 @@ mod tests {
         let creds = Credentials::default();
         assert!(!CredentialService::validate_claude(&creds));
     }
+
+    #[test]
+    fn copy_account_generates_new_id() {
+        let source = Account {
+            id: "source-id".to_string(),
+            name: "Personal".to_string(),
+            provider: "claude".to_string(),
+            credentials: Credentials::Claude(ClaudeCredentials {
+                org_id: Some("org-123".to_string()),
+                session_key: Some("sk-ant-xxx".to_string()),
+                session_keys: Vec::new(),
+                oauth: None,
+            }),
+            created_at: Utc::now() - chrono::Duration::days(3),
+            tags: vec!["team".to_string()],
+            display_name: Some("Team".to_string()),
+            extra_headers: BTreeMap::new(),
+        };
+
+        let copy = CredentialService::copy_account(&source, "Work");
+
+        assert_ne!(copy.id, source.id);
+        assert!(uuid::Uuid::parse_str(&copy.id).is_ok());
+        assert_eq!(copy.name, "Work");
+        assert_eq!(copy.provider, source.provider);
+        assert_eq!(
+            copy.credentials.claude().unwrap().org_id,
+            source.credentials.claude().unwrap().org_id
+        );
+        assert_eq!(
+            copy.credentials.claude().unwrap().session_key,
+            source.credentials.claude().unwrap().session_key
+        );
+        assert!(copy.created_at > source.created_at);
+        assert_eq!(copy.tags, source.tags);
+        assert_eq!(copy.display_name, None);
+    }
+
+    fn make_account(id: &str, name: &str) -> Account {
+        Account {
+            id: id.to_string(),
+            name: name.to_string(),
+            provider: "claude".to_string(),
+            credentials: Credentials::Claude(ClaudeCredentials {
+                org_id: Some("org-123".to_string()),
+                session_key: Some("enc:v1:c2VjcmV0".to_string()),
+                session_keys: Vec::new(),
+                oauth: None,
+            }),
+            created_at: Utc::now(),
+            tags: Vec::new(),
+            display_name: None,
+            extra_headers: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn merge_rejects_accounts_of_different_providers() {
+        let primary = make_account("a", "Personal");
+        let secondary = Account {
+            provider: "openai".to_string(),
+            ..make_account("b", "Work")
+        };
+
+        let err = CredentialService::check_mergeable(&primary, &secondary).unwrap_err();
+        assert!(matches!(err, AppError::InvalidInput(_)));
+
+        assert!(CredentialService::check_mergeable(&primary, &make_account("b", "Work")).is_ok());
+        assert!(CredentialService::check_mergeable(&primary, &primary).is_err());
+    }
+
+    #[test]
+    fn accounts_signature_roundtrip() {
+        let mut accounts = HashMap::new();
+        accounts.insert("a".to_string(), make_account("a", "Personal"));
+        accounts.insert("b".to_string(), make_account("b", "Work"));
+
+        let signature = CredentialService::sign_accounts(&accounts).unwrap();
+        assert!(CredentialService::verify_accounts(&accounts, Some(&signature)).is_ok());
+
+        // Serialization is canonical, so a rebuilt map verifies too
+        let rebuilt: HashMap<String, Account> =
+            serde_json::from_value(serde_json::to_value(&accounts).unwrap()).unwrap();
+        assert!(CredentialService::verify_accounts(&rebuilt, Some(&signature)).is_ok());
+    }
+
+    #[test]
+    fn accounts_signature_detects_tampering() {
+        let mut accounts = HashMap::new();
+        accounts.insert("a".to_string(), make_account("a", "Personal"));
+        let signature = CredentialService::sign_accounts(&accounts).unwrap();
+
+        accounts.get_mut("a").unwrap().provider = "codex".to_string();
+
+        match CredentialService::verify_accounts(&accounts, Some(&signature)) {
+            Err(AppError::IntegrityViolation(_)) => {}
+            other => panic!("Expected IntegrityViolation, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn resigning_accepts_a_modified_store() {
+        let store = MemoryStore::default();
+        let mut accounts = HashMap::new();
+        accounts.insert("a".to_string(), make_account("a", "Personal"));
+        CredentialService::store_accounts(&store, &accounts).unwrap();
+
+        // Edited by hand, e.g. to rename an account
+        let mut edited = store.get_value(ACCOUNTS_KEY).unwrap();
+        edited["a"]["name"] = serde_json::json!("Work");
+        store.set_value(ACCOUNTS_KEY, edited);
+        assert!(matches!(
+            CredentialService::load_accounts(&store),
+            Err(AppError::IntegrityViolation(_))
+        ));
+
+        assert_eq!(CredentialService::resign_store(&store).unwrap(), 1);
+        let loaded = CredentialService::load_accounts(&store).unwrap();
+        assert_eq!(loaded["a"].name, "Work");
+
+        // A store that no longer parses is not silently emptied
+        store.set_value(ACCOUNTS_KEY, serde_json::json!({ "a": { "id": 7 } }));
+        assert!(CredentialService::resign_store(&store).is_err());
+    }
+
+    #[test]
+    fn accounts_signature_missing() {
+        let accounts: HashMap<String, Account> = HashMap::new();
+        assert!(matches!(
+            CredentialService::verify_accounts(&accounts, None),
+            Err(AppError::IntegrityViolation(_))
+        ));
+    }
+
+    #[test]
+    fn untagged_v5_accounts_load_as_claude_and_are_resigned_tagged() {
+        // Exactly what v5 wrote and signed: credentials without a `type` tag
+        let legacy = concat!(
+            r#"{"a":{"id":"a","name":"Personal","provider":"claude","#,
+            r#""credentials":{"org_id":"org-123","session_key":"enc:v1:c2VjcmV0"},"#,
+            r#""createdAt":"2025-01-01T00:00:00Z"}}"#
+        );
+        let store = MemoryStore::default();
+        store.set_value(ACCOUNTS_KEY, serde_json::from_str(legacy).unwrap());
+        store.set_value(
+            INTEGRITY_KEY,
+            serde_json::json!(crypto::sign(legacy.as_bytes())),
+        );
+        store.set_value(VERSION_KEY, serde_json::json!(5));
+
+        let accounts = CredentialService::load_accounts(&store).unwrap();
+        let claude = accounts["a"].credentials.claude().unwrap();
+        assert_eq!(claude.org_id.as_deref(), Some("org-123"));
+
+        CredentialService::store_accounts(&store, &accounts).unwrap();
+        assert_eq!(
+            store.get_value(ACCOUNTS_KEY).unwrap()["a"]["credentials"]["type"],
+            "claude"
+        );
+        let reloaded = CredentialService::load_accounts(&store).unwrap();
+        assert_eq!(reloaded["a"].credentials.provider(), "claude");
+    }
+
+    #[test]
+    fn api_keys_are_encrypted_and_audited() {
+        let store = MemoryStore::default();
+        let mut codex = make_account("a", "Codex");
+        codex.provider = "codex".to_string();
+        codex.credentials = Credentials::Codex(CodexCredentials {
+            api_key: Some("sk-proj-abc".to_string()),
+        });
+        let mut gemini = make_account("b", "Gemini");
+        gemini.provider = "gemini".to_string();
+        gemini.credentials = Credentials::Gemini(GeminiCredentials {
+            api_key: Some("AIza-abc".to_string()),
+            project_id: Some("my-project".to_string()),
+        });
+
+        let accounts: HashMap<String, Account> = [codex, gemini]
+            .into_iter()
+            .map(|mut account| {
+                account.credentials = CredentialService::encrypt_credentials(&account.credentials);
+                (account.id.clone(), account)
+            })
+            .collect();
+        CredentialService::store_accounts(&store, &accounts).unwrap();
+
+        let statuses = CredentialService::audit_store(&store).unwrap();
+        assert_eq!(statuses.len(), 2);
+        assert!(statuses.iter().all(|s| s.field == "apiKey" && s.encrypted));
+
+        let loaded = CredentialService::load_accounts(&store).unwrap();
+        match CredentialService::decrypt_account(&loaded["b"]).credentials {
+            Credentials::Gemini(gemini) => {
+                assert_eq!(gemini.api_key.as_deref(), Some("AIza-abc"));
+                assert_eq!(gemini.project_id.as_deref(), Some("my-project"));
+            }
+            other => panic!("Expected Gemini credentials, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn migration_plan_from_v1() {
+        let input = MigrationInput {
+            version: 1,
+            legacy_keys: vec!["claude".to_string(), "codex".to_string()],
+            legacy_claude: Some(Credentials::Claude(ClaudeCredentials {
+                org_id: Some("org-123".to_string()),
+                session_key: Some("sk-ant-xxx".to_string()),
+                session_keys: Vec::new(),
+                oauth: None,
+            })),
+            accounts: HashMap::new(),
+        };
+
+        let plan = CredentialService::plan_migration(&input);
+
+        assert_eq!(plan.current_version, 1);
+        assert_eq!(plan.target_version, CURRENT_VERSION);
+        let versions: Vec<(u32, u32)> = plan
+            .steps
+            .iter()
+            .map(|s| (s.from_version, s.to_version))
+            .collect();
+        assert_eq!(versions, vec![(1, 2), (2, 3), (3, 4), (4, 5), (5, 6)]);
+
+        assert_eq!(
+            plan.steps[0].actions,
+            vec![
+                "convert legacy Claude credentials into 1 account".to_string(),
+                "remove legacy keys (claude, codex)".to_string(),
+            ]
+        );
+        assert_eq!(plan.steps[1].actions, vec!["encrypt 1 account".to_string()]);
+        assert_eq!(
+            plan.steps[2].actions,
+            vec!["sign 1 account with an integrity HMAC".to_string()]
+        );
+        assert_eq!(
+            plan.steps[3].actions,
+            vec!["move session keys into failover lists for 1 account".to_string()]
+        );
+        assert_eq!(
+            plan.steps[4].actions,
+            vec!["tag credentials of 1 account as Claude".to_string()]
+        );
+    }
+
+    #[test]
+    fn migration_plan_from_v2_counts_plaintext() {
+        let mut accounts = HashMap::new();
+        let mut plain = make_account("a", "Personal");
+        plain.credentials.claude_mut().unwrap().session_key = Some("sk-ant-plain".to_string());
+        accounts.insert("a".to_string(), plain);
+        accounts.insert("b".to_string(), make_account("b", "Work"));
+
+        let input = MigrationInput {
+            version: 2,
+            legacy_keys: vec![],
+            legacy_claude: None,
+            accounts,
+        };
+
+        let plan = CredentialService::plan_migration(&input);
+
+        assert_eq!(plan.steps.len(), 4);
+        assert_eq!(plan.steps[0].actions, vec!["encrypt 1 account".to_string()]);
+        assert_eq!(
+            plan.steps[1].actions,
+            vec!["sign 2 accounts with an integrity HMAC".to_string()]
+        );
+    }
+
+    #[test]
+    fn migration_plan_up_to_date() {
+        let input = MigrationInput {
+            version: CURRENT_VERSION,
+            legacy_keys: vec![],
+            legacy_claude: None,
+            accounts: HashMap::new(),
+        };
+
+        assert!(CredentialService::plan_migration(&input).steps.is_empty());
+    }
+
+    #[test]
+    fn field_updates_leave_ciphertext_untouched() {
+        let mut account = make_account("a", "Personal");
+        account.credentials =
+            CredentialService::encrypt_credentials(&Credentials::Claude(ClaudeCredentials {
+                org_id: Some("org-123".to_string()),
+                session_key: Some("sk-ant-secret".to_string()),
+                session_keys: Vec::new(),
+                oauth: None,
+            }));
+        let stored_before = serde_json::to_vec(&account.credentials).unwrap();
+
+        CredentialService::apply_field_updates(
+            &mut account,
+            Some("Work".to_string()),
+            Some(vec!["team".to_string()]),
+        );
+
+        assert_eq!(account.name, "Work");
+        assert_eq!(account.tags, vec!["team".to_string()]);
+        assert_eq!(serde_json::to_vec(&account.credentials).unwrap(), stored_before);
+    }
+
+    #[test]
+    fn field_updates_skip_missing_values() {
+        let mut account = make_account("a", "Personal");
+        account.tags = vec!["home".to_string()];
+
+        CredentialService::apply_field_updates(&mut account, None, None);
+
+        assert_eq!(account.name, "Personal");
+        assert_eq!(account.tags, vec!["home".to_string()]);
+    }
+
+    fn v2_store() -> MemoryStore {
+        let store = MemoryStore::default();
+        store.set_value(ACCOUNTS_KEY, serde_json::json!({ "acc-1": { "name": "Personal" } }));
+        store.set_value(VERSION_KEY, serde_json::json!(2));
+        store.set_value("claude", serde_json::json!({ "org_id": "org-123" }));
+        store
+    }
+
+    #[test]
+    fn failed_migration_step_restores_store() {
+        let store = v2_store();
+        let before = store.values.borrow().clone();
+
+        let result = CredentialService::run_migration_step(&store, 3, || {
+            // Fail after some keys were already rewritten
+            store.set_value(ACCOUNTS_KEY, serde_json::json!({}));
+            store.set_value(INTEGRITY_KEY, serde_json::json!("partial"));
+            store.delete_value("claude");
+            Err(AppError::Store("disk full".to_string()))
+        });
+
+        assert!(matches!(result, Err(AppError::Store(_))));
+        assert_eq!(*store.values.borrow(), before);
+        assert!(store.get_value(".pre-v3.bak").is_none());
+    }
+
+    #[test]
+    fn successful_migration_step_drops_backup() {
+        let store = v2_store();
+
+        CredentialService::run_migration_step(&store, 3, || {
+            store.set_value(VERSION_KEY, serde_json::json!(3));
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(store.get_value(VERSION_KEY), Some(serde_json::json!(3)));
+        assert!(store.get_value(".pre-v3.bak").is_none());
+    }
+
+    #[test]
+    fn leftover_backup_is_restored_on_retry() {
+        let store = v2_store();
+        let before = store.values.borrow().clone();
+
+        // Simulate a crash after the snapshot and a partial write
+        CredentialService::backup_migration_keys(&store, 3).unwrap();
+        store.set_value(VERSION_KEY, serde_json::json!(4));
+        store.delete_value(ACCOUNTS_KEY);
+
+        CredentialService::restore_leftover_backups(&store).unwrap();
+
+        assert_eq!(*store.values.borrow(), before);
+    }
+
+    #[test]
+    fn reset_store_leaves_no_accounts() {
+        let store = MemoryStore::default();
+        let mut accounts = HashMap::new();
+        accounts.insert("a".to_string(), make_account("a", "Personal"));
+        CredentialService::store_accounts(&store, &accounts).unwrap();
+        store.set_value("claude", serde_json::json!({ "org_id": "org-123" }));
+
+        CredentialService::reset_store(&store).unwrap();
+
+        assert!(CredentialService::load_accounts(&store).unwrap().is_empty());
+        assert!(store.get_value("claude").is_none());
+        assert_eq!(store.get_value(VERSION_KEY), Some(serde_json::json!(CURRENT_VERSION)));
+    }
+
+    #[test]
+    fn delete_matching_filters_by_provider_and_tag() {
+        let store = MemoryStore::default();
+        let mut accounts = HashMap::new();
+        for (id, provider, tags) in [
+            ("a", "claude", vec!["test"]),
+            ("b", "claude", vec!["test", ARCHIVED_TAG]),
+            ("c", "codex", vec!["test"]),
+            ("d", "claude", vec![]),
+        ] {
+            let mut account = make_account(id, id);
+            account.provider = provider.to_string();
+            account.tags = tags.into_iter().map(String::from).collect();
+            accounts.insert(id.to_string(), account);
+        }
+        CredentialService::store_accounts(&store, &accounts).unwrap();
+
+        let removed =
+            CredentialService::delete_matching(&store, Some("codex"), None, false, false).unwrap();
+        assert_eq!(removed, vec!["c"]);
+
+        let removed =
+            CredentialService::delete_matching(&store, None, Some("test"), true, false).unwrap();
+        assert_eq!(removed, vec!["b"]);
+
+        let mut left: Vec<_> = CredentialService::load_accounts(&store)
+            .unwrap()
+            .into_keys()
+            .collect();
+        left.sort();
+        assert_eq!(left, vec!["a", "d"]);
+
+        // Nothing matched, so the caller has nothing to save
+        let removed =
+            CredentialService::delete_matching(&store, Some("gemini"), None, false, false).unwrap();
+        assert!(removed.is_empty());
+    }
+
+    #[test]
+    fn delete_matching_requires_a_filter_or_all() {
+        let store = MemoryStore::default();
+        let mut accounts = HashMap::new();
+        accounts.insert("a".to_string(), make_account("a", "Personal"));
+        accounts.insert("b".to_string(), make_account("b", "Work"));
+        CredentialService::store_accounts(&store, &accounts).unwrap();
+
+        assert!(matches!(
+            CredentialService::delete_matching(&store, None, None, false, false),
+            Err(AppError::InvalidInput(_))
+        ));
+        assert_eq!(CredentialService::load_accounts(&store).unwrap().len(), 2);
+
+        let removed = CredentialService::delete_matching(&store, None, None, false, true).unwrap();
+        assert_eq!(removed.len(), 2);
+        assert!(CredentialService::load_accounts(&store).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rotated_oauth_tokens_persist_encrypted() {
+        let store = MemoryStore::default();
+        let rotated = OAuthCredentials {
+            access_token: "access-new".to_string(),
+            refresh_token: "refresh-new".to_string(),
+            expires_at: Utc::now() + chrono::Duration::hours(8),
+        };
+        let mut account = make_account("a", "Personal");
+        account.credentials.claude_mut().unwrap().oauth = Some(rotated.clone());
+        account.credentials = CredentialService::encrypt_credentials(&account.credentials);
+
+        let mut accounts = HashMap::new();
+        accounts.insert("a".to_string(), account);
+        CredentialService::store_accounts(&store, &accounts).unwrap();
+
+        let statuses = CredentialService::audit_store(&store).unwrap();
+        for field in ["oauth.accessToken", "oauth.refreshToken"] {
+            assert!(statuses.iter().any(|s| s.field == field && s.encrypted));
+        }
+
+        let loaded = CredentialService::load_accounts(&store).unwrap();
+        let credentials = CredentialService::decrypt_account(&loaded["a"]).credentials;
+        assert_eq!(credentials.claude().unwrap().oauth, Some(rotated));
+    }
+
+    #[test]
+    fn extra_header_values_are_encrypted_and_audited() {
+        let store = MemoryStore::default();
+        let mut account = make_account("a", "Personal");
+        account
+            .extra_headers
+            .insert("X-Gateway-Token".to_string(), "gw-secret".to_string());
+        CredentialService::insert_account(&store, &account).unwrap();
+
+        let loaded = CredentialService::load_accounts(&store).unwrap();
+        assert!(loaded["a"].extra_headers["X-Gateway-Token"].starts_with(ENCRYPTED_PREFIX));
+        let statuses = CredentialService::audit_store(&store).unwrap();
+        assert!(statuses
+            .iter()
+            .any(|s| s.field == "extraHeaders.X-Gateway-Token" && s.encrypted));
+
+        let decrypted = CredentialService::decrypt_account(&loaded["a"]);
+        assert_eq!(decrypted.extra_headers["X-Gateway-Token"], "gw-secret");
+    }
+
+    #[test]
+    fn reencrypt_recovers_accounts_after_user_rename() {
+        let current = KeyMaterial::current();
+        let old = KeyMaterial {
+            username: format!("{}-old", current.username),
+            home: format!("{}-old", current.home),
+        };
+        let old_field = |plain: &str| {
+            format!(
+                "{}{}",
+                ENCRYPTED_PREFIX,
+                crypto::encrypt_with(&old, plain).unwrap()
+            )
+        };
+
+        let mut renamed = make_account("a", "Personal");
+        renamed.credentials.claude_mut().unwrap().session_key = Some(old_field("sk-ant-first"));
+        renamed.credentials.claude_mut().unwrap().session_keys =
+            vec![old_field("sk-ant-first"), old_field("sk-ant-second")];
+
+        // Written under some other key entirely - must survive untouched
+        let mut foreign = make_account("b", "Work");
+        foreign.credentials.claude_mut().unwrap().session_key = Some("enc:v1:c2VjcmV0".to_string());
+
+        let mut accounts = HashMap::new();
+        accounts.insert("a".to_string(), renamed);
+        accounts.insert("b".to_string(), foreign.clone());
+
+        // A store written before USER/HOME changed: encrypted and signed with the old material
+        let store = MemoryStore::default();
+        let ordered: BTreeMap<&String, &Account> = accounts.iter().collect();
+        let signature = crypto::sign_with(&old, &serde_json::to_vec(&ordered).unwrap());
+        store.set_value(ACCOUNTS_KEY, serde_json::to_value(&accounts).unwrap());
+        store.set_value(INTEGRITY_KEY, serde_json::json!(signature));
+        assert!(CredentialService::load_accounts(&store).is_err());
+
+        let (recovered, skipped) = CredentialService::reencrypt_store(&store, &old).unwrap();
+        assert_eq!(recovered, vec!["a".to_string()]);
+        assert_eq!(skipped, vec!["b".to_string()]);
+
+        let loaded = CredentialService::load_accounts(&store).unwrap();
+        let decrypted = CredentialService::decrypt_account(&loaded["a"]).credentials;
+        let decrypted = decrypted.claude().unwrap();
+        assert_eq!(decrypted.session_key.as_deref(), Some("sk-ant-first"));
+        assert_eq!(
+            decrypted.session_keys,
+            vec!["sk-ant-first", "sk-ant-second"]
+        );
+        assert_eq!(
+            loaded["b"].credentials.claude().unwrap().session_key,
+            foreign.credentials.claude().unwrap().session_key
+        );
+    }
+
+    #[test]
+    fn plaintext_session_key_is_detected_and_encrypted() {
+        let mut leaked = make_account("a", "Personal");
+        leaked.credentials.claude_mut().unwrap().session_key = Some("sk-ant-plaintext".to_string());
+        leaked.credentials.claude_mut().unwrap().session_keys = vec![
+            "sk-ant-plaintext".to_string(),
+            "enc:v2:c2VjcmV0".to_string(),
+        ];
+
+        let store = MemoryStore::default();
+        let mut accounts = HashMap::new();
+        accounts.insert("a".to_string(), leaked);
+        accounts.insert("b".to_string(), make_account("b", "Work"));
+        CredentialService::store_accounts(&store, &accounts).unwrap();
+
+        let audit = CredentialService::audit_store(&store).unwrap();
+        let plaintext: Vec<_> = audit
+            .iter()
+            .filter(|s| !s.encrypted)
+            .map(|s| (s.account_id.as_str(), s.field.as_str()))
+            .collect();
+        assert_eq!(
+            plaintext,
+            vec![("a", "sessionKey"), ("a", "sessionKeys[0]")]
+        );
+        assert!(audit
+            .iter()
+            .any(|s| s.field == "sessionKeys[1]" && s.scheme.as_deref() == Some("enc:v2")));
+
+        let fixed = CredentialService::encrypt_plaintext_in_store(&store).unwrap();
+        assert_eq!(fixed.len(), 2);
+        assert!(CredentialService::audit_store(&store)
+            .unwrap()
+            .iter()
+            .all(|s| s.encrypted));
+
+        let loaded = CredentialService::load_accounts(&store).unwrap();
+        let decrypted = CredentialService::decrypt_account(&loaded["a"]).credentials;
+        let decrypted = decrypted.claude().unwrap();
+        assert_eq!(decrypted.session_key.as_deref(), Some("sk-ant-plaintext"));
+        assert_eq!(
+            loaded["a"].credentials.claude().unwrap().session_keys[1],
+            "enc:v2:c2VjcmV0",
+            "already-encrypted fields must not be touched"
+        );
+
+        assert!(CredentialService::encrypt_plaintext_in_store(&store)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn crypto_info_groups_versions_per_account() {
+        let status = |account_id: &str, field: &str, scheme: Option<&str>| FieldEncryptionStatus {
+            account_id: account_id.to_string(),
+            field: field.to_string(),
+            encrypted: scheme.is_some(),
+            scheme: scheme.map(String::from),
+            version: scheme.and_then(|s| crypto::scheme_version(&format!("{}:", s))),
+        };
+        let statuses = vec![
+            status("b", "sessionKey", Some("enc:v1")),
+            status("a", "sessionKey", Some("enc:v2")),
+            status("a", "sessionKeys[0]", Some("enc:v1")),
+            status("a", "sessionKeys[1]", Some("enc:v2")),
+            status("c", "sessionKey", None),
+        ];
+
+        let info = CredentialService::assemble_crypto_info(&statuses);
+        assert_eq!(info.current_version, crypto::ENCRYPTION_VERSION);
+        assert_eq!(info.kdf, crypto::KDF);
+        assert!(!info.keychain_active);
+
+        let summary: Vec<_> = info
+            .accounts
+            .iter()
+            .map(|a| {
+                (
+                    a.account_id.as_str(),
+                    a.versions.clone(),
+                    a.plaintext_fields,
+                    a.needs_reencryption,
+                    a.unsupported,
+                )
+            })
+            .collect();
+        // v2 is newer than this build, so it is unsupported rather than up to date
+        assert_eq!(
+            summary,
+            vec![
+                ("a", vec![1, 2], 0, false, true),
+                ("b", vec![1], 0, false, false),
+                ("c", vec![], 1, true, false),
+            ]
+        );
+
+        assert!(CredentialService::assemble_crypto_info(&[])
+            .accounts
+            .is_empty());
+    }
+
+    #[test]
+    fn reencrypt_rejects_tampered_store() {
+        let old = KeyMaterial {
+            username: "someone-else".to_string(),
+            home: "/home/someone-else".to_string(),
+        };
+        let store = MemoryStore::default();
+        let mut accounts = HashMap::new();
+        accounts.insert("a".to_string(), make_account("a", "Personal"));
+        store.set_value(ACCOUNTS_KEY, serde_json::to_value(&accounts).unwrap());
+        store.set_value(INTEGRITY_KEY, serde_json::json!("bogus"));
+
+        assert!(matches!(
+            CredentialService::reencrypt_store(&store, &old),
+            Err(AppError::IntegrityViolation(_))
+        ));
+    }
 }
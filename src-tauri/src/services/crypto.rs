@@ -1,33 +1,88 @@
+use crate::models::CryptoBenchResult;
 use aes_gcm::{
     aead::{Aead, KeyInit},
     Aes256Gcm, Nonce,
 };
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use hmac::{Hmac, Mac};
 use rand::Rng;
+use sha2::Sha256;
 use std::env;
+use std::time::Instant;
 
 /// Fixed app-specific salt for key derivation
 const APP_SALT: &[u8] = b"ai-pulse-credential-encryption-v1";
 
+/// Salt for the integrity (HMAC) key, kept separate from the encryption key
+const INTEGRITY_SALT: &[u8] = b"ai-pulse-store-integrity-v1";
+
+/// Literal behind `ENCRYPTION_VERSION`, so the prefix can be built from it at compile time
+macro_rules! encryption_version {
+    () => {
+        1
+    };
+}
+
+/// Version written into the `enc:vN:` prefix of newly encrypted values
+pub const ENCRYPTION_VERSION: u32 = encryption_version!();
+
+/// Prefix of values encrypted under `ENCRYPTION_VERSION`
+pub const ENCRYPTED_PREFIX: &str = concat!("enc:v", encryption_version!(), ":");
+
+/// Key derivation used by `ENCRYPTION_VERSION`
+pub const KDF: &str = "xor-fold-1000";
+
+/// Cipher used by `ENCRYPTION_VERSION`
+pub const CIPHER: &str = "AES-256-GCM";
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Machine-specific inputs to key derivation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyMaterial {
+    pub username: String,
+    pub home: String,
+}
+
+impl KeyMaterial {
+    /// Key material from the current environment (`USER`/`HOME`, or their Windows equivalents)
+    pub fn current() -> Self {
+        let username = env::var("USER")
+            .or_else(|_| env::var("USERNAME"))
+            .unwrap_or_else(|_| "default-user".to_string());
+
+        let home = env::var("HOME")
+            .or_else(|_| env::var("USERPROFILE"))
+            .unwrap_or_else(|_| "/unknown".to_string());
+
+        Self { username, home }
+    }
+}
+
 /// Derives a 256-bit encryption key from machine-specific info
 /// This provides encryption at rest without requiring user interaction
 fn derive_key() -> [u8; 32] {
+    derive_key_with_salt(APP_SALT)
+}
+
+/// Derives a 256-bit key from machine-specific info and the given salt
+fn derive_key_with_salt(salt: &[u8]) -> [u8; 32] {
+    derive_key_from(salt, &KeyMaterial::current())
+}
+
+/// Derives a 256-bit key from explicit key material and the given salt
+fn derive_key_from(salt: &[u8], material: &KeyMaterial) -> [u8; 32] {
     // Combine multiple sources for key material:
     // 1. App-specific salt
     // 2. Username (machine-specific)
     // 3. Home directory (machine-specific)
-    let username = env::var("USER")
-        .or_else(|_| env::var("USERNAME"))
-        .unwrap_or_else(|_| "default-user".to_string());
-
-    let home = env::var("HOME")
-        .or_else(|_| env::var("USERPROFILE"))
-        .unwrap_or_else(|_| "/unknown".to_string());
+    let username = &material.username;
+    let home = &material.home;
 
     // Simple key derivation using repeated hashing
     // For production, consider using a proper KDF like PBKDF2 or Argon2
     let mut key_material = Vec::new();
-    key_material.extend_from_slice(APP_SALT);
+    key_material.extend_from_slice(salt);
     key_material.extend_from_slice(username.as_bytes());
     key_material.extend_from_slice(home.as_bytes());
 
@@ -60,8 +115,11 @@ fn derive_key() -> [u8; 32] {
 /// Encrypts a string value using AES-256-GCM
 /// Returns a base64-encoded string containing the nonce and ciphertext
 pub fn encrypt(plaintext: &str) -> Result<String, String> {
-    let key = derive_key();
-    let cipher = Aes256Gcm::new_from_slice(&key)
+    encrypt_with_key(&derive_key(), plaintext)
+}
+
+fn encrypt_with_key(key: &[u8; 32], plaintext: &str) -> Result<String, String> {
+    let cipher = Aes256Gcm::new_from_slice(key)
         .map_err(|e| format!("Failed to create cipher: {}", e))?;
 
     // Generate a random 12-byte nonce
@@ -83,8 +141,16 @@ pub fn encrypt(plaintext: &str) -> Result<String, String> {
 
 /// Decrypts a base64-encoded encrypted string
 pub fn decrypt(encrypted: &str) -> Result<String, String> {
-    let key = derive_key();
-    let cipher = Aes256Gcm::new_from_slice(&key)
+    decrypt_with_key(&derive_key(), encrypted)
+}
+
+/// Decrypts a value encrypted under different (e.g. pre-rename) key material
+pub fn decrypt_with(material: &KeyMaterial, encrypted: &str) -> Result<String, String> {
+    decrypt_with_key(&derive_key_from(APP_SALT, material), encrypted)
+}
+
+fn decrypt_with_key(key: &[u8; 32], encrypted: &str) -> Result<String, String> {
+    let cipher = Aes256Gcm::new_from_slice(key)
         .map_err(|e| format!("Failed to create cipher: {}", e))?;
 
     // Decode from base64
@@ -108,6 +174,118 @@ pub fn decrypt(encrypted: &str) -> Result<String, String> {
     String::from_utf8(plaintext).map_err(|e| format!("Invalid UTF-8: {}", e))
 }
 
+/// Version a stored value was encrypted under, from its `enc:vN:` prefix
+///
+/// None for plaintext, including look-alikes such as `enc:v:` or `enc:vx:`.
+pub fn scheme_version(value: &str) -> Option<u32> {
+    let (digits, _) = value.strip_prefix("enc:v")?.split_once(':')?;
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+/// Computes a base64-encoded HMAC-SHA256 over the given data
+pub fn sign(data: &[u8]) -> String {
+    let key = derive_key_with_salt(INTEGRITY_SALT);
+    let mut mac =
+        <HmacSha256 as Mac>::new_from_slice(&key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    BASE64.encode(mac.finalize().into_bytes())
+}
+
+/// Encrypts under explicit key material (tests simulate a store written before a rename)
+#[cfg(test)]
+pub fn encrypt_with(material: &KeyMaterial, plaintext: &str) -> Result<String, String> {
+    encrypt_with_key(&derive_key_from(APP_SALT, material), plaintext)
+}
+
+/// Signs under explicit key material
+#[cfg(test)]
+pub fn sign_with(material: &KeyMaterial, data: &[u8]) -> String {
+    let key = derive_key_from(INTEGRITY_SALT, material);
+    let mut mac =
+        <HmacSha256 as Mac>::new_from_slice(&key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    BASE64.encode(mac.finalize().into_bytes())
+}
+
+/// Verifies a base64-encoded HMAC-SHA256 signature in constant time
+pub fn verify(data: &[u8], signature: &str) -> bool {
+    verify_with(&KeyMaterial::current(), data, signature)
+}
+
+/// Verifies a signature made under the given key material
+pub fn verify_with(material: &KeyMaterial, data: &[u8], signature: &str) -> bool {
+    let expected = match BASE64.decode(signature) {
+        Ok(bytes) => bytes,
+        Err(_) => return false,
+    };
+
+    let key = derive_key_from(INTEGRITY_SALT, material);
+    let mut mac =
+        <HmacSha256 as Mac>::new_from_slice(&key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.verify_slice(&expected).is_ok()
+}
+
+/// Compare a secret given by a client with the expected one in constant time
+///
+/// Both sides are hashed first, so the comparison is always over 32 bytes and
+/// takes the same time however much of the secret matched.
+pub fn secrets_match(given: &[u8], expected: &[u8]) -> bool {
+    use sha2::Digest;
+
+    let (given, expected) = (Sha256::digest(given), Sha256::digest(expected));
+    given
+        .iter()
+        .zip(expected.iter())
+        .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+        == 0
+}
+
+/// A random 256-bit token, hex-encoded, for authenticating local clients
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Time `iterations` encrypt+decrypt round trips (at least one), key derivation included
+pub fn benchmark_round_trips(iterations: usize) -> Result<CryptoBenchResult, String> {
+    let plaintext = "sk-ant-REDACTED";
+
+    let mut micros = Vec::with_capacity(iterations.max(1));
+    for _ in 0..iterations.max(1) {
+        let started = Instant::now();
+        let decrypted = decrypt(&encrypt(plaintext)?)?;
+        micros.push(started.elapsed().as_secs_f64() * 1_000_000.0);
+
+        if decrypted != plaintext {
+            return Err("Round trip returned a different value".to_string());
+        }
+    }
+
+    // Report the round trips actually timed, not the count asked for
+    let count = micros.len();
+    micros.sort_by(f64::total_cmp);
+    let mid = count / 2;
+    let median = if count % 2 == 0 {
+        (micros[mid - 1] + micros[mid]) / 2.0
+    } else {
+        micros[mid]
+    };
+
+    Ok(CryptoBenchResult {
+        iterations: count,
+        kdf: KDF.to_string(),
+        average_micros: micros.iter().sum::<f64>() / count as f64,
+        median_micros: median,
+        min_micros: micros[0],
+        max_micros: micros[count - 1],
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -142,6 +320,33 @@ mod tests {
         assert_eq!(decrypt(&encrypted2).unwrap(), original);
     }
 
+    #[test]
+    fn generated_tokens_are_unique_and_match_only_themselves() {
+        let token = generate_token();
+        assert_eq!(token.len(), 64);
+        assert_ne!(token, generate_token());
+
+        assert!(secrets_match(token.as_bytes(), token.as_bytes()));
+        assert!(!secrets_match(&token.as_bytes()[..63], token.as_bytes()));
+        assert!(!secrets_match(b"", token.as_bytes()));
+    }
+
+    #[test]
+    fn benchmark_runs_the_requested_round_trips() {
+        let result = benchmark_round_trips(5).unwrap();
+
+        assert_eq!(result.iterations, 5);
+        assert_eq!(result.kdf, KDF);
+        assert!(result.min_micros > 0.0);
+        assert!(result.min_micros <= result.median_micros);
+        assert!(result.median_micros <= result.max_micros);
+        assert!(result.average_micros >= result.min_micros);
+        assert!(result.average_micros <= result.max_micros);
+
+        // Zero still times one round trip, and says so
+        assert_eq!(benchmark_round_trips(0).unwrap().iterations, 1);
+    }
+
     #[test]
     fn test_decrypt_invalid_base64() {
         let result = decrypt("not-valid-base64!!!");
@@ -170,4 +375,53 @@ mod tests {
         let result = decrypt(&tampered);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_sign_verify_roundtrip() {
+        let data = b"{\"accounts\":{}}";
+        let signature = sign(data);
+
+        assert!(verify(data, &signature));
+        assert_eq!(sign(data), signature);
+    }
+
+    #[test]
+    fn test_verify_detects_modified_data() {
+        let signature = sign(b"original");
+        assert!(!verify(b"modified", &signature));
+        assert!(!verify(b"original", "not-valid-base64!!!"));
+    }
+
+    #[test]
+    fn test_integrity_key_differs_from_encryption_key() {
+        assert_ne!(derive_key(), derive_key_with_salt(INTEGRITY_SALT));
+    }
+
+    #[test]
+    fn test_scheme_version_from_prefix() {
+        assert_eq!(ENCRYPTED_PREFIX, format!("enc:v{}:", ENCRYPTION_VERSION));
+        assert_eq!(scheme_version(ENCRYPTED_PREFIX), Some(ENCRYPTION_VERSION));
+        assert_eq!(scheme_version("enc:v1:c2VjcmV0"), Some(1));
+        assert_eq!(scheme_version("enc:v12:c2VjcmV0"), Some(12));
+        assert_eq!(scheme_version("enc:v2:"), Some(2));
+        assert_eq!(scheme_version("sk-ant-plaintext"), None);
+        assert_eq!(scheme_version("enc:v:c2VjcmV0"), None);
+        assert_eq!(scheme_version("enc:vx:c2VjcmV0"), None);
+        assert_eq!(scheme_version("enc:v1"), None);
+        assert_eq!(scheme_version("ENC:V1:c2VjcmV0"), None);
+    }
+
+    #[test]
+    fn test_decrypt_with_old_key_material() {
+        let current = KeyMaterial::current();
+        let old = KeyMaterial {
+            username: format!("{}-before-rename", current.username),
+            home: "/Users/old-name".to_string(),
+        };
+
+        let encrypted = encrypt_with(&old, "secret").unwrap();
+
+        assert!(decrypt(&encrypted).is_err());
+        assert_eq!(decrypt_with(&old, &encrypted).unwrap(), "secret");
+    }
 }
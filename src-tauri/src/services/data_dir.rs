@@ -0,0 +1,199 @@
+use crate::error::AppError;
+use crate::services::key_value::KeyValueStore;
+use crate::services::settings;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::{Manager, Runtime};
+use tauri_plugin_store::{Store, StoreExt};
+
+/// Environment variable that moves every store to another directory
+pub const DATA_DIR_ENV: &str = "AI_PULSE_DATA_DIR";
+
+/// Key in the default settings store holding the data directory override
+///
+/// It lives outside `app_settings` because it has to be read before the
+/// relocated settings store can be found.
+const OVERRIDE_KEY: &str = "data_dir";
+
+/// Probe file written to check that a directory is writable
+const PROBE_FILE: &str = ".ai-pulse-write-test";
+
+/// Directory holding the stores, history file and snapshots, resolved once at startup
+pub struct DataDir(PathBuf);
+
+impl DataDir {
+    /// Resolve the data directory from the environment and the saved override
+    pub fn from_app<R: Runtime>(app: &impl Manager<R>) -> Result<Self, AppError> {
+        let default = app.path().app_data_dir()?;
+        let env_override = std::env::var(DATA_DIR_ENV).ok();
+        let settings_override = Self::saved_override(app);
+
+        Ok(Self(Self::resolve(
+            env_override.as_deref(),
+            settings_override.as_deref(),
+            &default,
+        )))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    /// Pick the directory to use: the env var, then the saved override, then the default
+    ///
+    /// Blank overrides are ignored. An override that is relative or can't be
+    /// written to is logged and skipped.
+    pub fn resolve(
+        env_override: Option<&str>,
+        settings_override: Option<&str>,
+        default: &Path,
+    ) -> PathBuf {
+        let candidates = [
+            (DATA_DIR_ENV, env_override),
+            ("data_dir setting", settings_override),
+        ];
+
+        for (source, value) in candidates {
+            let Some(value) = value.map(str::trim).filter(|v| !v.is_empty()) else {
+                continue;
+            };
+            match Self::check_writable(Path::new(value)) {
+                Ok(()) => {
+                    log::info!("Using data directory {} (from {})", value, source);
+                    return PathBuf::from(value);
+                }
+                Err(e) => log::error!(
+                    "Data directory {} (from {}) is not usable, falling back: {}",
+                    value,
+                    source,
+                    e
+                ),
+            }
+        }
+
+        default.to_path_buf()
+    }
+
+    /// Create the directory if needed and confirm a file can be written in it
+    pub fn check_writable(dir: &Path) -> io::Result<()> {
+        if !dir.is_absolute() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "path must be absolute",
+            ));
+        }
+        fs::create_dir_all(dir)?;
+        let probe = dir.join(PROBE_FILE);
+        fs::write(&probe, b"ok")?;
+        fs::remove_file(&probe)
+    }
+
+    /// Read the override saved in the default settings store
+    pub fn saved_override<R: Runtime>(app: &impl Manager<R>) -> Option<String> {
+        let store = app.store(settings::STORE_FILE).ok()?;
+        store
+            .get(OVERRIDE_KEY)
+            .and_then(|v| v.as_str().map(str::to_string))
+    }
+
+    /// Save (or with None, clear) the override; it takes effect on the next launch
+    pub fn set_saved_override<R: Runtime>(
+        app: &impl Manager<R>,
+        dir: Option<&str>,
+    ) -> Result<(), AppError> {
+        let store = app.store(settings::STORE_FILE)?;
+        match dir.map(str::trim).filter(|d| !d.is_empty()) {
+            Some(dir) => {
+                Self::check_writable(Path::new(dir)).map_err(|e| {
+                    AppError::InvalidInput(format!("Data directory {} is not usable: {}", dir, e))
+                })?;
+                store.set(OVERRIDE_KEY, serde_json::json!(dir));
+            }
+            None => {
+                store.delete(OVERRIDE_KEY);
+            }
+        }
+        store.persist()?;
+        Ok(())
+    }
+}
+
+/// Open stores and files inside the resolved data directory
+pub trait DataDirExt<R: Runtime> {
+    /// Path of a file in the data directory
+    fn data_path(&self, file: &str) -> Result<PathBuf, AppError>;
+
+    /// Open a store file in the data directory
+    fn data_store(&self, file: &str) -> Result<Arc<Store<R>>, AppError>;
+}
+
+impl<R: Runtime, M: Manager<R>> DataDirExt<R> for M {
+    fn data_path(&self, file: &str) -> Result<PathBuf, AppError> {
+        match self.try_state::<DataDir>() {
+            Some(dir) => Ok(dir.path().join(file)),
+            None => Ok(self.path().app_data_dir()?.join(file)),
+        }
+    }
+
+    fn data_store(&self, file: &str) -> Result<Arc<Store<R>>, AppError> {
+        Ok(self.store(self.data_path(file)?)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "ai-pulse-data-dir-{}-{}",
+            name,
+            uuid::Uuid::new_v4()
+        ))
+    }
+
+    #[test]
+    fn resolve_honors_overrides_and_falls_back() {
+        let default = temp_dir("default");
+        let from_env = temp_dir("env");
+        let from_settings = temp_dir("settings");
+        let env = from_env.to_str().unwrap();
+        let setting = from_settings.to_str().unwrap();
+
+        assert_eq!(
+            DataDir::resolve(Some(env), Some(setting), &default),
+            from_env
+        );
+        assert!(from_env.is_dir());
+        assert_eq!(
+            DataDir::resolve(None, Some(setting), &default),
+            from_settings
+        );
+        assert_eq!(
+            DataDir::resolve(Some("  "), Some(setting), &default),
+            from_settings
+        );
+        assert_eq!(DataDir::resolve(None, None, &default), default);
+
+        // A directory under a regular file can't be created
+        let file = temp_dir("file");
+        fs::write(&file, b"").unwrap();
+        let unusable = file.join("data");
+        assert_eq!(
+            DataDir::resolve(unusable.to_str(), Some(setting), &default),
+            from_settings
+        );
+        assert_eq!(DataDir::resolve(unusable.to_str(), None, &default), default);
+        assert_eq!(
+            DataDir::resolve(Some("relative/dir"), None, &default),
+            default
+        );
+
+        for dir in [&from_env, &from_settings] {
+            fs::remove_dir_all(dir).unwrap();
+        }
+        fs::remove_file(&file).unwrap();
+    }
+}
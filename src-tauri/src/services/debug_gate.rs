@@ -0,0 +1,208 @@
+use crate::error::AppError;
+use crate::services::crypto::secrets_match;
+use sha2::{Digest, Sha256};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Hex SHA-256 of the passphrase that unlocks debug commands in release builds, set at build time
+///
+/// Only the digest is compiled in, so the passphrase can't be read out of the
+/// binary. Without it, release builds can't unlock debug commands at all.
+const UNLOCK_PASSPHRASE_SHA256: Option<&str> = option_env!("AI_PULSE_DEBUG_PASSPHRASE_SHA256");
+
+/// Failed unlocks allowed before further attempts have to wait
+const FREE_ATTEMPTS: u32 = 3;
+
+/// Longest wait imposed after repeated failed unlocks
+const MAX_UNLOCK_DELAY: Duration = Duration::from_secs(300);
+
+/// Whether debug/raw commands (raw fetches, simulated errors, benchmarks) may run
+///
+/// Always open in debug builds. Release builds start locked and are opened for a
+/// support session with `unlock_debug_commands`; the flag is not persisted.
+pub struct DebugGate {
+    unlocked: AtomicBool,
+    /// Lowercase hex SHA-256 of the passphrase
+    passphrase_sha256: Option<String>,
+    failures: Mutex<UnlockFailures>,
+}
+
+/// Failed unlock attempts since the last success
+#[derive(Default)]
+struct UnlockFailures {
+    count: u32,
+    /// No attempt is checked before this
+    retry_at: Option<Instant>,
+}
+
+impl Default for DebugGate {
+    fn default() -> Self {
+        Self::new(cfg!(debug_assertions), UNLOCK_PASSPHRASE_SHA256)
+    }
+}
+
+impl DebugGate {
+    /// `passphrase_sha256` is the hex digest of the passphrase; with anything else the gate stays shut
+    pub fn new(unlocked: bool, passphrase_sha256: Option<&str>) -> Self {
+        let passphrase_sha256 = passphrase_sha256
+            .map(|digest| digest.trim().to_ascii_lowercase())
+            .filter(|digest| !digest.is_empty());
+        let passphrase_sha256 = match passphrase_sha256 {
+            Some(digest) if digest.len() == 64 && digest.chars().all(|c| c.is_ascii_hexdigit()) => {
+                Some(digest)
+            }
+            Some(_) => {
+                log::warn!("Debug passphrase digest is not a hex SHA-256; unlocking is disabled");
+                None
+            }
+            None => None,
+        };
+
+        Self {
+            unlocked: AtomicBool::new(unlocked),
+            passphrase_sha256,
+            failures: Mutex::new(UnlockFailures::default()),
+        }
+    }
+
+    pub fn is_unlocked(&self) -> bool {
+        self.unlocked.load(Ordering::SeqCst)
+    }
+
+    /// Unlock if `passphrase` matches the configured one
+    pub fn unlock(&self, passphrase: &str) -> Result<(), AppError> {
+        self.unlock_at(passphrase, Instant::now())
+    }
+
+    /// `unlock` as of `now`; after `FREE_ATTEMPTS` failures each further attempt waits twice as long
+    fn unlock_at(&self, passphrase: &str, now: Instant) -> Result<(), AppError> {
+        let Some(expected) = &self.passphrase_sha256 else {
+            return Err(AppError::InvalidInput(
+                "Debug commands can't be unlocked in this build".to_string(),
+            ));
+        };
+
+        let mut failures = self.failures.lock().unwrap_or_else(PoisonError::into_inner);
+        let wait = failures
+            .retry_at
+            .and_then(|at| at.checked_duration_since(now))
+            .filter(|wait| !wait.is_zero());
+        if let Some(wait) = wait {
+            return Err(AppError::RateLimit(format!(
+                "Too many failed attempts; try again in {}s",
+                wait.as_secs().max(1)
+            )));
+        }
+
+        let digest: String = Sha256::digest(passphrase.as_bytes())
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        if !secrets_match(digest.as_bytes(), expected.as_bytes()) {
+            failures.count += 1;
+            if failures.count >= FREE_ATTEMPTS {
+                let doublings = (failures.count - FREE_ATTEMPTS).min(16);
+                let delay = Duration::from_secs(1 << doublings).min(MAX_UNLOCK_DELAY);
+                failures.retry_at = Some(now + delay);
+            }
+            log::warn!("Rejected debug unlock attempt ({} failed)", failures.count);
+            return Err(AppError::InvalidInput("Incorrect passphrase".to_string()));
+        }
+
+        *failures = UnlockFailures::default();
+        self.unlocked.store(true, Ordering::SeqCst);
+        log::info!("Debug commands unlocked");
+        Ok(())
+    }
+
+    pub fn lock(&self) {
+        self.unlocked.store(false, Ordering::SeqCst);
+    }
+
+    /// Allow `command` to run only while unlocked
+    pub fn check(&self, command: &str) -> Result<(), AppError> {
+        if self.is_unlocked() {
+            Ok(())
+        } else {
+            Err(AppError::InvalidInput(format!(
+                "{} is a debug command; unlock debug commands first",
+                command
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digest_of(passphrase: &str) -> String {
+        Sha256::digest(passphrase.as_bytes())
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    #[test]
+    fn gate_allows_commands_only_while_unlocked() {
+        let gate = DebugGate::new(false, Some(&digest_of("support-2026").to_uppercase()));
+        assert!(matches!(
+            gate.check("simulate_session_error"),
+            Err(AppError::InvalidInput(_))
+        ));
+
+        assert!(gate.unlock("support-2025").is_err());
+        assert!(gate.check("simulate_session_error").is_err());
+
+        gate.unlock("support-2026").unwrap();
+        assert!(gate.check("simulate_session_error").is_ok());
+
+        gate.lock();
+        assert!(gate.check("simulate_session_error").is_err());
+    }
+
+    #[test]
+    fn gate_without_a_valid_digest_cannot_be_unlocked() {
+        // A plaintext passphrase in place of its digest is refused too
+        for digest in [None, Some(""), Some("support-2026")] {
+            let gate = DebugGate::new(false, digest);
+            assert!(gate.unlock("").is_err());
+            assert!(gate.unlock("support-2026").is_err());
+            assert!(!gate.is_unlocked());
+        }
+
+        // Debug builds start unlocked and need no passphrase
+        assert!(DebugGate::new(true, None).check("benchmark_crypto").is_ok());
+    }
+
+    #[test]
+    fn repeated_failures_delay_further_attempts() {
+        let gate = DebugGate::new(false, Some(&digest_of("support-2026")));
+        let start = Instant::now();
+
+        for _ in 0..FREE_ATTEMPTS {
+            assert!(matches!(
+                gate.unlock_at("guess", start),
+                Err(AppError::InvalidInput(_))
+            ));
+        }
+        // Even the right passphrase waits out the delay
+        assert!(matches!(
+            gate.unlock_at("support-2026", start),
+            Err(AppError::RateLimit(_))
+        ));
+
+        // Another miss doubles the wait
+        let later = start + Duration::from_secs(1);
+        assert!(gate.unlock_at("guess", later).is_err());
+        assert!(matches!(
+            gate.unlock_at("support-2026", later + Duration::from_secs(1)),
+            Err(AppError::RateLimit(_))
+        ));
+
+        gate.unlock_at("support-2026", later + Duration::from_secs(2))
+            .unwrap();
+        assert!(gate.is_unlocked());
+    }
+}
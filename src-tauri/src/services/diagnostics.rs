@@ -0,0 +1,201 @@
+use crate::models::{DiagnosticCheck, DiagnosticStatus, DiagnosticsReport};
+use crate::services::{credentials, crypto, history, settings, DataDirExt, SchedulerState};
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+use tauri::AppHandle;
+use tauri_plugin_notification::{NotificationExt, PermissionState};
+
+/// Host probed by the network check
+const CLAUDE_HOST_URL: &str = "https://claude.ai";
+
+/// How long the network check waits before giving up
+const NETWORK_TIMEOUT_SECS: u64 = 5;
+
+/// Throwaway value used to confirm encryption round-trips
+const ENCRYPTION_PROBE: &str = "ai-pulse-diagnostics-probe";
+
+pub struct DiagnosticsService;
+
+impl DiagnosticsService {
+    /// Run every check and collect the results into a report
+    pub async fn run(app: &AppHandle, state: &SchedulerState) -> DiagnosticsReport {
+        let mut checks = Self::check_stores(app);
+        checks.push(Self::check_encryption());
+        checks.push(Self::check_notification_permission(app));
+        checks.push(Self::check_scheduler(state).await);
+        checks.push(Self::check_network().await);
+
+        Self::build_report(app.package_info().version.to_string(), Utc::now(), checks)
+    }
+
+    /// Assemble a report, deriving the overall status and text summary
+    pub fn build_report(
+        app_version: String,
+        generated_at: DateTime<Utc>,
+        checks: Vec<DiagnosticCheck>,
+    ) -> DiagnosticsReport {
+        let overall = checks
+            .iter()
+            .map(|c| c.status)
+            .max()
+            .unwrap_or(DiagnosticStatus::Ok);
+
+        let mut summary = format!(
+            "AI Pulse {} diagnostics ({}): {:?}\n",
+            app_version,
+            generated_at.to_rfc3339(),
+            overall
+        );
+        for check in &checks {
+            summary.push_str(&format!("[{:?}] {}: {}\n", check.status, check.name, check.message));
+        }
+
+        DiagnosticsReport {
+            generated_at,
+            app_version,
+            overall,
+            checks,
+            summary,
+        }
+    }
+
+    fn check(name: &str, status: DiagnosticStatus, message: impl Into<String>) -> DiagnosticCheck {
+        DiagnosticCheck {
+            name: name.to_string(),
+            status,
+            message: message.into(),
+        }
+    }
+
+    /// Each store file can be opened and loaded
+    fn check_stores(app: &AppHandle) -> Vec<DiagnosticCheck> {
+        [credentials::STORE_FILE, settings::STORE_FILE, history::STORE_FILE]
+            .into_iter()
+            .map(|file| match app.data_store(file) {
+                Ok(store) => Self::check(
+                    &format!("store:{}", file),
+                    DiagnosticStatus::Ok,
+                    format!("Readable ({} keys)", store.length()),
+                ),
+                Err(e) => Self::check(&format!("store:{}", file), DiagnosticStatus::Fail, e.to_string()),
+            })
+            .collect()
+    }
+
+    /// Encrypting then decrypting a probe value gives it back unchanged
+    pub fn check_encryption() -> DiagnosticCheck {
+        let result = crypto::encrypt(ENCRYPTION_PROBE).and_then(|encrypted| crypto::decrypt(&encrypted));
+
+        match result {
+            Ok(decrypted) if decrypted == ENCRYPTION_PROBE => {
+                Self::check("encryption", DiagnosticStatus::Ok, "Round-trip succeeded")
+            }
+            Ok(_) => Self::check(
+                "encryption",
+                DiagnosticStatus::Fail,
+                "Decrypted value did not match the original",
+            ),
+            Err(e) => Self::check("encryption", DiagnosticStatus::Fail, e),
+        }
+    }
+
+    fn check_notification_permission(app: &AppHandle) -> DiagnosticCheck {
+        match app.notification().permission_state() {
+            Ok(PermissionState::Granted) => {
+                Self::check("notifications", DiagnosticStatus::Ok, "Permission granted")
+            }
+            Ok(PermissionState::Denied) => Self::check(
+                "notifications",
+                DiagnosticStatus::Warn,
+                "Permission denied - alerts will not be shown",
+            ),
+            Ok(state) => Self::check(
+                "notifications",
+                DiagnosticStatus::Warn,
+                format!("Permission not yet granted ({})", state),
+            ),
+            Err(e) => Self::check("notifications", DiagnosticStatus::Fail, e.to_string()),
+        }
+    }
+
+    async fn check_scheduler(state: &SchedulerState) -> DiagnosticCheck {
+        if !state.is_running() {
+            return Self::check("scheduler", DiagnosticStatus::Warn, "Scheduler is stopped");
+        }
+
+        let failed = state.get_failed_accounts().await.len();
+        let message = format!(
+            "Running every {}s, {} account(s) failed last fetch",
+            state.get_interval(),
+            failed
+        );
+        let status = if failed > 0 {
+            DiagnosticStatus::Warn
+        } else {
+            DiagnosticStatus::Ok
+        };
+        Self::check("scheduler", status, message)
+    }
+
+    /// Any HTTP response (even a Cloudflare challenge) means the host is reachable
+    async fn check_network() -> DiagnosticCheck {
+        let client = match reqwest::Client::builder()
+            .timeout(Duration::from_secs(NETWORK_TIMEOUT_SECS))
+            .build()
+        {
+            Ok(client) => client,
+            Err(e) => return Self::check("network", DiagnosticStatus::Fail, e.to_string()),
+        };
+
+        match client.head(CLAUDE_HOST_URL).send().await {
+            Ok(response) => Self::check(
+                "network",
+                DiagnosticStatus::Ok,
+                format!("{} reachable (HTTP {})", CLAUDE_HOST_URL, response.status().as_u16()),
+            ),
+            Err(e) => Self::check(
+                "network",
+                DiagnosticStatus::Fail,
+                format!("{} unreachable: {}", CLAUDE_HOST_URL, e),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encryption_check_round_trips() {
+        let check = DiagnosticsService::check_encryption();
+        assert_eq!(check.status, DiagnosticStatus::Ok);
+        assert_eq!(check.name, "encryption");
+    }
+
+    #[test]
+    fn report_overall_is_worst_check() {
+        let checks = vec![
+            DiagnosticsService::check("a", DiagnosticStatus::Ok, "fine"),
+            DiagnosticsService::check("b", DiagnosticStatus::Warn, "meh"),
+            DiagnosticsService::check("c", DiagnosticStatus::Ok, "fine"),
+        ];
+        let report = DiagnosticsService::build_report("1.0.0".to_string(), Utc::now(), checks);
+        assert_eq!(report.overall, DiagnosticStatus::Warn);
+        assert_eq!(report.checks.len(), 3);
+        assert!(report.summary.contains("[Warn] b: meh"));
+
+        let failing = vec![
+            DiagnosticsService::check("a", DiagnosticStatus::Warn, "meh"),
+            DiagnosticsService::check("b", DiagnosticStatus::Fail, "broken"),
+        ];
+        let report = DiagnosticsService::build_report("1.0.0".to_string(), Utc::now(), failing);
+        assert_eq!(report.overall, DiagnosticStatus::Fail);
+    }
+
+    #[test]
+    fn report_with_no_checks_is_ok() {
+        let report = DiagnosticsService::build_report("1.0.0".to_string(), Utc::now(), Vec::new());
+        assert_eq!(report.overall, DiagnosticStatus::Ok);
+    }
+}
@@ -0,0 +1,178 @@
+use crate::error::AppError;
+use crate::models::{FileAccess, PermissionReport};
+use crate::services::{credentials, DataDirExt};
+use std::path::Path;
+use tauri::AppHandle;
+
+/// Mode `tighten` sets: read and write for the owner only
+#[cfg(unix)]
+pub(crate) const OWNER_ONLY_MODE: u32 = 0o600;
+
+/// Classify permission bits by who besides the owner can read or write the file
+///
+/// Execute bits are ignored; only access to the contents matters here.
+pub fn classify_mode(mode: u32) -> FileAccess {
+    if mode & 0o006 != 0 {
+        FileAccess::WorldReadable
+    } else if mode & 0o060 != 0 {
+        FileAccess::GroupReadable
+    } else {
+        FileAccess::OwnerOnly
+    }
+}
+
+/// Checks that the credentials file isn't open to other users
+///
+/// Stored secrets are encrypted with a key derived on this machine, so a copy
+/// readable by others is only as safe as that key; the file mode is the first
+/// line of defense.
+pub struct FilePermissionService;
+
+impl FilePermissionService {
+    /// Report who can access `credentials.json`
+    pub fn check_credentials(app: &AppHandle) -> Result<PermissionReport, AppError> {
+        let report = Self::check(&app.data_path(credentials::STORE_FILE)?)?;
+        if report.warning {
+            log::warn!("{} ({})", report.message, report.path);
+        }
+        Ok(report)
+    }
+
+    /// Restrict `credentials.json` to its owner (600), then report the result
+    ///
+    /// Does nothing if the file doesn't exist yet or on platforms without Unix modes.
+    pub fn tighten_credentials(app: &AppHandle) -> Result<PermissionReport, AppError> {
+        let path = app.data_path(credentials::STORE_FILE)?;
+        Self::tighten(&path)?;
+        Self::check(&path)
+    }
+
+    #[cfg(unix)]
+    pub fn check(path: &Path) -> Result<PermissionReport, AppError> {
+        use std::os::unix::fs::PermissionsExt;
+
+        match std::fs::metadata(path) {
+            Ok(metadata) => {
+                let mode = metadata.permissions().mode() & 0o777;
+                Ok(Self::report(path, classify_mode(mode), Some(mode)))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Ok(Self::report(path, FileAccess::Missing, None))
+            }
+            Err(e) => Err(AppError::Store(format!(
+                "Failed to read permissions of {}: {}",
+                path.display(),
+                e
+            ))),
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub fn check(path: &Path) -> Result<PermissionReport, AppError> {
+        Ok(Self::report(path, FileAccess::Unsupported, None))
+    }
+
+    #[cfg(unix)]
+    pub fn tighten(path: &Path) -> Result<(), AppError> {
+        use std::os::unix::fs::PermissionsExt;
+
+        if !path.exists() {
+            return Ok(());
+        }
+
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(OWNER_ONLY_MODE)).map_err(
+            |e| {
+                AppError::Store(format!(
+                    "Failed to restrict permissions of {}: {}",
+                    path.display(),
+                    e
+                ))
+            },
+        )?;
+        log::info!("Restricted {} to mode 600", path.display());
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    pub fn tighten(_path: &Path) -> Result<(), AppError> {
+        Ok(())
+    }
+
+    fn report(path: &Path, access: FileAccess, mode: Option<u32>) -> PermissionReport {
+        let message = match access {
+            FileAccess::OwnerOnly => "Only your user can read the credentials file",
+            FileAccess::GroupReadable => {
+                "Other members of the file's group can read the credentials file; restrict it to 600"
+            }
+            FileAccess::WorldReadable => {
+                "Every user on this machine can read the credentials file; restrict it to 600"
+            }
+            FileAccess::Missing => "No credentials file yet; it is created when an account is saved",
+            FileAccess::Unsupported => {
+                "File modes aren't used on this platform; the credentials file follows its folder's access rules"
+            }
+        };
+
+        PermissionReport {
+            path: path.display().to_string(),
+            mode: mode.map(|mode| format!("{:o}", mode)),
+            access,
+            warning: matches!(
+                access,
+                FileAccess::GroupReadable | FileAccess::WorldReadable
+            ),
+            message: message.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modes_classify_by_group_and_world_access() {
+        assert_eq!(classify_mode(0o600), FileAccess::OwnerOnly);
+        assert_eq!(classify_mode(0o400), FileAccess::OwnerOnly);
+        assert_eq!(classify_mode(0o700), FileAccess::OwnerOnly);
+        // Execute alone gives no access to the contents
+        assert_eq!(classify_mode(0o611), FileAccess::OwnerOnly);
+        assert_eq!(classify_mode(0o640), FileAccess::GroupReadable);
+        assert_eq!(classify_mode(0o620), FileAccess::GroupReadable);
+        assert_eq!(classify_mode(0o644), FileAccess::WorldReadable);
+        assert_eq!(classify_mode(0o602), FileAccess::WorldReadable);
+        assert_eq!(classify_mode(0o666), FileAccess::WorldReadable);
+        // File type bits from st_mode are ignored
+        assert_eq!(classify_mode(0o100600), FileAccess::OwnerOnly);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn tighten_restricts_a_world_readable_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!(
+            "ai-pulse-permissions-{}.json",
+            uuid::Uuid::new_v4()
+        ));
+        assert_eq!(
+            FilePermissionService::check(&path).unwrap().access,
+            FileAccess::Missing
+        );
+
+        std::fs::write(&path, "{}").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        let report = FilePermissionService::check(&path).unwrap();
+        assert_eq!(report.access, FileAccess::WorldReadable);
+        assert_eq!(report.mode.as_deref(), Some("644"));
+        assert!(report.warning);
+
+        FilePermissionService::tighten(&path).unwrap();
+        let report = FilePermissionService::check(&path).unwrap();
+        assert_eq!(report.access, FileAccess::OwnerOnly);
+        assert_eq!(report.mode.as_deref(), Some("600"));
+        assert!(!report.warning);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,28 +1,122 @@
 use crate::error::AppError;
 use crate::models::{
-    HistoryMetadata, HistoryQuery, RetentionPolicy, UsageData, UsageHistoryEntry,
+    AccountUsageStats, AnonymizedAccount, AnonymizedPoint, AnonymizedReport, BudgetLevel,
+    BudgetStatus, DayBucket, HistoryAnnotation, HistoryMetadata, HistoryQuery, LimitSpend,
+    LimitUsageStats, RecoveryForecast, ResetSchedule, RetentionPolicy, ScheduleConfidence,
+    SpendEstimate, SpendPeriod, ThresholdCrossing, UnseenChange, UsageData, UsageHistoryEntry,
     UsageLimitSnapshot, UsageStats,
 };
-use chrono::{DateTime, Duration, Utc};
-use tauri::AppHandle;
-use tauri_plugin_store::StoreExt;
+use crate::services::key_value::{persist_unlocked, KeyValueStore};
+use crate::services::{
+    DataDirExt, DisplayTimezone, NotificationService, SettingsService, StoreLocks,
+};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, TimeZone, Timelike, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use sha2::Sha256;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use tauri::{AppHandle, Manager};
 
-const STORE_FILE: &str = "history.json";
-const HISTORY_KEY: &str = "entries";
+pub(crate) const STORE_FILE: &str = "history.json";
+/// Gzip file holding the entries when `AppSettings.compress_history` is on
+pub(crate) const COMPRESSED_FILE: &str = "history.json.gz";
+pub(crate) const HISTORY_KEY: &str = "entries";
 const METADATA_KEY: &str = "metadata";
 const RETENTION_KEY: &str = "retention";
+const LAST_VIEWED_KEY: &str = "last_viewed";
+const ANNOTATIONS_KEY: &str = "annotations";
+const SCRATCH_KEY: &str = "scratch";
+
+/// Prefix of compacted entry IDs, so a bucket never shares an ID with a raw snapshot at its start
+const COMPACTED_ID_PREFIX: &str = "compacted";
+
+/// Account ID and name scratch entries are recorded under, apart from real accounts
+pub const SCRATCH_ACCOUNT: &str = "scratch";
+
+/// Scratch entries kept; the oldest are dropped beyond this
+const MAX_SCRATCH_ENTRIES: usize = 100;
+
+/// Annotations kept; the oldest are dropped beyond this
+const MAX_ANNOTATIONS: usize = 500;
+
+/// Minimum forward movement of `resets_at` that counts as a new window
+pub(crate) const RESET_TOLERANCE_SECS: i64 = 60;
+
+/// Longest heatmap served, for unlimited retention or a longer window
+const MAX_HEATMAP_DAYS: u32 = 366;
+
+/// Observed resets needed before a cadence is inferred (two intervals)
+const MIN_SCHEDULE_RESETS: usize = 3;
+/// Upcoming resets projected by a reset schedule
+const PREDICTED_RESETS: i64 = 3;
+/// Spread of the intervals (standard deviation over mean) allowed for high / medium confidence
+const HIGH_CONFIDENCE_SPREAD: f64 = 0.05;
+const MEDIUM_CONFIDENCE_SPREAD: f64 = 0.2;
+
+/// History used to estimate the burn rate for a recovery forecast
+const RECOVERY_LOOKBACK_DAYS: i64 = 7;
+/// Samples and time span needed before a burn rate is trusted
+const MIN_RECOVERY_SAMPLES: usize = 4;
+const MIN_RECOVERY_SPAN_MINUTES: i64 = 60;
+
+/// Sparkline characters, from empty to full
+const SPARKLINE_BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+type HmacSha256 = Hmac<Sha256>;
 
 pub struct HistoryService;
 
 impl HistoryService {
+    /// Lock guarding read-modify-write sequences on the history store
+    ///
+    /// Entry writes persist before the compressed file is touched, so they keep
+    /// the lock through that save; the trailing metadata save releases it.
+    fn store_lock(app: &AppHandle) -> Arc<Mutex<()>> {
+        app.state::<StoreLocks>().for_file(STORE_FILE)
+    }
+
     /// Add a new usage snapshot to history
     pub fn add_entry(app: &AppHandle, usage_data: &UsageData) -> Result<(), AppError> {
-        let store = app
-            .store(STORE_FILE)
-            .map_err(|e| AppError::Store(e.to_string()))?;
+        let entry = Self::entry_for(usage_data);
+
+        let lock = Self::store_lock(app);
+        let guard = lock.lock().unwrap_or_else(PoisonError::into_inner);
+
+        // Get existing entries
+        let mut entries = Self::get_all_entries(app)?;
+
+        // Avoid duplicate entries (same timestamp and provider)
+        if entries.iter().any(|e| e.id == entry.id) {
+            return Ok(());
+        }
+
+        entries.push(entry);
+
+        // Save entries
+        Self::save_entries(app, &entries)?;
+
+        // Update metadata
+        Self::update_metadata(app, guard)?;
+
+        log::debug!(
+            "Added history entry for provider: {} (total: {})",
+            usage_data.provider,
+            entries.len()
+        );
+
+        Ok(())
+    }
 
-        // Create history entry from usage data
-        let entry = UsageHistoryEntry {
+    /// History entry for a usage snapshot
+    fn entry_for(usage_data: &UsageData) -> UsageHistoryEntry {
+        UsageHistoryEntry {
             id: format!(
                 "{}-{}-{}",
                 usage_data.timestamp.timestamp(),
@@ -40,50 +134,188 @@ impl HistoryService {
                     id: l.id.clone(),
                     utilization: l.utilization,
                     resets_at: l.resets_at,
+                    min_utilization: None,
+                    max_utilization: None,
                 })
                 .collect(),
-        };
+        }
+    }
 
-        // Get existing entries
-        let mut entries = Self::get_all_entries(app)?;
+    /// Record a fetch made with unsaved credentials in the scratch bucket
+    ///
+    /// Scratch entries are labeled `SCRATCH_ACCOUNT` and kept apart from the
+    /// real history, so stats, exports and charts never see them.
+    pub fn add_scratch_entry(
+        app: &AppHandle,
+        usage_data: &UsageData,
+    ) -> Result<UsageHistoryEntry, AppError> {
+        let store = app.data_store(STORE_FILE)?;
 
-        // Avoid duplicate entries (same timestamp and provider)
-        if entries.iter().any(|e| e.id == entry.id) {
-            return Ok(());
-        }
+        let lock = Self::store_lock(app);
+        let guard = lock.lock().unwrap_or_else(PoisonError::into_inner);
 
-        entries.push(entry);
+        let entry = Self::push_scratch(&*store, usage_data)?;
+        persist_unlocked(&*store, guard)?;
+        Ok(entry)
+    }
 
-        // Save entries
-        let value = serde_json::to_value(&entries)?;
-        store.set(HISTORY_KEY.to_string(), value);
-        store.save().map_err(|e| AppError::Store(e.to_string()))?;
+    /// Scratch entries, oldest first
+    pub fn get_scratch_entries(app: &AppHandle) -> Result<Vec<UsageHistoryEntry>, AppError> {
+        let store = app.data_store(STORE_FILE)?;
+        Ok(Self::load_scratch(&*store))
+    }
 
-        // Update metadata
-        Self::update_metadata(app)?;
+    pub(crate) fn push_scratch(
+        store: &impl KeyValueStore,
+        usage_data: &UsageData,
+    ) -> Result<UsageHistoryEntry, AppError> {
+        let mut usage = usage_data.clone();
+        usage.account_id = SCRATCH_ACCOUNT.to_string();
+        usage.account_name = SCRATCH_ACCOUNT.to_string();
+        let entry = Self::entry_for(&usage);
 
-        log::debug!(
-            "Added history entry for provider: {} (total: {})",
-            usage_data.provider,
-            entries.len()
-        );
+        let mut entries = Self::load_scratch(store);
+        entries.push(entry.clone());
+        let excess = entries.len().saturating_sub(MAX_SCRATCH_ENTRIES);
+        entries.drain(..excess);
 
-        Ok(())
+        store.set_value(SCRATCH_KEY, serde_json::to_value(&entries)?);
+        Ok(entry)
+    }
+
+    pub(crate) fn load_scratch(store: &impl KeyValueStore) -> Vec<UsageHistoryEntry> {
+        store
+            .get_value(SCRATCH_KEY)
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default()
     }
 
     /// Get all history entries
     pub fn get_all_entries(app: &AppHandle) -> Result<Vec<UsageHistoryEntry>, AppError> {
-        let store = app
-            .store(STORE_FILE)
-            .map_err(|e| AppError::Store(e.to_string()))?;
+        let store = app.data_store(STORE_FILE)?;
 
-        match store.get(HISTORY_KEY) {
-            Some(v) => {
-                let entries: Vec<UsageHistoryEntry> = serde_json::from_value(v.clone())?;
-                Ok(entries)
+        Self::load_entries(
+            &*store,
+            &Self::compressed_path(app)?,
+            Self::compression_enabled(app),
+        )
+    }
+
+    /// Replace all history entries, in whichever format the settings ask for
+    fn save_entries(app: &AppHandle, entries: &[UsageHistoryEntry]) -> Result<(), AppError> {
+        let store = app.data_store(STORE_FILE)?;
+
+        Self::store_entries(
+            &*store,
+            &Self::compressed_path(app)?,
+            Self::compression_enabled(app),
+            entries,
+        )
+    }
+
+    /// Location of the compressed entries file, next to the history store
+    fn compressed_path(app: &AppHandle) -> Result<PathBuf, AppError> {
+        app.data_path(COMPRESSED_FILE)
+    }
+
+    fn compression_enabled(app: &AppHandle) -> bool {
+        SettingsService::get(app)
+            .map(|s| s.compress_history)
+            .unwrap_or(false)
+    }
+
+    /// Read entries from the copy matching `compress`, falling back to the other copy
+    ///
+    /// The fallback covers a store written before the setting was last toggled;
+    /// the next `store_entries` call moves it over. The compressed file is only
+    /// opened here, and is inflated as it is parsed rather than up front.
+    pub(crate) fn load_entries(
+        store: &impl KeyValueStore,
+        compressed: &Path,
+        compress: bool,
+    ) -> Result<Vec<UsageHistoryEntry>, AppError> {
+        let inline = || -> Result<Option<Vec<UsageHistoryEntry>>, AppError> {
+            match store.get_value(HISTORY_KEY) {
+                Some(v) => Ok(Some(serde_json::from_value(v)?)),
+                None => Ok(None),
+            }
+        };
+        let from_file = || Self::read_compressed(compressed);
+
+        let entries = if compress {
+            match from_file()? {
+                Some(entries) => Some(entries),
+                None => inline()?,
+            }
+        } else {
+            match inline()? {
+                Some(entries) => Some(entries),
+                None => from_file()?,
+            }
+        };
+
+        Ok(entries.unwrap_or_default())
+    }
+
+    /// Write entries in the format `compress` selects, then drop the other copy
+    ///
+    /// The new copy is written first, so a failure part-way leaves the old one
+    /// readable.
+    pub(crate) fn store_entries(
+        store: &impl KeyValueStore,
+        compressed: &Path,
+        compress: bool,
+        entries: &[UsageHistoryEntry],
+    ) -> Result<(), AppError> {
+        if compress {
+            Self::write_compressed(compressed, entries)?;
+
+            if store.get_value(HISTORY_KEY).is_some() {
+                store.delete_value(HISTORY_KEY);
+                store.persist()?;
+                log::info!("Migrated history entries to {}", COMPRESSED_FILE);
+            }
+        } else {
+            store.set_value(HISTORY_KEY, serde_json::to_value(entries)?);
+            store.persist()?;
+
+            if compressed.exists() {
+                fs::remove_file(compressed).map_err(|e| AppError::Store(e.to_string()))?;
+                log::info!("Moved history entries out of {}", COMPRESSED_FILE);
             }
-            None => Ok(Vec::new()),
         }
+
+        Ok(())
+    }
+
+    /// Parse the compressed entries file, None if it doesn't exist
+    fn read_compressed(path: &Path) -> Result<Option<Vec<UsageHistoryEntry>>, AppError> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(AppError::Store(e.to_string())),
+        };
+
+        let entries = serde_json::from_reader(GzDecoder::new(BufReader::new(file)))?;
+        Ok(Some(entries))
+    }
+
+    /// Gzip entries into `path`, via a temporary file so readers never see a partial write
+    fn write_compressed(path: &Path, entries: &[UsageHistoryEntry]) -> Result<(), AppError> {
+        let tmp = path.with_extension("gz.tmp");
+
+        let write = || -> io::Result<()> {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            let mut encoder =
+                GzEncoder::new(BufWriter::new(File::create(&tmp)?), Compression::default());
+            serde_json::to_writer(&mut encoder, entries)?;
+            encoder.finish()?.flush()?;
+            fs::rename(&tmp, path)
+        };
+
+        write().map_err(|e| AppError::Store(e.to_string()))
     }
 
     /// Query history with filters
@@ -135,9 +367,7 @@ impl HistoryService {
         let oldest = entries.iter().map(|e| e.timestamp).min();
         let newest = entries.iter().map(|e| e.timestamp).max();
 
-        let store = app
-            .store(STORE_FILE)
-            .map_err(|e| AppError::Store(e.to_string()))?;
+        let store = app.data_store(STORE_FILE)?;
 
         let last_cleanup = match store.get(METADATA_KEY) {
             Some(v) => {
@@ -164,26 +394,20 @@ impl HistoryService {
         })
     }
 
-    /// Update metadata after changes
-    fn update_metadata(app: &AppHandle) -> Result<(), AppError> {
+    /// Update metadata after changes, releasing the store lock before saving
+    fn update_metadata(app: &AppHandle, guard: MutexGuard<'_, ()>) -> Result<(), AppError> {
         let metadata = Self::get_metadata(app)?;
 
-        let store = app
-            .store(STORE_FILE)
-            .map_err(|e| AppError::Store(e.to_string()))?;
+        let store = app.data_store(STORE_FILE)?;
 
         let value = serde_json::to_value(&metadata)?;
         store.set(METADATA_KEY.to_string(), value);
-        store.save().map_err(|e| AppError::Store(e.to_string()))?;
-
-        Ok(())
+        persist_unlocked(&*store, guard)
     }
 
     /// Get retention policy
     pub fn get_retention_policy(app: &AppHandle) -> Result<RetentionPolicy, AppError> {
-        let store = app
-            .store(STORE_FILE)
-            .map_err(|e| AppError::Store(e.to_string()))?;
+        let store = app.data_store(STORE_FILE)?;
 
         match store.get(RETENTION_KEY) {
             Some(v) => {
@@ -196,13 +420,11 @@ impl HistoryService {
 
     /// Set retention policy
     pub fn set_retention_policy(app: &AppHandle, policy: &RetentionPolicy) -> Result<(), AppError> {
-        let store = app
-            .store(STORE_FILE)
-            .map_err(|e| AppError::Store(e.to_string()))?;
+        let store = app.data_store(STORE_FILE)?;
 
         let value = serde_json::to_value(policy)?;
         store.set(RETENTION_KEY.to_string(), value);
-        store.save().map_err(|e| AppError::Store(e.to_string()))?;
+        store.persist()?;
 
         log::info!(
             "Updated retention policy: {} days, auto_cleanup: {}",
@@ -222,6 +444,9 @@ impl HistoryService {
             return Ok(0);
         }
 
+        let lock = Self::store_lock(app);
+        let guard = lock.lock().unwrap_or_else(PoisonError::into_inner);
+
         let cutoff = Utc::now() - Duration::days(policy.retention_days as i64);
         let mut entries = Self::get_all_entries(app)?;
         let original_count = entries.len();
@@ -230,12 +455,9 @@ impl HistoryService {
         let removed_count = original_count - entries.len();
 
         if removed_count > 0 {
-            let store = app
-                .store(STORE_FILE)
-                .map_err(|e| AppError::Store(e.to_string()))?;
+            Self::save_entries(app, &entries)?;
 
-            let value = serde_json::to_value(&entries)?;
-            store.set(HISTORY_KEY.to_string(), value);
+            let store = app.data_store(STORE_FILE)?;
 
             // Update last_cleanup timestamp
             let mut metadata = Self::get_metadata(app)?;
@@ -243,7 +465,7 @@ impl HistoryService {
             let meta_value = serde_json::to_value(&metadata)?;
             store.set(METADATA_KEY.to_string(), meta_value);
 
-            store.save().map_err(|e| AppError::Store(e.to_string()))?;
+            persist_unlocked(&*store, guard)?;
 
             log::info!(
                 "Cleaned up {} history entries older than {} days",
@@ -255,6 +477,198 @@ impl HistoryService {
         Ok(removed_count)
     }
 
+    /// Downsample entries older than `older_than_days` into `bucket_minutes` buckets
+    ///
+    /// Returns how many entries were removed. Recent entries are left untouched.
+    pub fn compact(app: &AppHandle, older_than_days: u32, bucket_minutes: u32) -> Result<usize, AppError> {
+        if bucket_minutes == 0 {
+            return Err(AppError::InvalidInput("bucket_minutes must be greater than 0".to_string()));
+        }
+
+        let lock = Self::store_lock(app);
+        let guard = lock.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let cutoff = Utc::now() - Duration::days(older_than_days as i64);
+        let entries = Self::get_all_entries(app)?;
+        let original_count = entries.len();
+
+        let compacted = Self::compact_entries(entries, cutoff, Duration::minutes(bucket_minutes as i64));
+        let removed_count = original_count - compacted.len();
+
+        if removed_count > 0 {
+            Self::save_entries(app, &compacted)?;
+
+            Self::update_metadata(app, guard)?;
+
+            log::info!(
+                "Compacted history older than {} days into {}-minute buckets ({} entries removed)",
+                older_than_days,
+                bucket_minutes,
+                removed_count
+            );
+        }
+
+        Ok(removed_count)
+    }
+
+    /// Merge entries before `cutoff` into one entry per provider, account and bucket
+    ///
+    /// Each limit in a bucket keeps the average as `utilization` plus the
+    /// min/max seen, and the latest `resets_at`.
+    pub fn compact_entries(
+        entries: Vec<UsageHistoryEntry>,
+        cutoff: DateTime<Utc>,
+        bucket: Duration,
+    ) -> Vec<UsageHistoryEntry> {
+        let bucket_secs = bucket.num_seconds().max(1);
+
+        let (recent, old): (Vec<_>, Vec<_>) = entries.into_iter().partition(|e| e.timestamp >= cutoff);
+
+        let mut buckets: BTreeMap<(String, String, i64), Vec<UsageHistoryEntry>> = BTreeMap::new();
+        for entry in old {
+            let start = entry.timestamp.timestamp().div_euclid(bucket_secs) * bucket_secs;
+            buckets
+                .entry((entry.provider.clone(), entry.account_id.clone(), start))
+                .or_default()
+                .push(entry);
+        }
+
+        let mut result: Vec<UsageHistoryEntry> = buckets
+            .into_iter()
+            .map(|((provider, account_id, start), mut group)| {
+                group.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+                let timestamp = DateTime::from_timestamp(start, 0).unwrap_or(group[0].timestamp);
+
+                // Keep limits in first-seen order
+                let mut limit_ids: Vec<&str> = Vec::new();
+                for limit in group.iter().flat_map(|e| &e.limits) {
+                    if !limit_ids.contains(&limit.id.as_str()) {
+                        limit_ids.push(&limit.id);
+                    }
+                }
+
+                let limits = limit_ids
+                    .iter()
+                    .map(|&id| {
+                        let points: Vec<&UsageLimitSnapshot> = group
+                            .iter()
+                            .flat_map(|e| &e.limits)
+                            .filter(|l| l.id == id)
+                            .collect();
+
+                        let avg = points.iter().map(|l| l.utilization).sum::<f64>() / points.len() as f64;
+                        let min = points
+                            .iter()
+                            .map(|l| l.min_utilization.unwrap_or(l.utilization))
+                            .fold(f64::INFINITY, f64::min);
+                        let max = points
+                            .iter()
+                            .map(|l| l.max_utilization.unwrap_or(l.utilization))
+                            .fold(f64::NEG_INFINITY, f64::max);
+
+                        UsageLimitSnapshot {
+                            id: id.to_string(),
+                            utilization: avg,
+                            resets_at: points.iter().map(|l| l.resets_at).max().unwrap_or(timestamp),
+                            min_utilization: Some(min),
+                            max_utilization: Some(max),
+                        }
+                    })
+                    .collect();
+
+                UsageHistoryEntry {
+                    id: format!("{}-{}-{}-{}", COMPACTED_ID_PREFIX, start, provider, account_id),
+                    account_name: group.last().map(|e| e.account_name.clone()).unwrap_or_default(),
+                    provider,
+                    account_id,
+                    timestamp,
+                    limits,
+                }
+            })
+            .collect();
+
+        result.extend(recent);
+        result.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+        result
+    }
+
+    /// Move every snapshot recorded for `from_id` over to `to_id`
+    ///
+    /// Returns how many snapshots were moved. See `reassign_entries` for how
+    /// overlapping timestamps are resolved.
+    pub fn reassign_account(
+        app: &AppHandle,
+        from_id: &str,
+        to_id: &str,
+        to_name: &str,
+    ) -> Result<usize, AppError> {
+        let lock = Self::store_lock(app);
+        let guard = lock.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let entries = Self::get_all_entries(app)?;
+        let (merged, moved) = Self::reassign_entries(entries, from_id, to_id, to_name);
+
+        Self::save_entries(app, &merged)?;
+
+        Self::update_metadata(app, guard)?;
+
+        log::info!(
+            "Reassigned {} history entries from {} to {}",
+            moved,
+            from_id,
+            to_id
+        );
+        Ok(moved)
+    }
+
+    /// Relabel `from_id`'s entries as `to_id`'s, returning the entries and how many moved
+    ///
+    /// Where both accounts have a snapshot at the same timestamp, the one
+    /// already belonging to `to_id` wins and the other is dropped. Only the
+    /// account suffix of an ID changes, so compacted entries keep their prefix.
+    pub fn reassign_entries(
+        entries: Vec<UsageHistoryEntry>,
+        from_id: &str,
+        to_id: &str,
+        to_name: &str,
+    ) -> (Vec<UsageHistoryEntry>, usize) {
+        let taken: HashSet<DateTime<Utc>> = entries
+            .iter()
+            .filter(|e| e.account_id == to_id)
+            .map(|e| e.timestamp)
+            .collect();
+
+        let mut moved = 0;
+        let mut result: Vec<UsageHistoryEntry> = entries
+            .into_iter()
+            .filter_map(|mut entry| {
+                if entry.account_id != from_id {
+                    return Some(entry);
+                }
+                if taken.contains(&entry.timestamp) {
+                    return None;
+                }
+
+                entry.id = match entry.id.strip_suffix(from_id) {
+                    Some(rest) => format!("{}{}", rest, to_id),
+                    None => format!(
+                        "{}-{}-{}",
+                        entry.timestamp.timestamp(),
+                        entry.provider,
+                        to_id
+                    ),
+                };
+                entry.account_id = to_id.to_string();
+                entry.account_name = to_name.to_string();
+                moved += 1;
+                Some(entry)
+            })
+            .collect();
+
+        result.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+        (result, moved)
+    }
+
     /// Calculate usage statistics for a time period
     pub fn get_stats(
         app: &AppHandle,
@@ -304,55 +718,1753 @@ impl HistoryService {
         }))
     }
 
-    /// Export history to JSON string
-    pub fn export_json(app: &AppHandle, query: Option<&HistoryQuery>) -> Result<String, AppError> {
-        let entries = match query {
-            Some(q) => Self::query(app, q)?,
-            None => Self::get_all_entries(app)?,
+    /// Calculate per-limit statistics for one account over the last `days` days
+    pub fn get_account_stats(
+        app: &AppHandle,
+        account_id: &str,
+        days: u32,
+    ) -> Result<AccountUsageStats, AppError> {
+        let end = Utc::now();
+        let start = end - Duration::days(days as i64);
+
+        let query = HistoryQuery {
+            provider: None,
+            account_id: Some(account_id.to_string()),
+            start_date: Some(start),
+            end_date: Some(end),
+            limit: Some(usize::MAX),
+            offset: None,
         };
 
-        let json = serde_json::to_string_pretty(&entries)?;
-        Ok(json)
+        let entries = Self::query(app, &query)?;
+        Ok(Self::compute_account_stats(account_id, &entries, start, end))
     }
 
-    /// Export history to CSV string
-    pub fn export_csv(app: &AppHandle, query: Option<&HistoryQuery>) -> Result<String, AppError> {
-        let entries = match query {
-            Some(q) => Self::query(app, q)?,
-            None => Self::get_all_entries(app)?,
+    /// Compute per-limit statistics from a set of history entries
+    pub fn compute_account_stats(
+        account_id: &str,
+        entries: &[UsageHistoryEntry],
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> AccountUsageStats {
+        // Walk snapshots oldest first so resets can be detected in order
+        let mut sorted: Vec<&UsageHistoryEntry> = entries.iter().collect();
+        sorted.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+
+        let mut samples: BTreeMap<&str, Vec<(DateTime<Utc>, &UsageLimitSnapshot)>> = BTreeMap::new();
+        for entry in &sorted {
+            for limit in &entry.limits {
+                samples
+                    .entry(limit.id.as_str())
+                    .or_default()
+                    .push((entry.timestamp, limit));
+            }
+        }
+
+        let limits = samples
+            .into_iter()
+            .map(|(limit_id, points)| {
+                let count = points.len();
+                let sum: f64 = points.iter().map(|(_, l)| l.utilization).sum();
+                let max = points
+                    .iter()
+                    .map(|(_, l)| l.utilization)
+                    .fold(f64::NEG_INFINITY, f64::max);
+                let min = points
+                    .iter()
+                    .map(|(_, l)| l.utilization)
+                    .fold(f64::INFINITY, f64::min);
+
+                let reset_count = points
+                    .windows(2)
+                    .filter(|pair| Self::is_reset(pair[0].1, pair[1].1))
+                    .count();
+
+                // Average utilization per hour of day, then pick the busiest hour
+                let mut by_hour: BTreeMap<u32, (f64, usize)> = BTreeMap::new();
+                for (timestamp, limit) in &points {
+                    let bucket = by_hour.entry(timestamp.hour()).or_insert((0.0, 0));
+                    bucket.0 += limit.utilization;
+                    bucket.1 += 1;
+                }
+                let peak_hour = by_hour
+                    .into_iter()
+                    .map(|(hour, (total, n))| (hour, total / n as f64))
+                    .fold(None, |best: Option<(u32, f64)>, (hour, avg)| match best {
+                        Some((_, best_avg)) if best_avg >= avg => best,
+                        _ => Some((hour, avg)),
+                    })
+                    .map(|(hour, _)| hour);
+
+                LimitUsageStats {
+                    limit_id: limit_id.to_string(),
+                    avg_utilization: sum / count as f64,
+                    max_utilization: max,
+                    min_utilization: min,
+                    peak_hour,
+                    reset_count,
+                    sample_count: count,
+                }
+            })
+            .collect();
+
+        AccountUsageStats {
+            account_id: account_id.to_string(),
+            period_start: start,
+            period_end: end,
+            sample_count: sorted.len(),
+            limits,
+        }
+    }
+
+    /// Estimate spend for one account over a period using per-limit unit costs
+    pub fn estimate_spend(
+        app: &AppHandle,
+        account_id: &str,
+        provider: &str,
+        period: SpendPeriod,
+        pricing: &HashMap<String, f64>,
+    ) -> Result<SpendEstimate, AppError> {
+        let end = Utc::now();
+        let start = end - Duration::days(period.days());
+
+        let query = HistoryQuery {
+            provider: Some(provider.to_string()),
+            account_id: Some(account_id.to_string()),
+            start_date: Some(start),
+            end_date: Some(end),
+            limit: Some(usize::MAX),
+            offset: None,
         };
 
-        let mut csv = String::from("id,provider,timestamp,limit_id,utilization,resets_at\n");
+        let entries = Self::query(app, &query)?;
+        Ok(Self::compute_spend(account_id, provider, &entries, pricing, start, end))
+    }
 
-        for entry in entries {
+    /// Multiply consumed units per limit by the configured rates
+    pub fn compute_spend(
+        account_id: &str,
+        provider: &str,
+        entries: &[UsageHistoryEntry],
+        pricing: &HashMap<String, f64>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> SpendEstimate {
+        let mut sorted: Vec<&UsageHistoryEntry> = entries.iter().collect();
+        sorted.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+
+        let mut samples: BTreeMap<&str, Vec<&UsageLimitSnapshot>> = BTreeMap::new();
+        for entry in &sorted {
             for limit in &entry.limits {
-                csv.push_str(&format!(
-                    "{},{},{},{},{:.2},{}\n",
-                    entry.id,
-                    entry.provider,
-                    entry.timestamp.to_rfc3339(),
-                    limit.id,
-                    limit.utilization,
-                    limit.resets_at.to_rfc3339()
-                ));
+                samples.entry(limit.id.as_str()).or_default().push(limit);
             }
         }
 
-        Ok(csv)
+        let limits: Vec<LimitSpend> = samples
+            .into_iter()
+            .map(|(limit_id, points)| {
+                let consumed_units = Self::consumed_units(&points);
+                let unit_cost = pricing.get(limit_id).copied();
+                LimitSpend {
+                    limit_id: limit_id.to_string(),
+                    consumed_units,
+                    unit_cost,
+                    cost: unit_cost.map(|rate| rate * consumed_units),
+                }
+            })
+            .collect();
+
+        SpendEstimate {
+            account_id: account_id.to_string(),
+            provider: provider.to_string(),
+            period_start: start,
+            period_end: end,
+            total_cost: limits.iter().filter_map(|l| l.cost).sum(),
+            unpriced_limits: limits
+                .iter()
+                .filter(|l| l.unit_cost.is_none())
+                .map(|l| l.limit_id.clone())
+                .collect(),
+            limits,
+        }
     }
 
-    /// Clear all history data
-    pub fn clear_all(app: &AppHandle) -> Result<(), AppError> {
-        let store = app
-            .store(STORE_FILE)
-            .map_err(|e| AppError::Store(e.to_string()))?;
+    /// Percentage points consumed across snapshots (oldest first), counting usage after resets
+    fn consumed_units(points: &[&UsageLimitSnapshot]) -> f64 {
+        points
+            .windows(2)
+            .map(|pair| {
+                if Self::is_reset(pair[0], pair[1]) {
+                    pair[1].utilization
+                } else {
+                    (pair[1].utilization - pair[0].utilization).max(0.0)
+                }
+            })
+            .sum()
+    }
+
+    /// Whether a limit rolled over into a new window between two snapshots
+    pub fn is_reset(previous: &UsageLimitSnapshot, current: &UsageLimitSnapshot) -> bool {
+        current
+            .resets_at
+            .signed_duration_since(previous.resets_at)
+            .num_seconds()
+            > RESET_TOLERANCE_SECS
+    }
+
+    /// Peak utilization per day, in the display time zone, for one limit over the last `days` days
+    ///
+    /// `days` is capped at the retention window, since older days can't have data.
+    pub fn get_daily_heatmap(
+        app: &AppHandle,
+        account_id: &str,
+        limit_id: &str,
+        days: u32,
+    ) -> Result<Vec<DayBucket>, AppError> {
+        let days = Self::heatmap_days(days, Self::get_retention_policy(app)?.retention_days);
+        let now = Utc::now();
+        // One extra day covers the part of the oldest local day before `now - days`
+        let query = HistoryQuery {
+            provider: None,
+            account_id: Some(account_id.to_string()),
+            start_date: Some(now - Duration::days(days as i64 + 1)),
+            end_date: Some(now),
+            limit: Some(usize::MAX),
+            offset: None,
+        };
+
+        let entries = Self::query(app, &query)?;
+        let zone = DisplayTimezone::from_setting(&SettingsService::get(app)?.display_timezone);
+        Ok(match zone {
+            DisplayTimezone::Local => Self::daily_heatmap(&entries, limit_id, days, now, &Local),
+            DisplayTimezone::Utc => Self::daily_heatmap(&entries, limit_id, days, now, &Utc),
+            DisplayTimezone::Named(tz) => Self::daily_heatmap(&entries, limit_id, days, now, &tz),
+        })
+    }
+
+    /// Days a heatmap covers: `requested`, at most the retention window and `MAX_HEATMAP_DAYS`
+    fn heatmap_days(requested: u32, retention_days: u32) -> u32 {
+        // 0 means unlimited retention
+        let cap = match retention_days {
+            0 => MAX_HEATMAP_DAYS,
+            days => days.min(MAX_HEATMAP_DAYS),
+        };
+        requested.min(cap)
+    }
 
-        store.set(HISTORY_KEY.to_string(), serde_json::json!([]));
-        store.save().map_err(|e| AppError::Store(e.to_string()))?;
+    /// Bucket a limit's history into calendar days in `tz`, ending with the day containing `now`
+    ///
+    /// Returns one bucket per day, oldest first. Days without samples have no peak,
+    /// so the UI can tell "no data" from 0%.
+    pub fn daily_heatmap<Tz: TimeZone>(
+        entries: &[UsageHistoryEntry],
+        limit_id: &str,
+        days: u32,
+        now: DateTime<Utc>,
+        tz: &Tz,
+    ) -> Vec<DayBucket> {
+        let today = now.with_timezone(tz).date_naive();
+        let mut buckets: BTreeMap<NaiveDate, DayBucket> = (0..days as i64)
+            .rev()
+            .filter_map(|ago| today.checked_sub_signed(Duration::days(ago)))
+            .map(|date| {
+                let bucket = DayBucket {
+                    date,
+                    peak_utilization: None,
+                    sample_count: 0,
+                };
+                (date, bucket)
+            })
+            .collect();
 
-        Self::update_metadata(app)?;
+        for entry in entries {
+            let date = entry.timestamp.with_timezone(tz).date_naive();
+            let Some(bucket) = buckets.get_mut(&date) else {
+                continue;
+            };
 
-        log::info!("Cleared all history data");
-        Ok(())
+            for limit in entry.limits.iter().filter(|l| l.id == limit_id) {
+                // Compacted entries keep the bucket's true peak separately
+                let peak = limit.max_utilization.unwrap_or(limit.utilization);
+                bucket.peak_utilization =
+                    Some(bucket.peak_utilization.map_or(peak, |p| p.max(peak)));
+                bucket.sample_count += 1;
+            }
+        }
+
+        buckets.into_values().collect()
+    }
+
+    /// Infer a limit's reset cadence from the account's full history
+    pub fn get_reset_schedule(
+        app: &AppHandle,
+        account_id: &str,
+        limit_id: &str,
+    ) -> Result<ResetSchedule, AppError> {
+        let query = HistoryQuery {
+            provider: None,
+            account_id: Some(account_id.to_string()),
+            start_date: None,
+            end_date: None,
+            limit: Some(usize::MAX),
+            offset: None,
+        };
+
+        let entries = Self::query(app, &query)?;
+        let mut schedule = Self::reset_schedule(account_id, limit_id, &entries, Utc::now());
+
+        let zone = DisplayTimezone::from_setting(&SettingsService::get(app)?.display_timezone);
+        schedule.predicted_resets_display = schedule
+            .predicted_resets
+            .iter()
+            .map(|&at| zone.format(at))
+            .collect();
+        Ok(schedule)
+    }
+
+    /// Infer the interval between a limit's resets and project the next few after `now`
+    ///
+    /// Every distinct `resets_at` the limit reported counts as one reset. The interval
+    /// is the median gap between them, and confidence drops as the gaps vary more.
+    pub fn reset_schedule(
+        account_id: &str,
+        limit_id: &str,
+        entries: &[UsageHistoryEntry],
+        now: DateTime<Utc>,
+    ) -> ResetSchedule {
+        let mut resets: Vec<DateTime<Utc>> = entries
+            .iter()
+            .flat_map(|entry| &entry.limits)
+            .filter(|limit| limit.id == limit_id)
+            .map(|limit| limit.resets_at)
+            .collect();
+        resets.sort();
+        // Small drift in the reported time is the same reset
+        resets.dedup_by(|later, earlier| {
+            later.signed_duration_since(*earlier).num_seconds() <= RESET_TOLERANCE_SECS
+        });
+
+        let mut schedule = ResetSchedule {
+            account_id: account_id.to_string(),
+            limit_id: limit_id.to_string(),
+            observed_resets: resets,
+            interval_secs: None,
+            predicted_resets: Vec::new(),
+            predicted_resets_display: Vec::new(),
+            confidence: ScheduleConfidence::Unknown,
+        };
+
+        let resets = &schedule.observed_resets;
+        if resets.len() < MIN_SCHEDULE_RESETS {
+            return schedule;
+        }
+        let last = resets[resets.len() - 1];
+
+        let mut intervals: Vec<i64> = resets
+            .windows(2)
+            .map(|pair| pair[1].signed_duration_since(pair[0]).num_seconds())
+            .collect();
+        intervals.sort_unstable();
+
+        let count = intervals.len() as f64;
+        let mean = intervals.iter().sum::<i64>() as f64 / count;
+        let variance = intervals
+            .iter()
+            .map(|&secs| (secs as f64 - mean).powi(2))
+            .sum::<f64>()
+            / count;
+        let spread = variance.sqrt() / mean;
+
+        let mid = intervals.len() / 2;
+        let interval = if intervals.len() % 2 == 0 {
+            (intervals[mid - 1] + intervals[mid]) / 2
+        } else {
+            intervals[mid]
+        };
+
+        // Step forward from the last known reset to the first one still ahead
+        let steps = if last > now {
+            0
+        } else {
+            now.signed_duration_since(last).num_seconds() / interval + 1
+        };
+        let next = last + Duration::seconds(interval * steps);
+
+        schedule.interval_secs = Some(interval);
+        schedule.predicted_resets = (0..PREDICTED_RESETS)
+            .map(|i| next + Duration::seconds(interval * i))
+            .collect();
+        schedule.confidence = if spread <= HIGH_CONFIDENCE_SPREAD {
+            ScheduleConfidence::High
+        } else if spread <= MEDIUM_CONFIDENCE_SPREAD {
+            ScheduleConfidence::Medium
+        } else {
+            ScheduleConfidence::Low
+        };
+
+        schedule
+    }
+
+    /// Forecast how soon a limit caps again after its next reset, from the last week of history
+    pub fn get_recovery_forecast(
+        app: &AppHandle,
+        account_id: &str,
+        limit_id: &str,
+    ) -> Result<RecoveryForecast, AppError> {
+        let now = Utc::now();
+        let query = HistoryQuery {
+            provider: None,
+            account_id: Some(account_id.to_string()),
+            start_date: Some(now - Duration::days(RECOVERY_LOOKBACK_DAYS)),
+            end_date: Some(now),
+            limit: Some(usize::MAX),
+            offset: None,
+        };
+
+        let entries = Self::query(app, &query)?;
+        let cap = SettingsService::get(app)?.cap_threshold;
+        Ok(Self::recovery_forecast(
+            account_id, limit_id, &entries, cap, now,
+        ))
+    }
+
+    /// Estimate when a limit reaches `cap` again after its next reset
+    ///
+    /// The burn rate is the limit's consumption over the lookback divided by the
+    /// time the samples span, so idle stretches count as typical use too.
+    pub fn recovery_forecast(
+        account_id: &str,
+        limit_id: &str,
+        entries: &[UsageHistoryEntry],
+        cap: f64,
+        now: DateTime<Utc>,
+    ) -> RecoveryForecast {
+        let since = now - Duration::days(RECOVERY_LOOKBACK_DAYS);
+        let mut samples: Vec<(DateTime<Utc>, &UsageLimitSnapshot)> = entries
+            .iter()
+            .filter(|e| e.account_id == account_id && e.timestamp >= since && e.timestamp <= now)
+            .flat_map(|e| {
+                e.limits
+                    .iter()
+                    .filter(|l| l.id == limit_id)
+                    .map(move |l| (e.timestamp, l))
+            })
+            .collect();
+        samples.sort_by_key(|(timestamp, _)| *timestamp);
+
+        let mut forecast = RecoveryForecast {
+            account_id: account_id.to_string(),
+            limit_id: limit_id.to_string(),
+            insufficient_data: true,
+            resets_at: samples.last().map(|(_, limit)| limit.resets_at),
+            burn_rate_per_hour: None,
+            headroom_secs: None,
+            recaps_at: None,
+            recaps_before_next_reset: None,
+        };
+
+        let (Some((first, _)), Some((last, latest))) = (samples.first(), samples.last()) else {
+            return forecast;
+        };
+        let span_secs = last.signed_duration_since(*first).num_seconds();
+        if samples.len() < MIN_RECOVERY_SAMPLES || span_secs < MIN_RECOVERY_SPAN_MINUTES * 60 {
+            return forecast;
+        }
+
+        let points: Vec<&UsageLimitSnapshot> = samples.iter().map(|(_, limit)| *limit).collect();
+        let rate = Self::consumed_units(&points) / (span_secs as f64 / 3600.0);
+        forecast.insufficient_data = false;
+        forecast.burn_rate_per_hour = Some(rate);
+
+        if rate <= 0.0 {
+            forecast.recaps_before_next_reset = Some(false);
+            return forecast;
+        }
+
+        // A trickle of use can put the recap past what a timestamp can hold; it
+        // won't happen this window either way
+        let recap = Duration::try_seconds((cap / rate * 3600.0) as i64).and_then(|headroom| {
+            latest
+                .resets_at
+                .checked_add_signed(headroom)
+                .map(|recaps_at| (headroom, recaps_at))
+        });
+        let Some((headroom, recaps_at)) = recap else {
+            forecast.recaps_before_next_reset = Some(false);
+            return forecast;
+        };
+
+        forecast.headroom_secs = Some(headroom.num_seconds());
+        forecast.recaps_at = Some(recaps_at);
+        forecast.recaps_before_next_reset =
+            Some(headroom < NotificationService::limit_window(limit_id));
+        forecast
+    }
+
+    /// Sparkline of a limit's utilization over its last `points` snapshots, oldest first
+    pub fn get_usage_sparkline(
+        app: &AppHandle,
+        account_id: &str,
+        limit_id: &str,
+        points: usize,
+    ) -> Result<String, AppError> {
+        let query = HistoryQuery {
+            provider: None,
+            account_id: Some(account_id.to_string()),
+            start_date: None,
+            end_date: None,
+            limit: Some(usize::MAX),
+            offset: None,
+        };
+
+        let entries = Self::query(app, &query)?;
+        Ok(Self::sparkline(&Self::recent_utilization(
+            &entries, limit_id, points,
+        )))
+    }
+
+    /// A limit's utilization in its last `points` snapshots, oldest first
+    ///
+    /// Returns fewer values when history holds fewer snapshots of the limit.
+    pub fn recent_utilization(
+        entries: &[UsageHistoryEntry],
+        limit_id: &str,
+        points: usize,
+    ) -> Vec<f64> {
+        let mut samples: Vec<(DateTime<Utc>, f64)> = entries
+            .iter()
+            .filter_map(|e| {
+                e.limits
+                    .iter()
+                    .find(|l| l.id == limit_id)
+                    .map(|l| (e.timestamp, l.utilization))
+            })
+            .collect();
+        samples.sort_by_key(|(timestamp, _)| *timestamp);
+
+        let skip = samples.len().saturating_sub(points);
+        samples.into_iter().skip(skip).map(|(_, u)| u).collect()
+    }
+
+    /// Render utilization percentages as one Unicode block each
+    ///
+    /// The scale is fixed at 0-100% rather than fitted to the values, so a flat
+    /// line near the cap reads as full. Values outside the range are clamped.
+    pub fn sparkline(values: &[f64]) -> String {
+        values
+            .iter()
+            .map(|v| {
+                let level = (v.clamp(0.0, 100.0) / 100.0 * SPARKLINE_BLOCKS.len() as f64) as usize;
+                SPARKLINE_BLOCKS[level.min(SPARKLINE_BLOCKS.len() - 1)]
+            })
+            .collect()
+    }
+
+    /// Consumption against an account's monthly budget for the current local calendar month
+    pub fn get_budget_status(
+        app: &AppHandle,
+        account_id: &str,
+        budget: f64,
+        warning_percent: u32,
+    ) -> Result<BudgetStatus, AppError> {
+        let month_start = Self::month_start(&Local::now());
+        let query = HistoryQuery {
+            provider: None,
+            account_id: Some(account_id.to_string()),
+            start_date: Some(month_start),
+            end_date: None,
+            limit: Some(usize::MAX),
+            offset: None,
+        };
+
+        let entries = Self::query(app, &query)?;
+        Ok(Self::budget_status(
+            account_id,
+            &entries,
+            budget,
+            warning_percent,
+            month_start,
+        ))
+    }
+
+    /// Midnight on the first day of the month containing `now`, in `now`'s time zone
+    pub fn month_start<Tz: TimeZone>(now: &DateTime<Tz>) -> DateTime<Utc> {
+        let first = now
+            .date_naive()
+            .with_day(1)
+            .and_then(|day| day.and_hms_opt(0, 0, 0))
+            .expect("the first of a month is a valid date");
+
+        // A DST jump at midnight can skip the local time; fall back to midnight UTC
+        now.timezone()
+            .from_local_datetime(&first)
+            .earliest()
+            .map(|start| start.with_timezone(&Utc))
+            .unwrap_or_else(|| Utc.from_utc_datetime(&first))
+    }
+
+    /// Sum consumption across all limits since `month_start` and rate it against `budget`
+    ///
+    /// Entries from before `month_start` are ignored, so the total starts over each month.
+    pub fn budget_status(
+        account_id: &str,
+        entries: &[UsageHistoryEntry],
+        budget: f64,
+        warning_percent: u32,
+        month_start: DateTime<Utc>,
+    ) -> BudgetStatus {
+        let mut sorted: Vec<&UsageHistoryEntry> = entries
+            .iter()
+            .filter(|e| e.account_id == account_id && e.timestamp >= month_start)
+            .collect();
+        sorted.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+
+        let mut samples: BTreeMap<&str, Vec<&UsageLimitSnapshot>> = BTreeMap::new();
+        for entry in &sorted {
+            for limit in &entry.limits {
+                samples.entry(limit.id.as_str()).or_default().push(limit);
+            }
+        }
+
+        let consumed: f64 = samples
+            .values()
+            .map(|points| Self::consumed_units(points))
+            .sum();
+
+        let level = if consumed >= budget {
+            BudgetLevel::Exceeded
+        } else if consumed >= budget * warning_percent as f64 / 100.0 {
+            BudgetLevel::Warning
+        } else {
+            BudgetLevel::Under
+        };
+
+        BudgetStatus {
+            account_id: account_id.to_string(),
+            month_start,
+            consumed,
+            budget,
+            warning_percent,
+            level,
+        }
+    }
+
+    /// Get when each account was last viewed, keyed by account ID
+    pub fn get_last_viewed(app: &AppHandle) -> Result<HashMap<String, DateTime<Utc>>, AppError> {
+        let store = app.data_store(STORE_FILE)?;
+
+        match store.get(LAST_VIEWED_KEY) {
+            Some(v) => Ok(serde_json::from_value(v)?),
+            None => Ok(HashMap::new()),
+        }
+    }
+
+    /// Record that the user has seen the current usage for an account
+    pub fn mark_viewed(app: &AppHandle, account_id: &str) -> Result<(), AppError> {
+        let store = app.data_store(STORE_FILE)?;
+
+        let lock = Self::store_lock(app);
+        let guard = lock.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let mut last_viewed = Self::get_last_viewed(app)?;
+        last_viewed.insert(account_id.to_string(), Utc::now());
+
+        store.set(LAST_VIEWED_KEY.to_string(), serde_json::to_value(&last_viewed)?);
+        persist_unlocked(&*store, guard)?;
+
+        log::debug!("Marked account {} as viewed", account_id);
+        Ok(())
+    }
+
+    /// Keep an annotation (e.g. a quota change) for charts to mark
+    pub fn add_annotation(app: &AppHandle, annotation: HistoryAnnotation) -> Result<(), AppError> {
+        let store = app.data_store(STORE_FILE)?;
+
+        let lock = Self::store_lock(app);
+        let guard = lock.lock().unwrap_or_else(PoisonError::into_inner);
+
+        Self::push_annotation(&*store, annotation)?;
+        persist_unlocked(&*store, guard)
+    }
+
+    /// Annotations for one account (or all with None), oldest first
+    pub fn get_annotations(
+        app: &AppHandle,
+        account_id: Option<&str>,
+    ) -> Result<Vec<HistoryAnnotation>, AppError> {
+        let store = app.data_store(STORE_FILE)?;
+
+        Ok(Self::load_annotations(&*store)
+            .into_iter()
+            .filter(|a| account_id.map_or(true, |id| a.account_id() == id))
+            .collect())
+    }
+
+    pub(crate) fn push_annotation(
+        store: &impl KeyValueStore,
+        annotation: HistoryAnnotation,
+    ) -> Result<(), AppError> {
+        let mut annotations = Self::load_annotations(store);
+        annotations.push(annotation);
+        annotations.sort_by_key(|a| a.at());
+        let excess = annotations.len().saturating_sub(MAX_ANNOTATIONS);
+        annotations.drain(..excess);
+
+        store.set_value(ANNOTATIONS_KEY, serde_json::to_value(&annotations)?);
+        Ok(())
+    }
+
+    pub(crate) fn load_annotations(store: &impl KeyValueStore) -> Vec<HistoryAnnotation> {
+        store
+            .get_value(ANNOTATIONS_KEY)
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default()
+    }
+
+    /// Get accounts with threshold crossings the user has not seen yet
+    pub fn get_unseen_changes(
+        app: &AppHandle,
+        thresholds: &[u32],
+    ) -> Result<Vec<UnseenChange>, AppError> {
+        let last_viewed = Self::get_last_viewed(app)?;
+
+        let mut by_account: BTreeMap<String, Vec<UsageHistoryEntry>> = BTreeMap::new();
+        for entry in Self::get_all_entries(app)? {
+            by_account.entry(entry.account_id.clone()).or_default().push(entry);
+        }
+
+        Ok(by_account
+            .iter()
+            .filter_map(|(account_id, entries)| {
+                Self::detect_unseen_change(entries, last_viewed.get(account_id).copied(), thresholds)
+            })
+            .collect())
+    }
+
+    /// Detect whether one account's newest snapshot crossed a threshold since `last_viewed`
+    ///
+    /// The baseline is the newest snapshot taken at or before `last_viewed`; an account
+    /// that was never viewed is compared against zero usage.
+    pub fn detect_unseen_change(
+        entries: &[UsageHistoryEntry],
+        last_viewed: Option<DateTime<Utc>>,
+        thresholds: &[u32],
+    ) -> Option<UnseenChange> {
+        let latest = entries.iter().max_by_key(|e| e.timestamp)?;
+        if last_viewed.is_some_and(|viewed| latest.timestamp <= viewed) {
+            return None;
+        }
+
+        let baseline = last_viewed.and_then(|viewed| {
+            entries
+                .iter()
+                .filter(|e| e.timestamp <= viewed)
+                .max_by_key(|e| e.timestamp)
+        });
+
+        let crossings: Vec<ThresholdCrossing> = latest
+            .limits
+            .iter()
+            .filter_map(|limit| {
+                let previous = baseline
+                    .and_then(|b| b.limits.iter().find(|l| l.id == limit.id))
+                    .map(|l| l.utilization)
+                    .unwrap_or(0.0);
+
+                thresholds
+                    .iter()
+                    .copied()
+                    .filter(|&t| previous < t as f64 && limit.utilization >= t as f64)
+                    .max()
+                    .map(|threshold| ThresholdCrossing {
+                        limit_id: limit.id.clone(),
+                        threshold,
+                        utilization: limit.utilization,
+                    })
+            })
+            .collect();
+
+        if crossings.is_empty() {
+            return None;
+        }
+
+        Some(UnseenChange {
+            account_id: latest.account_id.clone(),
+            account_name: latest.account_name.clone(),
+            latest_snapshot_at: latest.timestamp,
+            last_viewed_at: last_viewed,
+            crossings,
+        })
+    }
+
+    /// Export history to JSON string
+    pub fn export_json(app: &AppHandle, query: Option<&HistoryQuery>) -> Result<String, AppError> {
+        let entries = match query {
+            Some(q) => Self::query(app, q)?,
+            None => Self::get_all_entries(app)?,
+        };
+
+        let json = serde_json::to_string_pretty(&entries)?;
+        Ok(json)
+    }
+
+    /// Export history to CSV string
+    pub fn export_csv(app: &AppHandle, query: Option<&HistoryQuery>) -> Result<String, AppError> {
+        let entries = match query {
+            Some(q) => Self::query(app, q)?,
+            None => Self::get_all_entries(app)?,
+        };
+
+        let mut csv = String::from("id,provider,timestamp,limit_id,utilization,resets_at\n");
+
+        for entry in entries {
+            for limit in &entry.limits {
+                csv.push_str(&format!(
+                    "{},{},{},{},{:.2},{}\n",
+                    entry.id,
+                    entry.provider,
+                    entry.timestamp.to_rfc3339(),
+                    limit.id,
+                    limit.utilization,
+                    limit.resets_at.to_rfc3339()
+                ));
+            }
+        }
+
+        Ok(csv)
+    }
+
+    /// Export the last `days` of history with accounts relabelled and limit IDs hashed
+    ///
+    /// The hash key is random per export, so IDs stay consistent within one
+    /// report but can't be matched across reports or reversed.
+    pub fn export_anonymized(app: &AppHandle, days: u32) -> Result<String, AppError> {
+        let now = Utc::now();
+        let cutoff = now - Duration::days(i64::from(days));
+        let entries: Vec<UsageHistoryEntry> = Self::get_all_entries(app)?
+            .into_iter()
+            .filter(|e| e.timestamp >= cutoff)
+            .collect();
+
+        let mut key = [0u8; 32];
+        rand::thread_rng().fill(&mut key);
+
+        let report = Self::anonymize_entries(&entries, &key, days, now);
+        Ok(serde_json::to_string_pretty(&report)?)
+    }
+
+    /// Build an anonymized report, numbering accounts in order of first appearance
+    pub fn anonymize_entries(
+        entries: &[UsageHistoryEntry],
+        key: &[u8],
+        days: u32,
+        generated_at: DateTime<Utc>,
+    ) -> AnonymizedReport {
+        let mut sorted: Vec<&UsageHistoryEntry> = entries.iter().collect();
+        sorted.sort_by_key(|e| e.timestamp);
+
+        let mut accounts: Vec<AnonymizedAccount> = Vec::new();
+        let mut index_by_account: HashMap<(&str, &str), usize> = HashMap::new();
+
+        for entry in sorted {
+            let index = *index_by_account
+                .entry((entry.provider.as_str(), entry.account_id.as_str()))
+                .or_insert_with(|| {
+                    accounts.push(AnonymizedAccount {
+                        label: format!("Account {}", accounts.len() + 1),
+                        provider: entry.provider.clone(),
+                        points: Vec::new(),
+                    });
+                    accounts.len() - 1
+                });
+
+            let limits = entry
+                .limits
+                .iter()
+                .map(|limit| (Self::hash_limit_id(key, &limit.id), limit.utilization))
+                .collect();
+
+            accounts[index].points.push(AnonymizedPoint {
+                timestamp: entry.timestamp,
+                limits,
+            });
+        }
+
+        AnonymizedReport {
+            generated_at,
+            days,
+            accounts,
+        }
+    }
+
+    /// Keyed, truncated hash of a limit ID
+    fn hash_limit_id(key: &[u8], limit_id: &str) -> String {
+        let mut mac =
+            <HmacSha256 as Mac>::new_from_slice(key).expect("HMAC accepts keys of any length");
+        mac.update(limit_id.as_bytes());
+        let digest = mac.finalize().into_bytes();
+
+        let hex: String = digest[..6].iter().map(|b| format!("{:02x}", b)).collect();
+        format!("limit-{}", hex)
+    }
+
+    /// Clear all history data
+    pub fn clear_all(app: &AppHandle) -> Result<(), AppError> {
+        let lock = Self::store_lock(app);
+        let guard = lock.lock().unwrap_or_else(PoisonError::into_inner);
+
+        Self::save_entries(app, &[])?;
+
+        let store = app.data_store(STORE_FILE)?;
+        store.delete_value(ANNOTATIONS_KEY);
+        store.delete_value(SCRATCH_KEY);
+
+        Self::update_metadata(app, guard)?;
+
+        log::info!("Cleared all history data");
+        Ok(())
+    }
+
+    /// Remove everything in the history store, including view markers and retention policy
+    pub fn wipe(app: &AppHandle) -> Result<(), AppError> {
+        let store = app.data_store(STORE_FILE)?;
+
+        let lock = Self::store_lock(app);
+        let _guard = lock.lock().unwrap_or_else(PoisonError::into_inner);
+
+        store.clear();
+        store.persist()?;
+
+        let compressed = Self::compressed_path(app)?;
+        if compressed.exists() {
+            fs::remove_file(&compressed).map_err(|e| AppError::Store(e.to_string()))?;
+        }
+
+        log::info!("Wiped history store");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::key_value::MemoryStore;
+    use chrono::TimeZone;
+
+    fn snapshot(id: &str, utilization: f64, resets_at: DateTime<Utc>) -> UsageLimitSnapshot {
+        UsageLimitSnapshot {
+            id: id.to_string(),
+            utilization,
+            resets_at,
+            min_utilization: None,
+            max_utilization: None,
+        }
+    }
+
+    fn entry(timestamp: DateTime<Utc>, limits: Vec<UsageLimitSnapshot>) -> UsageHistoryEntry {
+        UsageHistoryEntry {
+            id: format!("{}-claude-acc-1", timestamp.timestamp()),
+            provider: "claude".to_string(),
+            account_id: "acc-1".to_string(),
+            account_name: "Personal".to_string(),
+            timestamp,
+            limits,
+        }
+    }
+
+    #[test]
+    fn daily_heatmap_buckets_by_local_day_across_midnight() {
+        // UTC+2: 21:30 and 22:30 UTC fall on either side of local midnight
+        let tz = chrono::FixedOffset::east_opt(2 * 3600).unwrap();
+        let resets_at = Utc.with_ymd_and_hms(2025, 1, 11, 0, 0, 0).unwrap();
+        let sample = |day: u32, hour: u32, minute: u32, utilization: f64| {
+            entry(
+                Utc.with_ymd_and_hms(2025, 1, day, hour, minute, 0).unwrap(),
+                vec![
+                    snapshot("five_hour", utilization, resets_at),
+                    snapshot("seven_day", 99.0, resets_at),
+                ],
+            )
+        };
+        let entries = vec![
+            sample(8, 10, 0, 30.0),  // Jan 8 local
+            sample(8, 21, 30, 45.0), // 23:30 Jan 8 local
+            sample(8, 22, 30, 20.0), // 00:30 Jan 9 local
+            sample(9, 12, 0, 0.0),   // Jan 9 local
+            sample(10, 8, 0, 60.0),  // Jan 10 local
+            sample(4, 12, 0, 90.0),  // before the window
+        ];
+        let now = Utc.with_ymd_and_hms(2025, 1, 10, 23, 0, 0).unwrap(); // 01:00 Jan 11 local
+
+        let buckets = HistoryService::daily_heatmap(&entries, "five_hour", 5, now, &tz);
+
+        let day = |d: u32| NaiveDate::from_ymd_opt(2025, 1, d).unwrap();
+        let summary: Vec<(NaiveDate, Option<f64>, usize)> = buckets
+            .iter()
+            .map(|b| (b.date, b.peak_utilization, b.sample_count))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (day(7), None, 0),
+                (day(8), Some(45.0), 2),
+                // 0% is real data, unlike the empty Jan 7 and Jan 11
+                (day(9), Some(20.0), 2),
+                (day(10), Some(60.0), 1),
+                (day(11), None, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn heatmap_days_are_capped_at_retention() {
+        assert_eq!(HistoryService::heatmap_days(30, 90), 30);
+        assert_eq!(HistoryService::heatmap_days(365, 90), 90);
+        assert_eq!(HistoryService::heatmap_days(u32::MAX, 0), MAX_HEATMAP_DAYS);
+        assert_eq!(
+            HistoryService::heatmap_days(u32::MAX, 5000),
+            MAX_HEATMAP_DAYS
+        );
+    }
+
+    #[test]
+    fn reset_schedule_follows_regular_five_hour_cadence() {
+        let base = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+
+        // Hourly samples over 20 hours; the window rolls over every 5 hours
+        let entries: Vec<UsageHistoryEntry> = (0..20)
+            .map(|i| {
+                let resets_at = base + Duration::hours(5 * (i / 5 + 1));
+                entry(
+                    base + Duration::hours(i),
+                    vec![snapshot("five_hour", 10.0, resets_at)],
+                )
+            })
+            .collect();
+        let now = base + Duration::minutes(19 * 60 + 30);
+
+        let schedule = HistoryService::reset_schedule("acc-1", "five_hour", &entries, now);
+
+        assert_eq!(schedule.observed_resets.len(), 4);
+        assert_eq!(schedule.interval_secs, Some(5 * 3600));
+        assert_eq!(schedule.confidence, ScheduleConfidence::High);
+        // The API's own next reset comes first, then the cadence continues from it
+        assert_eq!(
+            schedule.predicted_resets,
+            vec![
+                base + Duration::hours(20),
+                base + Duration::hours(25),
+                base + Duration::hours(30),
+            ]
+        );
+    }
+
+    #[test]
+    fn budget_consumption_starts_over_each_month() {
+        let tz = chrono::FixedOffset::east_opt(2 * 3600).unwrap();
+        // Local midnight on Feb 1 is still Jan 31 in UTC
+        let now = tz.with_ymd_and_hms(2025, 2, 1, 9, 0, 0).unwrap();
+        let month_start = HistoryService::month_start(&now);
+        assert_eq!(
+            month_start,
+            Utc.with_ymd_and_hms(2025, 1, 31, 22, 0, 0).unwrap()
+        );
+
+        let window = Utc.with_ymd_and_hms(2025, 2, 1, 2, 0, 0).unwrap();
+        let next_window = window + Duration::hours(5);
+        let sample = |minutes: i64, utilization: f64, resets_at: DateTime<Utc>| {
+            entry(
+                month_start + Duration::minutes(minutes),
+                vec![snapshot("five_hour", utilization, resets_at)],
+            )
+        };
+        let entries = vec![
+            sample(-60, 70.0, window),      // January, ignored
+            sample(30, 10.0, window),       // February from here on
+            sample(90, 30.0, window),       // +20
+            sample(180, 25.0, window),      // a drop isn't negative consumption
+            sample(300, 15.0, next_window), // reset: +15
+        ];
+
+        let status = HistoryService::budget_status("acc-1", &entries, 100.0, 80, month_start);
+        assert_eq!(status.consumed, 35.0);
+        assert_eq!(status.level, BudgetLevel::Under);
+    }
+
+    #[test]
+    fn budget_levels_at_warning_and_budget() {
+        let month_start = Utc.with_ymd_and_hms(2025, 3, 1, 0, 0, 0).unwrap();
+        let resets_at = month_start + Duration::days(7);
+        let entries_at = |used: f64| {
+            vec![
+                entry(month_start, vec![snapshot("seven_day", 0.0, resets_at)]),
+                entry(
+                    month_start + Duration::days(1),
+                    vec![snapshot("seven_day", used, resets_at)],
+                ),
+            ]
+        };
+        let level = |used: f64| {
+            HistoryService::budget_status("acc-1", &entries_at(used), 50.0, 80, month_start).level
+        };
+
+        assert_eq!(level(39.0), BudgetLevel::Under);
+        assert_eq!(level(40.0), BudgetLevel::Warning);
+        assert_eq!(level(49.9), BudgetLevel::Warning);
+        assert_eq!(level(50.0), BudgetLevel::Exceeded);
+    }
+
+    #[test]
+    fn heavy_burn_recaps_soon_after_reset() {
+        let start = Utc.with_ymd_and_hms(2025, 3, 1, 8, 0, 0).unwrap();
+        let resets_at = start + Duration::hours(5);
+        // 6 points every 15 minutes: 24 points an hour
+        let entries: Vec<_> = (0..=16)
+            .map(|i| {
+                let timestamp = start + Duration::minutes(15 * i);
+                entry(
+                    timestamp,
+                    vec![snapshot("five_hour", 6.0 * i as f64, resets_at)],
+                )
+            })
+            .collect();
+
+        let now = start + Duration::hours(4);
+        let forecast =
+            HistoryService::recovery_forecast("acc-1", "five_hour", &entries, 100.0, now);
+
+        assert!(!forecast.insufficient_data);
+        assert_eq!(forecast.burn_rate_per_hour, Some(24.0));
+        assert_eq!(forecast.headroom_secs, Some(15_000));
+        assert_eq!(
+            forecast.recaps_at,
+            Some(resets_at + Duration::seconds(15_000))
+        );
+        assert_eq!(forecast.recaps_before_next_reset, Some(true));
+    }
+
+    #[test]
+    fn light_burn_lasts_the_window_and_thin_history_is_insufficient() {
+        let start = Utc.with_ymd_and_hms(2025, 3, 1, 8, 0, 0).unwrap();
+        let resets_at = start + Duration::days(7);
+        // Across a reset the new window's usage counts: 3 + 3 + 3 + 3 over 4 hours
+        let entries = vec![
+            entry(start, vec![snapshot("seven_day", 90.0, start)]),
+            entry(
+                start + Duration::hours(1),
+                vec![snapshot("seven_day", 3.0, resets_at)],
+            ),
+            entry(
+                start + Duration::hours(2),
+                vec![snapshot("seven_day", 6.0, resets_at)],
+            ),
+            entry(
+                start + Duration::hours(3),
+                vec![snapshot("seven_day", 9.0, resets_at)],
+            ),
+            entry(
+                start + Duration::hours(4),
+                vec![snapshot("seven_day", 12.0, resets_at)],
+            ),
+        ];
+
+        let now = start + Duration::hours(4);
+        let forecast =
+            HistoryService::recovery_forecast("acc-1", "seven_day", &entries, 100.0, now);
+        assert_eq!(forecast.burn_rate_per_hour, Some(3.0));
+        assert_eq!(forecast.headroom_secs, Some(120_000));
+        assert_eq!(forecast.recaps_before_next_reset, Some(false));
+
+        let thin =
+            HistoryService::recovery_forecast("acc-1", "seven_day", &entries[3..], 100.0, now);
+        assert!(thin.insufficient_data);
+        assert_eq!(thin.resets_at, Some(resets_at));
+        assert_eq!(thin.burn_rate_per_hour, None);
+        assert_eq!(thin.recaps_before_next_reset, None);
+    }
+
+    #[test]
+    fn negligible_burn_has_no_recap_instead_of_overflowing() {
+        let start = Utc.with_ymd_and_hms(2025, 3, 1, 8, 0, 0).unwrap();
+        let resets_at = start + Duration::days(7);
+        let entries: Vec<_> = (0..4)
+            .map(|i| {
+                entry(
+                    start + Duration::hours(i),
+                    vec![snapshot("seven_day", 1e-300 * i as f64, resets_at)],
+                )
+            })
+            .collect();
+
+        let now = start + Duration::hours(3);
+        let forecast =
+            HistoryService::recovery_forecast("acc-1", "seven_day", &entries, 100.0, now);
+
+        assert!(!forecast.insufficient_data);
+        assert_eq!(forecast.headroom_secs, None);
+        assert_eq!(forecast.recaps_at, None);
+        assert_eq!(forecast.recaps_before_next_reset, Some(false));
+    }
+
+    #[test]
+    fn sparkline_maps_utilization_to_blocks() {
+        assert_eq!(
+            HistoryService::sparkline(&[0.0, 12.5, 25.0, 37.5, 50.0, 62.5, 75.0, 87.5, 100.0]),
+            "▁▂▃▄▅▆▇██"
+        );
+        assert_eq!(
+            HistoryService::sparkline(&[-5.0, 12.4, 99.9, 140.0]),
+            "▁▁██"
+        );
+        assert_eq!(HistoryService::sparkline(&[]), "");
+    }
+
+    #[test]
+    fn sparkline_uses_latest_points_and_renders_short_history() {
+        let start = Utc.with_ymd_and_hms(2025, 3, 1, 8, 0, 0).unwrap();
+        let resets_at = start + Duration::hours(5);
+        // Out of order, and one entry without the limit
+        let entries = vec![
+            entry(
+                start + Duration::minutes(20),
+                vec![snapshot("five_hour", 60.0, resets_at)],
+            ),
+            entry(start, vec![snapshot("five_hour", 10.0, resets_at)]),
+            entry(
+                start + Duration::minutes(10),
+                vec![snapshot("seven_day", 30.0, resets_at)],
+            ),
+            entry(
+                start + Duration::minutes(30),
+                vec![snapshot("five_hour", 95.0, resets_at)],
+            ),
+        ];
+
+        assert_eq!(
+            HistoryService::recent_utilization(&entries, "five_hour", 2),
+            vec![60.0, 95.0]
+        );
+        let all = HistoryService::recent_utilization(&entries, "five_hour", 24);
+        assert_eq!(all, vec![10.0, 60.0, 95.0]);
+        assert_eq!(HistoryService::sparkline(&all), "▁▅█");
+        assert!(HistoryService::recent_utilization(&entries, "opus", 24).is_empty());
+    }
+
+    #[test]
+    fn reset_schedule_irregular_series_has_low_confidence() {
+        let base = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+        let entries: Vec<UsageHistoryEntry> = [5, 14, 20, 34]
+            .iter()
+            .map(|&hours| {
+                let resets_at = base + Duration::hours(hours);
+                entry(
+                    resets_at - Duration::hours(1),
+                    vec![snapshot("five_hour", 80.0, resets_at)],
+                )
+            })
+            .collect();
+
+        // Gaps of 9, 6 and 14 hours: median 9h, projected past the last reset
+        let schedule = HistoryService::reset_schedule(
+            "acc-1",
+            "five_hour",
+            &entries,
+            base + Duration::hours(40),
+        );
+
+        assert_eq!(schedule.interval_secs, Some(9 * 3600));
+        assert_eq!(schedule.confidence, ScheduleConfidence::Low);
+        assert_eq!(
+            schedule.predicted_resets,
+            vec![
+                base + Duration::hours(43),
+                base + Duration::hours(52),
+                base + Duration::hours(61),
+            ]
+        );
+
+        // Two resets aren't enough to call it a cadence
+        let schedule = HistoryService::reset_schedule("acc-1", "five_hour", &entries[..2], base);
+        assert_eq!(schedule.confidence, ScheduleConfidence::Unknown);
+        assert_eq!(schedule.observed_resets.len(), 2);
+        assert_eq!(schedule.interval_secs, None);
+        assert!(schedule.predicted_resets.is_empty());
+    }
+
+    #[test]
+    fn compact_downsamples_old_entries_into_buckets() {
+        let base = Utc.with_ymd_and_hms(2025, 1, 1, 10, 0, 0).unwrap();
+        let resets_at = base + Duration::hours(5);
+        let cutoff = base + Duration::days(1);
+
+        // Two hours of 5-minute samples: 10..=21 in the first hour, 22..=33 in the second
+        let mut entries: Vec<UsageHistoryEntry> = (0..24)
+            .map(|i| {
+                entry(
+                    base + Duration::minutes(5 * i),
+                    vec![snapshot("five_hour", 10.0 + i as f64, resets_at)],
+                )
+            })
+            .collect();
+        let recent = entry(cutoff + Duration::hours(1), vec![snapshot("five_hour", 50.0, resets_at)]);
+        entries.push(recent.clone());
+
+        let compacted = HistoryService::compact_entries(entries, cutoff, Duration::minutes(60));
+
+        assert_eq!(compacted.len(), 3);
+        // The first bucket starts at the first raw sample but doesn't take its ID
+        assert_eq!(
+            compacted[0].id,
+            format!("compacted-{}-claude-acc-1", base.timestamp())
+        );
+
+        let first = &compacted[0].limits[0];
+        assert_eq!(compacted[0].timestamp, base);
+        assert_eq!(first.min_utilization, Some(10.0));
+        assert_eq!(first.max_utilization, Some(21.0));
+        assert!((first.utilization - 15.5).abs() < 1e-9);
+
+        let second = &compacted[1].limits[0];
+        assert_eq!(compacted[1].timestamp, base + Duration::hours(1));
+        assert_eq!(second.min_utilization, Some(22.0));
+        assert_eq!(second.max_utilization, Some(33.0));
+        assert!((second.utilization - 27.5).abs() < 1e-9);
+
+        // The recent window is untouched
+        assert_eq!(compacted[2].id, recent.id);
+        assert_eq!(compacted[2].limits[0].min_utilization, None);
+    }
+
+    #[test]
+    fn reassign_moves_secondary_history_and_prefers_primary_on_overlap() {
+        let base = Utc.with_ymd_and_hms(2025, 1, 1, 10, 0, 0).unwrap();
+        let resets_at = base + Duration::hours(5);
+
+        let primary = entry(base, vec![snapshot("five_hour", 10.0, resets_at)]);
+        let secondary = |timestamp: DateTime<Utc>, utilization: f64| UsageHistoryEntry {
+            id: format!("{}-claude-acc-2", timestamp.timestamp()),
+            account_id: "acc-2".to_string(),
+            account_name: "Old Laptop".to_string(),
+            ..entry(
+                timestamp,
+                vec![snapshot("five_hour", utilization, resets_at)],
+            )
+        };
+
+        let compacted = UsageHistoryEntry {
+            id: format!(
+                "{}-{}-claude-acc-2",
+                COMPACTED_ID_PREFIX,
+                (base - Duration::hours(1)).timestamp()
+            ),
+            ..secondary(base - Duration::hours(1), 5.0)
+        };
+
+        let entries = vec![
+            compacted,
+            primary,
+            secondary(base, 99.0),
+            secondary(base + Duration::minutes(5), 15.0),
+        ];
+
+        let (merged, moved) =
+            HistoryService::reassign_entries(entries, "acc-2", "acc-1", "Personal");
+
+        assert_eq!(moved, 2);
+        assert_eq!(merged.len(), 3);
+        assert!(merged
+            .iter()
+            .all(|e| e.account_id == "acc-1" && e.account_name == "Personal"));
+
+        // A compacted bucket keeps its prefix, so it can't collide with a raw snapshot
+        assert_eq!(
+            merged[0].id,
+            format!(
+                "compacted-{}-claude-acc-1",
+                (base - Duration::hours(1)).timestamp()
+            )
+        );
+
+        // The primary's snapshot wins the overlapping timestamp
+        assert_eq!(merged[1].limits[0].utilization, 10.0);
+
+        let moved_entry = &merged[2];
+        assert_eq!(moved_entry.limits[0].utilization, 15.0);
+        assert_eq!(
+            moved_entry.id,
+            format!("{}-claude-acc-1", (base + Duration::minutes(5)).timestamp())
+        );
+    }
+
+    /// Path for a compressed entries file unique to one test
+    fn temp_compressed_path() -> PathBuf {
+        std::env::temp_dir().join(format!("ai-pulse-history-{}.json.gz", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn compressed_store_round_trips_snapshots() {
+        let base = Utc.with_ymd_and_hms(2025, 1, 1, 10, 0, 0).unwrap();
+        let resets_at = base + Duration::hours(5);
+        let entries = vec![
+            entry(base, vec![snapshot("five_hour", 10.0, resets_at)]),
+            entry(
+                base + Duration::minutes(5),
+                vec![
+                    snapshot("five_hour", 12.5, resets_at),
+                    snapshot("seven_day", 40.0, resets_at),
+                ],
+            ),
+        ];
+
+        let store = MemoryStore::default();
+        let path = temp_compressed_path();
+
+        HistoryService::store_entries(&store, &path, true, &entries).unwrap();
+
+        let bytes = fs::read(&path).unwrap();
+        assert_eq!(&bytes[..2], &[0x1f, 0x8b], "not gzip");
+        assert!(store.get_value(HISTORY_KEY).is_none());
+
+        let loaded = HistoryService::load_entries(&store, &path, true).unwrap();
+        assert_eq!(
+            serde_json::to_value(&loaded).unwrap(),
+            serde_json::to_value(&entries).unwrap()
+        );
+
+        // Turning compression off moves the entries back inline
+        HistoryService::store_entries(&store, &path, false, &loaded).unwrap();
+        assert!(!path.exists());
+        assert_eq!(
+            HistoryService::load_entries(&store, &path, false)
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn first_compressed_write_migrates_uncompressed_store() {
+        let base = Utc.with_ymd_and_hms(2025, 1, 1, 10, 0, 0).unwrap();
+        let resets_at = base + Duration::hours(5);
+        let existing = vec![entry(base, vec![snapshot("five_hour", 10.0, resets_at)])];
+
+        let store = MemoryStore::default();
+        store.set_value(HISTORY_KEY, serde_json::to_value(&existing).unwrap());
+        let path = temp_compressed_path();
+
+        // Before any compressed write, reads fall back to the inline entries
+        let mut entries = HistoryService::load_entries(&store, &path, true).unwrap();
+        assert_eq!(entries.len(), 1);
+
+        entries.push(entry(
+            base + Duration::minutes(5),
+            vec![snapshot("five_hour", 15.0, resets_at)],
+        ));
+        HistoryService::store_entries(&store, &path, true, &entries).unwrap();
+
+        assert!(store.get_value(HISTORY_KEY).is_none());
+        assert_eq!(store.persist_count.get(), 1);
+
+        let loaded = HistoryService::load_entries(&store, &path, true).unwrap();
+        let ids: Vec<&str> = loaded.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec![entries[0].id.as_str(), entries[1].id.as_str()]);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn scratch_entries_are_kept_apart_from_real_history() {
+        let base = Utc.with_ymd_and_hms(2025, 1, 1, 10, 0, 0).unwrap();
+        let existing = vec![entry(
+            base,
+            vec![snapshot("five_hour", 10.0, base + Duration::hours(5))],
+        )];
+        let store = MemoryStore::default();
+        store.set_value(HISTORY_KEY, serde_json::to_value(&existing).unwrap());
+        let path = temp_compressed_path();
+
+        let usage = UsageData {
+            provider: "claude".to_string(),
+            account_id: "acc-1".to_string(),
+            account_name: "Work".to_string(),
+            timestamp: base + Duration::minutes(5),
+            limits: Vec::new(),
+            raw: None,
+            rate_limit: None,
+        };
+        let recorded = HistoryService::push_scratch(&store, &usage).unwrap();
+
+        // Labeled as scratch even when the caller passed a real account's ID
+        assert_eq!(recorded.account_id, SCRATCH_ACCOUNT);
+        assert_eq!(recorded.account_name, SCRATCH_ACCOUNT);
+        assert_eq!(HistoryService::load_scratch(&store).len(), 1);
+
+        let real = HistoryService::load_entries(&store, &path, false).unwrap();
+        assert_eq!(real.len(), 1);
+        assert_eq!(real[0].id, existing[0].id);
+        assert!(real.iter().all(|e| e.account_id != SCRATCH_ACCOUNT));
+    }
+
+    #[test]
+    fn compact_is_stable_when_rerun() {
+        let base = Utc.with_ymd_and_hms(2025, 1, 1, 10, 0, 0).unwrap();
+        let resets_at = base + Duration::hours(5);
+        let cutoff = base + Duration::days(1);
+        let entries: Vec<UsageHistoryEntry> = (0..12)
+            .map(|i| entry(base + Duration::minutes(5 * i), vec![snapshot("five_hour", i as f64, resets_at)]))
+            .collect();
+
+        let once = HistoryService::compact_entries(entries, cutoff, Duration::minutes(60));
+        let twice = HistoryService::compact_entries(once.clone(), cutoff, Duration::minutes(60));
+
+        assert_eq!(once.len(), 1);
+        assert_eq!(twice.len(), 1);
+        assert_eq!(twice[0].id, once[0].id);
+        assert_eq!(twice[0].limits[0].min_utilization, Some(0.0));
+        assert_eq!(twice[0].limits[0].max_utilization, Some(11.0));
+    }
+
+    #[test]
+    fn account_stats_averages_per_limit() {
+        let base = Utc.with_ymd_and_hms(2025, 1, 15, 9, 0, 0).unwrap();
+        let reset = base + Duration::hours(5);
+        let weekly = base + Duration::days(5);
+
+        let entries = vec![
+            entry(base, vec![snapshot("five_hour", 10.0, reset), snapshot("seven_day", 40.0, weekly)]),
+            entry(base + Duration::hours(1), vec![snapshot("five_hour", 20.0, reset)]),
+            entry(base + Duration::hours(2), vec![snapshot("five_hour", 60.0, reset)]),
+        ];
+
+        let stats = HistoryService::compute_account_stats("acc-1", &entries, base, base + Duration::days(1));
+
+        assert_eq!(stats.sample_count, 3);
+        assert_eq!(stats.limits.len(), 2);
+
+        let five_hour = stats.limits.iter().find(|l| l.limit_id == "five_hour").unwrap();
+        assert_eq!(five_hour.sample_count, 3);
+        assert!((five_hour.avg_utilization - 30.0).abs() < 0.001);
+        assert!((five_hour.max_utilization - 60.0).abs() < 0.001);
+        assert!((five_hour.min_utilization - 10.0).abs() < 0.001);
+        assert_eq!(five_hour.peak_hour, Some(11));
+
+        // Sparse limit reports only the samples it actually has
+        let seven_day = stats.limits.iter().find(|l| l.limit_id == "seven_day").unwrap();
+        assert_eq!(seven_day.sample_count, 1);
+        assert_eq!(seven_day.reset_count, 0);
+    }
+
+    #[test]
+    fn account_stats_counts_resets() {
+        let base = Utc.with_ymd_and_hms(2025, 1, 15, 0, 0, 0).unwrap();
+        let first_window = base + Duration::hours(5);
+        let second_window = base + Duration::hours(10);
+        let third_window = base + Duration::hours(15);
+
+        // Input is newest first, as returned by `query`
+        let entries = vec![
+            entry(base + Duration::hours(11), vec![snapshot("five_hour", 5.0, third_window)]),
+            entry(base + Duration::hours(9), vec![snapshot("five_hour", 70.0, second_window)]),
+            entry(base + Duration::hours(6), vec![snapshot("five_hour", 10.0, second_window)]),
+            entry(base + Duration::hours(4), vec![snapshot("five_hour", 90.0, first_window)]),
+            entry(base + Duration::hours(1), vec![snapshot("five_hour", 30.0, first_window)]),
+        ];
+
+        let stats = HistoryService::compute_account_stats("acc-1", &entries, base, base + Duration::days(1));
+        let five_hour = &stats.limits[0];
+
+        assert_eq!(five_hour.reset_count, 2);
+        assert_eq!(five_hour.sample_count, 5);
+    }
+
+    #[test]
+    fn account_stats_empty_history() {
+        let base = Utc.with_ymd_and_hms(2025, 1, 15, 0, 0, 0).unwrap();
+        let stats = HistoryService::compute_account_stats("acc-1", &[], base, base + Duration::days(7));
+
+        assert_eq!(stats.account_id, "acc-1");
+        assert_eq!(stats.sample_count, 0);
+        assert!(stats.limits.is_empty());
+    }
+
+    #[test]
+    fn reset_detection_ignores_jitter() {
+        let resets_at = Utc.with_ymd_and_hms(2025, 1, 15, 5, 0, 0).unwrap();
+        let previous = snapshot("five_hour", 80.0, resets_at);
+        let jittered = snapshot("five_hour", 81.0, resets_at + Duration::seconds(30));
+        let rolled = snapshot("five_hour", 2.0, resets_at + Duration::hours(5));
+
+        assert!(!HistoryService::is_reset(&previous, &jittered));
+        assert!(HistoryService::is_reset(&previous, &rolled));
+    }
+
+    #[test]
+    fn unseen_change_reports_threshold_crossed_after_view() {
+        let base = Utc.with_ymd_and_hms(2025, 1, 15, 9, 0, 0).unwrap();
+        let reset = base + Duration::hours(5);
+        let entries = vec![
+            entry(base, vec![snapshot("five_hour", 40.0, reset)]),
+            entry(base + Duration::hours(2), vec![snapshot("five_hour", 80.0, reset)]),
+        ];
+
+        let change = HistoryService::detect_unseen_change(
+            &entries,
+            Some(base + Duration::hours(1)),
+            &[50, 75, 90],
+        )
+        .unwrap();
+
+        assert_eq!(change.account_id, "acc-1");
+        assert_eq!(change.latest_snapshot_at, base + Duration::hours(2));
+        assert_eq!(change.crossings.len(), 1);
+        assert_eq!(change.crossings[0].limit_id, "five_hour");
+        assert_eq!(change.crossings[0].threshold, 75);
+    }
+
+    #[test]
+    fn unseen_change_ignores_movement_without_crossing() {
+        let base = Utc.with_ymd_and_hms(2025, 1, 15, 9, 0, 0).unwrap();
+        let reset = base + Duration::hours(5);
+        let entries = vec![
+            entry(base, vec![snapshot("five_hour", 55.0, reset)]),
+            entry(base + Duration::hours(2), vec![snapshot("five_hour", 70.0, reset)]),
+        ];
+
+        let viewed = Some(base + Duration::hours(1));
+        assert!(HistoryService::detect_unseen_change(&entries, viewed, &[50, 75, 90]).is_none());
+    }
+
+    #[test]
+    fn unseen_change_ignores_snapshots_already_viewed() {
+        let base = Utc.with_ymd_and_hms(2025, 1, 15, 9, 0, 0).unwrap();
+        let reset = base + Duration::hours(5);
+        let entries = vec![entry(base, vec![snapshot("five_hour", 95.0, reset)])];
+
+        let viewed = Some(base + Duration::minutes(1));
+        assert!(HistoryService::detect_unseen_change(&entries, viewed, &[50, 75, 90]).is_none());
+    }
+
+    #[test]
+    fn unseen_change_for_never_viewed_account() {
+        let base = Utc.with_ymd_and_hms(2025, 1, 15, 9, 0, 0).unwrap();
+        let reset = base + Duration::hours(5);
+        let entries = vec![entry(base, vec![snapshot("five_hour", 60.0, reset)])];
+
+        let change = HistoryService::detect_unseen_change(&entries, None, &[50, 75, 90]).unwrap();
+        assert_eq!(change.last_viewed_at, None);
+        assert_eq!(change.crossings[0].threshold, 50);
+    }
+
+    #[test]
+    fn spend_multiplies_consumed_units_by_rate() {
+        let base = Utc.with_ymd_and_hms(2025, 1, 15, 0, 0, 0).unwrap();
+        let first_window = base + Duration::hours(5);
+        let second_window = base + Duration::hours(10);
+        let entries = vec![
+            entry(base, vec![snapshot("five_hour", 10.0, first_window)]),
+            entry(base + Duration::hours(2), vec![snapshot("five_hour", 40.0, first_window)]),
+            // Reset: the 15 points used in the new window count too
+            entry(base + Duration::hours(6), vec![snapshot("five_hour", 15.0, second_window)]),
+        ];
+        let pricing = HashMap::from([("five_hour".to_string(), 0.5)]);
+
+        let estimate = HistoryService::compute_spend(
+            "acc-1",
+            "claude",
+            &entries,
+            &pricing,
+            base,
+            base + Duration::days(1),
+        );
+
+        let five_hour = &estimate.limits[0];
+        assert!((five_hour.consumed_units - 45.0).abs() < 0.001);
+        assert_eq!(five_hour.unit_cost, Some(0.5));
+        assert!((five_hour.cost.unwrap() - 22.5).abs() < 0.001);
+        assert!((estimate.total_cost - 22.5).abs() < 0.001);
+        assert!(estimate.unpriced_limits.is_empty());
+    }
+
+    #[test]
+    fn spend_reports_unpriced_limits() {
+        let base = Utc.with_ymd_and_hms(2025, 1, 15, 0, 0, 0).unwrap();
+        let reset = base + Duration::hours(5);
+        let weekly = base + Duration::days(5);
+        let entries = vec![
+            entry(base, vec![snapshot("five_hour", 10.0, reset), snapshot("seven_day", 20.0, weekly)]),
+            entry(
+                base + Duration::hours(1),
+                vec![snapshot("five_hour", 30.0, reset), snapshot("seven_day", 25.0, weekly)],
+            ),
+        ];
+        let pricing = HashMap::from([("five_hour".to_string(), 1.0)]);
+
+        let estimate = HistoryService::compute_spend(
+            "acc-1",
+            "claude",
+            &entries,
+            &pricing,
+            base,
+            base + Duration::days(1),
+        );
+
+        let seven_day = estimate.limits.iter().find(|l| l.limit_id == "seven_day").unwrap();
+        assert!((seven_day.consumed_units - 5.0).abs() < 0.001);
+        assert_eq!(seven_day.cost, None);
+        assert_eq!(estimate.unpriced_limits, vec!["seven_day".to_string()]);
+        // Unpriced usage is excluded from the total rather than counted as zero-cost
+        assert!((estimate.total_cost - 20.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn anonymized_report_hides_account_names_and_ids() {
+        let base = Utc.with_ymd_and_hms(2025, 1, 1, 10, 0, 0).unwrap();
+        let resets_at = base + Duration::hours(5);
+
+        let mut work = entry(
+            base + Duration::minutes(5),
+            vec![snapshot("five_hour", 40.0, resets_at)],
+        );
+        work.account_id = "acc-secret-42".to_string();
+        work.account_name = "Acme Corp Work".to_string();
+        work.id = format!("{}-claude-acc-secret-42", work.timestamp.timestamp());
+
+        let entries = vec![
+            entry(base, vec![snapshot("five_hour", 10.0, resets_at)]),
+            work,
+            entry(
+                base + Duration::minutes(10),
+                vec![snapshot("five_hour", 20.0, resets_at)],
+            ),
+        ];
+
+        let report = HistoryService::anonymize_entries(&entries, b"export-key", 7, base);
+        let json = serde_json::to_string(&report).unwrap();
+
+        for secret in [
+            "acc-1",
+            "Personal",
+            "acc-secret-42",
+            "Acme Corp Work",
+            "five_hour",
+        ] {
+            assert!(!json.contains(secret), "report leaked {:?}", secret);
+        }
+
+        let labels: Vec<&str> = report.accounts.iter().map(|a| a.label.as_str()).collect();
+        assert_eq!(labels, vec!["Account 1", "Account 2"]);
+        assert_eq!(report.accounts[0].points.len(), 2);
+
+        // The same limit hashes identically across accounts within one export
+        let hashed: Vec<&String> = report
+            .accounts
+            .iter()
+            .flat_map(|a| a.points.iter().flat_map(|p| p.limits.keys()))
+            .collect();
+        assert!(hashed.iter().all(|h| *h == hashed[0]));
+
+        // ...but differently under another export's key
+        let other = HistoryService::anonymize_entries(&entries, b"other-key", 7, base);
+        assert!(!other.accounts[0].points[0].limits.contains_key(hashed[0]));
     }
 }
@@ -0,0 +1,145 @@
+use crate::error::ProviderError;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, PoisonError};
+use tokio::sync::oneshot;
+
+/// Cancel handles by request ID, each tagged with the run that registered it
+type CancelSenders = HashMap<String, (u64, oneshot::Sender<()>)>;
+
+/// Fetches started with a caller-supplied request ID, so the frontend can abort them
+#[derive(Default)]
+pub struct InFlightFetches {
+    cancel_senders: Mutex<CancelSenders>,
+    /// Tells apart runs that reuse a request ID
+    next_generation: AtomicU64,
+}
+
+impl InFlightFetches {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `fetch`, returning `ProviderError::Canceled` if `cancel(request_id)` is called first
+    ///
+    /// Without a request ID the fetch simply runs to completion.
+    pub async fn run<T, Fut>(
+        &self,
+        request_id: Option<&str>,
+        fetch: Fut,
+    ) -> Result<T, ProviderError>
+    where
+        Fut: Future<Output = Result<T, ProviderError>>,
+    {
+        let Some(request_id) = request_id else {
+            return fetch.await;
+        };
+
+        let generation = self.next_generation.fetch_add(1, Ordering::Relaxed);
+        let (cancel_tx, cancel_rx) = oneshot::channel();
+        self.lock()
+            .insert(request_id.to_string(), (generation, cancel_tx));
+
+        let result = tokio::select! {
+            result = fetch => result,
+            // A dropped sender means the ID was reused by a newer request, not a cancel
+            Ok(()) = cancel_rx => {
+                log::info!("Fetch {} canceled", request_id);
+                Err(ProviderError::Canceled)
+            }
+        };
+
+        // A newer run with the same ID owns the entry now; leave it cancelable
+        let mut senders = self.lock();
+        if senders
+            .get(request_id)
+            .is_some_and(|(registered, _)| *registered == generation)
+        {
+            senders.remove(request_id);
+        }
+        result
+    }
+
+    /// Abort the in-flight fetch with this ID; returns false if none is running
+    pub fn cancel(&self, request_id: &str) -> bool {
+        match self.lock().remove(request_id) {
+            Some((_, cancel_tx)) => cancel_tx.send(()).is_ok(),
+            None => false,
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CancelSenders> {
+        self.cancel_senders
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn cancel_mid_fetch_returns_canceled() {
+        let fetches = InFlightFetches::new();
+
+        let slow_fetch = async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok::<_, ProviderError>("usage")
+        };
+        let cancel_soon = async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            assert!(fetches.cancel("req-1"));
+        };
+
+        let (result, ()) = tokio::join!(fetches.run(Some("req-1"), slow_fetch), cancel_soon);
+
+        assert!(matches!(result, Err(ProviderError::Canceled)));
+        assert!(!fetches.cancel("req-1"));
+    }
+
+    #[tokio::test]
+    async fn uncanceled_fetch_completes() {
+        let fetches = InFlightFetches::new();
+
+        let result = fetches
+            .run(Some("req-1"), async { Ok::<_, ProviderError>(42) })
+            .await;
+
+        assert_eq!(result.unwrap(), 42);
+        assert!(!fetches.cancel("req-1"));
+    }
+
+    #[tokio::test]
+    async fn finished_run_leaves_a_reused_id_cancelable() {
+        let fetches = InFlightFetches::new();
+
+        let first = async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            Ok::<_, ProviderError>("first")
+        };
+        let second = async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok::<_, ProviderError>("second")
+        };
+        let cancel_after_first = async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            assert!(fetches.cancel("req-1"));
+        };
+
+        let (first, second, ()) = tokio::join!(
+            fetches.run(Some("req-1"), first),
+            async {
+                // Start after the first has registered, reusing its ID
+                tokio::time::sleep(Duration::from_millis(5)).await;
+                fetches.run(Some("req-1"), second).await
+            },
+            cancel_after_first
+        );
+
+        assert_eq!(first.unwrap(), "first");
+        assert!(matches!(second, Err(ProviderError::Canceled)));
+    }
+}
@@ -0,0 +1,229 @@
+use crate::error::AppError;
+use serde_json::Value as JsonValue;
+use std::io;
+use std::sync::MutexGuard;
+use std::time::Duration;
+use tauri::Runtime;
+use tauri_plugin_store::Store;
+
+/// Attempts made to write a store file before the error is returned
+const SAVE_ATTEMPTS: u32 = 4;
+
+/// Wait before the first retry; doubled for each one after
+const SAVE_RETRY_DELAY: Duration = Duration::from_millis(50);
+
+/// Minimal key-value view of a store, so store logic can be tested without an AppHandle
+pub(crate) trait KeyValueStore {
+    fn get_value(&self, key: &str) -> Option<JsonValue>;
+    fn set_value(&self, key: &str, value: JsonValue);
+    fn delete_value(&self, key: &str);
+    fn key_list(&self) -> Vec<String>;
+    fn persist(&self) -> Result<(), AppError>;
+}
+
+impl<R: Runtime> KeyValueStore for Store<R> {
+    fn get_value(&self, key: &str) -> Option<JsonValue> {
+        self.get(key)
+    }
+
+    fn set_value(&self, key: &str, value: JsonValue) {
+        self.set(key, value);
+    }
+
+    fn delete_value(&self, key: &str) {
+        self.delete(key);
+    }
+
+    fn key_list(&self) -> Vec<String> {
+        self.keys()
+    }
+
+    fn persist(&self) -> Result<(), AppError> {
+        save_with_retry(|| self.save(), is_transient, std::thread::sleep)
+    }
+}
+
+/// Release a store's lock, then save the store
+///
+/// A save writes everything the store holds, so once a read-modify-write is
+/// done in memory the lock has nothing left to protect, and backing off on a
+/// file another process holds shouldn't keep other writers waiting.
+pub(crate) fn persist_unlocked<T>(
+    store: &impl KeyValueStore,
+    guard: MutexGuard<'_, T>,
+) -> Result<(), AppError> {
+    drop(guard);
+    store.persist()
+}
+
+/// Run `save`, retrying with backoff while `is_transient` says the error may clear up
+///
+/// On Windows a file briefly locked by an antivirus scan or sync client usually
+/// frees up within a few hundred milliseconds. Every other failure is returned
+/// straight away, so elsewhere this never blocks the calling thread.
+pub(crate) fn save_with_retry(
+    mut save: impl FnMut() -> Result<(), tauri_plugin_store::Error>,
+    is_transient: impl Fn(&tauri_plugin_store::Error) -> bool,
+    mut sleep: impl FnMut(Duration),
+) -> Result<(), AppError> {
+    let mut delay = SAVE_RETRY_DELAY;
+    let mut attempt = 1;
+    loop {
+        match save() {
+            Ok(()) => return Ok(()),
+            Err(e) if attempt < SAVE_ATTEMPTS && is_transient(&e) => {
+                log::warn!(
+                    "Saving store failed (attempt {} of {}), retrying in {:?}: {}",
+                    attempt,
+                    SAVE_ATTEMPTS,
+                    delay,
+                    e
+                );
+                sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// Whether a save error is likely to clear up if the write is tried again
+///
+/// Only Windows sharing and lock violations qualify: a denied permission or a
+/// full disk stays that way, and retrying would just stall the caller.
+fn is_transient(error: &tauri_plugin_store::Error) -> bool {
+    let tauri_plugin_store::Error::Io(e) = error else {
+        return false;
+    };
+    cfg!(windows) && is_lock_violation(e)
+}
+
+/// Windows reports a file held open by another process as a sharing (32) or lock (33) violation
+fn is_lock_violation(error: &io::Error) -> bool {
+    matches!(error.raw_os_error(), Some(32 | 33))
+}
+
+/// In-memory stand-in for the store plugin
+#[cfg(test)]
+#[derive(Default)]
+pub(crate) struct MemoryStore {
+    pub values: std::cell::RefCell<std::collections::HashMap<String, JsonValue>>,
+    /// Number of times `persist` was called
+    pub persist_count: std::cell::Cell<usize>,
+}
+
+#[cfg(test)]
+impl KeyValueStore for MemoryStore {
+    fn get_value(&self, key: &str) -> Option<JsonValue> {
+        self.values.borrow().get(key).cloned()
+    }
+
+    fn set_value(&self, key: &str, value: JsonValue) {
+        self.values.borrow_mut().insert(key.to_string(), value);
+    }
+
+    fn delete_value(&self, key: &str) {
+        self.values.borrow_mut().remove(key);
+    }
+
+    fn key_list(&self) -> Vec<String> {
+        self.values.borrow().keys().cloned().collect()
+    }
+
+    fn persist(&self) -> Result<(), AppError> {
+        self.persist_count.set(self.persist_count.get() + 1);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn io_error(kind: io::ErrorKind) -> tauri_plugin_store::Error {
+        tauri_plugin_store::Error::Io(io::Error::new(kind, "simulated"))
+    }
+
+    /// ERROR_SHARING_VIOLATION, as Windows reports a file another process has open
+    fn sharing_violation() -> tauri_plugin_store::Error {
+        tauri_plugin_store::Error::Io(io::Error::from_raw_os_error(32))
+    }
+
+    /// `is_transient` as it behaves on Windows, so retries are tested on every platform
+    fn windows_is_transient(error: &tauri_plugin_store::Error) -> bool {
+        matches!(error, tauri_plugin_store::Error::Io(e) if is_lock_violation(e))
+    }
+
+    #[test]
+    fn lock_violations_are_retried_until_success() {
+        let mut calls = 0;
+        let mut delays = Vec::new();
+
+        let result = save_with_retry(
+            || {
+                calls += 1;
+                if calls < 3 {
+                    Err(sharing_violation())
+                } else {
+                    Ok(())
+                }
+            },
+            windows_is_transient,
+            |d| delays.push(d),
+        );
+
+        assert!(result.is_ok());
+        assert_eq!(calls, 3);
+        assert_eq!(
+            delays,
+            vec![Duration::from_millis(50), Duration::from_millis(100)]
+        );
+
+        let mut calls = 0;
+        let result = save_with_retry(
+            || {
+                calls += 1;
+                Err(sharing_violation())
+            },
+            windows_is_transient,
+            |_| {},
+        );
+        assert!(matches!(result, Err(AppError::Store(_))));
+        assert_eq!(calls, SAVE_ATTEMPTS);
+    }
+
+    #[test]
+    fn other_save_failures_are_not_retried() {
+        let errors = || {
+            vec![
+                io_error(io::ErrorKind::PermissionDenied),
+                io_error(io::ErrorKind::TimedOut),
+                io_error(io::ErrorKind::NotFound),
+                tauri_plugin_store::Error::Serialize("bad value".into()),
+            ]
+        };
+
+        for error in errors() {
+            assert!(!windows_is_transient(&error));
+            assert!(!is_transient(&error));
+        }
+        // Outside Windows error 32 is something else entirely
+        assert_eq!(is_transient(&sharing_violation()), cfg!(windows));
+
+        for error in errors() {
+            let mut error = Some(error);
+            let mut calls = 0;
+            let result = save_with_retry(
+                || {
+                    calls += 1;
+                    Err(error.take().expect("save is called once"))
+                },
+                windows_is_transient,
+                |_| panic!("a permanent error must not wait"),
+            );
+            assert!(matches!(result, Err(AppError::Store(_))));
+            assert_eq!(calls, 1);
+        }
+    }
+}
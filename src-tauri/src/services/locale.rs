@@ -0,0 +1,132 @@
+/// Locale used when the requested locale or key has no translation
+const FALLBACK_LOCALE: &str = "en";
+
+/// English strings (the fallback for every other locale)
+const EN: &[(&str, &str)] = &[
+    ("threshold_alert.title", "{threshold}% Usage Alert"),
+    ("threshold_alert.body", "{label} is at {percent}% usage"),
+    ("threshold_digest.title", "{count} Usage Alerts"),
+    ("threshold_digest.body", "{count} limits crossed thresholds: {list}"),
+    ("usage_reset.title", "Usage Reset"),
+    ("usage_reset.body", "{label} has reset! Now at {percent}%"),
+    ("reset_soon.title", "Limit Reset Soon"),
+    (
+        "reset_soon.body",
+        "{label} will reset in {minutes} minutes (currently at {percent}%)",
+    ),
+    ("session_expiring.title", "Session Expiring"),
+    (
+        "session_expiring.body",
+        "Your Claude session may be expiring soon. Please refresh your credentials.",
+    ),
+    ("budget_warning.title", "Monthly Budget Warning"),
+    (
+        "budget_warning.body",
+        "{consumed} of {budget} monthly budget used",
+    ),
+    ("budget_exceeded.title", "Monthly Budget Exceeded"),
+    (
+        "budget_exceeded.body",
+        "{consumed} used this month, over the {budget} budget",
+    ),
+];
+
+const DE: &[(&str, &str)] = &[
+    ("threshold_alert.title", "Nutzungswarnung: {threshold}%"),
+    ("threshold_alert.body", "{label} liegt bei {percent}% Nutzung"),
+    ("threshold_digest.title", "{count} Nutzungswarnungen"),
+    (
+        "threshold_digest.body",
+        "{count} Limits haben Schwellenwerte überschritten: {list}",
+    ),
+    ("usage_reset.title", "Nutzung zurückgesetzt"),
+    (
+        "usage_reset.body",
+        "{label} wurde zurückgesetzt! Jetzt bei {percent}%",
+    ),
+    ("reset_soon.title", "Limit wird bald zurückgesetzt"),
+    (
+        "reset_soon.body",
+        "{label} wird in {minutes} Minuten zurückgesetzt (aktuell {percent}%)",
+    ),
+    ("session_expiring.title", "Sitzung läuft ab"),
+    (
+        "session_expiring.body",
+        "Deine Claude-Sitzung läuft möglicherweise bald ab. Bitte aktualisiere deine Zugangsdaten.",
+    ),
+    ("budget_warning.title", "Monatsbudget fast erreicht"),
+    (
+        "budget_warning.body",
+        "{consumed} von {budget} des Monatsbudgets verbraucht",
+    ),
+    ("budget_exceeded.title", "Monatsbudget überschritten"),
+    (
+        "budget_exceeded.body",
+        "{consumed} diesen Monat verbraucht, über dem Budget von {budget}",
+    ),
+];
+
+/// String table for a locale, matching "de-DE" to "de" when there is no exact table
+fn table(locale: &str) -> Option<&'static [(&'static str, &'static str)]> {
+    let by_code = |code: &str| match code.to_ascii_lowercase().as_str() {
+        "en" => Some(EN),
+        "de" => Some(DE),
+        _ => None,
+    };
+
+    by_code(locale).or_else(|| locale.split(['-', '_']).next().and_then(by_code))
+}
+
+fn lookup(locale: &str, key: &str) -> Option<&'static str> {
+    table(locale)?
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, text)| *text)
+}
+
+/// Translate `key` into `locale`, substituting `{name}` placeholders from `args`
+///
+/// Falls back to English when the locale or key is unknown, and to the key
+/// itself if English has no entry either.
+pub fn localize(locale: &str, key: &str, args: &[(&str, String)]) -> String {
+    let template = lookup(locale, key)
+        .or_else(|| lookup(FALLBACK_LOCALE, key))
+        .unwrap_or(key);
+
+    args.iter().fold(template.to_string(), |text, (name, value)| {
+        text.replace(&format!("{{{}}}", name), value)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_locale_substitutes_args() {
+        let text = localize(
+            "de",
+            "threshold_alert.body",
+            &[("label", "Session".to_string()), ("percent", "80".to_string())],
+        );
+        assert_eq!(text, "Session liegt bei 80% Nutzung");
+
+        let title = localize("de-DE", "threshold_alert.title", &[("threshold", "90".to_string())]);
+        assert_eq!(title, "Nutzungswarnung: 90%");
+    }
+
+    #[test]
+    fn unknown_locale_falls_back_to_english() {
+        let text = localize(
+            "xx",
+            "usage_reset.body",
+            &[("label", "Weekly".to_string()), ("percent", "5".to_string())],
+        );
+        assert_eq!(text, "Weekly has reset! Now at 5%");
+    }
+
+    #[test]
+    fn unknown_key_returns_key() {
+        assert_eq!(localize("en", "no.such.key", &[]), "no.such.key");
+    }
+}
@@ -1,12 +1,57 @@
+mod account_import;
 mod credentials;
 mod crypto;
+mod data_dir;
+mod debug_gate;
+mod diagnostics;
+mod file_permissions;
 mod history;
+mod in_flight;
+mod key_value;
+mod locale;
+mod network;
+mod notification_channel;
+mod notification_log;
 mod notifications;
+mod power;
+mod response_cache;
+mod scheduled_fetch;
 mod scheduler;
 mod settings;
+mod shortcut;
+mod snapshot;
+mod startup;
+mod store_lock;
+mod timezone;
+mod usage_stream;
+mod window_mode;
 
+pub use account_import::AccountImportService;
 pub use credentials::CredentialService;
+pub use crypto::{benchmark_round_trips, generate_token, secrets_match, KeyMaterial};
+pub use data_dir::{DataDir, DataDirExt};
+pub use debug_gate::DebugGate;
+pub use diagnostics::DiagnosticsService;
+pub use file_permissions::FilePermissionService;
 pub use history::HistoryService;
-pub use notifications::{NotificationService, NotificationState};
-pub use scheduler::{SchedulerService, SchedulerState};
+pub use in_flight::InFlightFetches;
+pub use network::Connectivity;
+pub use notification_channel::{channels_for, NotificationChannel};
+pub use notification_log::NotificationLogService;
+pub use notifications::{
+    NotificationPermission, NotificationService, NotificationState, NotificationStateSnapshot,
+};
+pub use power::{
+    watch_power_events, PowerEvent, PowerState, SleepDetector, SystemSleepEvent, SystemSleptEvent,
+};
+pub use response_cache::ResponseCacheService;
+pub use scheduled_fetch::{ScheduledFetch, ScheduledFetches};
+pub use scheduler::{RetryResult, SchedulerService, SchedulerState};
 pub use settings::SettingsService;
+pub use shortcut::{GlobalShortcutState, ShortcutService};
+pub use snapshot::SnapshotService;
+pub use startup::{startup_actions, StartupAction};
+pub use store_lock::{OAuthRefreshLocks, StoreLocks};
+pub use timezone::DisplayTimezone;
+pub use usage_stream::UsageStream;
+pub use window_mode::{apply_window_action, window_actions, window_changes, WindowAction};
@@ -0,0 +1,196 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+
+/// URL probed to tell a network outage apart from an API failure
+pub const PROBE_URL: &str = "https://claude.ai";
+
+/// How long the reachability probe waits for a response
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Longest the scheduler waits between attempts while offline
+const MAX_OFFLINE_BACKOFF: Duration = Duration::from_secs(1800);
+
+/// Whether a HEAD request to `url` gets any HTTP response within `timeout`
+///
+/// Goes through reqwest like the real fetches, so `HTTP(S)_PROXY` applies and a
+/// network that only allows proxied egress still counts as online. Any status,
+/// even a Cloudflare challenge, means the host answered.
+pub async fn is_reachable(url: &str, timeout: Duration) -> bool {
+    let client = match reqwest::Client::builder().timeout(timeout).build() {
+        Ok(client) => client,
+        Err(_) => return false,
+    };
+    client.head(url).send().await.is_ok()
+}
+
+/// Payload of `offline` and `online`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectivityEvent {
+    pub offline_since: DateTime<Utc>,
+    /// How long the host was unreachable; 0 on `offline`
+    pub offline_secs: u64,
+}
+
+#[derive(Debug)]
+struct Offline {
+    since: DateTime<Utc>,
+    failed_probes: u32,
+}
+
+/// Whether the API host was last found unreachable, and for how long
+#[derive(Debug, Default)]
+pub struct Connectivity {
+    offline: Mutex<Option<Offline>>,
+}
+
+impl Connectivity {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.offline
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .is_some()
+    }
+
+    /// Record a failed probe, returning the event to emit if this starts an outage
+    pub fn mark_offline(&self, now: DateTime<Utc>) -> Option<ConnectivityEvent> {
+        let mut offline = self.offline.lock().unwrap_or_else(PoisonError::into_inner);
+        match offline.as_mut() {
+            Some(outage) => {
+                outage.failed_probes = outage.failed_probes.saturating_add(1);
+                None
+            }
+            None => {
+                *offline = Some(Offline {
+                    since: now,
+                    failed_probes: 1,
+                });
+                Some(ConnectivityEvent {
+                    offline_since: now,
+                    offline_secs: 0,
+                })
+            }
+        }
+    }
+
+    /// Record that the host is reachable, returning the event to emit if this ends an outage
+    pub fn mark_online(&self, now: DateTime<Utc>) -> Option<ConnectivityEvent> {
+        let outage = self
+            .offline
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()?;
+
+        Some(ConnectivityEvent {
+            offline_since: outage.since,
+            offline_secs: (now - outage.since).num_seconds().max(0) as u64,
+        })
+    }
+
+    /// Time to wait between fetches: `interval` online, doubled per failed probe while offline
+    ///
+    /// The backoff is capped at `MAX_OFFLINE_BACKOFF`, or at `interval` if that is longer.
+    pub fn backoff(&self, interval: Duration) -> Duration {
+        let failed_probes = match self
+            .offline
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_ref()
+        {
+            Some(outage) => outage.failed_probes,
+            None => return interval,
+        };
+
+        interval
+            .checked_mul(2u32.saturating_pow(failed_probes))
+            .unwrap_or(Duration::MAX)
+            .min(MAX_OFFLINE_BACKOFF.max(interval))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn outage_emits_one_offline_and_one_online() {
+        let connectivity = Connectivity::new();
+        let start = Utc.with_ymd_and_hms(2026, 5, 1, 9, 0, 0).unwrap();
+
+        assert_eq!(connectivity.mark_online(start), None);
+
+        let offline = connectivity.mark_offline(start).unwrap();
+        assert_eq!(offline.offline_since, start);
+        for n in 1..5 {
+            assert_eq!(
+                connectivity.mark_offline(start + chrono::Duration::minutes(n)),
+                None
+            );
+        }
+        assert!(connectivity.is_offline());
+
+        let online = connectivity
+            .mark_online(start + chrono::Duration::minutes(90))
+            .unwrap();
+        assert_eq!(online.offline_since, start);
+        assert_eq!(online.offline_secs, 5400);
+        assert_eq!(connectivity.mark_online(start), None);
+        assert!(!connectivity.is_offline());
+    }
+
+    #[test]
+    fn backoff_doubles_while_offline_up_to_the_cap() {
+        let connectivity = Connectivity::new();
+        let interval = Duration::from_secs(300);
+        let now = Utc::now();
+
+        assert_eq!(connectivity.backoff(interval), interval);
+
+        connectivity.mark_offline(now);
+        assert_eq!(connectivity.backoff(interval), Duration::from_secs(600));
+        connectivity.mark_offline(now);
+        assert_eq!(connectivity.backoff(interval), Duration::from_secs(1200));
+        for _ in 0..40 {
+            connectivity.mark_offline(now);
+        }
+        assert_eq!(connectivity.backoff(interval), MAX_OFFLINE_BACKOFF);
+        // An interval already past the cap is left alone
+        assert_eq!(
+            connectivity.backoff(Duration::from_secs(3600)),
+            Duration::from_secs(3600)
+        );
+
+        connectivity.mark_online(now);
+        assert_eq!(connectivity.backoff(interval), interval);
+    }
+
+    #[tokio::test]
+    async fn probe_needs_an_http_response() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 1024];
+            let _ = socket.read(&mut request).await;
+            // An error status still proves the host is reachable
+            socket
+                .write_all(b"HTTP/1.1 403 Forbidden\r\ncontent-length: 0\r\n\r\n")
+                .await
+                .unwrap();
+        });
+        assert!(is_reachable(&url, Duration::from_secs(2)).await);
+        server.await.unwrap();
+
+        // Nothing listening any more
+        assert!(!is_reachable(&url, Duration::from_secs(2)).await);
+    }
+}
@@ -0,0 +1,351 @@
+use crate::error::AppError;
+use crate::models::{EmailSettings, NotificationSettings, NotificationUrgency};
+use crate::services::{NotificationPermission, NotificationService};
+use lettre::message::{header::ContentType, Mailbox};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::{NotificationExt, PermissionState};
+
+/// Implicit-TLS SMTP port; other ports connect in plain text and upgrade with STARTTLS
+const SMTPS_PORT: u16 = 465;
+
+/// Somewhere a notification can be delivered
+///
+/// `NotificationService` holds one of these per configured channel and offers
+/// every notification to each of them.
+pub trait NotificationChannel: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Deliver a notification, returning whether it was handed off successfully
+    fn send(&self, title: &str, body: &str) -> bool;
+
+    /// Deliver a critical notification; channels without a louder form just send it
+    fn send_critical(&self, title: &str, body: &str) -> bool {
+        self.send(title, body)
+    }
+
+    /// Whether this channel takes notifications of this kind
+    fn accepts(&self, _urgency: NotificationUrgency, _is_reset: bool) -> bool {
+        true
+    }
+}
+
+/// Channels a notification can go out on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelKind {
+    Desktop,
+    Email,
+}
+
+/// Channels configured in the notification settings
+///
+/// Desktop is always on. Email needs to be enabled with a server and recipient.
+pub fn configured_channels(settings: &NotificationSettings) -> Vec<ChannelKind> {
+    let mut channels = vec![ChannelKind::Desktop];
+
+    let email_configured = settings.email.as_ref().is_some_and(|email| {
+        email.enabled && !email.smtp_host.trim().is_empty() && !email.recipient.trim().is_empty()
+    });
+    if email_configured {
+        channels.push(ChannelKind::Email);
+    }
+
+    channels
+}
+
+/// Build the configured channels for the app
+pub fn channels_for(
+    app: &AppHandle,
+    settings: &NotificationSettings,
+) -> Vec<Box<dyn NotificationChannel>> {
+    configured_channels(settings)
+        .into_iter()
+        .filter_map(|kind| -> Option<Box<dyn NotificationChannel>> {
+            match kind {
+                ChannelKind::Desktop => Some(Box::new(DesktopChannel::new(
+                    app.clone(),
+                    settings.critical_sound.clone(),
+                ))),
+                ChannelKind::Email => settings
+                    .email
+                    .clone()
+                    .map(|email| Box::new(EmailChannel::new(email)) as _),
+            }
+        })
+        .collect()
+}
+
+/// OS notification through the Tauri notification plugin
+pub struct DesktopChannel {
+    app: AppHandle,
+    /// Sound for critical alerts, for platforms that support it
+    critical_sound: Option<String>,
+}
+
+impl DesktopChannel {
+    pub fn new(app: AppHandle, critical_sound: Option<String>) -> Self {
+        Self {
+            app,
+            critical_sound,
+        }
+    }
+
+    fn show(&self, title: &str, body: &str, sound: Option<&str>) -> bool {
+        let mut builder = self.app.notification().builder().title(title).body(body);
+        if let Some(sound) = sound {
+            builder = builder.sound(sound);
+        }
+
+        // Some platforms report success even when permission is denied
+        let known_denied = self
+            .app
+            .try_state::<NotificationPermission>()
+            .is_some_and(|p| p.is_denied());
+
+        match builder.show() {
+            // The cached state may predate the user granting permission, so ask again
+            Ok(_)
+                if known_denied
+                    && matches!(
+                        NotificationService::check_permission(&self.app),
+                        Ok(PermissionState::Denied)
+                    ) =>
+            {
+                NotificationService::report_permission_denied(&self.app);
+                false
+            }
+            Ok(_) => {
+                log::debug!("Notification sent: {} - {}", title, body);
+                true
+            }
+            Err(e) => {
+                log::error!("Failed to send notification: {}", e);
+                if matches!(
+                    NotificationService::check_permission(&self.app),
+                    Ok(PermissionState::Denied)
+                ) {
+                    NotificationService::report_permission_denied(&self.app);
+                }
+                false
+            }
+        }
+    }
+}
+
+impl NotificationChannel for DesktopChannel {
+    fn name(&self) -> &'static str {
+        "desktop"
+    }
+
+    fn send(&self, title: &str, body: &str) -> bool {
+        self.show(title, body, None)
+    }
+
+    /// Platforms without sound support ignore it and show a normal notification
+    fn send_critical(&self, title: &str, body: &str) -> bool {
+        self.show(title, body, self.critical_sound.as_deref())
+    }
+}
+
+/// Email over SMTP
+///
+/// Sending runs in the background so a slow mail server never holds up the caller;
+/// `send` only reports whether the message could be built.
+pub struct EmailChannel {
+    settings: EmailSettings,
+}
+
+impl EmailChannel {
+    pub fn new(settings: EmailSettings) -> Self {
+        Self { settings }
+    }
+
+    async fn deliver(settings: &EmailSettings, message: Message) -> Result<(), AppError> {
+        let smtp_error = |e: lettre::transport::smtp::Error| AppError::Notification(e.to_string());
+
+        let mut transport = if settings.smtp_port == SMTPS_PORT {
+            AsyncSmtpTransport::<Tokio1Executor>::relay(&settings.smtp_host)
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&settings.smtp_host)
+        }
+        .map_err(smtp_error)?
+        .port(settings.smtp_port);
+
+        if let (Some(username), Some(password)) = (&settings.username, &settings.password) {
+            transport = transport.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+
+        transport.build().send(message).await.map_err(smtp_error)?;
+        Ok(())
+    }
+}
+
+impl NotificationChannel for EmailChannel {
+    fn name(&self) -> &'static str {
+        "email"
+    }
+
+    /// Only critical alerts and resets are worth an email
+    fn accepts(&self, urgency: NotificationUrgency, is_reset: bool) -> bool {
+        urgency == NotificationUrgency::Critical || is_reset
+    }
+
+    fn send(&self, title: &str, body: &str) -> bool {
+        let message = match email_message(&self.settings, title, body) {
+            Ok(message) => message,
+            Err(e) => {
+                log::error!("Failed to build notification email: {}", e);
+                return false;
+            }
+        };
+
+        let settings = self.settings.clone();
+        tauri::async_runtime::spawn(async move {
+            match Self::deliver(&settings, message).await {
+                Ok(()) => log::debug!("Notification email sent to {}", settings.recipient),
+                Err(e) => log::error!("Failed to send notification email: {}", e),
+            }
+        });
+
+        true
+    }
+}
+
+/// Subject and plain-text body of a notification email
+pub fn email_content(title: &str, body: &str) -> (String, String) {
+    let subject = format!("[AI Pulse] {}", title);
+    let text = format!(
+        "{}\n\n{}\n\n--\nSent by AI Pulse. Email alerts can be turned off in Settings > Notifications.\n",
+        title, body
+    );
+    (subject, text)
+}
+
+/// Address the email is sent from: `from`, else the SMTP username, else the recipient
+pub fn sender_address(settings: &EmailSettings) -> &str {
+    settings
+        .from
+        .as_deref()
+        .or(settings.username.as_deref())
+        .unwrap_or(&settings.recipient)
+}
+
+/// Build the email for a notification
+pub fn email_message(
+    settings: &EmailSettings,
+    title: &str,
+    body: &str,
+) -> Result<Message, AppError> {
+    let parse = |address: &str| {
+        address.parse::<Mailbox>().map_err(|e| {
+            AppError::InvalidInput(format!("Invalid email address '{}': {}", address, e))
+        })
+    };
+    let (subject, text) = email_content(title, body);
+
+    Message::builder()
+        .from(parse(sender_address(settings))?)
+        .to(parse(&settings.recipient)?)
+        .subject(subject)
+        .header(ContentType::TEXT_PLAIN)
+        .body(text)
+        .map_err(|e| AppError::Notification(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn email() -> EmailSettings {
+        EmailSettings {
+            enabled: true,
+            smtp_host: "smtp.example.com".to_string(),
+            smtp_port: 587,
+            username: Some("alerts@example.com".to_string()),
+            password: Some("app-password".to_string()),
+            from: None,
+            recipient: "me@example.com".to_string(),
+        }
+    }
+
+    fn settings(email: Option<EmailSettings>) -> NotificationSettings {
+        NotificationSettings {
+            email,
+            ..crate::models::AppSettings::default().notifications
+        }
+    }
+
+    #[test]
+    fn email_only_for_critical_and_reset_notifications() {
+        let channel = EmailChannel::new(email());
+
+        assert!(!channel.accepts(NotificationUrgency::Normal, false));
+        assert!(channel.accepts(NotificationUrgency::Critical, false));
+        assert!(channel.accepts(NotificationUrgency::Normal, true));
+    }
+
+    #[test]
+    fn email_configured_only_when_enabled_and_complete() {
+        assert_eq!(
+            configured_channels(&settings(Some(email()))),
+            vec![ChannelKind::Desktop, ChannelKind::Email]
+        );
+
+        let disabled = EmailSettings {
+            enabled: false,
+            ..email()
+        };
+        let no_host = EmailSettings {
+            smtp_host: " ".to_string(),
+            ..email()
+        };
+        let no_recipient = EmailSettings {
+            recipient: String::new(),
+            ..email()
+        };
+
+        for email in [None, Some(disabled), Some(no_host), Some(no_recipient)] {
+            assert_eq!(
+                configured_channels(&settings(email)),
+                vec![ChannelKind::Desktop]
+            );
+        }
+    }
+
+    #[test]
+    fn email_body_includes_title_body_and_footer() {
+        let (subject, text) = email_content("Usage at 90%", "Personal: Session is at 90%");
+
+        assert_eq!(subject, "[AI Pulse] Usage at 90%");
+        assert!(text.starts_with("Usage at 90%\n\nPersonal: Session is at 90%\n"));
+        assert!(text.contains("Settings > Notifications"));
+    }
+
+    #[test]
+    fn sender_falls_back_to_username_then_recipient() {
+        let mut settings = email();
+        assert_eq!(sender_address(&settings), "alerts@example.com");
+
+        settings.from = Some("AI Pulse <pulse@example.com>".to_string());
+        assert_eq!(sender_address(&settings), "AI Pulse <pulse@example.com>");
+
+        settings.from = None;
+        settings.username = None;
+        assert_eq!(sender_address(&settings), "me@example.com");
+    }
+
+    #[test]
+    fn invalid_recipient_is_rejected() {
+        assert!(email_message(&email(), "Title", "Body").is_ok());
+
+        let settings = EmailSettings {
+            recipient: "not an address".to_string(),
+            ..email()
+        };
+        assert!(matches!(
+            email_message(&settings, "Title", "Body"),
+            Err(AppError::InvalidInput(_))
+        ));
+    }
+}
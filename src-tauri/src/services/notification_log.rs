@@ -0,0 +1,146 @@
+use crate::error::AppError;
+use crate::models::NotificationRecord;
+use crate::services::key_value::{persist_unlocked, KeyValueStore};
+use crate::services::{DataDirExt, StoreLocks};
+use std::collections::VecDeque;
+use std::sync::PoisonError;
+use tauri::{AppHandle, Manager};
+
+pub(crate) const STORE_FILE: &str = "notification_log.json";
+const RECORDS_KEY: &str = "records";
+
+/// Most records kept; the oldest are dropped first
+const MAX_RECORDS: usize = 200;
+
+/// Persisted log of the notifications that were sent, newest last
+pub struct NotificationLogService;
+
+impl NotificationLogService {
+    /// Append a record to the log
+    pub fn record(app: &AppHandle, record: NotificationRecord) -> Result<(), AppError> {
+        let lock = app.state::<StoreLocks>().for_file(STORE_FILE);
+        let guard = lock.lock().unwrap_or_else(PoisonError::into_inner);
+        let store = app.data_store(STORE_FILE)?;
+
+        if Self::record_in(&*store, record, MAX_RECORDS)? {
+            persist_unlocked(&*store, guard)?;
+        }
+        Ok(())
+    }
+
+    /// The most recent records, newest first; `limit` of None returns all of them
+    pub fn get(app: &AppHandle, limit: Option<usize>) -> Result<Vec<NotificationRecord>, AppError> {
+        let store = app.data_store(STORE_FILE)?;
+        let records = Self::load(&*store);
+
+        Ok(records
+            .into_iter()
+            .rev()
+            .take(limit.unwrap_or(usize::MAX))
+            .collect())
+    }
+
+    /// Delete every record, returning how many there were
+    pub fn clear(app: &AppHandle) -> Result<usize, AppError> {
+        let lock = app.state::<StoreLocks>().for_file(STORE_FILE);
+        let guard = lock.lock().unwrap_or_else(PoisonError::into_inner);
+        let store = app.data_store(STORE_FILE)?;
+
+        let count = Self::load(&*store).len();
+        store.delete_value(RECORDS_KEY);
+        persist_unlocked(&*store, guard)?;
+        Ok(count)
+    }
+
+    fn load(store: &impl KeyValueStore) -> VecDeque<NotificationRecord> {
+        store
+            .get_value(RECORDS_KEY)
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default()
+    }
+
+    /// Append a record, dropping the oldest beyond `capacity`
+    ///
+    /// A suppressed record identical to one already logged is skipped, since a
+    /// notification held back by DND is retried on every fetch. Returns whether
+    /// the store changed.
+    pub(crate) fn record_in(
+        store: &impl KeyValueStore,
+        record: NotificationRecord,
+        capacity: usize,
+    ) -> Result<bool, AppError> {
+        let mut records = Self::load(store);
+
+        let repeat = record.suppressed
+            && records.iter().any(|r| {
+                r.suppressed
+                    && r.kind == record.kind
+                    && r.limit_id == record.limit_id
+                    && r.title == record.title
+                    && r.body == record.body
+            });
+        if repeat {
+            return Ok(false);
+        }
+
+        records.push_back(record);
+        while records.len() > capacity {
+            records.pop_front();
+        }
+
+        store.set_value(RECORDS_KEY, serde_json::to_value(&records)?);
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::NotificationKind;
+    use crate::services::key_value::MemoryStore;
+    use chrono::{Duration, TimeZone, Utc};
+
+    fn record(n: i64, suppressed: bool) -> NotificationRecord {
+        NotificationRecord {
+            timestamp: Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap() + Duration::minutes(n),
+            title: format!("Alert {}", n),
+            body: "Session is at 90%".to_string(),
+            kind: NotificationKind::Threshold,
+            limit_id: Some("five_hour".to_string()),
+            suppressed,
+        }
+    }
+
+    #[test]
+    fn records_are_kept_in_order_up_to_capacity() {
+        let store = MemoryStore::default();
+
+        for n in 0..5 {
+            assert!(NotificationLogService::record_in(&store, record(n, false), 3).unwrap());
+        }
+
+        let titles: Vec<_> = NotificationLogService::load(&store)
+            .into_iter()
+            .map(|r| r.title)
+            .collect();
+        assert_eq!(titles, vec!["Alert 2", "Alert 3", "Alert 4"]);
+    }
+
+    #[test]
+    fn suppressed_records_are_flagged_and_not_repeated() {
+        let store = MemoryStore::default();
+
+        assert!(NotificationLogService::record_in(&store, record(1, true), 10).unwrap());
+        let mut retry = record(1, true);
+        retry.timestamp += Duration::minutes(5);
+        assert!(!NotificationLogService::record_in(&store, retry, 10).unwrap());
+        // Once delivered it is logged again, unflagged
+        assert!(NotificationLogService::record_in(&store, record(1, false), 10).unwrap());
+
+        let flags: Vec<_> = NotificationLogService::load(&store)
+            .into_iter()
+            .map(|r| r.suppressed)
+            .collect();
+        assert_eq!(flags, vec![true, false]);
+    }
+}
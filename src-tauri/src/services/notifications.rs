@@ -1,10 +1,53 @@
-use crate::models::{AppSettings, NotificationSettings, UsageData, UsageLimit};
-use crate::services::SettingsService;
-use chrono::{Duration, Local, NaiveTime, Utc};
-use std::collections::HashSet;
+use crate::error::AppError;
+use crate::models::{
+    AppSettings, BudgetLevel, NotificationKind, NotificationRecord, NotificationSettings,
+    NotificationUrgency, SimulatedNotification, SimulatedNotificationKind, UsageData, UsageLimit,
+};
+use crate::services::locale::localize;
+use crate::services::{
+    channels_for, HistoryService, NotificationChannel, NotificationLogService, SettingsService,
+};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveTime, Utc, Weekday};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Mutex;
-use tauri::{AppHandle, Emitter};
-use tauri_plugin_notification::NotificationExt;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_notification::{NotificationExt, PermissionState};
+
+/// Furthest in the future a `resets_at` can plausibly be
+const MAX_RESET_HORIZON_DAYS: i64 = 30;
+
+/// Event payload emitted when a reset time looks wrong for the local clock
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClockSkewWarningEvent {
+    pub account_id: String,
+    pub limit_id: String,
+    pub resets_at: DateTime<Utc>,
+    pub local_time: DateTime<Utc>,
+}
+
+/// Serializable copy of `NotificationState`, kept in app snapshots
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationStateSnapshot {
+    pub sent_thresholds: Vec<(String, String, u32)>,
+    pub sent_reset_warnings: Vec<(String, String)>,
+    pub budget_levels: Vec<(String, DateTime<Utc>, BudgetLevel)>,
+}
+
+/// A limit's threshold crossings held back for the cycle's digest (`digest_mode`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct DigestItem {
+    pub account_id: String,
+    pub limit_id: String,
+    /// Every threshold crossed, ascending; all are marked sent with the digest
+    pub thresholds: Vec<u32>,
+    /// The alert for the highest threshold, used when it is the only item
+    pub title: String,
+    pub body: String,
+    pub urgency: NotificationUrgency,
+}
 
 /// Tracks which notifications have been sent to avoid duplicates
 pub struct NotificationState {
@@ -12,6 +55,12 @@ pub struct NotificationState {
     sent_thresholds: Mutex<HashSet<(String, String, u32)>>,
     /// Set of (account_id, limit_id) pairs that have been notified for upcoming reset
     sent_reset_warnings: Mutex<HashSet<(String, String)>>,
+    /// Budget level last seen per account, with the start of the month it was for
+    budget_levels: Mutex<HashMap<String, (DateTime<Utc>, BudgetLevel)>>,
+    /// Threshold crossings waiting for the end of the poll cycle, in digest mode
+    pending_digest: Mutex<Vec<DigestItem>>,
+    /// Limits already logged as having absolute thresholds but no total
+    missing_totals_reported: Mutex<HashSet<String>>,
 }
 
 impl Default for NotificationState {
@@ -19,6 +68,9 @@ impl Default for NotificationState {
         Self {
             sent_thresholds: Mutex::new(HashSet::new()),
             sent_reset_warnings: Mutex::new(HashSet::new()),
+            budget_levels: Mutex::new(HashMap::new()),
+            pending_digest: Mutex::new(Vec::new()),
+            missing_totals_reported: Mutex::new(HashSet::new()),
         }
     }
 }
@@ -46,6 +98,12 @@ impl NotificationState {
         sent.remove(&(account_id.to_string(), limit_id.to_string(), threshold));
     }
 
+    /// True the first time a limit is reported as missing the total its absolute thresholds need
+    pub fn take_missing_total_report(&self, limit_id: &str) -> bool {
+        let mut reported = self.missing_totals_reported.lock().unwrap();
+        reported.insert(limit_id.to_string())
+    }
+
     /// Clear all thresholds above a certain value for a limit on this account
     pub fn clear_thresholds_above(&self, account_id: &str, limit_id: &str, current_percent: u32) {
         let mut sent = self.sent_thresholds.lock().unwrap();
@@ -71,11 +129,247 @@ impl NotificationState {
         let mut sent = self.sent_reset_warnings.lock().unwrap();
         sent.remove(&(account_id.to_string(), limit_id.to_string()));
     }
+
+    /// Hold a limit's crossings for the digest, replacing any already held for it
+    pub fn queue_digest(&self, item: DigestItem) {
+        let mut pending = self.pending_digest.lock().unwrap();
+        pending.retain(|p| !(p.account_id == item.account_id && p.limit_id == item.limit_id));
+        pending.push(item);
+    }
+
+    /// Take every crossing held for the digest
+    pub fn take_digest(&self) -> Vec<DigestItem> {
+        std::mem::take(&mut *self.pending_digest.lock().unwrap())
+    }
+
+    /// Record an account's budget level for the month, returning true if it went up
+    ///
+    /// A new `month_start` starts over from `Under`, so each month alerts again.
+    pub fn escalate_budget_level(
+        &self,
+        account_id: &str,
+        month_start: DateTime<Utc>,
+        level: BudgetLevel,
+    ) -> bool {
+        let mut levels = self.budget_levels.lock().unwrap();
+        let previous = levels
+            .get(account_id)
+            .filter(|(month, _)| *month == month_start)
+            .map_or(BudgetLevel::Under, |(_, previous)| *previous);
+
+        // Stored even when lower, so raising the budget re-arms the alerts
+        levels.insert(account_id.to_string(), (month_start, level));
+        level > previous
+    }
+
+    /// Copy of everything sent so far, sorted so equal states compare equal
+    pub fn snapshot(&self) -> NotificationStateSnapshot {
+        let mut snapshot = NotificationStateSnapshot {
+            sent_thresholds: self
+                .sent_thresholds
+                .lock()
+                .unwrap()
+                .iter()
+                .cloned()
+                .collect(),
+            sent_reset_warnings: self
+                .sent_reset_warnings
+                .lock()
+                .unwrap()
+                .iter()
+                .cloned()
+                .collect(),
+            budget_levels: self
+                .budget_levels
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(account_id, (month, level))| (account_id.clone(), *month, *level))
+                .collect(),
+        };
+        snapshot.sent_thresholds.sort();
+        snapshot.sent_reset_warnings.sort();
+        snapshot.budget_levels.sort_by(|a, b| a.0.cmp(&b.0));
+        snapshot
+    }
+
+    /// Replace the current state with a snapshot's
+    pub fn restore(&self, snapshot: &NotificationStateSnapshot) {
+        *self.sent_thresholds.lock().unwrap() = snapshot.sent_thresholds.iter().cloned().collect();
+        *self.sent_reset_warnings.lock().unwrap() =
+            snapshot.sent_reset_warnings.iter().cloned().collect();
+        *self.budget_levels.lock().unwrap() = snapshot
+            .budget_levels
+            .iter()
+            .map(|(account_id, month, level)| (account_id.clone(), (*month, *level)))
+            .collect();
+    }
+
+    /// Forget the thresholds and reset warnings sent for a limit on every account
+    ///
+    /// With None every limit is cleared. Budget levels are per account, so they
+    /// are kept. Returns how many entries were removed.
+    pub fn clear_limit(&self, limit_id: Option<&str>) -> usize {
+        let is_target = |id: &String| limit_id.map_or(true, |limit_id| id == limit_id);
+
+        let mut thresholds = self.sent_thresholds.lock().unwrap();
+        let before = thresholds.len();
+        thresholds.retain(|(_, id, _)| !is_target(id));
+        let mut removed = before - thresholds.len();
+
+        let mut warnings = self.sent_reset_warnings.lock().unwrap();
+        let before = warnings.len();
+        warnings.retain(|(_, id)| !is_target(id));
+        removed += before - warnings.len();
+
+        removed
+    }
+
+    /// Forget every notification sent for every account
+    pub fn clear_all(&self) {
+        self.sent_thresholds.lock().unwrap().clear();
+        self.sent_reset_warnings.lock().unwrap().clear();
+        self.budget_levels.lock().unwrap().clear();
+        self.pending_digest.lock().unwrap().clear();
+    }
+}
+
+/// Notification permission as last checked, and whether the UI was told it's denied
+#[derive(Default)]
+pub struct NotificationPermission {
+    state: Mutex<Option<PermissionState>>,
+    denied_reported: AtomicBool,
 }
 
-pub struct NotificationService;
+impl NotificationPermission {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Last checked permission state, None if it hasn't been checked yet
+    pub fn get(&self) -> Option<PermissionState> {
+        *self.state.lock().unwrap()
+    }
+
+    pub fn set(&self, state: PermissionState) {
+        *self.state.lock().unwrap() = Some(state);
+    }
+
+    pub fn is_denied(&self) -> bool {
+        self.get() == Some(PermissionState::Denied)
+    }
+
+    /// True the first time it's called while permission is denied, false ever after
+    ///
+    /// Keeps the `notification-permission-denied` event to once per session.
+    pub fn take_denied_report(&self) -> bool {
+        self.is_denied() && !self.denied_reported.swap(true, Ordering::SeqCst)
+    }
+}
+
+/// Receives the record of each notification sent
+type LogSink = Box<dyn Fn(NotificationRecord) + Send + Sync>;
+
+/// Sends notifications to every configured channel
+///
+/// The threshold and reset checks are associated functions that build a service
+/// from the app's settings for each pass; tests build one over mock channels.
+pub struct NotificationService {
+    channels: Vec<Box<dyn NotificationChannel>>,
+    /// Receives a record of every notification delivered, or held back when suppressed
+    log: Option<LogSink>,
+    /// Notifications are being held back (DND) rather than sent
+    suppressed: bool,
+}
 
 impl NotificationService {
+    pub fn new(channels: Vec<Box<dyn NotificationChannel>>) -> Self {
+        Self {
+            channels,
+            log: None,
+            suppressed: false,
+        }
+    }
+
+    /// Record what gets sent through `log`
+    pub fn with_log(mut self, log: impl Fn(NotificationRecord) + Send + Sync + 'static) -> Self {
+        self.log = Some(Box::new(log));
+        self
+    }
+
+    /// Service that sends nothing, only logging what it holds back if given a log
+    fn held_back(log: Option<LogSink>) -> Self {
+        Self {
+            channels: Vec::new(),
+            log,
+            suppressed: true,
+        }
+    }
+
+    /// Service for the app's configured channels, or one that sends nothing during DND
+    fn for_app(app: &AppHandle, settings: &NotificationSettings) -> Self {
+        let app_handle = app.clone();
+        let sink = move |record: NotificationRecord| {
+            if let Err(e) = NotificationLogService::record(&app_handle, record) {
+                log::warn!("Failed to record notification: {}", e);
+            }
+        };
+
+        if Self::is_dnd_active(settings) {
+            log::debug!("Notifications suppressed (DND active)");
+            return Self::held_back(settings.log_suppressed.then(|| Box::new(sink) as LogSink));
+        }
+
+        Self::new(channels_for(app, settings)).with_log(sink)
+    }
+
+    /// Offer a notification to every channel that takes it
+    ///
+    /// Returns whether at least one channel delivered it. Delivered notifications
+    /// (and, on a suppressed service, held-back ones) are passed to the log.
+    pub fn dispatch(
+        &self,
+        title: &str,
+        body: &str,
+        urgency: NotificationUrgency,
+        kind: NotificationKind,
+        limit_id: Option<&str>,
+    ) -> bool {
+        let is_reset = kind == NotificationKind::Reset;
+        let mut sent = false;
+        for channel in self
+            .channels
+            .iter()
+            .filter(|c| c.accepts(urgency, is_reset))
+        {
+            let delivered = match urgency {
+                NotificationUrgency::Critical => channel.send_critical(title, body),
+                NotificationUrgency::Normal => channel.send(title, body),
+            };
+            if delivered {
+                sent = true;
+            } else {
+                log::debug!(
+                    "Notification not delivered via {}: {}",
+                    channel.name(),
+                    title
+                );
+            }
+        }
+
+        if let Some(log) = self.log.as_ref().filter(|_| sent || self.suppressed) {
+            log(NotificationRecord {
+                timestamp: Utc::now(),
+                title: title.to_string(),
+                body: body.to_string(),
+                kind,
+                limit_id: limit_id.map(str::to_string),
+                suppressed: self.suppressed,
+            });
+        }
+        sent
+    }
+
     /// Format a message with optional account prefix
     fn format_with_account(account_name: &str, message: String) -> String {
         if account_name != "Default" && !account_name.is_empty() {
@@ -86,11 +380,15 @@ impl NotificationService {
     }
 
     /// Process usage data and send appropriate notifications
+    ///
+    /// `account_name` prefixes notification bodies; callers pass `Account::notification_label`
+    /// so a nickname wins over the account name.
     pub fn process_usage(
         app: &AppHandle,
         state: &NotificationState,
         usage: &UsageData,
         previous_usage: Option<&UsageData>,
+        account_name: &str,
     ) {
         // Get notification settings
         let settings = match SettingsService::get(app) {
@@ -103,28 +401,57 @@ impl NotificationService {
         }
 
         let account_id = &usage.account_id;
-        let account_name = &usage.account_name;
+        let service = Self::for_app(app, &settings.notifications);
+
+        let now = Utc::now();
 
         for limit in &usage.limits {
-            // utilization is already a percentage (0-100) from the API
-            let current_percent = limit.utilization as u32;
+            // Muted limits get no threshold, reset, or confetti events
+            if !Self::is_limit_enabled(&settings.notifications, &limit.id) {
+                continue;
+            }
 
-            // Clear thresholds that are now above current usage (usage dropped)
-            state.clear_thresholds_above(account_id, &limit.id, current_percent);
+            service.check_threshold_notifications(state, account_id, account_name, limit, &settings);
 
-            // Check threshold notifications
-            Self::check_threshold_notifications(app, state, account_id, account_name, limit, &settings);
+            // A wrong system clock makes reset times meaningless - warn instead of notifying
+            if !Self::is_reset_time_plausible(&limit.id, limit.resets_at, now) {
+                log::warn!(
+                    "Implausible reset time for {} ({}): resets_at={}, now={}",
+                    limit.id,
+                    account_name,
+                    limit.resets_at,
+                    now
+                );
+                let _ = app.emit(
+                    "clock-skew-warning",
+                    ClockSkewWarningEvent {
+                        account_id: account_id.clone(),
+                        limit_id: limit.id.clone(),
+                        resets_at: limit.resets_at,
+                        local_time: now,
+                    },
+                );
+                continue;
+            }
 
             // Check for reset notifications
             if settings.notifications.notify_on_reset {
-                Self::check_reset_notification(app, state, account_id, account_name, limit, previous_usage);
+                service.check_reset_notification(
+                    app,
+                    state,
+                    account_id,
+                    account_name,
+                    limit,
+                    previous_usage,
+                    &settings.locale,
+                );
             }
         }
     }
 
     /// Check and send threshold notifications
     fn check_threshold_notifications(
-        app: &AppHandle,
+        &self,
         state: &NotificationState,
         account_id: &str,
         account_name: &str,
@@ -134,6 +461,9 @@ impl NotificationService {
         // utilization is already a percentage (0-100) from the API
         let current_percent = limit.utilization as u32;
 
+        // Clear thresholds that are now above current usage (usage dropped)
+        state.clear_thresholds_above(account_id, &limit.id, current_percent);
+
         log::info!(
             "Checking notifications for {} ({}): utilization={}, current_percent={}%",
             limit.id,
@@ -142,69 +472,381 @@ impl NotificationService {
             current_percent
         );
 
-        for &threshold in &settings.notifications.thresholds {
-            if current_percent >= threshold && !state.was_threshold_notified(account_id, &limit.id, threshold) {
-                let title = format!("{}% Usage Alert", threshold);
-                let body = Self::format_with_account(
-                    account_name,
-                    format!("{} is at {}% usage", limit.label, current_percent.min(100)),
+        let pending = Self::pending_thresholds(state, account_id, limit, &settings.notifications);
+
+        // In digest mode the crossings wait for `send_digest` at the end of the cycle
+        if settings.notifications.digest_mode {
+            if let Some(&highest) = pending.last() {
+                let (title, body) = Self::threshold_message(settings, account_name, limit, highest);
+                state.queue_digest(DigestItem {
+                    account_id: account_id.to_string(),
+                    limit_id: limit.id.clone(),
+                    urgency: Self::urgency_for_threshold(highest, &settings.notifications),
+                    thresholds: pending,
+                    title,
+                    body,
+                });
+            }
+            return;
+        }
+
+        for threshold in pending {
+            let (title, body) = Self::threshold_message(settings, account_name, limit, threshold);
+
+            let urgency = Self::urgency_for_threshold(threshold, &settings.notifications);
+            if self.dispatch(
+                &title,
+                &body,
+                urgency,
+                NotificationKind::Threshold,
+                Some(&limit.id),
+            ) {
+                state.mark_threshold_notified(account_id, &limit.id, threshold);
+                log::info!(
+                    "Sent {}% threshold notification for {} ({})",
+                    threshold,
+                    limit.id,
+                    account_name
                 );
+            }
+        }
+    }
 
-                if Self::send_notification(app, &title, &body) {
-                    state.mark_threshold_notified(account_id, &limit.id, threshold);
-                    log::info!(
-                        "Sent {}% threshold notification for {} ({})",
-                        threshold,
-                        limit.id,
-                        account_name
-                    );
+    /// Send the threshold crossings held during this poll cycle as one notification
+    ///
+    /// Goes through the same DND handling as single alerts; crossings that aren't
+    /// delivered stay unmarked, so they are picked up again next cycle.
+    pub fn flush_digest(app: &AppHandle, state: &NotificationState) {
+        let settings = match SettingsService::get(app) {
+            Ok(s) => s,
+            Err(_) => return,
+        };
+
+        Self::for_app(app, &settings.notifications).send_digest(state, &settings.locale);
+    }
+
+    /// Dispatch the held crossings as one notification, marking them sent if delivered
+    fn send_digest(&self, state: &NotificationState, locale: &str) {
+        let items = state.take_digest();
+        if items.is_empty() {
+            return;
+        }
+
+        let (title, body) = Self::digest_message(locale, &items);
+        let urgency = if items
+            .iter()
+            .any(|item| item.urgency == NotificationUrgency::Critical)
+        {
+            NotificationUrgency::Critical
+        } else {
+            NotificationUrgency::Normal
+        };
+        let limit_id = match items.as_slice() {
+            [only] => Some(only.limit_id.as_str()),
+            _ => None,
+        };
+
+        if self.dispatch(
+            &title,
+            &body,
+            urgency,
+            NotificationKind::Threshold,
+            limit_id,
+        ) {
+            for item in &items {
+                for &threshold in &item.thresholds {
+                    state.mark_threshold_notified(&item.account_id, &item.limit_id, threshold);
                 }
             }
+            log::info!("Sent digest of {} threshold crossing(s)", items.len());
+        }
+    }
+
+    /// Title and body of a digest; a single crossing keeps its own alert text
+    fn digest_message(locale: &str, items: &[DigestItem]) -> (String, String) {
+        if let [only] = items {
+            return (only.title.clone(), only.body.clone());
+        }
+
+        let count = items.len().to_string();
+        let list = items
+            .iter()
+            .map(|item| item.body.as_str())
+            .collect::<Vec<_>>()
+            .join("; ");
+
+        (
+            localize(
+                locale,
+                "threshold_digest.title",
+                &[("count", count.clone())],
+            ),
+            localize(
+                locale,
+                "threshold_digest.body",
+                &[("count", count), ("list", list)],
+            ),
+        )
+    }
+
+    /// Title and body of a threshold alert
+    fn threshold_message(
+        settings: &AppSettings,
+        account_name: &str,
+        limit: &UsageLimit,
+        threshold: u32,
+    ) -> (String, String) {
+        // utilization is already a percentage (0-100) from the API
+        let current_percent = limit.utilization as u32;
+
+        let title = localize(
+            &settings.locale,
+            "threshold_alert.title",
+            &[("threshold", threshold.to_string())],
+        );
+        let body = Self::format_with_account(
+            account_name,
+            localize(
+                &settings.locale,
+                "threshold_alert.body",
+                &[
+                    ("label", limit.label.clone()),
+                    ("percent", current_percent.min(100).to_string()),
+                ],
+            ),
+        );
+
+        (title, body)
+    }
+
+    /// Urgency for a threshold crossing: critical at or above the configured cutoff
+    pub fn urgency_for_threshold(threshold: u32, settings: &NotificationSettings) -> NotificationUrgency {
+        if threshold >= settings.critical_threshold {
+            NotificationUrgency::Critical
+        } else {
+            NotificationUrgency::Normal
         }
     }
 
+    /// Whether notifications are enabled for a limit
+    fn is_limit_enabled(settings: &NotificationSettings, limit_id: &str) -> bool {
+        !settings.disabled_limit_ids.contains(limit_id)
+    }
+
+    /// Thresholds the limit has crossed that haven't been notified yet
+    fn pending_thresholds(
+        state: &NotificationState,
+        account_id: &str,
+        limit: &UsageLimit,
+        settings: &NotificationSettings,
+    ) -> Vec<u32> {
+        if !Self::is_limit_enabled(settings, &limit.id) {
+            return Vec::new();
+        }
+
+        // utilization is already a percentage (0-100) from the API
+        let current_percent = limit.utilization as u32;
+
+        let has_absolute = settings
+            .absolute_thresholds
+            .get(&limit.id)
+            .is_some_and(|units_left| !units_left.is_empty());
+        if has_absolute && limit.total.is_none() && state.take_missing_total_report(&limit.id) {
+            log::debug!(
+                "Limit {} has no total; using percent thresholds only",
+                limit.id
+            );
+        }
+
+        Self::thresholds_for(limit, settings)
+            .into_iter()
+            .filter(|&threshold| {
+                current_percent >= threshold
+                    && !state.was_threshold_notified(account_id, &limit.id, threshold)
+            })
+            .collect()
+    }
+
+    /// Percent thresholds for a limit, with its absolute thresholds converted, ascending
+    ///
+    /// Absolute thresholds need the limit's total; without one only the percent
+    /// thresholds apply.
+    fn thresholds_for(limit: &UsageLimit, settings: &NotificationSettings) -> Vec<u32> {
+        let mut thresholds = settings.thresholds.clone();
+
+        if let (Some(units_left), Some(total)) =
+            (settings.absolute_thresholds.get(&limit.id), limit.total)
+        {
+            thresholds.extend(
+                units_left
+                    .iter()
+                    .filter_map(|&left| Self::percent_for_units_left(left, total)),
+            );
+        }
+
+        thresholds.sort_unstable();
+        thresholds.dedup();
+        thresholds
+    }
+
+    /// Utilization percent at which `units_left` of `total` remain, rounded up
+    ///
+    /// Rounding up means the alert never fires while more than `units_left`
+    /// remain. None when the threshold would fire at no usage at all.
+    pub fn percent_for_units_left(units_left: f64, total: f64) -> Option<u32> {
+        if !total.is_finite() || total <= 0.0 || !units_left.is_finite() || units_left < 0.0 {
+            return None;
+        }
+
+        // Allow for float noise so e.g. exactly 7.0% doesn't round up to 8
+        let percent = ((total - units_left) * 100.0 / total - 1e-9).ceil();
+        (percent > 0.0).then_some(percent.min(100.0) as u32)
+    }
+
     /// Check and send reset notification
     fn check_reset_notification(
+        &self,
         app: &AppHandle,
         state: &NotificationState,
         account_id: &str,
         account_name: &str,
         limit: &UsageLimit,
         previous_usage: Option<&UsageData>,
+        locale: &str,
     ) {
         // Check if this limit just reset (previous was high, now low)
-        if let Some(prev) = previous_usage {
-            if let Some(prev_limit) = prev.limits.iter().find(|l| l.id == limit.id) {
-                // utilization is already a percentage (0-100) from the API
-                let prev_percent = prev_limit.utilization as u32;
-                let curr_percent = limit.utilization as u32;
-
-                // If usage dropped significantly (more than 50%) and was previously high
-                if prev_percent >= 50 && curr_percent < prev_percent.saturating_sub(40) {
-                    let title = "Usage Reset";
-                    let body = Self::format_with_account(
-                        account_name,
-                        format!("{} has reset! Now at {}%", limit.label, curr_percent),
-                    );
-
-                    Self::send_notification(app, title, &body);
-                    state.clear_reset_warning(account_id, &limit.id);
-
-                    // Clear all threshold notifications for this limit
-                    for thresh in [50, 75, 90, 100] {
-                        state.clear_threshold(account_id, &limit.id, thresh);
-                    }
-
-                    // Emit event for frontend confetti animation
-                    let _ = app.emit("usage-reset", &limit.id);
-
-                    log::info!("Sent reset notification for {} ({})", limit.id, account_name);
+        let Some(prev_limit) =
+            previous_usage.and_then(|prev| prev.limits.iter().find(|l| l.id == limit.id))
+        else {
+            return;
+        };
+
+        if Self::is_usage_reset(prev_limit, limit) {
+            let (title, body) = Self::reset_message(locale, account_name, limit);
+
+            self.dispatch(
+                &title,
+                &body,
+                NotificationUrgency::Normal,
+                NotificationKind::Reset,
+                Some(&limit.id),
+            );
+            Self::clear_after_reset(state, account_id, &limit.id);
+
+            // Emit event for frontend confetti animation
+            let _ = app.emit("usage-reset", &limit.id);
+
+            log::info!("Sent reset notification for {} ({})", limit.id, account_name);
+        }
+    }
+
+    /// Whether usage dropped enough since the previous reading to count as a reset
+    fn is_usage_reset(previous: &UsageLimit, current: &UsageLimit) -> bool {
+        // utilization is already a percentage (0-100) from the API
+        let prev_percent = previous.utilization as u32;
+        let curr_percent = current.utilization as u32;
+
+        // If usage dropped significantly (more than 40 points) and was previously high
+        prev_percent >= 50 && curr_percent < prev_percent.saturating_sub(40)
+    }
+
+    /// Title and body of a usage reset notification
+    fn reset_message(locale: &str, account_name: &str, limit: &UsageLimit) -> (String, String) {
+        let title = localize(locale, "usage_reset.title", &[]);
+        let body = Self::format_with_account(
+            account_name,
+            localize(
+                locale,
+                "usage_reset.body",
+                &[
+                    ("label", limit.label.clone()),
+                    ("percent", (limit.utilization as u32).to_string()),
+                ],
+            ),
+        );
+
+        (title, body)
+    }
+
+    /// Forget notifications for a limit that just reset so they can fire again
+    fn clear_after_reset(state: &NotificationState, account_id: &str, limit_id: &str) {
+        state.clear_reset_warning(account_id, limit_id);
+
+        // Clear all threshold notifications for this limit
+        for thresh in [50, 75, 90, 100] {
+            state.clear_threshold(account_id, limit_id, thresh);
+        }
+    }
+
+    /// Replay a usage trajectory through the threshold and reset checks without sending anything
+    ///
+    /// Uses a fresh `NotificationState`, so dedup behaves as it would from app start.
+    /// Do Not Disturb is ignored; each notification is stamped with its snapshot's time.
+    pub fn simulate(snapshots: &[UsageData], settings: &AppSettings) -> Vec<SimulatedNotification> {
+        let state = NotificationState::new();
+        let mut fired = Vec::new();
+        let mut previous: Option<&UsageData> = None;
+
+        for usage in snapshots {
+            let account_id = &usage.account_id;
+
+            for limit in &usage.limits {
+                if !Self::is_limit_enabled(&settings.notifications, &limit.id) {
+                    continue;
+                }
+
+                state.clear_thresholds_above(account_id, &limit.id, limit.utilization as u32);
+
+                for threshold in
+                    Self::pending_thresholds(&state, account_id, limit, &settings.notifications)
+                {
+                    let (title, body) =
+                        Self::threshold_message(settings, &usage.account_name, limit, threshold);
+                    state.mark_threshold_notified(account_id, &limit.id, threshold);
+                    fired.push(SimulatedNotification {
+                        at: usage.timestamp,
+                        limit_id: limit.id.clone(),
+                        kind: SimulatedNotificationKind::Threshold,
+                        threshold: Some(threshold),
+                        urgency: Self::urgency_for_threshold(threshold, &settings.notifications),
+                        title,
+                        body,
+                    });
+                }
+
+                if !settings.notifications.notify_on_reset
+                    || !Self::is_reset_time_plausible(&limit.id, limit.resets_at, usage.timestamp)
+                {
+                    continue;
+                }
+
+                let prev_limit =
+                    previous.and_then(|prev| prev.limits.iter().find(|l| l.id == limit.id));
+                if prev_limit.is_some_and(|prev| Self::is_usage_reset(prev, limit)) {
+                    let (title, body) =
+                        Self::reset_message(&settings.locale, &usage.account_name, limit);
+                    Self::clear_after_reset(&state, account_id, &limit.id);
+                    fired.push(SimulatedNotification {
+                        at: usage.timestamp,
+                        limit_id: limit.id.clone(),
+                        kind: SimulatedNotificationKind::Reset,
+                        threshold: None,
+                        urgency: NotificationUrgency::Normal,
+                        title,
+                        body,
+                    });
                 }
             }
+
+            previous = Some(usage);
         }
+
+        fired
     }
 
-    /// Send notification for upcoming reset (within 1 hour)
+    /// Send notification for an upcoming reset of a heavily used limit
+    ///
+    /// The lead time and usage gate come from the reset warning settings.
     pub fn check_upcoming_reset(
         app: &AppHandle,
         state: &NotificationState,
@@ -217,38 +859,209 @@ impl NotificationService {
             Err(_) => return,
         };
 
-        if !settings.notifications.enabled || !settings.notifications.notify_on_reset {
+        if !settings.notifications.enabled
+            || !settings.notifications.notify_on_reset
+            || !Self::is_limit_enabled(&settings.notifications, &limit.id)
+        {
             return;
         }
 
-        // Check if reset is within 1 hour and usage is high
         let now = Utc::now();
-        let time_until_reset = limit.resets_at.signed_duration_since(now);
-        // utilization is already a percentage (0-100) from the API
-        let current_percent = limit.utilization as u32;
 
-        if time_until_reset > Duration::zero()
-            && time_until_reset <= Duration::hours(1)
-            && current_percent >= 75
+        // process_usage already emitted a clock-skew warning for this limit
+        if !Self::is_reset_time_plausible(&limit.id, limit.resets_at, now) {
+            log::debug!("Skipping upcoming reset check for {}: implausible reset time", limit.id);
+            return;
+        }
+
+        if Self::reset_warning_due(limit, &settings.notifications, now)
             && !state.was_reset_warning_sent(account_id, &limit.id)
         {
-            let minutes = time_until_reset.num_minutes();
-            let title = "Limit Reset Soon";
+            let minutes = limit.resets_at.signed_duration_since(now).num_minutes();
+            // utilization is already a percentage (0-100) from the API
+            let current_percent = limit.utilization as u32;
+            let title = localize(&settings.locale, "reset_soon.title", &[]);
             let body = Self::format_with_account(
                 account_name,
-                format!(
-                    "{} will reset in {} minutes (currently at {}%)",
-                    limit.label, minutes, current_percent
+                localize(
+                    &settings.locale,
+                    "reset_soon.body",
+                    &[
+                        ("label", limit.label.clone()),
+                        ("minutes", minutes.to_string()),
+                        ("percent", current_percent.to_string()),
+                    ],
                 ),
             );
 
-            if Self::send_notification(app, title, &body) {
+            let service = Self::for_app(app, &settings.notifications);
+            if service.dispatch(
+                &title,
+                &body,
+                NotificationUrgency::Normal,
+                NotificationKind::ResetSoon,
+                Some(&limit.id),
+            ) {
                 state.mark_reset_warning_sent(account_id, &limit.id);
                 log::info!("Sent upcoming reset notification for {} ({})", limit.id, account_name);
             }
         }
     }
 
+    /// Lead time and minimum utilization for a limit's reset warning, preferring its override
+    fn reset_warning_for(limit_id: &str, settings: &NotificationSettings) -> (Duration, u32) {
+        let limit_override = settings.reset_warning_overrides.get(limit_id);
+        let lead_minutes = limit_override
+            .and_then(|o| o.lead_minutes)
+            .unwrap_or(settings.reset_warning_lead_minutes);
+        let min_percent = limit_override
+            .and_then(|o| o.min_percent)
+            .unwrap_or(settings.reset_warning_min_percent);
+
+        (Duration::minutes(lead_minutes as i64), min_percent)
+    }
+
+    /// Whether a limit is close enough to its reset, and used enough, for a reset warning
+    fn reset_warning_due(
+        limit: &UsageLimit,
+        settings: &NotificationSettings,
+        now: DateTime<Utc>,
+    ) -> bool {
+        let (lead, min_percent) = Self::reset_warning_for(&limit.id, settings);
+        let time_until_reset = limit.resets_at.signed_duration_since(now);
+
+        time_until_reset > Duration::zero()
+            && time_until_reset <= lead
+            && limit.utilization as u32 >= min_percent
+    }
+
+    /// Record each budgeted account's current level without alerting
+    ///
+    /// Levels are only kept in memory, so without this every launch would alert
+    /// again for a budget already warned about or exceeded this month.
+    pub fn seed_budget_levels(app: &AppHandle, state: &NotificationState, settings: &AppSettings) {
+        for (account_id, &budget) in &settings.monthly_budgets {
+            match HistoryService::get_budget_status(
+                app,
+                account_id,
+                budget,
+                settings.budget_warning_percent,
+            ) {
+                Ok(status) => {
+                    state.escalate_budget_level(account_id, status.month_start, status.level);
+                }
+                Err(e) => log::warn!("Failed to read monthly budget for {}: {}", account_id, e),
+            }
+        }
+    }
+
+    /// Alert when an account's monthly consumption reaches the warning level or its budget
+    ///
+    /// Emits `budget-warning` or `budget-exceeded` once per level per month, and sends a
+    /// notification too when notifications are on.
+    pub fn check_budget(
+        app: &AppHandle,
+        state: &NotificationState,
+        account_id: &str,
+        account_name: &str,
+    ) {
+        let settings = match SettingsService::get(app) {
+            Ok(s) => s,
+            Err(_) => return,
+        };
+
+        let Some(&budget) = settings.monthly_budgets.get(account_id) else {
+            return;
+        };
+
+        let status = match HistoryService::get_budget_status(
+            app,
+            account_id,
+            budget,
+            settings.budget_warning_percent,
+        ) {
+            Ok(status) => status,
+            Err(e) => {
+                log::warn!("Failed to check monthly budget for {}: {}", account_name, e);
+                return;
+            }
+        };
+
+        if !state.escalate_budget_level(account_id, status.month_start, status.level) {
+            return;
+        }
+
+        let (event, key, urgency) = match status.level {
+            BudgetLevel::Under => return,
+            BudgetLevel::Warning => (
+                "budget-warning",
+                "budget_warning",
+                NotificationUrgency::Normal,
+            ),
+            BudgetLevel::Exceeded => (
+                "budget-exceeded",
+                "budget_exceeded",
+                NotificationUrgency::Critical,
+            ),
+        };
+
+        log::info!(
+            "{} has used {:.1} of its {:.1} monthly budget",
+            account_name,
+            status.consumed,
+            status.budget
+        );
+        let _ = app.emit(event, &status);
+
+        if !settings.notifications.enabled {
+            return;
+        }
+
+        let title = localize(&settings.locale, &format!("{}.title", key), &[]);
+        let body = Self::format_with_account(
+            account_name,
+            localize(
+                &settings.locale,
+                &format!("{}.body", key),
+                &[
+                    ("consumed", format!("{:.0}", status.consumed)),
+                    ("budget", format!("{:.0}", status.budget)),
+                ],
+            ),
+        );
+
+        Self::for_app(app, &settings.notifications).dispatch(
+            &title,
+            &body,
+            urgency,
+            NotificationKind::Budget,
+            None,
+        );
+    }
+
+    /// Length of the rolling window for a limit (5 hours or 7 days)
+    pub(crate) fn limit_window(limit_id: &str) -> Duration {
+        if limit_id.starts_with("five_hour") {
+            Duration::hours(5)
+        } else {
+            Duration::days(7)
+        }
+    }
+
+    /// Check whether a reset time is believable given the local clock
+    ///
+    /// A reset more than 30 days out, or one that passed longer ago than the
+    /// limit's own window, means the system clock (or the API) is off.
+    pub fn is_reset_time_plausible(
+        limit_id: &str,
+        resets_at: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> bool {
+        let too_far_ahead = resets_at > now + Duration::days(MAX_RESET_HORIZON_DAYS);
+        let long_past = resets_at < now - Self::limit_window(limit_id);
+        !too_far_ahead && !long_past
+    }
+
     /// Send a session expiry warning
     pub fn send_session_expiry_warning(app: &AppHandle) {
         let settings = match SettingsService::get(app) {
@@ -260,75 +1073,867 @@ impl NotificationService {
             return;
         }
 
-        Self::send_notification(
-            app,
-            "Session Expiring",
-            "Your Claude session may be expiring soon. Please refresh your credentials.",
+        Self::for_app(app, &settings.notifications).dispatch(
+            &localize(&settings.locale, "session_expiring.title", &[]),
+            &localize(&settings.locale, "session_expiring.body", &[]),
+            NotificationUrgency::Normal,
+            NotificationKind::SessionExpiry,
+            None,
         );
     }
 
     /// Check if currently in Do Not Disturb time window
     fn is_dnd_active(settings: &NotificationSettings) -> bool {
+        let now = Local::now();
+        Self::dnd_active_for(settings, now.weekday(), now.time())
+    }
+
+    /// Whether the DND schedule covers `now` on `weekday`
+    ///
+    /// Each day uses its own window from `dnd_schedule`, or the global window if it
+    /// has none. A window whose start is after its end spans midnight (e.g. 22:00 to
+    /// 08:00), so the previous day's window can still be active early in the morning.
+    pub fn dnd_active_for(
+        settings: &NotificationSettings,
+        weekday: Weekday,
+        now: NaiveTime,
+    ) -> bool {
         if !settings.dnd_enabled {
             return false;
         }
 
-        let (start_str, end_str) = match (&settings.dnd_start_time, &settings.dnd_end_time) {
-            (Some(s), Some(e)) => (s.as_str(), e.as_str()),
-            _ => return false,
+        // Tonight's window: overnight windows run from the start to midnight today
+        if let Some((start, end)) = Self::dnd_window_for(settings, weekday) {
+            let active = if start > end {
+                now >= start
+            } else {
+                now >= start && now < end
+            };
+            if active {
+                return true;
+            }
+        }
+
+        // The tail of yesterday's overnight window
+        matches!(
+            Self::dnd_window_for(settings, weekday.pred()),
+            Some((start, end)) if start > end && now < end
+        )
+    }
+
+    /// Parsed DND window for a day, or None if it is missing or malformed
+    fn dnd_window_for(
+        settings: &NotificationSettings,
+        weekday: Weekday,
+    ) -> Option<(NaiveTime, NaiveTime)> {
+        let (start, end) = match settings.dnd_schedule.for_day(weekday) {
+            Some(window) => (Some(window.start.as_str()), Some(window.end.as_str())),
+            None => (
+                settings.dnd_start_time.as_deref(),
+                settings.dnd_end_time.as_deref(),
+            ),
+        };
+
+        let parse = |time: &str| NaiveTime::parse_from_str(time, "%H:%M").ok();
+        Some((parse(start?)?, parse(end?)?))
+    }
+
+    /// Ask the notification plugin for the current permission and remember the answer
+    pub fn check_permission(app: &AppHandle) -> Result<PermissionState, AppError> {
+        let state = app
+            .notification()
+            .permission_state()
+            .map_err(|e| AppError::Notification(e.to_string()))?;
+
+        if let Some(permission) = app.try_state::<NotificationPermission>() {
+            permission.set(state);
+        }
+
+        Ok(state)
+    }
+
+    /// Tell the UI (once per session) that alerts can't be shown until permission is granted
+    pub(crate) fn report_permission_denied(app: &AppHandle) {
+        let Some(permission) = app.try_state::<NotificationPermission>() else {
+            return;
         };
 
-        let start = match NaiveTime::parse_from_str(start_str, "%H:%M") {
-            Ok(t) => t,
-            Err(_) => return false,
+        if permission.take_denied_report() {
+            log::warn!("Notification permission denied - alerts will not be shown");
+            let _ = app.emit("notification-permission-denied", ());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Account, DndWindow, LimitKind, ResetWarningOverride};
+    use chrono::TimeZone;
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap()
+    }
+
+    fn limit(id: &str, utilization: f64) -> UsageLimit {
+        UsageLimit {
+            id: id.to_string(),
+            label: id.to_string(),
+            utilization,
+            resets_at: now() + Duration::hours(2),
+            category: None,
+            kind: LimitKind::Unknown,
+            total: None,
+        }
+    }
+
+    #[test]
+    fn urgency_is_critical_from_configured_threshold() {
+        let mut settings = AppSettings::default().notifications;
+        assert_eq!(
+            NotificationService::urgency_for_threshold(50, &settings),
+            NotificationUrgency::Normal
+        );
+        assert_eq!(
+            NotificationService::urgency_for_threshold(75, &settings),
+            NotificationUrgency::Normal
+        );
+        assert_eq!(
+            NotificationService::urgency_for_threshold(90, &settings),
+            NotificationUrgency::Critical
+        );
+        assert_eq!(
+            NotificationService::urgency_for_threshold(100, &settings),
+            NotificationUrgency::Critical
+        );
+
+        settings.critical_threshold = 75;
+        assert_eq!(
+            NotificationService::urgency_for_threshold(75, &settings),
+            NotificationUrgency::Critical
+        );
+    }
+
+    /// Records what it is asked to send instead of showing anything
+    #[derive(Clone, Default)]
+    struct RecordingChannel {
+        sent: Arc<Mutex<Vec<(String, NotificationUrgency)>>>,
+        fail: bool,
+    }
+
+    impl RecordingChannel {
+        fn record(&self, title: &str, urgency: NotificationUrgency) -> bool {
+            self.sent.lock().unwrap().push((title.to_string(), urgency));
+            !self.fail
+        }
+
+        fn take(&self) -> Vec<(String, NotificationUrgency)> {
+            std::mem::take(&mut *self.sent.lock().unwrap())
+        }
+    }
+
+    impl NotificationChannel for RecordingChannel {
+        fn name(&self) -> &'static str {
+            "recording"
+        }
+
+        fn send(&self, title: &str, _body: &str) -> bool {
+            self.record(title, NotificationUrgency::Normal)
+        }
+
+        fn send_critical(&self, title: &str, _body: &str) -> bool {
+            self.record(title, NotificationUrgency::Critical)
+        }
+    }
+
+    #[test]
+    fn threshold_crossings_fire_once_through_channels() {
+        use NotificationUrgency::{Critical, Normal};
+
+        let settings = AppSettings::default();
+        let channel = RecordingChannel::default();
+        let service = NotificationService::new(vec![Box::new(channel.clone())]);
+        let state = NotificationState::new();
+        let title = |threshold: u32| {
+            localize(
+                &settings.locale,
+                "threshold_alert.title",
+                &[("threshold", threshold.to_string())],
+            )
+        };
+        let check = |utilization: f64| {
+            let limit = limit("five_hour", utilization);
+            service.check_threshold_notifications(&state, "acc-1", "Personal", &limit, &settings);
+            channel.take()
         };
 
-        let end = match NaiveTime::parse_from_str(end_str, "%H:%M") {
-            Ok(t) => t,
-            Err(_) => return false,
+        assert_eq!(check(40.0), vec![]);
+        assert_eq!(check(60.0), vec![(title(50), Normal)]);
+        assert_eq!(check(80.0), vec![(title(75), Normal)]);
+        assert_eq!(check(85.0), vec![]);
+        assert_eq!(check(95.0), vec![(title(90), Critical)]);
+
+        // Dropping back below clears the higher thresholds so they can fire again
+        assert_eq!(check(60.0), vec![]);
+        assert_eq!(
+            check(92.0),
+            vec![(title(75), Normal), (title(90), Critical)]
+        );
+    }
+
+    #[test]
+    fn undelivered_threshold_is_retried() {
+        let settings = AppSettings::default();
+        let failing = RecordingChannel {
+            fail: true,
+            ..Default::default()
         };
+        let state = NotificationState::new();
+        let limit = limit("five_hour", 60.0);
 
-        let now = Local::now().time();
+        NotificationService::new(vec![Box::new(failing.clone())])
+            .check_threshold_notifications(&state, "acc-1", "Personal", &limit, &settings);
+        assert_eq!(failing.take().len(), 1);
+        assert!(!state.was_threshold_notified("acc-1", "five_hour", 50));
 
-        // Handle overnight DND (e.g., 22:00 to 08:00)
-        if start > end {
-            // DND spans midnight: active if now >= start OR now < end
-            now >= start || now < end
-        } else {
-            // DND within same day: active if now >= start AND now < end
-            now >= start && now < end
+        // No channels at all (e.g. during DND) also leaves it pending
+        NotificationService::new(Vec::new())
+            .check_threshold_notifications(&state, "acc-1", "Personal", &limit, &settings);
+        assert!(!state.was_threshold_notified("acc-1", "five_hour", 50));
+
+        let working = RecordingChannel::default();
+        NotificationService::new(vec![Box::new(working.clone())])
+            .check_threshold_notifications(&state, "acc-1", "Personal", &limit, &settings);
+        assert_eq!(working.take().len(), 1);
+        assert!(state.was_threshold_notified("acc-1", "five_hour", 50));
+    }
+
+    #[test]
+    fn digest_mode_sends_one_notification_per_cycle() {
+        let mut settings = AppSettings::default();
+        settings.notifications.digest_mode = true;
+        let channel = RecordingChannel::default();
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let sink = records.clone();
+        let service = NotificationService::new(vec![Box::new(channel.clone())])
+            .with_log(move |record| sink.lock().unwrap().push(record));
+        let state = NotificationState::new();
+
+        for (account_id, id, utilization) in [
+            ("acc-1", "five_hour", 60.0),
+            ("acc-1", "seven_day", 80.0),
+            ("acc-2", "seven_day_opus", 95.0),
+        ] {
+            let limit = limit(id, utilization);
+            service
+                .check_threshold_notifications(&state, account_id, "Personal", &limit, &settings);
         }
+        // Nothing goes out until the end of the cycle
+        assert!(channel.take().is_empty());
+        assert!(!state.was_threshold_notified("acc-1", "five_hour", 50));
+
+        service.send_digest(&state, &settings.locale);
+        let sent = channel.take();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].1, NotificationUrgency::Critical);
+
+        let records = records.lock().unwrap();
+        assert_eq!(records.len(), 1);
+        assert!(records[0].body.starts_with("3 limits crossed thresholds: "));
+        for label in ["five_hour", "seven_day", "seven_day_opus"] {
+            assert!(records[0].body.contains(label), "{} missing", label);
+        }
+        for threshold in [50, 75, 90] {
+            assert!(state.was_threshold_notified("acc-2", "seven_day_opus", threshold));
+        }
+        assert!(state.was_threshold_notified("acc-1", "seven_day", 75));
+
+        // Already notified, so the next cycle's digest is empty
+        service.send_digest(&state, &settings.locale);
+        assert!(channel.take().is_empty());
     }
 
-    /// Send a notification using the Tauri notification plugin
-    fn send_notification(app: &AppHandle, title: &str, body: &str) -> bool {
-        // Check DND before sending
-        if let Ok(settings) = SettingsService::get(app) {
-            if Self::is_dnd_active(&settings.notifications) {
-                log::debug!(
-                    "Notification suppressed (DND active): {} - {}",
-                    title,
-                    body
-                );
-                return false;
-            }
+    #[test]
+    fn delivered_and_suppressed_notifications_are_logged() {
+        let settings = AppSettings::default();
+        let limit = limit("five_hour", 60.0);
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let log = |records: &Arc<Mutex<Vec<NotificationRecord>>>| {
+            let records = records.clone();
+            move |record| records.lock().unwrap().push(record)
+        };
+
+        // Undelivered notifications are not logged
+        let failing = RecordingChannel {
+            fail: true,
+            ..Default::default()
+        };
+        NotificationService::new(vec![Box::new(failing)])
+            .with_log(log(&records))
+            .check_threshold_notifications(
+                &NotificationState::new(),
+                "acc-1",
+                "Personal",
+                &limit,
+                &settings,
+            );
+        assert!(records.lock().unwrap().is_empty());
+
+        NotificationService::held_back(Some(Box::new(log(&records))))
+            .check_threshold_notifications(
+                &NotificationState::new(),
+                "acc-1",
+                "Personal",
+                &limit,
+                &settings,
+            );
+        NotificationService::new(vec![Box::new(RecordingChannel::default())])
+            .with_log(log(&records))
+            .check_threshold_notifications(
+                &NotificationState::new(),
+                "acc-1",
+                "Personal",
+                &limit,
+                &settings,
+            );
+
+        let records = records.lock().unwrap();
+        assert_eq!(records.len(), 2);
+        assert!(records[0].suppressed);
+        assert!(!records[1].suppressed);
+        assert_eq!(records[1].kind, NotificationKind::Threshold);
+        assert_eq!(records[1].limit_id.as_deref(), Some("five_hour"));
+        assert!(records[1].body.contains("Personal"));
+    }
+
+    #[test]
+    fn disabled_limit_gets_no_threshold_notifications() {
+        let state = NotificationState::new();
+        let mut settings = AppSettings::default().notifications;
+        settings.disabled_limit_ids.insert("seven_day".to_string());
+
+        let muted = limit("seven_day", 95.0);
+        assert!(NotificationService::pending_thresholds(&state, "acc-1", &muted, &settings).is_empty());
+        assert!(!NotificationService::is_limit_enabled(&settings, "seven_day"));
+
+        // Other limits on the same account are unaffected
+        let active = limit("five_hour", 95.0);
+        assert_eq!(
+            NotificationService::pending_thresholds(&state, "acc-1", &active, &settings),
+            vec![50, 75, 90]
+        );
+    }
+
+    #[test]
+    fn absolute_thresholds_convert_with_the_limit_total() {
+        assert_eq!(
+            NotificationService::percent_for_units_left(5.0, 50.0),
+            Some(90)
+        );
+        // 83.3% used leaves exactly 5 of 30; round up so it never fires early
+        assert_eq!(
+            NotificationService::percent_for_units_left(5.0, 30.0),
+            Some(84)
+        );
+        assert_eq!(
+            NotificationService::percent_for_units_left(0.0, 30.0),
+            Some(100)
+        );
+        assert_eq!(
+            NotificationService::percent_for_units_left(30.0, 30.0),
+            None
+        );
+        assert_eq!(NotificationService::percent_for_units_left(5.0, 0.0), None);
+        assert_eq!(
+            NotificationService::percent_for_units_left(-1.0, 30.0),
+            None
+        );
+
+        let mut settings = AppSettings::default().notifications;
+        settings
+            .absolute_thresholds
+            .insert("seven_day".to_string(), vec![5.0, 20.0]);
+
+        let mut weekly = limit("seven_day", 85.0);
+        weekly.total = Some(50.0);
+        assert_eq!(
+            NotificationService::thresholds_for(&weekly, &settings),
+            vec![50, 60, 75, 90]
+        );
+        assert_eq!(
+            NotificationService::pending_thresholds(
+                &NotificationState::new(),
+                "acc-1",
+                &weekly,
+                &settings
+            ),
+            vec![50, 60, 75]
+        );
+
+        // Other limits only get the percent thresholds
+        let mut session = limit("five_hour", 85.0);
+        session.total = Some(50.0);
+        assert_eq!(
+            NotificationService::thresholds_for(&session, &settings),
+            vec![50, 75, 90]
+        );
+    }
+
+    #[test]
+    fn absolute_thresholds_fall_back_to_percent_without_a_total() {
+        let mut settings = AppSettings::default().notifications;
+        settings
+            .absolute_thresholds
+            .insert("seven_day".to_string(), vec![5.0]);
+
+        let weekly = limit("seven_day", 95.0);
+        assert_eq!(weekly.total, None);
+        assert_eq!(
+            NotificationService::thresholds_for(&weekly, &settings),
+            vec![50, 75, 90]
+        );
+
+        // The missing total is reported once per limit, not on every fetch
+        let state = NotificationState::new();
+        assert!(state.take_missing_total_report("seven_day"));
+        assert!(!state.take_missing_total_report("seven_day"));
+        assert!(state.take_missing_total_report("five_hour"));
+    }
+
+    #[test]
+    fn reset_time_plausible_within_window() {
+        let soon = now() + Duration::hours(3);
+        let later = now() + Duration::days(6);
+        assert!(NotificationService::is_reset_time_plausible("five_hour", soon, now()));
+        assert!(NotificationService::is_reset_time_plausible("seven_day", later, now()));
+    }
+
+    #[test]
+    fn reset_time_future_boundary() {
+        let limit = now() + Duration::days(MAX_RESET_HORIZON_DAYS);
+        assert!(NotificationService::is_reset_time_plausible("seven_day", limit, now()));
+        assert!(!NotificationService::is_reset_time_plausible(
+            "seven_day",
+            limit + Duration::seconds(1),
+            now()
+        ));
+    }
+
+    #[test]
+    fn reset_time_past_boundary_uses_limit_window() {
+        let five_hours_ago = now() - Duration::hours(5);
+        assert!(NotificationService::is_reset_time_plausible("five_hour", five_hours_ago, now()));
+        assert!(!NotificationService::is_reset_time_plausible(
+            "five_hour",
+            five_hours_ago - Duration::seconds(1),
+            now()
+        ));
+
+        // The same time is fine for a weekly limit
+        assert!(NotificationService::is_reset_time_plausible(
+            "seven_day_opus",
+            five_hours_ago - Duration::seconds(1),
+            now()
+        ));
+        assert!(!NotificationService::is_reset_time_plausible(
+            "seven_day_opus",
+            now() - Duration::days(7) - Duration::seconds(1),
+            now()
+        ));
+    }
+
+    #[test]
+    fn simulation_follows_rising_then_reset_trajectory() {
+        let start = now();
+        let snapshot = |minutes: i64, utilization: f64| UsageData {
+            provider: "claude".to_string(),
+            account_id: "acc-1".to_string(),
+            account_name: "Default".to_string(),
+            timestamp: start + Duration::minutes(minutes),
+            limits: vec![limit("five_hour", utilization)],
+            raw: None,
+            rate_limit: None,
+        };
+        let trajectory = vec![
+            snapshot(0, 30.0),
+            snapshot(30, 55.0),
+            snapshot(60, 60.0),
+            snapshot(90, 80.0),
+            snapshot(100, 95.0),
+            snapshot(110, 5.0),
+            snapshot(115, 52.0),
+        ];
+
+        let mut settings = AppSettings::default();
+        settings.notifications.thresholds = vec![50, 75, 90];
+
+        let fired: Vec<(i64, SimulatedNotificationKind, Option<u32>)> =
+            NotificationService::simulate(&trajectory, &settings)
+                .iter()
+                .map(|n| ((n.at - start).num_minutes(), n.kind, n.threshold))
+                .collect();
+
+        use SimulatedNotificationKind::{Reset, Threshold};
+        assert_eq!(
+            fired,
+            vec![
+                (30, Threshold, Some(50)),
+                (90, Threshold, Some(75)),
+                (100, Threshold, Some(90)),
+                (110, Reset, None),
+                (115, Threshold, Some(50)),
+            ]
+        );
+    }
+
+    #[test]
+    fn notification_body_prefers_nickname() {
+        let mut account = Account {
+            id: "acc-1".to_string(),
+            name: "Default".to_string(),
+            provider: "claude".to_string(),
+            credentials: Default::default(),
+            created_at: now(),
+            tags: Vec::new(),
+            display_name: None,
+            extra_headers: BTreeMap::new(),
+        };
+        let body = |account: &Account| {
+            NotificationService::format_with_account(
+                account.notification_label(),
+                "5-hour is at 90%".to_string(),
+            )
+        };
+
+        // "Default" accounts get no prefix unless they have a nickname
+        assert_eq!(body(&account), "5-hour is at 90%");
+
+        account.display_name = Some("Work".to_string());
+        assert_eq!(body(&account), "[Work] 5-hour is at 90%");
+
+        // A blank nickname falls back to the account name
+        account.name = "Personal".to_string();
+        account.display_name = Some("  ".to_string());
+        assert_eq!(body(&account), "[Personal] 5-hour is at 90%");
+    }
+
+    #[test]
+    fn budget_alerts_fire_once_per_level_per_month() {
+        let state = NotificationState::new();
+        let january = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+        let february = Utc.with_ymd_and_hms(2025, 2, 1, 0, 0, 0).unwrap();
+
+        assert!(!state.escalate_budget_level("acc-1", january, BudgetLevel::Under));
+        assert!(state.escalate_budget_level("acc-1", january, BudgetLevel::Warning));
+        assert!(!state.escalate_budget_level("acc-1", january, BudgetLevel::Warning));
+        assert!(state.escalate_budget_level("acc-1", january, BudgetLevel::Exceeded));
+        assert!(!state.escalate_budget_level("acc-1", january, BudgetLevel::Exceeded));
+
+        // Other accounts track their own level
+        assert!(state.escalate_budget_level("acc-2", january, BudgetLevel::Warning));
+
+        // The new month starts over
+        assert!(!state.escalate_budget_level("acc-1", february, BudgetLevel::Under));
+        assert!(state.escalate_budget_level("acc-1", february, BudgetLevel::Warning));
+    }
+
+    #[test]
+    fn permission_denied_is_reported_once_per_session() {
+        let permission = NotificationPermission::new();
+        assert!(!permission.take_denied_report());
+
+        permission.set(PermissionState::Granted);
+        assert!(!permission.take_denied_report());
+
+        permission.set(PermissionState::Denied);
+        assert!(permission.take_denied_report());
+        assert!(!permission.take_denied_report());
+
+        // Re-checking and finding it still denied doesn't report again
+        permission.set(PermissionState::Denied);
+        assert!(!permission.take_denied_report());
+    }
+
+    #[test]
+    fn reset_warning_fires_within_custom_lead_time() {
+        let mut settings = AppSettings::default().notifications;
+        settings.reset_warning_overrides.insert(
+            "seven_day".to_string(),
+            ResetWarningOverride {
+                lead_minutes: Some(180),
+                min_percent: None,
+            },
+        );
+
+        let mut weekly = limit("seven_day", 80.0);
+        weekly.resets_at = now() + Duration::minutes(181);
+        assert!(!NotificationService::reset_warning_due(
+            &weekly,
+            &settings,
+            now()
+        ));
+        weekly.resets_at = now() + Duration::minutes(180);
+        assert!(NotificationService::reset_warning_due(
+            &weekly,
+            &settings,
+            now()
+        ));
+        // Already reset
+        weekly.resets_at = now();
+        assert!(!NotificationService::reset_warning_due(
+            &weekly,
+            &settings,
+            now()
+        ));
+
+        // Limits without an override keep the global lead time
+        let session = limit("five_hour", 80.0);
+        assert!(!NotificationService::reset_warning_due(
+            &session,
+            &settings,
+            now()
+        ));
+        settings.reset_warning_lead_minutes = 120;
+        assert!(NotificationService::reset_warning_due(
+            &session,
+            &settings,
+            now()
+        ));
+    }
+
+    #[test]
+    fn reset_warning_needs_minimum_utilization() {
+        let mut settings = AppSettings::default().notifications;
+        let resets_at = now() + Duration::minutes(30);
+
+        let mut session = limit("five_hour", 74.9);
+        session.resets_at = resets_at;
+        assert!(!NotificationService::reset_warning_due(
+            &session,
+            &settings,
+            now()
+        ));
+        session.utilization = 75.0;
+        assert!(NotificationService::reset_warning_due(
+            &session,
+            &settings,
+            now()
+        ));
+
+        settings.reset_warning_overrides.insert(
+            "five_hour".to_string(),
+            ResetWarningOverride {
+                lead_minutes: None,
+                min_percent: Some(90),
+            },
+        );
+        assert!(!NotificationService::reset_warning_due(
+            &session,
+            &settings,
+            now()
+        ));
+        settings.reset_warning_min_percent = 50;
+        let mut weekly = limit("seven_day", 60.0);
+        weekly.resets_at = resets_at;
+        assert!(NotificationService::reset_warning_due(
+            &weekly,
+            &settings,
+            now()
+        ));
+    }
+
+    #[test]
+    fn clearing_one_limit_keeps_other_limits_state() {
+        let state = NotificationState::new();
+        for account_id in ["acc-1", "acc-2"] {
+            state.mark_threshold_notified(account_id, "five_hour", 50);
+            state.mark_threshold_notified(account_id, "five_hour", 75);
+            state.mark_threshold_notified(account_id, "seven_day", 50);
+            state.mark_reset_warning_sent(account_id, "five_hour");
+            state.mark_reset_warning_sent(account_id, "seven_day");
         }
+        let month = now();
+        state.escalate_budget_level("acc-1", month, BudgetLevel::Warning);
 
-        match app
-            .notification()
-            .builder()
-            .title(title)
-            .body(body)
-            .show()
-        {
-            Ok(_) => {
-                log::debug!("Notification sent: {} - {}", title, body);
-                true
-            }
-            Err(e) => {
-                log::error!("Failed to send notification: {}", e);
-                false
-            }
+        assert_eq!(state.clear_limit(Some("five_hour")), 6);
+
+        for account_id in ["acc-1", "acc-2"] {
+            assert!(!state.was_threshold_notified(account_id, "five_hour", 50));
+            assert!(!state.was_threshold_notified(account_id, "five_hour", 75));
+            assert!(!state.was_reset_warning_sent(account_id, "five_hour"));
+            assert!(state.was_threshold_notified(account_id, "seven_day", 50));
+            assert!(state.was_reset_warning_sent(account_id, "seven_day"));
         }
+        assert_eq!(state.clear_limit(Some("five_hour")), 0);
+
+        assert_eq!(state.clear_limit(None), 4);
+        let snapshot = state.snapshot();
+        assert!(snapshot.sent_thresholds.is_empty());
+        assert!(snapshot.sent_reset_warnings.is_empty());
+        // Budget alerts aren't per limit
+        assert_eq!(snapshot.budget_levels.len(), 1);
+    }
+
+    fn dnd_settings(start: &str, end: &str) -> NotificationSettings {
+        let mut settings = AppSettings::default().notifications;
+        settings.dnd_enabled = true;
+        settings.dnd_start_time = Some(start.to_string());
+        settings.dnd_end_time = Some(end.to_string());
+        settings
+    }
+
+    fn at(time: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(time, "%H:%M").unwrap()
+    }
+
+    #[test]
+    fn same_day_dnd_window() {
+        let settings = dnd_settings("09:00", "17:00");
+
+        assert!(!NotificationService::dnd_active_for(
+            &settings,
+            Weekday::Wed,
+            at("08:59")
+        ));
+        assert!(NotificationService::dnd_active_for(
+            &settings,
+            Weekday::Wed,
+            at("09:00")
+        ));
+        assert!(NotificationService::dnd_active_for(
+            &settings,
+            Weekday::Wed,
+            at("12:30")
+        ));
+        assert!(!NotificationService::dnd_active_for(
+            &settings,
+            Weekday::Wed,
+            at("17:00")
+        ));
+        assert!(!NotificationService::dnd_active_for(
+            &settings,
+            Weekday::Wed,
+            at("23:00")
+        ));
+    }
+
+    #[test]
+    fn overnight_dnd_window() {
+        let settings = dnd_settings("22:00", "08:00");
+
+        assert!(!NotificationService::dnd_active_for(
+            &settings,
+            Weekday::Wed,
+            at("21:59")
+        ));
+        assert!(NotificationService::dnd_active_for(
+            &settings,
+            Weekday::Wed,
+            at("22:00")
+        ));
+        assert!(NotificationService::dnd_active_for(
+            &settings,
+            Weekday::Wed,
+            at("00:00")
+        ));
+        assert!(NotificationService::dnd_active_for(
+            &settings,
+            Weekday::Wed,
+            at("07:59")
+        ));
+        assert!(!NotificationService::dnd_active_for(
+            &settings,
+            Weekday::Wed,
+            at("08:00")
+        ));
+        assert!(!NotificationService::dnd_active_for(
+            &settings,
+            Weekday::Wed,
+            at("12:00")
+        ));
+    }
+
+    #[test]
+    fn disabled_or_malformed_dnd_is_inactive() {
+        let mut settings = dnd_settings("22:00", "08:00");
+        settings.dnd_enabled = false;
+        assert!(!NotificationService::dnd_active_for(
+            &settings,
+            Weekday::Wed,
+            at("23:00")
+        ));
+
+        let settings = dnd_settings("late", "08:00");
+        assert!(!NotificationService::dnd_active_for(
+            &settings,
+            Weekday::Wed,
+            at("23:00")
+        ));
+    }
+
+    #[test]
+    fn weekday_schedule_overrides_global_window() {
+        let mut settings = dnd_settings("22:00", "08:00");
+        // Weeknights: short overnight window; Wednesday off; Friday runs late into Saturday
+        settings.dnd_schedule.mon = Some(DndWindow {
+            start: "23:30".to_string(),
+            end: "06:00".to_string(),
+        });
+        settings.dnd_schedule.wed = Some(DndWindow {
+            start: "01:00".to_string(),
+            end: "01:00".to_string(),
+        });
+        settings.dnd_schedule.fri = Some(DndWindow {
+            start: "23:00".to_string(),
+            end: "10:00".to_string(),
+        });
+
+        let active = |day, time| NotificationService::dnd_active_for(&settings, day, at(time));
+
+        // Monday uses its own window, not the global 22:00 start
+        assert!(!active(Weekday::Mon, "22:30"));
+        assert!(active(Weekday::Mon, "23:45"));
+        // ...and the tail of Monday's window carries into Tuesday morning
+        assert!(active(Weekday::Tue, "05:59"));
+        assert!(!active(Weekday::Tue, "06:30"));
+
+        // An empty window (start == end) means no DND that day, nor a tail the next morning
+        assert!(!active(Weekday::Wed, "23:00"));
+        assert!(!active(Weekday::Thu, "07:00"));
+        // Thursday has no entry, so the global overnight window still covers early Friday
+        assert!(active(Weekday::Fri, "07:00"));
+
+        // Friday starts later than the global window but runs past 08:00 on Saturday
+        assert!(!active(Weekday::Fri, "22:30"));
+        assert!(active(Weekday::Fri, "23:30"));
+        assert!(active(Weekday::Sat, "09:30"));
+        assert!(!active(Weekday::Sat, "10:30"));
+    }
+
+    #[test]
+    fn weekend_without_schedule_uses_global_window() {
+        let mut settings = dnd_settings("22:00", "08:00");
+        settings.dnd_schedule.wed = Some(DndWindow {
+            start: "20:00".to_string(),
+            end: "21:00".to_string(),
+        });
+
+        assert!(NotificationService::dnd_active_for(
+            &settings,
+            Weekday::Sat,
+            at("23:00")
+        ));
+        assert!(NotificationService::dnd_active_for(
+            &settings,
+            Weekday::Sun,
+            at("07:00")
+        ));
+        assert!(!NotificationService::dnd_active_for(
+            &settings,
+            Weekday::Sun,
+            at("12:00")
+        ));
     }
 }
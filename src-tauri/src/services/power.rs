@@ -0,0 +1,275 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::process::Child;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, SystemTime};
+
+/// Payload of `system-slept`
+///
+/// Sent as the system goes to sleep where the OS announces it (Linux, via
+/// logind), otherwise on the first tick after waking.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemSleptEvent {
+    pub slept_at: DateTime<Utc>,
+}
+
+/// Payload of `system-woke`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemSleepEvent {
+    /// Last time the app was seen running before the sleep
+    pub slept_at: DateTime<Utc>,
+    pub woke_at: DateTime<Utc>,
+    pub slept_secs: u64,
+}
+
+/// Spots system sleep from gaps between the scheduler's one-second ticks
+///
+/// This is the fallback on every platform: without an OS notice, a sleep is
+/// recognized on the first tick after waking. The wall clock is used because
+/// `Instant` stops while suspended on Linux and macOS, which would hide the gap.
+pub struct SleepDetector {
+    last_tick: SystemTime,
+    threshold: Duration,
+}
+
+impl SleepDetector {
+    pub fn new(now: SystemTime, threshold: Duration) -> Self {
+        Self {
+            last_tick: now,
+            threshold,
+        }
+    }
+
+    /// Record a tick, returning the sleep it ended if the gap since the last tick exceeds the threshold
+    ///
+    /// A clock set backwards counts as no gap.
+    pub fn tick(&mut self, now: SystemTime) -> Option<SystemSleepEvent> {
+        let gap = now.duration_since(self.last_tick).unwrap_or_default();
+        let slept_at = self.last_tick;
+        self.last_tick = now;
+
+        (gap > self.threshold).then(|| SystemSleepEvent {
+            slept_at: slept_at.into(),
+            woke_at: now.into(),
+            slept_secs: gap.as_secs(),
+        })
+    }
+
+    /// Restart the gap from `now` without reporting anything
+    ///
+    /// Called after a fetch, which can take longer than the threshold on its own.
+    pub fn reset(&mut self, now: SystemTime) {
+        self.last_tick = now;
+    }
+}
+
+/// The OS's own notice that the system is about to sleep or has resumed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerEvent {
+    Suspending,
+    Resumed,
+}
+
+/// What the OS has said about sleep, shared between its hook and the scheduler loop
+#[derive(Debug, Default)]
+pub struct PowerState {
+    /// Between `Suspending` and `Resumed`: fetches would only fail, so none start
+    suspended: AtomicBool,
+    /// `system-slept` went out when the OS announced the sleep, not yet matched by a wake
+    announced: AtomicBool,
+    /// Process delivering the OS notices, stopped at shutdown
+    monitor: Mutex<Option<PowerMonitor>>,
+}
+
+impl PowerState {
+    /// Record an OS notice, returning true if `system-slept` should be emitted now
+    pub fn record(&self, event: PowerEvent) -> bool {
+        match event {
+            PowerEvent::Suspending => {
+                self.suspended.store(true, Ordering::SeqCst);
+                self.announced.store(true, Ordering::SeqCst);
+                true
+            }
+            PowerEvent::Resumed => {
+                self.suspended.store(false, Ordering::SeqCst);
+                false
+            }
+        }
+    }
+
+    pub fn is_suspended(&self) -> bool {
+        self.suspended.load(Ordering::SeqCst)
+    }
+
+    /// At wake: whether `system-slept` was already sent for this sleep
+    pub fn take_announced(&self) -> bool {
+        self.suspended.store(false, Ordering::SeqCst);
+        self.announced.swap(false, Ordering::SeqCst)
+    }
+
+    /// On a tick without a sleep gap: drop an announcement no wake will match
+    ///
+    /// A sleep shorter than the gap threshold (or a suspend that failed) never
+    /// produces a wake tick, and would otherwise swallow the next sleep's
+    /// `system-slept`.
+    pub fn settle(&self) {
+        if !self.is_suspended() {
+            self.announced.store(false, Ordering::SeqCst);
+        }
+    }
+
+    /// Keep the process delivering OS notices, so it can be stopped at shutdown
+    pub fn set_monitor(&self, monitor: PowerMonitor) {
+        *self.monitor.lock().unwrap_or_else(PoisonError::into_inner) = Some(monitor);
+    }
+
+    /// Stop the process delivering OS notices, if there is one
+    pub fn stop_monitor(&self) {
+        self.monitor
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+    }
+}
+
+/// The running `gdbus monitor` process, killed when dropped
+#[derive(Debug)]
+pub struct PowerMonitor {
+    child: Child,
+}
+
+impl Drop for PowerMonitor {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Pass the OS's sleep and resume notices to `on_event`
+///
+/// logind broadcasts `PrepareForSleep(true)` before suspending and `(false)`
+/// after resuming; `gdbus monitor` prints each signal as a line. Returns None
+/// if the monitor can't be started, leaving tick gaps as the only signal.
+#[cfg(target_os = "linux")]
+pub fn watch_power_events(on_event: impl Fn(PowerEvent) + Send + 'static) -> Option<PowerMonitor> {
+    use std::io::{BufRead, BufReader};
+    use std::process::{Command, Stdio};
+
+    let child = Command::new("gdbus")
+        .args([
+            "monitor",
+            "--system",
+            "--dest",
+            "org.freedesktop.login1",
+            "--object-path",
+            "/org/freedesktop/login1",
+        ])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(e) => {
+            log::debug!("Can't watch logind for sleep notices: {}", e);
+            return None;
+        }
+    };
+    // Dropping the monitor kills the process, so bailing out here doesn't leak it
+    let stdout = child.stdout.take();
+    let monitor = PowerMonitor { child };
+    let stdout = stdout?;
+
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if let Some(event) = parse_logind_signal(&line) {
+                on_event(event);
+            }
+        }
+        log::debug!("Stopped watching logind for sleep notices");
+    });
+    Some(monitor)
+}
+
+/// No OS sleep notices are hooked up on this platform
+#[cfg(not(target_os = "linux"))]
+pub fn watch_power_events(_on_event: impl Fn(PowerEvent) + Send + 'static) -> Option<PowerMonitor> {
+    None
+}
+
+/// Read a `gdbus monitor` line for logind's `PrepareForSleep` signal
+#[cfg(any(target_os = "linux", test))]
+fn parse_logind_signal(line: &str) -> Option<PowerEvent> {
+    if !line.contains("org.freedesktop.login1.Manager.PrepareForSleep") {
+        return None;
+    }
+    if line.contains("(true,)") {
+        Some(PowerEvent::Suspending)
+    } else if line.contains("(false,)") {
+        Some(PowerEvent::Resumed)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gap_over_threshold_is_reported_once() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let mut detector = SleepDetector::new(start, Duration::from_secs(30));
+
+        assert_eq!(detector.tick(start + Duration::from_secs(1)), None);
+
+        let woke = start + Duration::from_secs(3601);
+        let event = detector.tick(woke).unwrap();
+        assert_eq!(event.slept_secs, 3600);
+        assert_eq!(event.woke_at, DateTime::<Utc>::from(woke));
+
+        assert_eq!(detector.tick(woke + Duration::from_secs(1)), None);
+        // Clock set backwards
+        assert_eq!(detector.tick(start), None);
+
+        // A slow fetch followed by a reset is not a sleep
+        detector.reset(start + Duration::from_secs(120));
+        assert_eq!(detector.tick(start + Duration::from_secs(121)), None);
+    }
+
+    #[test]
+    fn os_notices_pause_fetches_and_announce_the_sleep() {
+        let state = PowerState::default();
+        assert!(state.record(PowerEvent::Suspending));
+        assert!(state.is_suspended());
+
+        assert!(!state.record(PowerEvent::Resumed));
+        assert!(!state.is_suspended());
+        assert!(state.take_announced());
+        // Nothing announced for a sleep only spotted by the tick gap
+        assert!(!state.take_announced());
+
+        // A sleep too short to leave a tick gap doesn't swallow the next announcement
+        state.record(PowerEvent::Suspending);
+        state.settle();
+        assert!(state.is_suspended());
+        state.record(PowerEvent::Resumed);
+        state.settle();
+        assert!(!state.take_announced());
+
+        let line =
+            "/org/freedesktop/login1: org.freedesktop.login1.Manager.PrepareForSleep (true,)";
+        assert_eq!(parse_logind_signal(line), Some(PowerEvent::Suspending));
+        let line =
+            "/org/freedesktop/login1: org.freedesktop.login1.Manager.PrepareForSleep (false,)";
+        assert_eq!(parse_logind_signal(line), Some(PowerEvent::Resumed));
+        assert_eq!(
+            parse_logind_signal(
+                "/org/freedesktop/login1: org.freedesktop.login1.Manager.SessionNew ('3',)"
+            ),
+            None
+        );
+    }
+}
@@ -0,0 +1,194 @@
+use crate::error::AppError;
+use crate::models::{CachedResponse, UsageData};
+use crate::services::crypto::{self, ENCRYPTED_PREFIX};
+use crate::services::key_value::{persist_unlocked, KeyValueStore};
+use crate::services::{DataDirExt, StoreLocks};
+use std::collections::VecDeque;
+use std::sync::PoisonError;
+use tauri::{AppHandle, Manager};
+
+pub(crate) const STORE_FILE: &str = "response_cache.json";
+
+/// Recent provider responses per account, kept encrypted for offline viewing
+///
+/// Entries are stored under the account ID, oldest first, each encrypted on
+/// its own so one unreadable entry doesn't hide the rest.
+pub struct ResponseCacheService;
+
+impl ResponseCacheService {
+    /// Cache a successful fetch, keeping at most `capacity` entries for the account
+    ///
+    /// A capacity of 0 turns caching off and drops what was cached.
+    pub fn record(app: &AppHandle, usage: &UsageData, capacity: usize) -> Result<(), AppError> {
+        let lock = app.state::<StoreLocks>().for_file(STORE_FILE);
+        let guard = lock.lock().unwrap_or_else(PoisonError::into_inner);
+        let store = app.data_store(STORE_FILE)?;
+
+        let entry = CachedResponse {
+            fetched_at: usage.timestamp,
+            usage: usage.clone(),
+        };
+        Self::record_in(&*store, &usage.account_id, entry, capacity)?;
+        persist_unlocked(&*store, guard)
+    }
+
+    /// Cached responses for an account, newest first
+    pub fn get(app: &AppHandle, account_id: &str) -> Result<Vec<CachedResponse>, AppError> {
+        let store = app.data_store(STORE_FILE)?;
+
+        Ok(Self::load_in(&*store, account_id)
+            .into_iter()
+            .rev()
+            .collect())
+    }
+
+    /// Drop everything cached for an account, e.g. after it is deleted
+    pub fn forget(app: &AppHandle, account_id: &str) -> Result<(), AppError> {
+        let lock = app.state::<StoreLocks>().for_file(STORE_FILE);
+        let guard = lock.lock().unwrap_or_else(PoisonError::into_inner);
+        let store = app.data_store(STORE_FILE)?;
+
+        if store.get_value(account_id).is_some() {
+            store.delete_value(account_id);
+            persist_unlocked(&*store, guard)?;
+        }
+        Ok(())
+    }
+
+    /// Drop every cached response
+    pub fn clear(app: &AppHandle) -> Result<(), AppError> {
+        let lock = app.state::<StoreLocks>().for_file(STORE_FILE);
+        let guard = lock.lock().unwrap_or_else(PoisonError::into_inner);
+        let store = app.data_store(STORE_FILE)?;
+
+        for key in store.key_list() {
+            store.delete_value(&key);
+        }
+        persist_unlocked(&*store, guard)
+    }
+
+    /// Append an entry, dropping the oldest beyond `capacity`
+    pub(crate) fn record_in(
+        store: &impl KeyValueStore,
+        account_id: &str,
+        entry: CachedResponse,
+        capacity: usize,
+    ) -> Result<(), AppError> {
+        if capacity == 0 {
+            store.delete_value(account_id);
+            return Ok(());
+        }
+
+        let mut entries = Self::load_encrypted(store, account_id);
+        let encrypted = crypto::encrypt(&serde_json::to_string(&entry)?)
+            .map_err(|e| AppError::Store(format!("Failed to encrypt cached response: {}", e)))?;
+        entries.push_back(format!("{}{}", ENCRYPTED_PREFIX, encrypted));
+        while entries.len() > capacity {
+            entries.pop_front();
+        }
+
+        store.set_value(account_id, serde_json::to_value(&entries)?);
+        Ok(())
+    }
+
+    /// Decrypt an account's entries, oldest first, skipping any that can't be read
+    pub(crate) fn load_in(store: &impl KeyValueStore, account_id: &str) -> Vec<CachedResponse> {
+        Self::load_encrypted(store, account_id)
+            .iter()
+            .filter_map(|stored| {
+                let decrypted = stored
+                    .strip_prefix(ENCRYPTED_PREFIX)
+                    .ok_or_else(|| "not encrypted".to_string())
+                    .and_then(crypto::decrypt)
+                    .and_then(|json| serde_json::from_str(&json).map_err(|e| e.to_string()));
+                match decrypted {
+                    Ok(entry) => Some(entry),
+                    Err(e) => {
+                        log::warn!(
+                            "Skipping unreadable cached response for {}: {}",
+                            account_id,
+                            e
+                        );
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    fn load_encrypted(store: &impl KeyValueStore, account_id: &str) -> VecDeque<String> {
+        store
+            .get_value(account_id)
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::key_value::MemoryStore;
+    use chrono::{Duration, TimeZone, Utc};
+
+    fn entry(n: i64) -> CachedResponse {
+        let fetched_at = Utc.with_ymd_and_hms(2026, 6, 1, 8, 0, 0).unwrap() + Duration::minutes(n);
+        CachedResponse {
+            fetched_at,
+            usage: UsageData {
+                provider: "claude".to_string(),
+                account_id: "acc-1".to_string(),
+                account_name: "Work Org".to_string(),
+                timestamp: fetched_at,
+                limits: Vec::new(),
+                raw: Some(serde_json::json!({ "organization": "org-secret-123" })),
+                rate_limit: None,
+            },
+        }
+    }
+
+    #[test]
+    fn cache_keeps_the_newest_entries_up_to_capacity() {
+        let store = MemoryStore::default();
+
+        for n in 0..5 {
+            ResponseCacheService::record_in(&store, "acc-1", entry(n), 3).unwrap();
+        }
+
+        let cached: Vec<_> = ResponseCacheService::load_in(&store, "acc-1")
+            .into_iter()
+            .map(|e| e.fetched_at)
+            .collect();
+        assert_eq!(
+            cached,
+            vec![
+                entry(2).fetched_at,
+                entry(3).fetched_at,
+                entry(4).fetched_at
+            ]
+        );
+        assert!(ResponseCacheService::load_in(&store, "acc-2").is_empty());
+
+        // Turning the cache off drops what was kept
+        ResponseCacheService::record_in(&store, "acc-1", entry(5), 0).unwrap();
+        assert!(ResponseCacheService::load_in(&store, "acc-1").is_empty());
+    }
+
+    #[test]
+    fn cached_responses_are_stored_encrypted() {
+        let store = MemoryStore::default();
+        ResponseCacheService::record_in(&store, "acc-1", entry(0), 10).unwrap();
+
+        let stored = store.get_value("acc-1").unwrap();
+        let stored: Vec<String> = serde_json::from_value(stored).unwrap();
+        assert_eq!(stored.len(), 1);
+        assert!(stored[0].starts_with(ENCRYPTED_PREFIX));
+        assert!(!stored[0].contains("org-secret-123"));
+        assert!(!stored[0].contains("Work Org"));
+
+        let loaded = ResponseCacheService::load_in(&store, "acc-1");
+        assert_eq!(
+            loaded[0].usage.raw,
+            Some(serde_json::json!({ "organization": "org-secret-123" }))
+        );
+    }
+}
@@ -0,0 +1,151 @@
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex, PoisonError};
+use tauri::async_runtime::JoinHandle;
+
+/// A one-shot fetch waiting for its time
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledFetch {
+    pub id: String,
+    pub account_id: String,
+    pub at: DateTime<Utc>,
+}
+
+struct PendingFetch {
+    info: ScheduledFetch,
+    handle: JoinHandle<()>,
+}
+
+/// One-shot fetches registered independently of the recurring scheduler
+#[derive(Default)]
+pub struct ScheduledFetches {
+    pending: Arc<Mutex<HashMap<String, PendingFetch>>>,
+}
+
+impl ScheduledFetches {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `task` for `account_id` at `at` (immediately if already past); returns the schedule ID
+    pub fn schedule<F, Fut>(&self, account_id: &str, at: DateTime<Utc>, task: F) -> ScheduledFetch
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let info = ScheduledFetch {
+            id: uuid::Uuid::new_v4().to_string(),
+            account_id: account_id.to_string(),
+            at,
+        };
+
+        let delay = (at - Utc::now()).to_std().unwrap_or_default();
+        let pending = Arc::clone(&self.pending);
+        let id = info.id.clone();
+
+        // Hold the map lock until the entry is inserted so a zero delay can't remove it first
+        let mut map = self.pending.lock().unwrap_or_else(PoisonError::into_inner);
+        let handle = tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(delay).await;
+
+            // Drop our entry before running so the task itself can't be cancelled mid-fetch
+            pending
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .remove(&id);
+            task().await;
+        });
+
+        map.insert(
+            info.id.clone(),
+            PendingFetch {
+                info: info.clone(),
+                handle,
+            },
+        );
+
+        info
+    }
+
+    /// Cancel a pending fetch; returns false if it already ran or never existed
+    pub fn cancel(&self, id: &str) -> bool {
+        let removed = self
+            .pending
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(id);
+
+        match removed {
+            Some(fetch) => {
+                fetch.handle.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Fetches still waiting to run, soonest first
+    pub fn list(&self) -> Vec<ScheduledFetch> {
+        let pending = self.pending.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut fetches: Vec<ScheduledFetch> = pending.values().map(|p| p.info.clone()).collect();
+        fetches.sort_by(|a, b| a.at.cmp(&b.at));
+        fetches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    fn flag_task(
+        flag: &Arc<AtomicBool>,
+    ) -> impl FnOnce() -> std::future::Ready<()> + Send + 'static {
+        let flag = Arc::clone(flag);
+        move || {
+            flag.store(true, Ordering::SeqCst);
+            std::future::ready(())
+        }
+    }
+
+    #[tokio::test]
+    async fn scheduled_fetch_fires() {
+        let fetches = ScheduledFetches::new();
+        let fired = Arc::new(AtomicBool::new(false));
+
+        let scheduled = fetches.schedule(
+            "acc-1",
+            Utc::now() + Duration::milliseconds(50),
+            flag_task(&fired),
+        );
+        assert_eq!(fetches.list().len(), 1);
+        assert_eq!(fetches.list()[0].id, scheduled.id);
+
+        tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+
+        assert!(fired.load(Ordering::SeqCst));
+        assert!(fetches.list().is_empty());
+        assert!(!fetches.cancel(&scheduled.id));
+    }
+
+    #[tokio::test]
+    async fn cancelled_fetch_never_fires() {
+        let fetches = ScheduledFetches::new();
+        let fired = Arc::new(AtomicBool::new(false));
+
+        let scheduled = fetches.schedule(
+            "acc-1",
+            Utc::now() + Duration::milliseconds(100),
+            flag_task(&fired),
+        );
+        assert!(fetches.cancel(&scheduled.id));
+
+        tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+
+        assert!(!fired.load(Ordering::SeqCst));
+        assert!(fetches.list().is_empty());
+    }
+}
@@ -1,27 +1,65 @@
 use crate::error::{AppError, ProviderError};
-use crate::models::{Account, UsageData};
-use crate::providers::{ClaudeProvider, UsageProvider};
+use crate::models::{
+    Account, AccountHealth, AccountInfo, AppSettings, HealthStatus, HistoryAnnotation,
+    HistoryQuery, ReconcileReport, UsageData, UsageHistoryEntry, UsageLimitSnapshot,
+};
+use crate::providers::{ClaudeProvider, RequestLimiter, UsageProvider};
+use crate::services::key_value::KeyValueStore;
+use crate::services::{credentials, history, network, settings};
 use crate::services::{
-    CredentialService, HistoryService, NotificationService, NotificationState, SettingsService,
+    watch_power_events, Connectivity, CredentialService, DataDirExt, HistoryService,
+    NotificationService, NotificationState, NotificationStateSnapshot, PowerEvent, PowerState,
+    ResponseCacheService, SettingsService, SleepDetector, SystemSleepEvent, SystemSleptEvent,
+    UsageStream,
 };
-use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use std::collections::BTreeMap;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::future::Future;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::{Duration, Instant};
-use tauri::{AppHandle, Emitter};
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Emitter, Manager};
 use tokio::sync::Mutex as AsyncMutex;
-use tokio::time::sleep;
+use tokio::time::{sleep, sleep_until};
 
 /// Minimum time between requests (rate limit protection)
-const MIN_REFRESH_INTERVAL_SECS: u64 = 10;
+pub(crate) const MIN_REFRESH_INTERVAL_SECS: u64 = 10;
+
+/// Bounds for suggested refresh intervals
+const MIN_SUGGESTED_INTERVAL_SECS: u64 = 60;
+const MAX_SUGGESTED_INTERVAL_SECS: u64 = 1800;
+
+/// How much history `suggest_refresh_interval` looks at
+const SUGGESTION_LOOKBACK_DAYS: i64 = 7;
+
+/// Recent window used to measure how fast utilization is moving
+const VOLATILITY_WINDOW_SECS: i64 = 3600;
 
 /// If we detect a gap larger than this, assume system was sleeping
 const SLEEP_DETECTION_THRESHOLD_SECS: u64 = 30;
 
+/// How long fetched account info is reused before asking the provider again
+const ACCOUNT_INFO_MAX_AGE_SECS: i64 = 24 * 3600;
+
+/// What one scheduler loop tick should do
+#[derive(Debug, PartialEq)]
+enum Tick {
+    Idle,
+    Fetch,
+    /// First tick after a system sleep
+    Wake(SystemSleepEvent),
+}
+
 /// Scheduler state shared across the app
 pub struct SchedulerState {
     /// Whether the scheduler is currently running
     running: AtomicBool,
+    /// Incremented on every start so a loop left over from a previous run exits
+    run_id: AtomicU64,
+    /// Whether the loop should fetch once right away instead of waiting an interval
+    warmup_pending: AtomicBool,
     /// Whether the scheduler is paused due to session issues (per account)
     paused_accounts: AsyncMutex<HashMap<String, bool>>,
     /// Count of consecutive session errors per account
@@ -30,17 +68,94 @@ pub struct SchedulerState {
     last_fetch: AtomicU64,
     /// Current interval in seconds
     interval_secs: AtomicU64,
+    /// When the loop began waiting for its next fetch (unix millis, 0 before the loop runs)
+    cycle_started: AtomicU64,
     /// Lock for fetch operations to prevent concurrent requests
     fetch_lock: AsyncMutex<()>,
     /// Previous usage data for detecting resets (per account)
     previous_usage: AsyncMutex<HashMap<String, UsageData>>,
+    /// Plan details per account; these rarely change, so they're kept for a day
+    account_info: AsyncMutex<HashMap<String, AccountInfo>>,
     /// Notification state for tracking sent notifications (account-aware)
     notification_state: NotificationState,
+    /// Accounts whose most recent fetch failed
+    failed_accounts: AsyncMutex<HashSet<String>>,
+    /// Smoothed (EMA) utilization per account, then per limit
+    smoothed_usage: AsyncMutex<HashMap<String, HashMap<String, SmoothedLimit>>>,
+    /// Most recent fetch outcomes per account, oldest first, capped at `HEALTH_WINDOW`
+    fetch_attempts: AsyncMutex<HashMap<String, VecDeque<FetchAttempt>>>,
+    /// Whether the API host was unreachable at the last probe (`quiet_when_offline` only)
+    connectivity: Connectivity,
+    /// Sleep and resume notices from the OS, where the platform sends them
+    power: PowerState,
+}
+
+/// Outcome of one fetch, kept for the account health score
+#[derive(Debug, Clone)]
+struct FetchAttempt {
+    at: DateTime<Utc>,
+    /// None on success
+    error: Option<String>,
+}
+
+/// Running EMA for one limit, restarted whenever the limit's window resets
+#[derive(Debug, Clone, Copy)]
+struct SmoothedLimit {
+    utilization: f64,
+    resets_at: DateTime<Utc>,
+}
+
+/// Exponential moving average step; `alpha` is clamped to (0, 1]
+fn ema(previous: f64, raw: f64, alpha: f64) -> f64 {
+    let alpha = if alpha > 0.0 { alpha.min(1.0) } else { 1.0 };
+    alpha * raw + (1.0 - alpha) * previous
 }
 
 /// Maximum consecutive session errors before pausing
 const MAX_SESSION_ERRORS: u64 = 3;
 
+/// Fetch attempts per account that the health score is based on
+const HEALTH_WINDOW: usize = 20;
+
+/// Success rate at or above which an account is healthy
+const HEALTHY_SUCCESS_RATE: f64 = 0.9;
+
+/// Success rate below which an account is failing
+const FAILING_SUCCESS_RATE: f64 = 0.5;
+
+/// Consecutive latest failures that make an account failing regardless of its rate
+const FAILING_STREAK: usize = 3;
+
+/// Classify fetch reliability from attempts, oldest first
+///
+/// A run of `FAILING_STREAK` failures at the end marks the account failing even if
+/// it was healthy before, so a freshly expired session shows up quickly.
+fn classify_health(attempts: &VecDeque<FetchAttempt>) -> HealthStatus {
+    if attempts.is_empty() {
+        return HealthStatus::Unknown;
+    }
+
+    let trailing_failures = attempts
+        .iter()
+        .rev()
+        .take_while(|a| a.error.is_some())
+        .count();
+    if trailing_failures >= FAILING_STREAK {
+        return HealthStatus::Failing;
+    }
+
+    let successes = attempts.iter().filter(|a| a.error.is_none()).count();
+    let rate = successes as f64 / attempts.len() as f64;
+
+    if rate >= HEALTHY_SUCCESS_RATE {
+        HealthStatus::Healthy
+    } else if rate >= FAILING_SUCCESS_RATE {
+        HealthStatus::Degraded
+    } else {
+        HealthStatus::Failing
+    }
+}
+
 /// Event payload for session status
 #[derive(Clone, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -51,17 +166,75 @@ pub struct SessionStatusEvent {
     pub paused: bool,
 }
 
+/// Event payload prompting the user to set up credentials for an account
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialsMissingEvent {
+    pub account_id: String,
+    pub account_name: String,
+    /// Which credential field is missing
+    pub field: String,
+}
+
+/// How the scheduler treats a failed fetch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FetchErrorKind {
+    /// Nothing stored for a required field - skip the account and prompt for setup
+    MissingCredentials(String),
+    /// Credentials are present but rejected as malformed
+    InvalidCredentials,
+    /// The session has expired; counts toward pausing the account
+    Session,
+    /// A successful response with a truncated or malformed body - retried on the next fetch
+    Parse,
+    /// A transient failure while the API host was unreachable; never counts toward pausing
+    Offline,
+    /// Network or other transient failures
+    Other,
+}
+
+impl FetchErrorKind {
+    pub fn classify(error: &AppError) -> Self {
+        match error {
+            AppError::Provider(ProviderError::MissingCredentials(field)) => {
+                Self::MissingCredentials(field.clone())
+            }
+            AppError::Provider(ProviderError::InvalidCredentials(_)) => Self::InvalidCredentials,
+            AppError::Provider(ProviderError::SessionExpired) => Self::Session,
+            // Checked before the message heuristics, since the body snippet can contain anything
+            AppError::Provider(ProviderError::Parse { .. }) => Self::Parse,
+            other => {
+                let message = other.to_string();
+                if message.contains("expired") || message.contains("401") {
+                    Self::Session
+                } else {
+                    Self::Other
+                }
+            }
+        }
+    }
+}
+
 impl Default for SchedulerState {
     fn default() -> Self {
         Self {
             running: AtomicBool::new(false),
+            run_id: AtomicU64::new(0),
+            warmup_pending: AtomicBool::new(false),
             paused_accounts: AsyncMutex::new(HashMap::new()),
             session_error_counts: AsyncMutex::new(HashMap::new()),
             last_fetch: AtomicU64::new(0),
             interval_secs: AtomicU64::new(300), // Default 5 minutes
+            cycle_started: AtomicU64::new(0),
             fetch_lock: AsyncMutex::new(()),
             previous_usage: AsyncMutex::new(HashMap::new()),
+            account_info: AsyncMutex::new(HashMap::new()),
             notification_state: NotificationState::new(),
+            failed_accounts: AsyncMutex::new(HashSet::new()),
+            smoothed_usage: AsyncMutex::new(HashMap::new()),
+            fetch_attempts: AsyncMutex::new(HashMap::new()),
+            connectivity: Connectivity::new(),
+            power: PowerState::default(),
         }
     }
 }
@@ -79,6 +252,30 @@ impl SchedulerState {
         self.running.store(running, Ordering::SeqCst);
     }
 
+    /// Mark the scheduler as running, returning the new run ID (None if already running)
+    pub fn try_start(&self, immediate_fetch: bool) -> Option<u64> {
+        if self
+            .running
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            return None;
+        }
+
+        self.warmup_pending.store(immediate_fetch, Ordering::SeqCst);
+        Some(self.run_id.fetch_add(1, Ordering::SeqCst) + 1)
+    }
+
+    /// Whether the loop started with `run_id` should keep going
+    pub fn is_current_run(&self, run_id: u64) -> bool {
+        self.is_running() && self.run_id.load(Ordering::SeqCst) == run_id
+    }
+
+    /// Consume the pending warm-up fetch, if any
+    pub fn take_warmup(&self) -> bool {
+        self.warmup_pending.swap(false, Ordering::SeqCst)
+    }
+
     /// Check if an account is paused
     pub async fn is_account_paused(&self, account_id: &str) -> bool {
         let paused = self.paused_accounts.lock().await;
@@ -125,6 +322,155 @@ impl SchedulerState {
         counts.clear();
     }
 
+    /// Count a failed fetch toward pausing the account
+    ///
+    /// Only session errors count; returns the new consecutive count and whether the
+    /// account is now paused, or None if the error doesn't affect the pause threshold.
+    pub async fn record_fetch_error(
+        &self,
+        account_id: &str,
+        kind: &FetchErrorKind,
+    ) -> Option<(u64, bool)> {
+        if *kind != FetchErrorKind::Session {
+            return None;
+        }
+
+        let error_count = self.increment_account_error_count(account_id).await;
+        let paused = error_count >= MAX_SESSION_ERRORS;
+        if paused {
+            self.set_account_paused(account_id, true).await;
+        }
+
+        Some((error_count, paused))
+    }
+
+    /// Count `count` session errors against an account as if that many fetches had failed
+    ///
+    /// Returns the resulting error count and whether the account is now paused.
+    pub async fn simulate_session_errors(&self, account_id: &str, count: u64) -> (u64, bool) {
+        for _ in 0..count {
+            self.record_fetch_error(account_id, &FetchErrorKind::Session).await;
+        }
+
+        (
+            self.get_account_error_count(account_id).await,
+            self.is_account_paused(account_id).await,
+        )
+    }
+
+    /// Record whether an account's latest fetch failed
+    pub async fn set_account_failed(&self, account_id: &str, failed: bool) {
+        let mut accounts = self.failed_accounts.lock().await;
+        if failed {
+            accounts.insert(account_id.to_string());
+        } else {
+            accounts.remove(account_id);
+        }
+    }
+
+    /// IDs of accounts whose latest fetch failed
+    pub async fn get_failed_accounts(&self) -> Vec<String> {
+        let accounts = self.failed_accounts.lock().await;
+        accounts.iter().cloned().collect()
+    }
+
+    /// Remember a fetch outcome for the account's health score (`error` is None on success)
+    pub async fn record_fetch_attempt(&self, account_id: &str, error: Option<String>) {
+        let mut attempts = self.fetch_attempts.lock().await;
+        let history = attempts.entry(account_id.to_string()).or_default();
+
+        if history.len() == HEALTH_WINDOW {
+            history.pop_front();
+        }
+        history.push_back(FetchAttempt {
+            at: Utc::now(),
+            error,
+        });
+    }
+
+    /// Success rate and classification over the account's recent fetches
+    pub async fn account_health(&self, account_id: &str) -> AccountHealth {
+        let attempts = self.fetch_attempts.lock().await;
+        let history = attempts.get(account_id).cloned().unwrap_or_default();
+
+        let successes = history.iter().filter(|a| a.error.is_none()).count();
+        let last_failure = history.iter().rev().find(|a| a.error.is_some());
+
+        AccountHealth {
+            account_id: account_id.to_string(),
+            attempts: history.len(),
+            successes,
+            success_rate: (!history.is_empty()).then(|| successes as f64 / history.len() as f64),
+            last_error: last_failure.and_then(|a| a.error.clone()),
+            last_error_at: last_failure.map(|a| a.at),
+            status: classify_health(&history),
+        }
+    }
+
+    /// Re-run `fetch` for every account that failed last time, clearing error state on success
+    pub async fn retry_failed<F, Fut>(&self, fetch: F) -> HashMap<String, RetryResult>
+    where
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = Result<(), String>>,
+    {
+        let mut results = HashMap::new();
+
+        for account_id in self.get_failed_accounts().await {
+            let result = match fetch(account_id.clone()).await {
+                Ok(()) => {
+                    self.set_account_failed(&account_id, false).await;
+                    self.reset_account_error_count(&account_id).await;
+                    self.set_account_paused(&account_id, false).await;
+                    RetryResult {
+                        success: true,
+                        error: None,
+                    }
+                }
+                Err(e) => RetryResult {
+                    success: false,
+                    error: Some(e),
+                },
+            };
+            results.insert(account_id, result);
+        }
+
+        results
+    }
+
+    /// Which notifications have been sent, for saving in a snapshot
+    pub fn notification_snapshot(&self) -> NotificationStateSnapshot {
+        self.notification_state.snapshot()
+    }
+
+    /// Treat budget levels already reached this month as alerted (at startup)
+    pub fn seed_budget_levels(&self, app: &AppHandle, settings: &AppSettings) {
+        NotificationService::seed_budget_levels(app, &self.notification_state, settings);
+    }
+
+    /// Replace the sent-notification state, e.g. after restoring a snapshot
+    pub fn restore_notification_state(&self, snapshot: &NotificationStateSnapshot) {
+        self.notification_state.restore(snapshot);
+    }
+
+    /// Let threshold and reset notifications for a limit (or all limits with None) fire again
+    ///
+    /// Returns how many sent-notification entries were cleared.
+    pub fn reset_notification_state(&self, limit_id: Option<&str>) -> usize {
+        self.notification_state.clear_limit(limit_id)
+    }
+
+    /// Forget all per-account state, cached usage, and sent notifications
+    pub async fn clear_all(&self) {
+        self.reset_all_account_states().await;
+        self.failed_accounts.lock().await.clear();
+        self.previous_usage.lock().await.clear();
+        self.account_info.lock().await.clear();
+        self.smoothed_usage.lock().await.clear();
+        self.fetch_attempts.lock().await.clear();
+        self.notification_state.clear_all();
+        self.set_last_fetch(0);
+    }
+
     pub fn get_interval(&self) -> u64 {
         self.interval_secs.load(Ordering::SeqCst)
     }
@@ -141,6 +487,37 @@ impl SchedulerState {
         self.last_fetch.store(millis, Ordering::SeqCst);
     }
 
+    /// Record that the loop's wait for its next fetch starts now
+    fn start_cycle(&self) {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        self.cycle_started.store(now, Ordering::SeqCst);
+    }
+
+    /// When the loop will next fetch (unix seconds), or 0 while the scheduler is stopped
+    ///
+    /// Follows the loop's own timer, offline backoff included. An overdue fetch
+    /// reports `now_millis`, since the loop picks it up within a second.
+    pub fn next_fetch_at(&self, now_millis: u64) -> u64 {
+        if !self.is_running() {
+            return 0;
+        }
+
+        let started = match self.cycle_started.load(Ordering::SeqCst) {
+            0 => now_millis,
+            millis => millis,
+        };
+        let wait = self
+            .connectivity
+            .backoff(Duration::from_secs(self.get_interval()));
+        started
+            .saturating_add(wait.as_millis() as u64)
+            .max(now_millis)
+            / 1000
+    }
+
     /// Check if enough time has passed since last fetch (rate limiting)
     pub fn can_fetch(&self) -> bool {
         let last = self.get_last_fetch();
@@ -161,11 +538,98 @@ impl SchedulerState {
         previous.get(account_id).cloned()
     }
 
+    /// Latest usage for every account fetched so far
+    pub async fn get_all_previous_usage(&self) -> Vec<UsageData> {
+        let previous = self.previous_usage.lock().await;
+        previous.values().cloned().collect()
+    }
+
     /// Set previous usage for an account
     pub async fn set_previous_usage(&self, account_id: &str, data: UsageData) {
         let mut previous = self.previous_usage.lock().await;
         previous.insert(account_id.to_string(), data);
     }
+
+    /// Cached account info, if fetched within `ACCOUNT_INFO_MAX_AGE_SECS` of `now`
+    pub async fn get_account_info(
+        &self,
+        account_id: &str,
+        now: DateTime<Utc>,
+    ) -> Option<AccountInfo> {
+        let cached = self.account_info.lock().await;
+        cached
+            .get(account_id)
+            .filter(|info| (now - info.fetched_at).num_seconds() < ACCOUNT_INFO_MAX_AGE_SECS)
+            .cloned()
+    }
+
+    /// Cache account info for an account
+    pub async fn set_account_info(&self, account_id: &str, info: AccountInfo) {
+        let mut cached = self.account_info.lock().await;
+        cached.insert(account_id.to_string(), info);
+    }
+
+    /// Drop an account's cached info, so the next `get_account_info` fetches it again
+    pub async fn forget_account_info(&self, account_id: &str) {
+        self.account_info.lock().await.remove(account_id);
+    }
+
+    /// Drop the cached info and fetch history of a deleted account
+    pub async fn forget_account(&self, account_id: &str) {
+        self.forget_account_info(account_id).await;
+        self.fetch_attempts.lock().await.remove(account_id);
+    }
+
+    /// Fold a new snapshot into the account's smoothed utilization, keyed by limit ID
+    ///
+    /// A limit seen for the first time, or whose window has reset since the last
+    /// snapshot, starts over from its raw value.
+    pub async fn smooth_usage(
+        &self,
+        account_id: &str,
+        data: &UsageData,
+        alpha: f64,
+    ) -> HashMap<String, f64> {
+        let mut smoothed = self.smoothed_usage.lock().await;
+        let previous = smoothed.remove(account_id).unwrap_or_default();
+
+        let current: HashMap<String, SmoothedLimit> = data
+            .limits
+            .iter()
+            .map(|limit| {
+                // Reported reset times drift by a few seconds within one window
+                let same_window = |prev: &SmoothedLimit| {
+                    limit
+                        .resets_at
+                        .signed_duration_since(prev.resets_at)
+                        .num_seconds()
+                        .abs()
+                        <= history::RESET_TOLERANCE_SECS
+                };
+                let utilization = match previous.get(&limit.id) {
+                    Some(prev) if same_window(prev) => {
+                        ema(prev.utilization, limit.utilization, alpha)
+                    }
+                    _ => limit.utilization,
+                };
+                (
+                    limit.id.clone(),
+                    SmoothedLimit {
+                        utilization,
+                        resets_at: limit.resets_at,
+                    },
+                )
+            })
+            .collect();
+
+        let values = current
+            .iter()
+            .map(|(id, limit)| (id.clone(), limit.utilization))
+            .collect();
+        smoothed.insert(account_id.to_string(), current);
+
+        values
+    }
 }
 
 /// Event payload for usage updates
@@ -176,6 +640,17 @@ pub struct UsageUpdateEvent {
     pub account_id: String,
     pub data: Option<UsageData>,
     pub error: Option<String>,
+    /// EMA-smoothed utilization by limit ID, when `smoothing_factor` is set; `data` stays raw
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub smoothed_utilization: Option<HashMap<String, f64>>,
+}
+
+/// Outcome of retrying a previously failed account
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetryResult {
+    pub success: bool,
+    pub error: Option<String>,
 }
 
 /// Event payload for scheduler status
@@ -192,24 +667,29 @@ pub struct SchedulerService;
 impl SchedulerService {
     /// Start the background scheduler
     pub fn start(app: AppHandle, state: Arc<SchedulerState>) {
-        if state.is_running() {
+        let settings = SettingsService::get(&app).ok();
+        let immediate_fetch = settings
+            .as_ref()
+            .map(|s| s.immediate_fetch_on_start)
+            .unwrap_or(true);
+
+        let Some(run_id) = state.try_start(immediate_fetch) else {
             log::info!("Scheduler already running");
             return;
-        }
+        };
 
-        state.set_running(true);
         log::info!("Starting background refresh scheduler");
 
         // Load initial interval from settings
-        if let Ok(settings) = SettingsService::get(&app) {
-            state.set_interval(settings.refresh_interval as u64);
+        if let Some(settings) = settings {
+            state.set_interval((settings.refresh_interval as u64).max(MIN_REFRESH_INTERVAL_SECS));
         }
 
         let app_clone = app.clone();
         let state_clone = state.clone();
 
         tauri::async_runtime::spawn(async move {
-            Self::scheduler_loop(app_clone, state_clone).await;
+            Self::scheduler_loop(app_clone, state_clone, run_id).await;
         });
 
         // Emit status update
@@ -244,6 +724,49 @@ impl SchedulerService {
         );
     }
 
+    /// Stop the scheduler, wait for any in-flight fetch, and flush every store to disk
+    pub async fn shutdown(app: &AppHandle, state: &SchedulerState) {
+        log::info!("Shutting down: stopping scheduler and flushing stores");
+        Self::stop(app, state);
+        state.power.stop_monitor();
+
+        let stores: Vec<_> = [credentials::STORE_FILE, settings::STORE_FILE, history::STORE_FILE]
+            .into_iter()
+            .filter_map(|file| match app.data_store(file) {
+                Ok(store) => Some(store),
+                Err(e) => {
+                    log::warn!("Failed to open {} for final save: {}", file, e);
+                    None
+                }
+            })
+            .collect();
+
+        let flushed = Self::shutdown_with(state, &stores).await;
+        log::info!("Shutdown complete: flushed {} of {} stores", flushed, stores.len());
+    }
+
+    /// Stop scheduling, wait out the fetch lock, then persist each store; returns how many saved
+    pub(crate) async fn shutdown_with<S: KeyValueStore>(
+        state: &SchedulerState,
+        stores: &[Arc<S>],
+    ) -> usize {
+        state.set_running(false);
+
+        // An in-progress fetch holds this until its results are written
+        let _lock = state.fetch_lock.lock().await;
+
+        stores
+            .iter()
+            .filter(|store| match store.persist() {
+                Ok(()) => true,
+                Err(e) => {
+                    log::error!("Failed to flush store on shutdown: {}", e);
+                    false
+                }
+            })
+            .count()
+    }
+
     /// Update the refresh interval
     pub fn set_interval(app: &AppHandle, state: &SchedulerState, secs: u64) {
         let interval = secs.max(MIN_REFRESH_INTERVAL_SECS);
@@ -265,6 +788,60 @@ impl SchedulerService {
         );
     }
 
+    /// Re-apply the settings' refresh interval to the scheduler if the two have drifted apart
+    ///
+    /// Only `set_refresh_interval` updates both; other paths change settings
+    /// alone. Whether the scheduler runs is left as the user set it: settings
+    /// only say whether it starts with the app, not whether it should be running now.
+    pub fn reconcile(
+        app: &AppHandle,
+        state: &Arc<SchedulerState>,
+    ) -> Result<ReconcileReport, AppError> {
+        let settings = SettingsService::get(app)?;
+        let report = Self::reconcile_with(state, &settings);
+
+        if !report.fixed.is_empty() {
+            let _ = app.emit(
+                "scheduler-status",
+                SchedulerStatusEvent {
+                    running: report.running,
+                    interval_secs: report.interval_secs,
+                    next_refresh_secs: report.running.then_some(report.interval_secs),
+                },
+            );
+            log::info!(
+                "Reconciled scheduler with settings: {}",
+                report.fixed.join(", ")
+            );
+        }
+        Ok(report)
+    }
+
+    /// Correct the interval to match `settings`
+    ///
+    /// In adaptive mode the interval follows usage, so it is not compared.
+    pub(crate) fn reconcile_with(
+        state: &SchedulerState,
+        settings: &AppSettings,
+    ) -> ReconcileReport {
+        let mut fixed = Vec::new();
+
+        let current = state.get_interval();
+        if settings.refresh_mode != "adaptive" {
+            let expected = (settings.refresh_interval as u64).max(MIN_REFRESH_INTERVAL_SECS);
+            if current != expected {
+                state.set_interval(expected);
+                fixed.push(format!("interval {}s -> {}s", current, expected));
+            }
+        }
+
+        ReconcileReport {
+            interval_secs: state.get_interval(),
+            running: state.is_running(),
+            fixed,
+        }
+    }
+
     /// Force an immediate refresh (respects rate limiting)
     pub async fn force_refresh(app: &AppHandle, state: &SchedulerState) -> Result<(), AppError> {
         if !state.can_fetch() {
@@ -278,35 +855,166 @@ impl SchedulerService {
         Ok(())
     }
 
+    /// Re-attempt only the accounts that failed in the previous cycle, using current credentials
+    pub async fn retry_last_failure(
+        app: &AppHandle,
+        state: &SchedulerState,
+    ) -> HashMap<String, RetryResult> {
+        let _lock = state.fetch_lock.lock().await;
+
+        let results = state
+            .retry_failed(|account_id| async move {
+                let account = match CredentialService::get_account(app, &account_id) {
+                    Ok(Some(account)) => account,
+                    Ok(None) => {
+                        // Deleted since it failed - nothing left to retry
+                        state.set_account_failed(&account_id, false).await;
+                        return Err(format!("Account not found: {}", account_id));
+                    }
+                    Err(e) => return Err(e.to_string()),
+                };
+
+                let result = Self::fetch_account_usage(app, &account).await;
+                let error = result.as_ref().err().map(|e| e.to_string());
+
+                let mut max_utilization = 0.0;
+                Self::process_account_result(app, state, &account, result, &mut max_utilization)
+                    .await;
+
+                match error {
+                    Some(e) => Err(e),
+                    None => Ok(()),
+                }
+            })
+            .await;
+
+        NotificationService::flush_digest(app, &state.notification_state);
+        results
+    }
+
+    /// Fetch one account outside the regular cycle, emitting and recording it like a scheduled fetch
+    ///
+    /// Fetch failures are reported through the usual error events; only a missing account is returned.
+    pub async fn fetch_single_account(
+        app: &AppHandle,
+        state: &SchedulerState,
+        account_id: &str,
+    ) -> Result<(), AppError> {
+        let _lock = state.fetch_lock.lock().await;
+
+        let account = CredentialService::get_account(app, account_id)?
+            .ok_or_else(|| AppError::AccountNotFound(account_id.to_string()))?;
+
+        let result = Self::fetch_account_usage(app, &account).await;
+        let mut max_utilization = 0.0;
+        Self::process_account_result(app, state, &account, result, &mut max_utilization).await;
+        NotificationService::flush_digest(app, &state.notification_state);
+
+        Ok(())
+    }
+
+    /// Push an account toward the session-error pause without a real failing fetch
+    ///
+    /// Emits the same `session-status` event a real session error would, so the
+    /// pause and resume flow can be exercised with working credentials.
+    pub async fn simulate_session_errors(
+        app: &AppHandle,
+        state: &SchedulerState,
+        account_id: &str,
+        count: u64,
+    ) -> Result<(), AppError> {
+        if CredentialService::get_account(app, account_id)?.is_none() {
+            return Err(AppError::AccountNotFound(account_id.to_string()));
+        }
+
+        let (error_count, paused) = state.simulate_session_errors(account_id, count).await;
+        log::warn!(
+            "Simulated {} session errors for account {} ({}/{}, paused: {})",
+            count,
+            account_id,
+            error_count,
+            MAX_SESSION_ERRORS,
+            paused
+        );
+
+        let _ = app.emit(
+            "session-status",
+            SessionStatusEvent {
+                account_id: account_id.to_string(),
+                valid: error_count == 0,
+                error_count,
+                paused,
+            },
+        );
+        Ok(())
+    }
+
     /// Main scheduler loop
-    async fn scheduler_loop(app: AppHandle, state: Arc<SchedulerState>) {
+    async fn scheduler_loop(app: AppHandle, state: Arc<SchedulerState>, run_id: u64) {
         let mut last_check = Instant::now();
-        let mut last_tick = Instant::now();
+        state.start_cycle();
+        let mut sleep_detector = SleepDetector::new(
+            SystemTime::now(),
+            Duration::from_secs(SLEEP_DETECTION_THRESHOLD_SECS),
+        );
+
+        // Warm-up fetch so the dashboard isn't empty for a full interval. Goes through
+        // the same rate limit and fetch lock as manual refreshes, so a refresh clicked
+        // right after Start doesn't fetch twice.
+        if state.take_warmup() {
+            if let Err(e) = Self::force_refresh(&app, &state).await {
+                log::debug!("Skipping warm-up fetch: {}", e);
+            }
+            last_check = Instant::now();
+            sleep_detector.reset(SystemTime::now());
+            state.start_cycle();
+        }
 
-        while state.is_running() {
-            let interval = state.get_interval();
-            let elapsed = last_check.elapsed().as_secs();
+        while state.is_current_run(run_id) {
+            let tick = Self::next_tick(
+                &mut sleep_detector,
+                SystemTime::now(),
+                last_check.elapsed(),
+                state
+                    .connectivity
+                    .backoff(Duration::from_secs(state.get_interval())),
+            );
 
-            // Detect if system was sleeping (time gap much larger than expected)
-            let tick_elapsed = last_tick.elapsed().as_secs();
-            if tick_elapsed > SLEEP_DETECTION_THRESHOLD_SECS {
-                log::info!(
-                    "Detected system wake ({}s gap), refreshing immediately",
-                    tick_elapsed
-                );
-                // System just woke up - refresh immediately (even if paused, to check if session is valid again)
-                Self::fetch_all_accounts(&app, &state).await;
-                last_check = Instant::now();
-
-                // Emit wake event to frontend
-                let _ = app.emit("system-wake", ());
-            } else if elapsed >= interval {
-                // Normal scheduled fetch
-                Self::fetch_all_accounts(&app, &state).await;
-                last_check = Instant::now();
+            if !matches!(tick, Tick::Wake(_)) {
+                state.power.settle();
             }
 
-            last_tick = Instant::now();
+            match tick {
+                Tick::Idle => {}
+                // The OS said it's going to sleep; the wake tick fetches instead
+                Tick::Fetch if state.power.is_suspended() => {}
+                Tick::Fetch => {
+                    Self::fetch_all_accounts(&app, &state).await;
+                    last_check = Instant::now();
+                    sleep_detector.reset(SystemTime::now());
+                    state.start_cycle();
+                }
+                Tick::Wake(event) => {
+                    log::info!(
+                        "Detected system wake ({}s asleep), refreshing once",
+                        event.slept_secs
+                    );
+                    if !state.power.take_announced() {
+                        let slept = SystemSleptEvent {
+                            slept_at: event.slept_at,
+                        };
+                        let _ = app.emit("system-slept", &slept);
+                    }
+
+                    // Refresh even if paused, to check if the session is valid again
+                    Self::fetch_all_accounts(&app, &state).await;
+                    last_check = Instant::now();
+                    sleep_detector.reset(SystemTime::now());
+                    state.start_cycle();
+
+                    let _ = app.emit("system-woke", &event);
+                }
+            }
 
             // Sleep for a short interval to check for stop signals and detect wake
             sleep(Duration::from_secs(1)).await;
@@ -315,6 +1023,50 @@ impl SchedulerService {
         log::info!("Scheduler loop ended");
     }
 
+    /// Pause fetching as soon as the OS says it is going to sleep, where it says so
+    ///
+    /// Elsewhere the loop only learns of a sleep from the gap after waking.
+    pub fn watch_power(app: AppHandle, state: Arc<SchedulerState>) {
+        let watched = state.clone();
+        let monitor = watch_power_events(move |event| {
+            if watched.power.record(event) {
+                log::info!("System going to sleep, pausing fetches");
+                let _ = app.emit(
+                    "system-slept",
+                    SystemSleptEvent {
+                        slept_at: Utc::now(),
+                    },
+                );
+            } else if event == PowerEvent::Resumed {
+                log::info!("System resumed");
+            }
+        });
+
+        match monitor {
+            Some(monitor) => state.power.set_monitor(monitor),
+            None => {
+                log::debug!("No OS sleep notices on this platform; detecting sleep from tick gaps")
+            }
+        }
+    }
+
+    /// Decide what a loop tick does
+    ///
+    /// Ticks missed while the system slept are not replayed: the first tick after
+    /// waking fetches once and restarts the interval, whatever was due.
+    fn next_tick(
+        detector: &mut SleepDetector,
+        now: SystemTime,
+        since_fetch: Duration,
+        interval: Duration,
+    ) -> Tick {
+        match detector.tick(now) {
+            Some(event) => Tick::Wake(event),
+            None if since_fetch >= interval => Tick::Fetch,
+            None => Tick::Idle,
+        }
+    }
+
     /// Fetch usage for all accounts and emit events
     async fn fetch_all_accounts(app: &AppHandle, state: &SchedulerState) {
         // Acquire the fetch lock to prevent concurrent fetches
@@ -347,39 +1099,162 @@ impl SchedulerService {
             return;
         }
 
+        let quiet_when_offline = SettingsService::get(app)
+            .map(|s| s.quiet_when_offline)
+            .unwrap_or(false);
+        if !Self::check_connectivity(app, state, quiet_when_offline).await {
+            log::debug!("API host still unreachable, skipping fetch");
+            return;
+        }
+
         log::info!("Scheduler fetching usage for {} account(s)", accounts.len());
 
         // Track max utilization across all accounts for adaptive refresh
         let mut max_utilization_overall: f64 = 0.0;
 
+        // Spread fetches over the start of the cycle instead of bursting them
+        let stagger_ms = SettingsService::get(app)
+            .map(|s| s.fetch_stagger_ms)
+            .unwrap_or(0);
+        let delays = Self::stagger_delays(
+            accounts.len(),
+            stagger_ms,
+            state.get_interval(),
+            &mut rand::thread_rng(),
+        );
+        let cycle_start = tokio::time::Instant::now();
+
         // Fetch for each account sequentially (to respect rate limits)
-        for account in accounts {
+        for (account, delay) in accounts.into_iter().zip(delays) {
             // Skip paused accounts
             if state.is_account_paused(&account.id).await {
                 log::debug!("Skipping paused account: {}", account.name);
                 continue;
             }
 
-            let result = Self::fetch_account_usage(&account).await;
+            sleep_until(cycle_start + delay).await;
+
+            let result = Self::fetch_account_usage(app, &account).await;
+
+            if let Err(e) = &result {
+                let probe_url = quiet_when_offline.then_some(network::PROBE_URL);
+                if Self::classify_fetch_error(e, probe_url).await == FetchErrorKind::Offline {
+                    // The remaining accounts would fail the same way, so stop the cycle here
+                    log::debug!("Fetch for {} failed while offline: {}", account.name, e);
+                    if let Some(event) = state.connectivity.mark_offline(Utc::now()) {
+                        log::warn!("API host unreachable, backing off until it is back");
+                        let _ = app.emit("offline", &event);
+                    }
+                    // Accounts processed before going offline may have queued crossings
+                    NotificationService::flush_digest(app, &state.notification_state);
+                    return;
+                }
+            }
+
             Self::process_account_result(app, state, &account, result, &mut max_utilization_overall).await;
         }
 
+        // Threshold crossings held back in digest mode go out together
+        NotificationService::flush_digest(app, &state.notification_state);
+
         // Adaptive refresh based on max utilization across all accounts
         Self::maybe_adjust_interval_from_utilization(app, state, max_utilization_overall);
     }
 
+    /// Classify a fetch failure, probing `probe_url` (if given) when it looks transient
+    async fn classify_fetch_error(error: &AppError, probe_url: Option<&str>) -> FetchErrorKind {
+        match (FetchErrorKind::classify(error), probe_url) {
+            (FetchErrorKind::Other, Some(url))
+                if !network::is_reachable(url, network::PROBE_TIMEOUT).await =>
+            {
+                FetchErrorKind::Offline
+            }
+            (kind, _) => kind,
+        }
+    }
+
+    /// Before a cycle, re-probe the API host if it was unreachable; returns whether to fetch
+    ///
+    /// With quiet mode off the outage is simply considered over.
+    async fn check_connectivity(
+        app: &AppHandle,
+        state: &SchedulerState,
+        quiet_when_offline: bool,
+    ) -> bool {
+        if !state.connectivity.is_offline() {
+            return true;
+        }
+
+        if quiet_when_offline
+            && !network::is_reachable(network::PROBE_URL, network::PROBE_TIMEOUT).await
+        {
+            state.connectivity.mark_offline(Utc::now());
+            return false;
+        }
+
+        Self::note_online(app, state);
+        true
+    }
+
+    /// End an outage, if one was recorded, and emit `online`
+    fn note_online(app: &AppHandle, state: &SchedulerState) {
+        if let Some(event) = state.connectivity.mark_online(Utc::now()) {
+            log::info!(
+                "API host reachable again after {}s offline",
+                event.offline_secs
+            );
+            let _ = app.emit("online", &event);
+        }
+    }
+
+    /// Randomized, distinct start offsets for each account's fetch within a cycle
+    ///
+    /// Each account gets its own slot of the window and a random point within it.
+    /// The window is capped at half the refresh interval so a staggered fetch
+    /// never runs into the next cycle.
+    fn stagger_delays(
+        count: usize,
+        stagger_ms: u64,
+        interval_secs: u64,
+        rng: &mut impl Rng,
+    ) -> Vec<Duration> {
+        let window_ms = stagger_ms.min(interval_secs * 1000 / 2);
+        if count <= 1 || window_ms == 0 {
+            return vec![Duration::ZERO; count];
+        }
+
+        let slot_ms = (window_ms / count as u64).max(1);
+        (0..count as u64)
+            .map(|i| Duration::from_millis(i * slot_ms + rng.gen_range(0..slot_ms)))
+            .collect()
+    }
+
     /// Fetch usage for a single account
-    async fn fetch_account_usage(account: &Account) -> Result<UsageData, AppError> {
-        let claude = ClaudeProvider::new()?;
+    async fn fetch_account_usage(app: &AppHandle, account: &Account) -> Result<UsageData, AppError> {
+        let claude = ClaudeProvider::new()?.with_extra_headers(&account.extra_headers);
+        let mut credentials = CredentialService::resolve_for_fetch(app, account)?;
 
-        if !claude.validate_credentials(&account.credentials) {
-            return Err(ProviderError::InvalidCredentials(
-                format!("Missing org_id or session_key for account {}", account.name),
+        if !claude.validate_credentials(&credentials) {
+            return Err(ProviderError::MissingCredentials(
+                format!("org_id or session_key for account {}", account.name),
             )
             .into());
         }
 
-        let mut usage = claude.fetch_usage(&account.credentials).await?;
+        CredentialService::refresh_oauth_for_fetch(app, account, &mut credentials).await?;
+
+        let (mut usage, key_index) = {
+            let _permit = RequestLimiter::global().acquire().await;
+            claude.fetch_usage_with_failover(&credentials).await?
+        };
+
+        // A fallback key worked - move it to the front so it is tried first next time
+        if key_index > 0 {
+            log::info!("Account {} failed over to session key {}", account.name, key_index + 1);
+            if let Err(e) = CredentialService::promote_session_key(app, &account.id, key_index) {
+                log::warn!("Failed to promote session key for {}: {}", account.name, e);
+            }
+        }
 
         // Set account info on the usage data
         usage.account_id = account.id.clone();
@@ -396,8 +1271,37 @@ impl SchedulerService {
         result: Result<UsageData, AppError>,
         max_utilization: &mut f64,
     ) {
+        if let Err(e) = &result {
+            if let FetchErrorKind::MissingCredentials(field) = FetchErrorKind::classify(e) {
+                // Nothing to fetch with - not a failure, so don't count it toward pausing or retry it
+                log::info!(
+                    "Skipping account {}: missing credentials ({})",
+                    account.name,
+                    field
+                );
+                state.set_account_failed(&account.id, false).await;
+                let _ = app.emit(
+                    "credentials-missing",
+                    CredentialsMissingEvent {
+                        account_id: account.id.clone(),
+                        account_name: account.name.clone(),
+                        field,
+                    },
+                );
+                return;
+            }
+        }
+
+        state.set_account_failed(&account.id, result.is_err()).await;
+        state
+            .record_fetch_attempt(&account.id, result.as_ref().err().map(|e| e.to_string()))
+            .await;
+
         let event = match result {
             Ok(data) => {
+                // A fetch that got through proves the API is reachable, whatever the probe says
+                Self::note_online(app, state);
+
                 // Session is valid - reset error count and unpause if needed
                 let error_count = state.get_account_error_count(&account.id).await;
                 let was_paused = state.is_account_paused(&account.id).await;
@@ -434,6 +1338,7 @@ impl SchedulerService {
                     &state.notification_state,
                     &data,
                     previous_data.as_ref(),
+                    account.notification_label(),
                 );
 
                 // Check for upcoming resets
@@ -442,7 +1347,7 @@ impl SchedulerService {
                         app,
                         &state.notification_state,
                         &account.id,
-                        &account.name,
+                        account.notification_label(),
                         limit,
                     );
                 }
@@ -452,30 +1357,92 @@ impl SchedulerService {
                     log::warn!("Failed to save usage to history: {}", e);
                 }
 
+                // A new total (plan change) makes percentages before and after incomparable
+                if let Some(previous) = previous_data.as_ref() {
+                    for change in data.quota_changes(previous) {
+                        log::info!(
+                            "Quota for {} on account {} changed from {} to {}",
+                            change.limit_id,
+                            account.name,
+                            change.old_total,
+                            change.new_total
+                        );
+                        let annotation = HistoryAnnotation::QuotaChanged(change.clone());
+                        if let Err(e) = HistoryService::add_annotation(app, annotation) {
+                            log::warn!("Failed to record quota change in history: {}", e);
+                        }
+                        let _ = app.emit("quota-changed", &change);
+                    }
+                }
+
+                // Month-to-date consumption comes from history, so check after saving
+                NotificationService::check_budget(
+                    app,
+                    &state.notification_state,
+                    &account.id,
+                    account.notification_label(),
+                );
+
                 // Store current usage as previous for next comparison
                 state.set_previous_usage(&account.id, data.clone()).await;
 
+                let settings = SettingsService::get(app).ok();
+
+                // Pinned limits first, so the UI and WebSocket clients render the list as received
+                let mut data = data;
+                if let Some(settings) = &settings {
+                    data.order_limits(&settings.limit_order);
+                }
+
+                // Fan out to WebSocket subscribers, if the server is running
+                if let Some(stream) = app.try_state::<UsageStream>() {
+                    stream.publish(&data);
+                }
+
+                // Keep a copy to show while offline
+                let cache_capacity = settings
+                    .as_ref()
+                    .map(|s| s.cached_responses_per_account)
+                    .unwrap_or_else(|| AppSettings::default().cached_responses_per_account);
+                if let Err(e) = ResponseCacheService::record(app, &data, cache_capacity) {
+                    log::warn!("Failed to cache response for {}: {}", account.name, e);
+                }
+
+                // Smoothing is display-only; notifications above already used the raw values
+                let smoothed_utilization = match settings.as_ref().and_then(|s| s.smoothing_factor)
+                {
+                    Some(alpha) => Some(state.smooth_usage(&account.id, &data, alpha).await),
+                    None => None,
+                };
+
                 UsageUpdateEvent {
                     provider: "claude".to_string(),
                     account_id: account.id.clone(),
                     data: Some(data),
                     error: None,
+                    smoothed_utilization,
                 }
             }
             Err(e) => {
                 log::error!("Failed to fetch usage for account {}: {}", account.name, e);
 
-                // Check if this is a session expiry error
                 let error_str = e.to_string();
-                let is_session_error = error_str.contains("expired")
-                    || error_str.contains("401")
-                    || error_str.contains("SessionExpired");
+                let kind = FetchErrorKind::classify(&e);
 
-                if is_session_error {
+                if kind == FetchErrorKind::Session {
                     NotificationService::send_session_expiry_warning(app);
+                }
+                if kind == FetchErrorKind::Parse {
+                    log::warn!(
+                        "Incomplete response for account {}, retrying on the next fetch",
+                        account.name
+                    );
+                }
 
-                    // Track consecutive session errors per account
-                    let error_count = state.increment_account_error_count(&account.id).await;
+                // Track consecutive session errors per account, pausing after too many
+                if let Some((error_count, paused)) =
+                    state.record_fetch_error(&account.id, &kind).await
+                {
                     log::warn!(
                         "Session error {}/{} for account {} - {}",
                         error_count,
@@ -484,37 +1451,24 @@ impl SchedulerService {
                         error_str
                     );
 
-                    // Pause this account after too many consecutive errors
-                    if error_count >= MAX_SESSION_ERRORS {
+                    if paused {
                         log::warn!(
                             "Too many session errors ({}) for account {}, pausing",
                             error_count,
                             account.name
                         );
-                        state.set_account_paused(&account.id, true).await;
-
-                        // Emit session status to frontend
-                        let _ = app.emit(
-                            "session-status",
-                            SessionStatusEvent {
-                                account_id: account.id.clone(),
-                                valid: false,
-                                error_count,
-                                paused: true,
-                            },
-                        );
-                    } else {
-                        // Emit session status (not paused yet)
-                        let _ = app.emit(
-                            "session-status",
-                            SessionStatusEvent {
-                                account_id: account.id.clone(),
-                                valid: false,
-                                error_count,
-                                paused: false,
-                            },
-                        );
                     }
+
+                    // Emit session status to frontend
+                    let _ = app.emit(
+                        "session-status",
+                        SessionStatusEvent {
+                            account_id: account.id.clone(),
+                            valid: false,
+                            error_count,
+                            paused,
+                        },
+                    );
                 }
 
                 UsageUpdateEvent {
@@ -522,6 +1476,7 @@ impl SchedulerService {
                     account_id: account.id.clone(),
                     data: None,
                     error: Some(error_str),
+                    smoothed_utilization: None,
                 }
             }
         };
@@ -575,8 +1530,690 @@ impl SchedulerService {
         }
     }
 
+    /// Recommend a refresh interval for an account from its recent history
+    ///
+    /// Falls back to `current_interval` when there is no history to go on.
+    pub fn suggest_refresh_interval(
+        app: &AppHandle,
+        account_id: &str,
+        current_interval: u64,
+    ) -> Result<u64, AppError> {
+        let now = Utc::now();
+        let query = HistoryQuery {
+            provider: None,
+            account_id: Some(account_id.to_string()),
+            start_date: Some(now - chrono::Duration::days(SUGGESTION_LOOKBACK_DAYS)),
+            end_date: Some(now),
+            limit: Some(usize::MAX),
+            offset: None,
+        };
+
+        let entries = HistoryService::query(app, &query)?;
+        Ok(Self::compute_suggested_interval(&entries, now).unwrap_or(current_interval))
+    }
+
+    /// Pick an interval from reset cadence, time to the next reset, and recent volatility
+    ///
+    /// Each limit proposes a ceiling: a sixtieth of its reset cadence, half the
+    /// time left until it resets, and a quarter of the time until it would hit
+    /// 100% at the recent rate. The tightest ceiling wins.
+    pub fn compute_suggested_interval(
+        entries: &[UsageHistoryEntry],
+        now: DateTime<Utc>,
+    ) -> Option<u64> {
+        let mut sorted: Vec<&UsageHistoryEntry> = entries.iter().collect();
+        sorted.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+
+        let mut samples: BTreeMap<&str, Vec<(DateTime<Utc>, &UsageLimitSnapshot)>> = BTreeMap::new();
+        for entry in &sorted {
+            for limit in &entry.limits {
+                samples
+                    .entry(limit.id.as_str())
+                    .or_default()
+                    .push((entry.timestamp, limit));
+            }
+        }
+
+        samples
+            .iter()
+            .filter_map(|(limit_id, points)| {
+                let (_, latest) = points.last()?;
+                let mut ceiling = Self::reset_cadence_secs(limit_id, points) / 60;
+
+                let until_reset = (latest.resets_at - now).num_seconds();
+                if until_reset > 0 {
+                    ceiling = ceiling.min(until_reset / 2);
+                }
+
+                let rate = Self::utilization_rate(points, now);
+                if rate > 0.0 {
+                    let headroom = (100.0 - latest.utilization).max(0.0);
+                    ceiling = ceiling.min((headroom / rate / 4.0) as i64);
+                }
+
+                Some(ceiling)
+            })
+            .min()
+            .map(|secs| {
+                (secs.max(0) as u64).clamp(MIN_SUGGESTED_INTERVAL_SECS, MAX_SUGGESTED_INTERVAL_SECS)
+            })
+    }
+
+    /// Typical gap between successive reset times, or the limit's nominal window
+    fn reset_cadence_secs(limit_id: &str, points: &[(DateTime<Utc>, &UsageLimitSnapshot)]) -> i64 {
+        let mut resets: Vec<&UsageLimitSnapshot> = Vec::new();
+        for (_, limit) in points {
+            match resets.last() {
+                Some(last) if !HistoryService::is_reset(last, limit) => {}
+                _ => resets.push(limit),
+            }
+        }
+
+        let mut gaps: Vec<i64> = resets
+            .windows(2)
+            .map(|pair| (pair[1].resets_at - pair[0].resets_at).num_seconds())
+            .filter(|gap| *gap > 0)
+            .collect();
+
+        if gaps.is_empty() {
+            return if limit_id.starts_with("five_hour") {
+                5 * 3600
+            } else {
+                7 * 24 * 3600
+            };
+        }
+
+        gaps.sort_unstable();
+        gaps[gaps.len() / 2]
+    }
+
+    /// Utilization change per second over the recent window, ignoring resets
+    fn utilization_rate(points: &[(DateTime<Utc>, &UsageLimitSnapshot)], now: DateTime<Utc>) -> f64 {
+        let window_start = now - chrono::Duration::seconds(VOLATILITY_WINDOW_SECS);
+        let recent: Vec<&(DateTime<Utc>, &UsageLimitSnapshot)> =
+            points.iter().filter(|(ts, _)| *ts >= window_start).collect();
+
+        let (change, elapsed) = recent
+            .windows(2)
+            .filter(|pair| !HistoryService::is_reset(pair[0].1, pair[1].1))
+            .fold((0.0, 0i64), |(change, elapsed), pair| {
+                (
+                    change + (pair[1].1.utilization - pair[0].1.utilization).abs(),
+                    elapsed + (pair[1].0 - pair[0].0).num_seconds(),
+                )
+            });
+
+        if elapsed > 0 {
+            change / elapsed as f64
+        } else {
+            0.0
+        }
+    }
+
     // Legacy function kept for backward compatibility with force_refresh
     async fn fetch_and_emit(app: &AppHandle, state: &SchedulerState) {
         Self::fetch_all_accounts(app, state).await;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::key_value::MemoryStore;
+
+    fn history_entry(
+        timestamp: DateTime<Utc>,
+        limit_id: &str,
+        utilization: f64,
+        resets_at: DateTime<Utc>,
+    ) -> UsageHistoryEntry {
+        UsageHistoryEntry {
+            id: format!("{}-claude-acc-1", timestamp.timestamp_millis()),
+            provider: "claude".to_string(),
+            account_id: "acc-1".to_string(),
+            account_name: "Default".to_string(),
+            timestamp,
+            limits: vec![UsageLimitSnapshot {
+                id: limit_id.to_string(),
+                utilization,
+                resets_at,
+                min_utilization: None,
+                max_utilization: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn wake_after_sleep_fetches_exactly_once() {
+        let interval = Duration::from_secs(300);
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let mut detector =
+            SleepDetector::new(start, Duration::from_secs(SLEEP_DETECTION_THRESHOLD_SECS));
+
+        // Mimic the loop: 1s ticks, with the machine asleep for two hours (24 intervals)
+        // after the 10th; `since_fetch` restarts whenever a tick fetches
+        let mut now = start;
+        let mut since_fetch = Duration::from_secs(100);
+        let mut wakes = 0;
+        let mut fetches = 0;
+        for second in 1..=20 {
+            let step = if second == 11 { 7200 } else { 1 };
+            now += Duration::from_secs(step);
+            // `Instant` doesn't advance while suspended on Linux and macOS
+            since_fetch += Duration::from_secs(1);
+
+            match SchedulerService::next_tick(&mut detector, now, since_fetch, interval) {
+                Tick::Idle => continue,
+                Tick::Fetch => fetches += 1,
+                Tick::Wake(event) => {
+                    assert_eq!(event.slept_secs, 7200);
+                    wakes += 1;
+                }
+            }
+            since_fetch = Duration::ZERO;
+        }
+
+        assert_eq!((wakes, fetches), (1, 0));
+
+        // Even when an interval is due on the wake tick, only the wake fetch happens
+        let mut detector = SleepDetector::new(start, Duration::from_secs(30));
+        let tick = SchedulerService::next_tick(
+            &mut detector,
+            start + Duration::from_secs(7200),
+            Duration::from_secs(7200),
+            interval,
+        );
+        assert!(matches!(tick, Tick::Wake(_)));
+        let tick = SchedulerService::next_tick(
+            &mut detector,
+            start + Duration::from_secs(7201),
+            Duration::from_secs(1),
+            interval,
+        );
+        assert_eq!(tick, Tick::Idle);
+    }
+
+    #[test]
+    fn slow_moving_limit_suggests_longer_interval() {
+        let now = Utc::now();
+        let resets_at = now + chrono::Duration::days(5);
+        let entries: Vec<UsageHistoryEntry> = (0..12)
+            .map(|i| history_entry(now - chrono::Duration::minutes(10 * i), "seven_day", 10.0, resets_at))
+            .collect();
+
+        let suggested = SchedulerService::compute_suggested_interval(&entries, now).unwrap();
+        assert_eq!(suggested, MAX_SUGGESTED_INTERVAL_SECS);
+        assert!(suggested > 300);
+    }
+
+    #[test]
+    fn near_reset_suggests_shorter_interval() {
+        let now = Utc::now();
+        let resets_at = now + chrono::Duration::minutes(3);
+        let entries = vec![
+            history_entry(now - chrono::Duration::minutes(10), "five_hour", 40.0, resets_at),
+            history_entry(now - chrono::Duration::minutes(5), "five_hour", 40.0, resets_at),
+        ];
+
+        let suggested = SchedulerService::compute_suggested_interval(&entries, now).unwrap();
+        assert_eq!(suggested, 90);
+        assert!(suggested < 300);
+    }
+
+    #[test]
+    fn no_history_gives_no_suggestion() {
+        assert!(SchedulerService::compute_suggested_interval(&[], Utc::now()).is_none());
+    }
+
+    #[tokio::test]
+    async fn shutdown_waits_for_fetch_then_saves() {
+        let state = Arc::new(SchedulerState::new());
+        state.try_start(false).unwrap();
+        let stores = vec![Arc::new(MemoryStore::default()), Arc::new(MemoryStore::default())];
+
+        // Simulate a fetch that is still writing when shutdown begins
+        let fetch_guard = state.fetch_lock.lock().await;
+        let shutdown = SchedulerService::shutdown_with(&state, &stores);
+        tokio::pin!(shutdown);
+        let early = tokio::time::timeout(Duration::from_millis(20), shutdown.as_mut()).await;
+        assert!(early.is_err());
+        assert_eq!(stores[0].persist_count.get(), 0);
+
+        drop(fetch_guard);
+        assert_eq!(shutdown.await, 2);
+        assert!(!state.is_running());
+        assert!(stores.iter().all(|s| s.persist_count.get() == 1));
+    }
+
+    #[test]
+    fn reconcile_corrects_interval_drift() {
+        let state = SchedulerState::new();
+        let mut settings = AppSettings {
+            refresh_mode: "fixed".to_string(),
+            ..AppSettings::default()
+        };
+        state.try_start(true).unwrap();
+        state.set_interval(42);
+
+        let report = SchedulerService::reconcile_with(&state, &settings);
+        assert_eq!(state.get_interval(), 300);
+        assert_eq!(report.interval_secs, 300);
+        assert!(report.running);
+        assert_eq!(report.fixed, vec!["interval 42s -> 300s".to_string()]);
+
+        // Already in agreement: nothing to fix
+        assert!(SchedulerService::reconcile_with(&state, &settings)
+            .fixed
+            .is_empty());
+
+        // Adaptive intervals are left alone, and so is a scheduler the user started
+        // even though it doesn't start with the app
+        settings.refresh_mode = "adaptive".to_string();
+        settings.autostart_scheduler = false;
+        state.set_interval(60);
+        let report = SchedulerService::reconcile_with(&state, &settings);
+        assert_eq!(state.get_interval(), 60);
+        assert!(report.running);
+        assert!(report.fixed.is_empty());
+    }
+
+    #[test]
+    fn start_is_idempotent() {
+        let state = SchedulerState::new();
+
+        let first = state.try_start(true);
+        assert!(first.is_some());
+        assert!(state.try_start(true).is_none());
+        assert!(state.is_current_run(first.unwrap()));
+    }
+
+    #[test]
+    fn start_schedules_one_immediate_fetch() {
+        let state = SchedulerState::new();
+        state.try_start(true).unwrap();
+
+        assert!(state.take_warmup());
+        assert!(!state.take_warmup());
+
+        // A duplicate start must not re-arm the warm-up
+        assert!(state.try_start(true).is_none());
+        assert!(!state.take_warmup());
+    }
+
+    #[test]
+    fn start_without_immediate_fetch() {
+        let state = SchedulerState::new();
+        state.try_start(false).unwrap();
+        assert!(!state.take_warmup());
+    }
+
+    #[test]
+    fn stagger_delays_stay_within_window_and_are_distinct() {
+        let mut rng = rand::thread_rng();
+        let delays = SchedulerService::stagger_delays(10, 2000, 300, &mut rng);
+
+        assert_eq!(delays.len(), 10);
+        assert!(delays.iter().all(|d| *d < Duration::from_millis(2000)));
+
+        let mut unique = delays.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), 10);
+    }
+
+    #[test]
+    fn stagger_window_capped_by_interval() {
+        let mut rng = rand::thread_rng();
+        // A 60s window on a 10s interval must stay within half the interval
+        let delays = SchedulerService::stagger_delays(4, 60_000, 10, &mut rng);
+        assert!(delays.iter().all(|d| *d < Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn stagger_disabled_for_single_account() {
+        let mut rng = rand::thread_rng();
+        assert_eq!(
+            SchedulerService::stagger_delays(1, 2000, 300, &mut rng),
+            vec![Duration::ZERO]
+        );
+        assert_eq!(
+            SchedulerService::stagger_delays(3, 0, 300, &mut rng),
+            vec![Duration::ZERO; 3]
+        );
+    }
+
+    #[tokio::test]
+    async fn retry_clears_only_recovered_accounts() {
+        let state = SchedulerState::new();
+        state.set_account_failed("acc-1", true).await;
+        state.set_account_failed("acc-2", true).await;
+        state.increment_account_error_count("acc-1").await;
+        state.set_account_paused("acc-1", true).await;
+
+        let results = state
+            .retry_failed(|account_id| async move {
+                if account_id == "acc-1" {
+                    Ok(())
+                } else {
+                    Err("Session expired".to_string())
+                }
+            })
+            .await;
+
+        assert_eq!(results.len(), 2);
+        assert!(results["acc-1"].success);
+        assert!(!results["acc-2"].success);
+        assert_eq!(results["acc-2"].error.as_deref(), Some("Session expired"));
+
+        assert_eq!(state.get_failed_accounts().await, vec!["acc-2".to_string()]);
+        assert_eq!(state.get_account_error_count("acc-1").await, 0);
+        assert!(!state.is_account_paused("acc-1").await);
+    }
+
+    /// Attempts from a pattern like "ooxo" (o = success, x = failure), oldest first
+    fn attempts(pattern: &str) -> VecDeque<FetchAttempt> {
+        pattern
+            .chars()
+            .map(|c| FetchAttempt {
+                at: Utc::now(),
+                error: (c == 'x').then(|| "Session expired".to_string()),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn health_classification_thresholds() {
+        assert_eq!(classify_health(&attempts("")), HealthStatus::Unknown);
+        assert_eq!(
+            classify_health(&attempts("oooooooooo")),
+            HealthStatus::Healthy
+        );
+        // 9/10 is exactly the healthy threshold
+        assert_eq!(
+            classify_health(&attempts("ooooxooooo")),
+            HealthStatus::Healthy
+        );
+        assert_eq!(
+            classify_health(&attempts("ooxoooxooo")),
+            HealthStatus::Degraded
+        );
+        // 5/10 is exactly the failing threshold, so still degraded
+        assert_eq!(
+            classify_health(&attempts("xoxoxoxoxo")),
+            HealthStatus::Degraded
+        );
+        assert_eq!(
+            classify_health(&attempts("xxoxxoxxoo")),
+            HealthStatus::Failing
+        );
+    }
+
+    #[test]
+    fn recent_failure_streak_marks_account_failing() {
+        // 17/20 would be degraded, but the last three attempts all failed
+        assert_eq!(
+            classify_health(&attempts("oooooooooooooooooxxx")),
+            HealthStatus::Failing
+        );
+        // Two failures in a row aren't enough on their own
+        assert_eq!(
+            classify_health(&attempts("ooooooooooooooooooxx")),
+            HealthStatus::Healthy
+        );
+    }
+
+    #[tokio::test]
+    async fn account_health_keeps_only_recent_attempts() {
+        let state = SchedulerState::new();
+        for _ in 0..HEALTH_WINDOW {
+            state
+                .record_fetch_attempt("acc-1", Some("Session expired".to_string()))
+                .await;
+        }
+        for _ in 0..HEALTH_WINDOW {
+            state.record_fetch_attempt("acc-1", None).await;
+        }
+
+        let health = state.account_health("acc-1").await;
+        assert_eq!(health.attempts, HEALTH_WINDOW);
+        assert_eq!(health.success_rate, Some(1.0));
+        assert_eq!(health.status, HealthStatus::Healthy);
+        // The failures aged out of the window, so there is no last error to report
+        assert_eq!(health.last_error, None);
+
+        let unknown = state.account_health("acc-2").await;
+        assert_eq!(unknown.status, HealthStatus::Unknown);
+        assert_eq!(unknown.success_rate, None);
+
+        // A deleted account's attempts go with it
+        state.forget_account("acc-1").await;
+        assert_eq!(state.account_health("acc-1").await.attempts, 0);
+    }
+
+    #[test]
+    fn restart_retires_previous_loop() {
+        let state = SchedulerState::new();
+        let first = state.try_start(false).unwrap();
+        state.set_running(false);
+        let second = state.try_start(false).unwrap();
+
+        assert!(!state.is_current_run(first));
+        assert!(state.is_current_run(second));
+    }
+
+    #[tokio::test]
+    async fn missing_credentials_do_not_count_toward_pause() {
+        let state = SchedulerState::new();
+        let missing =
+            AppError::Provider(ProviderError::MissingCredentials("session_key".to_string()));
+        let kind = FetchErrorKind::classify(&missing);
+        assert_eq!(
+            kind,
+            FetchErrorKind::MissingCredentials("session_key".to_string())
+        );
+
+        for _ in 0..MAX_SESSION_ERRORS + 1 {
+            assert_eq!(state.record_fetch_error("acc-1", &kind).await, None);
+        }
+        assert_eq!(state.get_account_error_count("acc-1").await, 0);
+        assert!(!state.is_account_paused("acc-1").await);
+
+        // Invalid credentials are distinct from missing ones but don't pause either
+        let invalid = AppError::Provider(ProviderError::InvalidCredentials("bad".to_string()));
+        assert_eq!(
+            FetchErrorKind::classify(&invalid),
+            FetchErrorKind::InvalidCredentials
+        );
+
+        // A malformed body is neither a credentials nor a session problem, whatever it contains
+        let parse = AppError::Provider(ProviderError::Parse {
+            message: "body ends early".to_string(),
+            snippet: r#"{"error": "401 expired"#.to_string(),
+        });
+        assert_eq!(FetchErrorKind::classify(&parse), FetchErrorKind::Parse);
+        assert_eq!(
+            state
+                .record_fetch_error("acc-1", &FetchErrorKind::Parse)
+                .await,
+            None
+        );
+
+        // Session errors still pause once the threshold is reached
+        let session = FetchErrorKind::classify(&AppError::Provider(ProviderError::SessionExpired));
+        for expected in 1..MAX_SESSION_ERRORS {
+            assert_eq!(
+                state.record_fetch_error("acc-1", &session).await,
+                Some((expected, false))
+            );
+        }
+        assert_eq!(
+            state.record_fetch_error("acc-1", &session).await,
+            Some((MAX_SESSION_ERRORS, true))
+        );
+        assert!(state.is_account_paused("acc-1").await);
+    }
+
+    #[test]
+    fn next_fetch_follows_the_loop_timer_and_offline_backoff() {
+        let state = SchedulerState::new();
+        let start = 1_800_000_000_000;
+        state.cycle_started.store(start, Ordering::SeqCst);
+        assert_eq!(state.next_fetch_at(start), 0);
+
+        state.set_running(true);
+        assert_eq!(state.next_fetch_at(start), start / 1000 + 300);
+
+        // Each failed probe doubles the wait
+        state.connectivity.mark_offline(Utc::now());
+        assert_eq!(state.next_fetch_at(start), start / 1000 + 600);
+        state.connectivity.mark_offline(Utc::now());
+        assert_eq!(state.next_fetch_at(start + 1000), start / 1000 + 1200);
+
+        // Overdue fetches happen on the next tick
+        let later = start + 3_600_000;
+        assert_eq!(state.next_fetch_at(later), later / 1000);
+
+        state.connectivity.mark_online(Utc::now());
+        assert_eq!(state.next_fetch_at(start), start / 1000 + 300);
+    }
+
+    #[tokio::test]
+    async fn offline_failures_do_not_count_toward_pause() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let reachable = format!("http://{}/", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 1024];
+            let _ = socket.read(&mut request).await;
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+                .await
+                .unwrap();
+        });
+        let closed = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let unreachable = format!("http://{}/", closed.local_addr().unwrap());
+        drop(closed);
+
+        let network_error =
+            AppError::Provider(ProviderError::HttpError("connection reset".to_string()));
+        assert_eq!(
+            SchedulerService::classify_fetch_error(&network_error, None).await,
+            FetchErrorKind::Other
+        );
+        assert_eq!(
+            SchedulerService::classify_fetch_error(&network_error, Some(&reachable)).await,
+            FetchErrorKind::Other
+        );
+        server.await.unwrap();
+        assert_eq!(
+            SchedulerService::classify_fetch_error(&network_error, Some(&unreachable)).await,
+            FetchErrorKind::Offline
+        );
+        // Only transient failures are put down to the network
+        let expired = AppError::Provider(ProviderError::SessionExpired);
+        assert_eq!(
+            SchedulerService::classify_fetch_error(&expired, Some(&unreachable)).await,
+            FetchErrorKind::Session
+        );
+
+        let state = SchedulerState::new();
+        for _ in 0..MAX_SESSION_ERRORS + 1 {
+            assert_eq!(
+                state
+                    .record_fetch_error("acc-1", &FetchErrorKind::Offline)
+                    .await,
+                None
+            );
+        }
+        assert_eq!(state.get_account_error_count("acc-1").await, 0);
+        assert!(!state.is_account_paused("acc-1").await);
+    }
+
+    #[tokio::test]
+    async fn simulated_session_errors_pause_at_threshold() {
+        let state = SchedulerState::new();
+
+        assert_eq!(
+            state
+                .simulate_session_errors("acc-1", MAX_SESSION_ERRORS - 1)
+                .await,
+            (MAX_SESSION_ERRORS - 1, false)
+        );
+        assert!(!state.any_account_paused().await);
+
+        assert_eq!(
+            state.simulate_session_errors("acc-1", 1).await,
+            (MAX_SESSION_ERRORS, true)
+        );
+        assert!(state.is_account_paused("acc-1").await);
+        assert!(!state.is_account_paused("acc-2").await);
+
+        // Resuming clears the simulated errors like real ones
+        state.reset_all_account_states().await;
+        assert_eq!(state.simulate_session_errors("acc-1", 0).await, (0, false));
+    }
+
+    #[test]
+    fn ema_weights_new_value_by_alpha() {
+        assert_eq!(ema(40.0, 60.0, 0.5), 50.0);
+        assert_eq!(ema(40.0, 60.0, 1.0), 60.0);
+        // Out-of-range alphas fall back to sensible bounds instead of diverging
+        assert_eq!(ema(40.0, 60.0, 2.0), 60.0);
+        assert_eq!(ema(40.0, 60.0, 0.0), 60.0);
+    }
+
+    #[tokio::test]
+    async fn smoothed_usage_carries_across_snapshots() {
+        let state = SchedulerState::new();
+        let resets_at = Utc::now() + chrono::Duration::hours(3);
+        let snapshot = |utilization: f64, resets_at: DateTime<Utc>| UsageData {
+            provider: "claude".to_string(),
+            account_id: "acc-1".to_string(),
+            account_name: "Default".to_string(),
+            timestamp: Utc::now(),
+            limits: vec![crate::models::UsageLimit {
+                id: "five_hour".to_string(),
+                label: "Session".to_string(),
+                utilization,
+                resets_at,
+                category: None,
+                kind: crate::models::LimitKind::FiveHour,
+                total: None,
+            }],
+            raw: None,
+            rate_limit: None,
+        };
+
+        let mut smoothed = Vec::new();
+        for utilization in [20.0, 60.0, 20.0, 60.0] {
+            let values = state
+                .smooth_usage("acc-1", &snapshot(utilization, resets_at), 0.5)
+                .await;
+            smoothed.push(values["five_hour"]);
+        }
+        // First snapshot seeds the average; later ones move halfway toward the raw value
+        assert_eq!(smoothed, vec![20.0, 40.0, 30.0, 45.0]);
+
+        // A reset time that drifts by a few seconds is still the same window
+        let drifted = state
+            .smooth_usage(
+                "acc-1",
+                &snapshot(65.0, resets_at + chrono::Duration::seconds(2)),
+                0.5,
+            )
+            .await;
+        assert_eq!(drifted["five_hour"], 55.0);
+
+        // A new reset window starts over from the raw value
+        let after_reset = state
+            .smooth_usage(
+                "acc-1",
+                &snapshot(5.0, resets_at + chrono::Duration::hours(5)),
+                0.5,
+            )
+            .await;
+        assert_eq!(after_reset["five_hour"], 5.0);
+    }
+}
@@ -1,42 +1,969 @@
 use crate::error::AppError;
-use crate::models::AppSettings;
+use crate::models::{
+    AppSettings, IssueSeverity, RefreshIntervalBounds, SettingsIssue, TeamConfigReport,
+};
+use crate::services::crypto::{self, KeyMaterial, ENCRYPTED_PREFIX};
+use crate::services::key_value::KeyValueStore;
+use crate::services::scheduler::MIN_REFRESH_INTERVAL_SECS;
+use crate::services::{DataDirExt, DisplayTimezone};
+use chrono::{NaiveTime, Weekday};
+use serde::de::DeserializeOwned;
+use serde_json::Value as JsonValue;
+use std::ops::RangeInclusive;
 use tauri::AppHandle;
-use tauri_plugin_store::StoreExt;
 
-const STORE_FILE: &str = "settings.json";
+pub(crate) const STORE_FILE: &str = "settings.json";
 const SETTINGS_KEY: &str = "app_settings";
+const VERSION_KEY: &str = "settings_version";
+const CURRENT_VERSION: u32 = 3; // v3: per-weekday DND schedule
+
+/// Longest refresh interval accepted (one day)
+const MAX_REFRESH_INTERVAL_SECS: u32 = 86_400;
+
+/// Most responses cached per account; each is a full provider response
+const MAX_CACHED_RESPONSES_PER_ACCOUNT: usize = 100;
+
+/// Longest reset-warning lead time a team config may set (the 7-day window)
+const MAX_RESET_WARNING_LEAD_MINUTES: u32 = 7 * 24 * 60;
 
 pub struct SettingsService;
 
 impl SettingsService {
     /// Get app settings
     pub fn get(app: &AppHandle) -> Result<AppSettings, AppError> {
-        let store = app
-            .store(STORE_FILE)
-            .map_err(|e| AppError::Store(e.to_string()))?;
+        let store = app.data_store(STORE_FILE)?;
 
-        let value = store.get(SETTINGS_KEY);
+        Self::ensure_migrated(&*store)?;
+        Self::load(&*store)
+    }
 
-        match value {
-            Some(v) => {
-                let settings: AppSettings = serde_json::from_value(v.clone())?;
+    /// Read settings from a store (decrypting sensitive fields), falling back to defaults
+    pub(crate) fn load(store: &impl KeyValueStore) -> Result<AppSettings, AppError> {
+        match store.get_value(SETTINGS_KEY) {
+            Some(mut v) => {
+                Self::decrypt_sensitive(&mut v);
+                let settings: AppSettings = serde_json::from_value(v)?;
                 Ok(settings)
             }
             None => Ok(AppSettings::default()),
         }
     }
 
+    /// The WebSocket server's token, generating and saving one if none is set
+    ///
+    /// The server never runs without a token: any page open in a browser can
+    /// reach localhost, so an open socket would hand usage to any website.
+    pub fn ensure_websocket_token(app: &AppHandle) -> Result<String, AppError> {
+        let mut settings = Self::get(app)?;
+        if let Some(token) = settings
+            .websocket_token
+            .as_deref()
+            .map(str::trim)
+            .filter(|token| !token.is_empty())
+        {
+            return Ok(token.to_string());
+        }
+
+        let token = crypto::generate_token();
+        settings.websocket_token = Some(token.clone());
+        Self::save(app, &settings)?;
+        log::info!("Generated a WebSocket server token");
+        Ok(token)
+    }
+
     /// Save app settings
     pub fn save(app: &AppHandle, settings: &AppSettings) -> Result<(), AppError> {
-        let store = app
-            .store(STORE_FILE)
-            .map_err(|e| AppError::Store(e.to_string()))?;
+        let store = app.data_store(STORE_FILE)?;
 
-        let value = serde_json::to_value(settings)?;
-        store.set(SETTINGS_KEY.to_string(), value);
-        store.save().map_err(|e| AppError::Store(e.to_string()))?;
+        Self::store_settings(&*store, settings)?;
 
         log::info!("Saved app settings");
         Ok(())
     }
+
+    /// Turn notifications for a single limit on or off
+    pub fn set_limit_notifications_enabled(
+        app: &AppHandle,
+        limit_id: &str,
+        enabled: bool,
+    ) -> Result<(), AppError> {
+        let mut settings = Self::get(app)?;
+        let disabled = &mut settings.notifications.disabled_limit_ids;
+
+        let changed = if enabled {
+            disabled.remove(limit_id)
+        } else {
+            disabled.insert(limit_id.to_string())
+        };
+
+        if changed {
+            Self::save(app, &settings)?;
+        }
+        Ok(())
+    }
+
+    /// Replace a limit's absolute thresholds (units left); an empty list removes them
+    pub fn set_absolute_thresholds(
+        app: &AppHandle,
+        limit_id: &str,
+        units_left: Vec<f64>,
+    ) -> Result<(), AppError> {
+        if let Some(bad) = units_left.iter().find(|v| !v.is_finite() || **v < 0.0) {
+            return Err(AppError::InvalidInput(format!(
+                "absolute thresholds must be non-negative numbers, got {}",
+                bad
+            )));
+        }
+
+        let mut units_left = units_left;
+        units_left.sort_by(|a, b| b.total_cmp(a));
+        units_left.dedup();
+
+        let mut settings = Self::get(app)?;
+        let thresholds = &mut settings.notifications.absolute_thresholds;
+        if units_left.is_empty() {
+            thresholds.remove(limit_id);
+        } else {
+            thresholds.insert(limit_id.to_string(), units_left);
+        }
+        Self::save(app, &settings)
+    }
+
+    /// Set the zone human-readable times are shown in, storing its canonical name
+    pub fn set_display_timezone(app: &AppHandle, zone: &str) -> Result<DisplayTimezone, AppError> {
+        let zone = DisplayTimezone::parse(zone)?;
+
+        let mut settings = Self::get(app)?;
+        settings.display_timezone = zone.name();
+        Self::save(app, &settings)?;
+        Ok(zone)
+    }
+
+    /// Replace the pinned limit order (limit IDs, first shown first)
+    ///
+    /// Duplicates and blank IDs are dropped, keeping the first occurrence.
+    pub fn set_limit_order(app: &AppHandle, limit_ids: Vec<String>) -> Result<(), AppError> {
+        let mut order: Vec<String> = Vec::with_capacity(limit_ids.len());
+        for id in limit_ids {
+            let id = id.trim();
+            if !id.is_empty() && !order.iter().any(|existing| existing == id) {
+                order.push(id.to_string());
+            }
+        }
+
+        let mut settings = Self::get(app)?;
+        if settings.limit_order != order {
+            settings.limit_order = order;
+            Self::save(app, &settings)?;
+        }
+        Ok(())
+    }
+
+    /// Set (or with None, clear) the primary account
+    pub fn set_primary_account(
+        app: &AppHandle,
+        account_id: Option<String>,
+    ) -> Result<(), AppError> {
+        let mut settings = Self::get(app)?;
+
+        if settings.primary_account_id != account_id {
+            settings.primary_account_id = account_id;
+            Self::save(app, &settings)?;
+        }
+        Ok(())
+    }
+
+    /// Set (or with None, remove) an account's monthly budget
+    pub fn set_monthly_budget(
+        app: &AppHandle,
+        account_id: &str,
+        budget: Option<f64>,
+    ) -> Result<(), AppError> {
+        let mut settings = Self::get(app)?;
+
+        let changed = match budget {
+            Some(budget) => {
+                settings
+                    .monthly_budgets
+                    .insert(account_id.to_string(), budget)
+                    != Some(budget)
+            }
+            None => settings.monthly_budgets.remove(account_id).is_some(),
+        };
+
+        if changed {
+            Self::save(app, &settings)?;
+        }
+        Ok(())
+    }
+
+    /// Drop the settings that refer to a deleted account: the primary account and its monthly budget
+    ///
+    /// Returns whether anything was cleared.
+    pub fn forget_account(app: &AppHandle, account_id: &str) -> Result<bool, AppError> {
+        let store = app.data_store(STORE_FILE)?;
+
+        Self::ensure_migrated(&*store)?;
+        Self::forget_account_in_store(&*store, account_id)
+    }
+
+    fn forget_account_in_store(
+        store: &impl KeyValueStore,
+        account_id: &str,
+    ) -> Result<bool, AppError> {
+        let mut settings = Self::load(store)?;
+
+        let was_primary = settings.primary_account_id.as_deref() == Some(account_id);
+        if was_primary {
+            settings.primary_account_id = None;
+            log::info!("Cleared primary account {}", account_id);
+        }
+        let had_budget = settings.monthly_budgets.remove(account_id).is_some();
+
+        if !was_primary && !had_budget {
+            return Ok(false);
+        }
+        Self::store_settings(store, &settings)?;
+        Ok(true)
+    }
+
+    /// Write settings to a store with sensitive fields encrypted
+    fn store_settings(store: &impl KeyValueStore, settings: &AppSettings) -> Result<(), AppError> {
+        let mut value = serde_json::to_value(settings)?;
+        Self::encrypt_sensitive(&mut value);
+
+        store.set_value(SETTINGS_KEY, value);
+        store.set_value(VERSION_KEY, serde_json::to_value(CURRENT_VERSION)?);
+        store.persist()
+    }
+
+    /// Bring settings written by older versions up to date
+    ///
+    /// v2 encrypts sensitive fields (v1 stored everything plain); v3 adds an empty
+    /// weekday DND schedule, so every day keeps using the existing global window.
+    fn ensure_migrated(store: &impl KeyValueStore) -> Result<(), AppError> {
+        let version: u32 = store
+            .get_value(VERSION_KEY)
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or(1);
+
+        if version >= CURRENT_VERSION {
+            return Ok(());
+        }
+
+        if let Some(mut value) = store.get_value(SETTINGS_KEY) {
+            Self::migrate_value(&mut value, version);
+            store.set_value(SETTINGS_KEY, value);
+        }
+
+        store.set_value(VERSION_KEY, serde_json::to_value(CURRENT_VERSION)?);
+        store.persist()?;
+
+        log::info!("Migrated settings from v{} to v{}", version, CURRENT_VERSION);
+        Ok(())
+    }
+
+    fn migrate_value(value: &mut JsonValue, from_version: u32) {
+        if from_version < 2 {
+            Self::encrypt_sensitive(value);
+        }
+
+        if from_version < 3 {
+            if let Some(notifications) = value
+                .get_mut("notifications")
+                .and_then(JsonValue::as_object_mut)
+            {
+                notifications
+                    .entry("dndSchedule")
+                    .or_insert_with(|| serde_json::json!({}));
+            }
+        }
+    }
+
+    /// Encrypt sensitive string fields in serialized settings, skipping ones already encrypted
+    fn encrypt_sensitive(value: &mut JsonValue) {
+        for field in AppSettings::SENSITIVE_FIELDS {
+            let Some(JsonValue::String(plain)) = value.pointer_mut(field) else {
+                continue;
+            };
+            if plain.starts_with(ENCRYPTED_PREFIX) {
+                continue;
+            }
+
+            match crypto::encrypt(plain) {
+                Ok(encrypted) => *plain = format!("{}{}", ENCRYPTED_PREFIX, encrypted),
+                Err(e) => log::error!("Failed to encrypt setting {}: {}", field, e),
+            }
+        }
+    }
+
+    /// Decrypt sensitive string fields in serialized settings
+    fn decrypt_sensitive(value: &mut JsonValue) {
+        for field in AppSettings::SENSITIVE_FIELDS {
+            let Some(JsonValue::String(stored)) = value.pointer_mut(field) else {
+                continue;
+            };
+            let Some(encrypted) = stored.strip_prefix(ENCRYPTED_PREFIX) else {
+                continue;
+            };
+
+            match crypto::decrypt(encrypted) {
+                Ok(decrypted) => *stored = decrypted,
+                Err(e) => log::error!("Failed to decrypt setting {}: {}", field, e),
+            }
+        }
+    }
+
+    /// Re-encrypt sensitive settings written under older key material, returning how many moved
+    pub fn reencrypt_with_key_material(
+        app: &AppHandle,
+        old: &KeyMaterial,
+    ) -> Result<usize, AppError> {
+        let store = app.data_store(STORE_FILE)?;
+
+        Self::reencrypt_store(&*store, old)
+    }
+
+    /// Rewrite only the fields that decrypt with `old`; anything else is left as stored
+    fn reencrypt_store(store: &impl KeyValueStore, old: &KeyMaterial) -> Result<usize, AppError> {
+        let Some(mut value) = store.get_value(SETTINGS_KEY) else {
+            return Ok(0);
+        };
+
+        let mut recovered = 0;
+        for field in AppSettings::SENSITIVE_FIELDS {
+            let Some(JsonValue::String(stored)) = value.pointer_mut(field) else {
+                continue;
+            };
+            let Some(plain) = stored
+                .strip_prefix(ENCRYPTED_PREFIX)
+                .and_then(|encrypted| crypto::decrypt_with(old, encrypted).ok())
+            else {
+                continue;
+            };
+
+            match crypto::encrypt(&plain) {
+                Ok(encrypted) => {
+                    *stored = format!("{}{}", ENCRYPTED_PREFIX, encrypted);
+                    recovered += 1;
+                }
+                Err(e) => log::error!("Failed to re-encrypt setting {}: {}", field, e),
+            }
+        }
+
+        if recovered > 0 {
+            store.set_value(SETTINGS_KEY, value);
+            store.persist()?;
+        }
+        Ok(recovered)
+    }
+
+    /// Check a settings object for values that would fail silently later
+    ///
+    /// Covers DND time formats, percentage thresholds, the refresh interval and
+    /// the other fields that are only parsed or range-checked when used.
+    pub fn validate(settings: &AppSettings) -> Vec<SettingsIssue> {
+        let mut issues = Vec::new();
+        let mut error = |field: &str, message: String| {
+            issues.push(SettingsIssue {
+                field: field.to_string(),
+                severity: IssueSeverity::Error,
+                message,
+            })
+        };
+
+        let interval_range = Self::refresh_interval_range(settings);
+        if !interval_range.contains(&settings.refresh_interval) {
+            error(
+                "refreshInterval",
+                format!(
+                    "Refresh interval must be between {} and {} seconds, got {}",
+                    interval_range.start(),
+                    interval_range.end(),
+                    settings.refresh_interval
+                ),
+            );
+        }
+
+        let notifications = &settings.notifications;
+        let dnd_times = [
+            (
+                "notifications.dndStartTime".to_string(),
+                notifications.dnd_start_time.as_deref(),
+            ),
+            (
+                "notifications.dndEndTime".to_string(),
+                notifications.dnd_end_time.as_deref(),
+            ),
+        ];
+        let schedule_times = [
+            (Weekday::Mon, "mon"),
+            (Weekday::Tue, "tue"),
+            (Weekday::Wed, "wed"),
+            (Weekday::Thu, "thu"),
+            (Weekday::Fri, "fri"),
+            (Weekday::Sat, "sat"),
+            (Weekday::Sun, "sun"),
+        ]
+        .into_iter()
+        .filter_map(|(day, key)| Some((key, notifications.dnd_schedule.for_day(day)?)))
+        .flat_map(|(key, window)| {
+            [
+                (
+                    format!("notifications.dndSchedule.{}.start", key),
+                    Some(window.start.as_str()),
+                ),
+                (
+                    format!("notifications.dndSchedule.{}.end", key),
+                    Some(window.end.as_str()),
+                ),
+            ]
+        });
+        for (field, time) in dnd_times.into_iter().chain(schedule_times) {
+            if let Some(time) = time {
+                if NaiveTime::parse_from_str(time, "%H:%M").is_err() {
+                    error(
+                        &field,
+                        format!(
+                            "\"{}\" is not a time in HH:MM format (e.g. \"22:00\")",
+                            time
+                        ),
+                    );
+                }
+            }
+        }
+
+        for (i, threshold) in notifications.thresholds.iter().enumerate() {
+            if !(1..=100).contains(threshold) {
+                error(
+                    &format!("notifications.thresholds[{}]", i),
+                    format!("Threshold must be between 1 and 100%, got {}", threshold),
+                );
+            }
+        }
+        let percents = [
+            (
+                "notifications.criticalThreshold",
+                notifications.critical_threshold,
+            ),
+            (
+                "notifications.resetWarningMinPercent",
+                notifications.reset_warning_min_percent,
+            ),
+            ("budgetWarningPercent", settings.budget_warning_percent),
+        ];
+        for (field, percent) in percents {
+            if percent > 100 {
+                error(
+                    field,
+                    format!("Must be a percentage up to 100, got {}", percent),
+                );
+            }
+        }
+        for (limit_id, over) in &notifications.reset_warning_overrides {
+            if let Some(percent) = over.min_percent.filter(|p| *p > 100) {
+                error(
+                    &format!(
+                        "notifications.resetWarningOverrides.{}.minPercent",
+                        limit_id
+                    ),
+                    format!("Must be a percentage up to 100, got {}", percent),
+                );
+            }
+        }
+
+        if !settings.cap_threshold.is_finite() || settings.cap_threshold <= 0.0 {
+            error(
+                "capThreshold",
+                format!(
+                    "Cap threshold must be above 0%, got {}",
+                    settings.cap_threshold
+                ),
+            );
+        }
+        if let Some(alpha) = settings.smoothing_factor {
+            if alpha.is_nan() || alpha <= 0.0 || alpha > 1.0 {
+                error(
+                    "smoothingFactor",
+                    format!("Smoothing factor must be in (0, 1], got {}", alpha),
+                );
+            }
+        }
+        if settings.cached_responses_per_account > MAX_CACHED_RESPONSES_PER_ACCOUNT {
+            error(
+                "cachedResponsesPerAccount",
+                format!(
+                    "At most {} responses can be cached per account, got {}",
+                    MAX_CACHED_RESPONSES_PER_ACCOUNT, settings.cached_responses_per_account
+                ),
+            );
+        }
+        if let Err(e) = DisplayTimezone::parse(&settings.display_timezone) {
+            error("displayTimezone", e.to_string());
+        }
+        if settings.api_server_enabled
+            && settings.websocket_enabled
+            && settings.api_server_port == settings.websocket_port
+        {
+            error(
+                "websocketPort",
+                format!(
+                    "The API server and WebSocket server can't share port {}",
+                    settings.api_server_port
+                ),
+            );
+        }
+        if let Some(email) = notifications.email.as_ref().filter(|e| e.enabled) {
+            if email.smtp_host.trim().is_empty() {
+                error(
+                    "notifications.email.smtpHost",
+                    "SMTP host is required".to_string(),
+                );
+            }
+            if !email.recipient.contains('@') {
+                error(
+                    "notifications.email.recipient",
+                    format!("\"{}\" is not an email address", email.recipient),
+                );
+            }
+        }
+
+        if notifications.dnd_enabled
+            && (notifications.dnd_start_time.is_none() || notifications.dnd_end_time.is_none())
+        {
+            issues.push(SettingsIssue {
+                field: "notifications.dndEnabled".to_string(),
+                severity: IssueSeverity::Warning,
+                message:
+                    "DND is on but its start or end time is missing, so only per-day windows apply"
+                        .to_string(),
+            });
+        }
+
+        issues
+    }
+
+    /// Refresh intervals `settings` allows: the team config's bounds if set, else the full range
+    pub fn refresh_interval_range(settings: &AppSettings) -> RangeInclusive<u32> {
+        match settings.refresh_interval_bounds {
+            Some(bounds) => bounds.min_secs..=bounds.max_secs,
+            None => MIN_REFRESH_INTERVAL_SECS as u32..=MAX_REFRESH_INTERVAL_SECS,
+        }
+    }
+
+    /// Apply a shared team config (JSON object) on top of the saved settings
+    ///
+    /// Only alerting fields are accepted: `thresholds`, `criticalThreshold`,
+    /// `resetWarningLeadMinutes`, `resetWarningMinPercent`, `disabledLimitIds` and
+    /// `refreshIntervalBounds`. Credentials and other personal settings are never
+    /// touched; keys outside that list, or with invalid values, are reported as
+    /// rejected while the rest still apply.
+    pub fn apply_team_config(app: &AppHandle, json: &str) -> Result<TeamConfigReport, AppError> {
+        let mut settings = Self::get(app)?;
+        let report = Self::merge_team_config(&mut settings, json)?;
+        if !report.applied.is_empty() {
+            Self::save(app, &settings)?;
+        }
+        Ok(report)
+    }
+
+    /// Merge a team config into `settings`, field by field
+    pub(crate) fn merge_team_config(
+        settings: &mut AppSettings,
+        json: &str,
+    ) -> Result<TeamConfigReport, AppError> {
+        let config: serde_json::Map<String, JsonValue> =
+            serde_json::from_str(json).map_err(|e| {
+                AppError::InvalidInput(format!("Team config is not a JSON object: {}", e))
+            })?;
+
+        let mut report = TeamConfigReport::default();
+        for (key, value) in config {
+            match Self::apply_team_field(settings, &key, value) {
+                Ok(()) => report.applied.push(key),
+                Err(message) => report.rejected.push(SettingsIssue {
+                    field: key,
+                    severity: IssueSeverity::Error,
+                    message,
+                }),
+            }
+        }
+        Ok(report)
+    }
+
+    fn apply_team_field(
+        settings: &mut AppSettings,
+        key: &str,
+        value: JsonValue,
+    ) -> Result<(), String> {
+        fn parse<T: DeserializeOwned>(value: JsonValue) -> Result<T, String> {
+            serde_json::from_value(value).map_err(|e| format!("Malformed value: {}", e))
+        }
+        fn percent(value: JsonValue) -> Result<u32, String> {
+            let percent: u32 = parse(value)?;
+            if percent > 100 {
+                return Err(format!("Must be a percentage up to 100, got {}", percent));
+            }
+            Ok(percent)
+        }
+
+        let notifications = &mut settings.notifications;
+        match key {
+            "thresholds" => {
+                let mut thresholds: Vec<u32> = parse(value)?;
+                if let Some(bad) = thresholds.iter().find(|t| !(1..=100).contains(*t)) {
+                    return Err(format!("Threshold must be between 1 and 100%, got {}", bad));
+                }
+                thresholds.sort_unstable();
+                thresholds.dedup();
+                notifications.thresholds = thresholds;
+            }
+            "criticalThreshold" => notifications.critical_threshold = percent(value)?,
+            "resetWarningMinPercent" => notifications.reset_warning_min_percent = percent(value)?,
+            "resetWarningLeadMinutes" => {
+                let minutes: u32 = parse(value)?;
+                if !(1..=MAX_RESET_WARNING_LEAD_MINUTES).contains(&minutes) {
+                    return Err(format!(
+                        "Lead time must be between 1 and {} minutes, got {}",
+                        MAX_RESET_WARNING_LEAD_MINUTES, minutes
+                    ));
+                }
+                notifications.reset_warning_lead_minutes = minutes;
+            }
+            "disabledLimitIds" => {
+                let ids: Vec<String> = parse(value)?;
+                notifications.disabled_limit_ids = ids
+                    .iter()
+                    .map(|id| id.trim())
+                    .filter(|id| !id.is_empty())
+                    .map(str::to_string)
+                    .collect();
+            }
+            "refreshIntervalBounds" => {
+                let bounds: RefreshIntervalBounds = parse(value)?;
+                let min_allowed = MIN_REFRESH_INTERVAL_SECS as u32;
+                if bounds.min_secs < min_allowed
+                    || bounds.max_secs > MAX_REFRESH_INTERVAL_SECS
+                    || bounds.min_secs > bounds.max_secs
+                {
+                    return Err(format!(
+                        "Bounds must satisfy {} <= minSecs <= maxSecs <= {}, got {}..{}",
+                        min_allowed, MAX_REFRESH_INTERVAL_SECS, bounds.min_secs, bounds.max_secs
+                    ));
+                }
+                // The personal interval is kept if it is already within bounds
+                settings.refresh_interval = settings
+                    .refresh_interval
+                    .clamp(bounds.min_secs, bounds.max_secs);
+                settings.refresh_interval_bounds = Some(bounds);
+            }
+            _ => {
+                return Err("Not a team setting; personal settings are left as they are".to_string())
+            }
+        }
+        Ok(())
+    }
+
+    /// Restore default settings
+    pub fn reset(app: &AppHandle) -> Result<(), AppError> {
+        let store = app.data_store(STORE_FILE)?;
+
+        Self::reset_store(&*store)?;
+        log::info!("Reset app settings to defaults");
+        Ok(())
+    }
+
+    /// Clear the settings store so every read returns the defaults
+    fn reset_store(store: &impl KeyValueStore) -> Result<(), AppError> {
+        for key in store.key_list() {
+            store.delete_value(&key);
+        }
+        store.persist()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::key_value::MemoryStore;
+
+    #[test]
+    fn team_config_applies_only_alerting_fields() {
+        let mut settings = AppSettings {
+            refresh_interval: 30,
+            api_server_token: Some("personal-token".to_string()),
+            ..AppSettings::default()
+        };
+        let json = r#"{
+            "thresholds": [90, 50, 75, 90],
+            "criticalThreshold": 95,
+            "resetWarningLeadMinutes": 45,
+            "resetWarningMinPercent": 60,
+            "disabledLimitIds": ["seven_day_opus", " "],
+            "refreshIntervalBounds": { "minSecs": 120, "maxSecs": 900 },
+            "apiServerToken": "team-token",
+            "theme": "light"
+        }"#;
+
+        let mut report = SettingsService::merge_team_config(&mut settings, json).unwrap();
+        report.applied.sort();
+        report.rejected.sort_by(|a, b| a.field.cmp(&b.field));
+
+        assert_eq!(
+            report.applied,
+            vec![
+                "criticalThreshold",
+                "disabledLimitIds",
+                "refreshIntervalBounds",
+                "resetWarningLeadMinutes",
+                "resetWarningMinPercent",
+                "thresholds"
+            ]
+        );
+        let rejected: Vec<_> = report.rejected.iter().map(|r| r.field.as_str()).collect();
+        assert_eq!(rejected, vec!["apiServerToken", "theme"]);
+
+        let notifications = &settings.notifications;
+        assert_eq!(notifications.thresholds, vec![50, 75, 90]);
+        assert_eq!(notifications.critical_threshold, 95);
+        assert_eq!(notifications.reset_warning_lead_minutes, 45);
+        assert_eq!(notifications.reset_warning_min_percent, 60);
+        assert_eq!(
+            notifications.disabled_limit_ids,
+            ["seven_day_opus".to_string()].into_iter().collect()
+        );
+        assert_eq!(settings.refresh_interval, 120);
+        assert_eq!(
+            settings.refresh_interval_bounds,
+            Some(RefreshIntervalBounds {
+                min_secs: 120,
+                max_secs: 900
+            })
+        );
+        // Personal fields are untouched
+        assert_eq!(settings.api_server_token.as_deref(), Some("personal-token"));
+        assert_eq!(settings.theme, AppSettings::default().theme);
+    }
+
+    #[test]
+    fn team_config_rejects_malformed_values() {
+        let mut settings = AppSettings::default();
+        let before = settings.clone();
+        let json = r#"{
+            "thresholds": [0, 150],
+            "criticalThreshold": "high",
+            "resetWarningLeadMinutes": 0,
+            "resetWarningMinPercent": 101,
+            "disabledLimitIds": "five_hour",
+            "refreshIntervalBounds": { "minSecs": 900, "maxSecs": 120 }
+        }"#;
+
+        let report = SettingsService::merge_team_config(&mut settings, json).unwrap();
+
+        assert!(report.applied.is_empty());
+        assert_eq!(report.rejected.len(), 6);
+        assert!(report
+            .rejected
+            .iter()
+            .all(|r| r.severity == IssueSeverity::Error));
+        assert_eq!(
+            serde_json::to_value(&settings).unwrap(),
+            serde_json::to_value(&before).unwrap()
+        );
+
+        assert!(matches!(
+            SettingsService::merge_team_config(&mut settings, "[1, 2]"),
+            Err(AppError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn sensitive_settings_stored_encrypted() {
+        let store = MemoryStore::default();
+        let settings = AppSettings {
+            api_server_token: Some("secret-token".to_string()),
+            ..AppSettings::default()
+        };
+
+        SettingsService::store_settings(&store, &settings).unwrap();
+
+        let raw = store.get_value(SETTINGS_KEY).unwrap();
+        let stored_token = raw["apiServerToken"].as_str().unwrap();
+        assert!(stored_token.starts_with(ENCRYPTED_PREFIX));
+        assert!(!stored_token.contains("secret-token"));
+        // Non-sensitive fields stay readable
+        assert_eq!(raw["theme"], "dark");
+
+        let loaded = SettingsService::load(&store).unwrap();
+        assert_eq!(loaded.api_server_token.as_deref(), Some("secret-token"));
+    }
+
+    #[test]
+    fn nested_smtp_password_stored_encrypted() {
+        let store = MemoryStore::default();
+        let mut settings = AppSettings::default();
+        settings.notifications.email = Some(crate::models::EmailSettings {
+            enabled: true,
+            smtp_host: "smtp.example.com".to_string(),
+            smtp_port: 587,
+            username: Some("me@example.com".to_string()),
+            password: Some("smtp-secret".to_string()),
+            from: None,
+            recipient: "me@example.com".to_string(),
+        });
+
+        SettingsService::store_settings(&store, &settings).unwrap();
+
+        let raw = store.get_value(SETTINGS_KEY).unwrap();
+        let email = &raw["notifications"]["email"];
+        assert!(email["password"]
+            .as_str()
+            .unwrap()
+            .starts_with(ENCRYPTED_PREFIX));
+        assert_eq!(email["username"], "me@example.com");
+
+        let loaded = SettingsService::load(&store).unwrap();
+        assert_eq!(loaded.notifications.email, settings.notifications.email);
+    }
+
+    #[test]
+    fn migration_encrypts_plaintext_settings() {
+        let store = MemoryStore::default();
+        let settings = AppSettings {
+            api_server_token: Some("legacy-token".to_string()),
+            ..AppSettings::default()
+        };
+        store.set_value(SETTINGS_KEY, serde_json::to_value(&settings).unwrap());
+
+        SettingsService::ensure_migrated(&store).unwrap();
+
+        let raw = store.get_value(SETTINGS_KEY).unwrap();
+        assert!(raw["apiServerToken"].as_str().unwrap().starts_with(ENCRYPTED_PREFIX));
+        assert_eq!(store.get_value(VERSION_KEY), Some(serde_json::json!(CURRENT_VERSION)));
+        assert_eq!(
+            SettingsService::load(&store).unwrap().api_server_token.as_deref(),
+            Some("legacy-token")
+        );
+    }
+
+    #[test]
+    fn reset_restores_default_settings() {
+        let store = MemoryStore::default();
+        let settings = AppSettings {
+            theme: "light".to_string(),
+            refresh_interval: 60,
+            ..AppSettings::default()
+        };
+        store.set_value(SETTINGS_KEY, serde_json::to_value(&settings).unwrap());
+
+        SettingsService::reset_store(&store).unwrap();
+
+        let loaded = SettingsService::load(&store).unwrap();
+        assert_eq!(
+            serde_json::to_value(&loaded).unwrap(),
+            serde_json::to_value(AppSettings::default()).unwrap()
+        );
+    }
+
+    #[test]
+    fn deleting_primary_account_clears_setting() {
+        let store = MemoryStore::default();
+        let settings = AppSettings {
+            primary_account_id: Some("acc-1".to_string()),
+            monthly_budgets: std::collections::HashMap::from([("acc-1".to_string(), 100.0)]),
+            ..AppSettings::default()
+        };
+        SettingsService::store_settings(&store, &settings).unwrap();
+
+        // Some other account going away leaves the primary alone
+        assert!(!SettingsService::forget_account_in_store(&store, "acc-2").unwrap());
+        assert_eq!(
+            SettingsService::load(&store)
+                .unwrap()
+                .primary_account_id
+                .as_deref(),
+            Some("acc-1")
+        );
+
+        // Its budget goes with it
+        assert!(SettingsService::forget_account_in_store(&store, "acc-1").unwrap());
+        let settings = SettingsService::load(&store).unwrap();
+        assert_eq!(settings.primary_account_id, None);
+        assert!(settings.monthly_budgets.is_empty());
+    }
+
+    #[test]
+    fn migration_keeps_global_dnd_window() {
+        let store = MemoryStore::default();
+        let mut value = serde_json::to_value(AppSettings::default()).unwrap();
+        let notifications = value["notifications"].as_object_mut().unwrap();
+        notifications.remove("dndSchedule");
+        notifications.insert("dndEnabled".to_string(), serde_json::json!(true));
+        store.set_value(SETTINGS_KEY, value);
+        store.set_value(VERSION_KEY, serde_json::json!(2));
+
+        SettingsService::ensure_migrated(&store).unwrap();
+
+        let raw = store.get_value(SETTINGS_KEY).unwrap();
+        assert_eq!(raw["notifications"]["dndSchedule"], serde_json::json!({}));
+        let loaded = SettingsService::load(&store).unwrap();
+        assert_eq!(loaded.notifications.dnd_schedule, Default::default());
+        assert_eq!(loaded.notifications.dnd_start_time.as_deref(), Some("22:00"));
+    }
+
+    #[test]
+    fn validate_flags_malformed_dnd_time_and_out_of_range_interval() {
+        assert!(SettingsService::validate(&AppSettings::default()).is_empty());
+
+        let mut settings = AppSettings {
+            refresh_interval: 5,
+            ..AppSettings::default()
+        };
+        settings.notifications.dnd_enabled = true;
+        settings.notifications.dnd_start_time = Some("10pm".to_string());
+        settings.notifications.dnd_end_time = Some("08:00".to_string());
+        settings.notifications.dnd_schedule.sat = Some(crate::models::DndWindow {
+            start: "23:00".to_string(),
+            end: "25:00".to_string(),
+        });
+        settings.notifications.thresholds = vec![50, 0, 90];
+
+        let issues = SettingsService::validate(&settings);
+        let fields: Vec<_> = issues.iter().map(|i| i.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            vec![
+                "refreshInterval",
+                "notifications.dndStartTime",
+                "notifications.dndSchedule.sat.end",
+                "notifications.thresholds[1]",
+            ]
+        );
+        assert!(issues.iter().all(|i| i.severity == IssueSeverity::Error));
+
+        settings.refresh_interval = MAX_REFRESH_INTERVAL_SECS + 1;
+        assert_eq!(
+            SettingsService::validate(&settings)[0].field,
+            "refreshInterval"
+        );
+
+        // A team config's bounds narrow the accepted range
+        settings.refresh_interval = 60;
+        settings.refresh_interval_bounds = Some(RefreshIntervalBounds {
+            min_secs: 120,
+            max_secs: 900,
+        });
+        let issue = &SettingsService::validate(&settings)[0];
+        assert_eq!(issue.field, "refreshInterval");
+        assert!(issue.message.contains("between 120 and 900"));
+
+        let settings = AppSettings {
+            cached_responses_per_account: MAX_CACHED_RESPONSES_PER_ACCOUNT + 1,
+            ..AppSettings::default()
+        };
+        assert_eq!(
+            SettingsService::validate(&settings)[0].field,
+            "cachedResponsesPerAccount"
+        );
+    }
 }
@@ -0,0 +1,180 @@
+use crate::error::AppError;
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+/// Payload of `global-shortcut-failed`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShortcutFailedEvent {
+    pub shortcut: String,
+    pub error: String,
+    /// Binding still in effect
+    pub active: Option<String>,
+}
+
+/// The show/hide shortcut currently registered with the OS
+#[derive(Default)]
+pub struct GlobalShortcutState {
+    current: Mutex<Option<String>>,
+}
+
+impl GlobalShortcutState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+pub struct ShortcutService;
+
+impl ShortcutService {
+    /// Parse a shortcut string such as "CommandOrControl+Shift+A"
+    ///
+    /// Modifiers come first and the key last, separated by `+`.
+    pub fn parse(shortcut: &str) -> Result<Shortcut, AppError> {
+        let trimmed = shortcut.trim();
+        if trimmed.is_empty() {
+            return Err(AppError::InvalidInput("Shortcut is empty".to_string()));
+        }
+
+        trimmed
+            .parse::<Shortcut>()
+            .map_err(|e| AppError::InvalidInput(format!("Invalid shortcut '{}': {}", trimmed, e)))
+    }
+
+    /// Whether two shortcut strings name the same key combination
+    ///
+    /// "Ctrl+Shift+A" and "Shift+Control+A" are one binding, as is
+    /// "CommandOrControl+Shift+A" outside macOS. Unparseable strings only match themselves.
+    fn same_binding(a: Option<&str>, b: Option<&str>) -> bool {
+        match (a, b) {
+            (None, None) => true,
+            (Some(a), Some(b)) => match (Self::parse(a), Self::parse(b)) {
+                (Ok(parsed_a), Ok(parsed_b)) => parsed_a == parsed_b,
+                _ => a == b,
+            },
+            _ => false,
+        }
+    }
+
+    /// Bind the shortcut that shows or hides the window, replacing the previous one
+    ///
+    /// If the new shortcut can't be registered (malformed, or taken by another app)
+    /// the old binding stays active, `global-shortcut-failed` is emitted and the
+    /// error is returned.
+    pub fn apply(app: &AppHandle, shortcut: Option<&str>) -> Result<(), AppError> {
+        let Some(state) = app.try_state::<GlobalShortcutState>() else {
+            return Ok(());
+        };
+        let mut current = state.current.lock().unwrap_or_else(|e| e.into_inner());
+        let wanted = shortcut.map(str::trim).filter(|s| !s.is_empty());
+        if Self::same_binding(current.as_deref(), wanted) {
+            // Already bound; only keep the new spelling
+            *current = wanted.map(str::to_string);
+            return Ok(());
+        }
+
+        if let Some(wanted) = wanted {
+            if let Err(e) = Self::register(app, wanted) {
+                log::warn!("Failed to register global shortcut {}: {}", wanted, e);
+                let _ = app.emit(
+                    "global-shortcut-failed",
+                    ShortcutFailedEvent {
+                        shortcut: wanted.to_string(),
+                        error: e.to_string(),
+                        active: current.clone(),
+                    },
+                );
+                return Err(e);
+            }
+        }
+
+        if let Some(previous) = current.take() {
+            let result = Self::parse(&previous).and_then(|s| {
+                app.global_shortcut()
+                    .unregister(s)
+                    .map_err(Self::plugin_error)
+            });
+            if let Err(e) = result {
+                log::warn!("Failed to unregister global shortcut {}: {}", previous, e);
+            }
+        }
+
+        log::info!("Global shortcut set to {:?}", wanted);
+        *current = wanted.map(str::to_string);
+        Ok(())
+    }
+
+    fn register(app: &AppHandle, shortcut: &str) -> Result<(), AppError> {
+        let shortcut = Self::parse(shortcut)?;
+        app.global_shortcut()
+            .on_shortcut(shortcut, |app, _shortcut, event| {
+                if event.state() == ShortcutState::Pressed {
+                    Self::toggle_window(app);
+                }
+            })
+            .map_err(Self::plugin_error)
+    }
+
+    fn toggle_window(app: &AppHandle) {
+        match app.get_webview_window("main") {
+            Some(window) if window.is_visible().unwrap_or(false) => {
+                let _ = window.hide();
+            }
+            _ => crate::show_window_and_emit(app, None::<(&str, ())>),
+        }
+    }
+
+    fn plugin_error(e: tauri_plugin_global_shortcut::Error) -> AppError {
+        AppError::InvalidInput(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tauri_plugin_global_shortcut::{Code, Modifiers};
+
+    #[test]
+    fn parses_and_validates_shortcuts() {
+        let shortcut = ShortcutService::parse(" CommandOrControl+Shift+A ").unwrap();
+        assert_eq!(shortcut.key, Code::KeyA);
+        assert!(shortcut.mods.contains(Modifiers::SHIFT));
+
+        assert!(ShortcutService::parse("Alt+Shift+P").is_ok());
+        assert!(ShortcutService::parse("F13").is_ok());
+
+        for invalid in ["", "   ", "Shift+", "Ctrl+Shift", "Foo+A", "A+Shift"] {
+            assert!(
+                matches!(
+                    ShortcutService::parse(invalid),
+                    Err(AppError::InvalidInput(_))
+                ),
+                "{:?} should be rejected",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn equivalent_spellings_are_the_same_binding() {
+        assert!(ShortcutService::same_binding(None, None));
+        assert!(ShortcutService::same_binding(
+            Some("Ctrl+Shift+A"),
+            Some("Shift+Control+A")
+        ));
+        #[cfg(not(target_os = "macos"))]
+        assert!(ShortcutService::same_binding(
+            Some("CommandOrControl+Shift+A"),
+            Some("Ctrl+Shift+A")
+        ));
+
+        assert!(!ShortcutService::same_binding(
+            Some("Ctrl+Shift+A"),
+            Some("Alt+Shift+A")
+        ));
+        assert!(!ShortcutService::same_binding(Some("Ctrl+Shift+A"), None));
+        assert!(!ShortcutService::same_binding(Some("Foo+A"), Some("Bar+A")));
+    }
+}
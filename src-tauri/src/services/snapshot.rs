@@ -0,0 +1,641 @@
+use crate::error::AppError;
+use crate::models::{SnapshotId, SnapshotInfo, SnapshotReason, UsageHistoryEntry};
+use crate::services::key_value::KeyValueStore;
+use crate::services::{credentials, history, settings};
+use crate::services::{
+    CredentialService, DataDirExt, HistoryService, NotificationStateSnapshot, SchedulerState,
+    SettingsService, StoreLocks,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value as JsonValue};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, PoisonError};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Directory under the data dir holding snapshot bundles
+const SNAPSHOT_DIR: &str = "snapshots";
+
+/// Bumped when the bundle layout changes; newer bundles are refused
+const SNAPSHOT_FORMAT: u32 = 1;
+
+/// Automatic snapshots kept; manual ones are only removed by the user
+const MAX_AUTOMATIC_SNAPSHOTS: usize = 5;
+
+/// Store files a snapshot covers, in the order they are written back
+const SNAPSHOT_STORES: [&str; 3] = [
+    credentials::STORE_FILE,
+    settings::STORE_FILE,
+    history::STORE_FILE,
+];
+
+/// Everything needed to put the app back the way it was
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Snapshot {
+    pub format: u32,
+    pub info: SnapshotInfo,
+    /// Raw key-value contents of each store file, keyed by file name
+    pub stores: BTreeMap<String, Map<String, JsonValue>>,
+    /// History entries, kept apart since they may live in the compressed file instead
+    pub history: Vec<UsageHistoryEntry>,
+    pub notification_state: NotificationStateSnapshot,
+}
+
+pub struct SnapshotService;
+
+impl SnapshotService {
+    fn dir(app: &AppHandle) -> Result<PathBuf, AppError> {
+        app.data_path(SNAPSHOT_DIR)
+    }
+
+    fn compressed_history_path(app: &AppHandle) -> Result<PathBuf, AppError> {
+        app.data_path(history::COMPRESSED_FILE)
+    }
+
+    /// Save the current accounts, settings, history and notification state to disk
+    ///
+    /// Doesn't take the store locks, so it can run from inside a locked migration.
+    pub fn create(app: &AppHandle, reason: SnapshotReason) -> Result<SnapshotInfo, AppError> {
+        let dir = Self::dir(app)?;
+        let snapshot = Self::capture(app, &dir, reason)?;
+
+        Self::write_snapshot(&dir, &snapshot)?;
+        Self::prune_automatic(&dir, MAX_AUTOMATIC_SNAPSHOTS)?;
+
+        log::info!(
+            "Created {:?} snapshot {} ({} accounts, {} history entries)",
+            reason,
+            snapshot.info.id,
+            snapshot.info.account_count,
+            snapshot.info.history_entry_count
+        );
+        Ok(snapshot.info)
+    }
+
+    /// Saved snapshots, newest first
+    pub fn list(app: &AppHandle) -> Result<Vec<SnapshotInfo>, AppError> {
+        Self::list_in(&Self::dir(app)?)
+    }
+
+    /// Replace the current state with a snapshot's
+    ///
+    /// The snapshot is validated first, and the current state is saved as a
+    /// `pre-restore` snapshot. If writing any store fails, everything is put
+    /// back from that snapshot, so the app never ends up with a mix of the two.
+    pub fn restore(app: &AppHandle, id: &str) -> Result<SnapshotInfo, AppError> {
+        let dir = Self::dir(app)?;
+        let snapshot = Self::read_snapshot(&dir, id)?;
+        Self::validate(&snapshot)?;
+
+        let backup = Self::capture(app, &dir, SnapshotReason::PreRestore)?;
+        Self::write_snapshot(&dir, &backup)?;
+
+        // Every store stays locked through its saves, so a rollback can't
+        // interleave with another writer
+        let locks: Vec<_> = SNAPSHOT_STORES
+            .iter()
+            .map(|file| app.state::<StoreLocks>().for_file(file))
+            .collect();
+        let _guards: Vec<_> = locks
+            .iter()
+            .map(|lock| lock.lock().unwrap_or_else(PoisonError::into_inner))
+            .collect();
+
+        let opened = SNAPSHOT_STORES
+            .iter()
+            .map(|file| Ok((*file, app.data_store(file)?)))
+            .collect::<Result<Vec<_>, AppError>>()?;
+        let stores: Vec<_> = opened
+            .iter()
+            .map(|(file, store)| (*file, &**store))
+            .collect();
+        let compressed = Self::compressed_history_path(app)?;
+
+        if let Err(e) = Self::apply(&snapshot, &stores, &compressed) {
+            log::error!("Failed to restore snapshot {}, rolling back: {}", id, e);
+            if let Err(rollback) = Self::apply(&backup, &stores, &compressed) {
+                log::error!("Failed to roll back snapshot restore: {}", rollback);
+            }
+            return Err(e);
+        }
+
+        if let Some(state) = app.try_state::<Arc<SchedulerState>>() {
+            state.restore_notification_state(&snapshot.notification_state);
+        }
+
+        Self::prune_automatic(&dir, MAX_AUTOMATIC_SNAPSHOTS)?;
+
+        log::warn!("Restored snapshot {}", id);
+        let _ = app.emit("snapshot-restored", &snapshot.info);
+        Ok(snapshot.info)
+    }
+
+    /// Read the current state into a snapshot
+    fn capture(app: &AppHandle, dir: &Path, reason: SnapshotReason) -> Result<Snapshot, AppError> {
+        let opened = SNAPSHOT_STORES
+            .iter()
+            .map(|file| Ok((*file, app.data_store(file)?)))
+            .collect::<Result<Vec<_>, AppError>>()?;
+        let stores: Vec<_> = opened
+            .iter()
+            .map(|(file, store)| (*file, &**store))
+            .collect();
+
+        let notification_state = app
+            .try_state::<Arc<SchedulerState>>()
+            .map(|state| state.notification_snapshot())
+            .unwrap_or_default();
+
+        Ok(Self::capture_from(
+            Self::unused_id(dir, Utc::now()),
+            reason,
+            &stores,
+            HistoryService::get_all_entries(app)?,
+            notification_state,
+        ))
+    }
+
+    /// Build a snapshot from open stores
+    pub(crate) fn capture_from<S: KeyValueStore>(
+        id: SnapshotId,
+        reason: SnapshotReason,
+        stores: &[(&str, &S)],
+        history: Vec<UsageHistoryEntry>,
+        notification_state: NotificationStateSnapshot,
+    ) -> Snapshot {
+        let mut captured = BTreeMap::new();
+        for (file, store) in stores {
+            let mut values: Map<String, JsonValue> = store
+                .key_list()
+                .into_iter()
+                .filter_map(|key| store.get_value(&key).map(|value| (key, value)))
+                .collect();
+            if *file == history::STORE_FILE {
+                values.remove(history::HISTORY_KEY);
+            }
+            captured.insert(file.to_string(), values);
+        }
+
+        let account_count = captured
+            .get(credentials::STORE_FILE)
+            .and_then(|values| values.get("accounts"))
+            .and_then(JsonValue::as_object)
+            .map_or(0, Map::len);
+
+        Snapshot {
+            format: SNAPSHOT_FORMAT,
+            info: SnapshotInfo {
+                created_at: Self::created_at(&id).unwrap_or_else(Utc::now),
+                id,
+                reason,
+                account_count,
+                history_entry_count: history.len(),
+            },
+            stores: captured,
+            history,
+            notification_state,
+        }
+    }
+
+    /// Refuse a snapshot from a newer version, missing a store, or with tampered accounts
+    pub(crate) fn validate(snapshot: &Snapshot) -> Result<(), AppError> {
+        if snapshot.format > SNAPSHOT_FORMAT {
+            return Err(AppError::InvalidInput(format!(
+                "Snapshot {} was made by a newer version of AI Pulse",
+                snapshot.info.id
+            )));
+        }
+
+        for file in SNAPSHOT_STORES {
+            if !snapshot.stores.contains_key(file) {
+                return Err(AppError::InvalidInput(format!(
+                    "Snapshot {} is missing {}",
+                    snapshot.info.id, file
+                )));
+            }
+        }
+
+        CredentialService::verify_store_values(&snapshot.stores[credentials::STORE_FILE])
+    }
+
+    /// Write a snapshot's contents over the given stores
+    pub(crate) fn apply<S: KeyValueStore>(
+        snapshot: &Snapshot,
+        stores: &[(&str, &S)],
+        compressed_history: &Path,
+    ) -> Result<(), AppError> {
+        let empty = Map::new();
+        for (file, store) in stores {
+            let values = snapshot.stores.get(*file).unwrap_or(&empty);
+
+            for key in store.key_list() {
+                if !values.contains_key(&key) {
+                    store.delete_value(&key);
+                }
+            }
+            for (key, value) in values {
+                store.set_value(key, value.clone());
+            }
+            store.persist()?;
+        }
+
+        // Entries go wherever the restored settings keep them
+        let store_named = |name: &str| {
+            stores
+                .iter()
+                .find(|(file, _)| *file == name)
+                .map(|(_, store)| *store)
+        };
+        let compress = match store_named(settings::STORE_FILE) {
+            Some(store) => SettingsService::load(store)?.compress_history,
+            None => false,
+        };
+        if let Some(store) = store_named(history::STORE_FILE) {
+            HistoryService::store_entries(store, compressed_history, compress, &snapshot.history)?;
+        }
+
+        Ok(())
+    }
+
+    /// An ID from `now` that no snapshot in `dir` has yet
+    fn unused_id(dir: &Path, now: DateTime<Utc>) -> SnapshotId {
+        let base = now.format("%Y%m%d-%H%M%S-%3f").to_string();
+        let mut id = base.clone();
+        let mut n = 1;
+        while Self::path_for(dir, &id).exists() {
+            id = format!("{}-{}", base, n);
+            n += 1;
+        }
+        id
+    }
+
+    /// Creation time encoded in an ID
+    fn created_at(id: &str) -> Option<DateTime<Utc>> {
+        let timestamp = id.get(..19)?;
+        chrono::NaiveDateTime::parse_from_str(timestamp, "%Y%m%d-%H%M%S-%3f")
+            .ok()
+            .map(|naive| naive.and_utc())
+    }
+
+    fn path_for(dir: &Path, id: &str) -> PathBuf {
+        dir.join(format!("{}.json", id))
+    }
+
+    /// Write the bundle via a temporary file so a crash never leaves half a snapshot
+    ///
+    /// The bundle holds a copy of `credentials.json`, so the file is created
+    /// readable by its owner only (600) rather than under the umask.
+    pub(crate) fn write_snapshot(dir: &Path, snapshot: &Snapshot) -> Result<(), AppError> {
+        let path = Self::path_for(dir, &snapshot.info.id);
+        let tmp = path.with_extension("json.tmp");
+
+        let write = || -> std::io::Result<()> {
+            fs::create_dir_all(dir)?;
+            // A leftover temp file would keep whatever mode it was created with
+            if let Err(e) = fs::remove_file(&tmp) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    return Err(e);
+                }
+            }
+
+            let mut options = fs::OpenOptions::new();
+            options.write(true).create_new(true);
+            #[cfg(unix)]
+            {
+                use crate::services::file_permissions::OWNER_ONLY_MODE;
+                use std::os::unix::fs::OpenOptionsExt;
+                options.mode(OWNER_ONLY_MODE);
+            }
+            options
+                .open(&tmp)?
+                .write_all(&serde_json::to_vec(snapshot)?)?;
+            fs::rename(&tmp, &path)
+        };
+
+        write().map_err(|e| AppError::Store(e.to_string()))
+    }
+
+    /// Delete every snapshot bundle, e.g. on factory reset, since each holds a copy of the credentials
+    pub fn wipe(app: &AppHandle) -> Result<(), AppError> {
+        Self::wipe_dir(&Self::dir(app)?)
+    }
+
+    pub(crate) fn wipe_dir(dir: &Path) -> Result<(), AppError> {
+        match fs::remove_dir_all(dir) {
+            Ok(()) => {
+                log::info!("Deleted all snapshots");
+                Ok(())
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(AppError::Store(format!(
+                "Failed to delete snapshots: {}",
+                e
+            ))),
+        }
+    }
+
+    pub(crate) fn read_snapshot(dir: &Path, id: &str) -> Result<Snapshot, AppError> {
+        // IDs become file names; anything else could point outside the snapshot dir
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Err(AppError::InvalidInput(format!(
+                "Invalid snapshot ID '{}'",
+                id
+            )));
+        }
+
+        let bytes = fs::read(Self::path_for(dir, id)).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => {
+                AppError::InvalidInput(format!("Snapshot {} not found", id))
+            }
+            _ => AppError::Store(e.to_string()),
+        })?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    /// Snapshots in `dir`, newest first; unreadable files are skipped
+    pub(crate) fn list_in(dir: &Path) -> Result<Vec<SnapshotInfo>, AppError> {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(AppError::Store(e.to_string())),
+        };
+
+        let mut infos: Vec<SnapshotInfo> = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|path| {
+                let snapshot: Snapshot = serde_json::from_slice(&fs::read(&path).ok()?)
+                    .map_err(|e| log::warn!("Skipping unreadable snapshot {:?}: {}", path, e))
+                    .ok()?;
+                Some(snapshot.info)
+            })
+            .collect();
+
+        infos.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(b.id.cmp(&a.id)));
+        Ok(infos)
+    }
+
+    /// Delete the oldest automatic snapshots beyond `keep`; returns how many were removed
+    pub(crate) fn prune_automatic(dir: &Path, keep: usize) -> Result<usize, AppError> {
+        let stale: Vec<SnapshotInfo> = Self::list_in(dir)?
+            .into_iter()
+            .filter(|info| info.reason.is_automatic())
+            .skip(keep)
+            .collect();
+
+        for info in &stale {
+            fs::remove_file(Self::path_for(dir, &info.id))
+                .map_err(|e| AppError::Store(e.to_string()))?;
+            log::info!("Pruned automatic snapshot {}", info.id);
+        }
+
+        Ok(stale.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Account, AppSettings, ClaudeCredentials, Credentials, UsageLimitSnapshot};
+    use crate::services::key_value::MemoryStore;
+    use std::collections::HashMap;
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("ai-pulse-snapshots-{}", uuid::Uuid::new_v4()))
+    }
+
+    fn account(id: &str) -> Account {
+        Account {
+            id: id.to_string(),
+            name: "Personal".to_string(),
+            provider: "claude".to_string(),
+            credentials: Credentials::Claude(ClaudeCredentials {
+                org_id: Some("org-123".to_string()),
+                session_key: Some("enc:v1:c2VjcmV0".to_string()),
+                ..ClaudeCredentials::default()
+            }),
+            created_at: Utc::now(),
+            tags: Vec::new(),
+            display_name: None,
+            extra_headers: BTreeMap::new(),
+        }
+    }
+
+    fn entry(utilization: f64) -> UsageHistoryEntry {
+        let timestamp = Utc::now();
+        UsageHistoryEntry {
+            id: format!("{}-claude-acc-1", timestamp.timestamp()),
+            provider: "claude".to_string(),
+            account_id: "acc-1".to_string(),
+            account_name: "Personal".to_string(),
+            timestamp,
+            limits: vec![UsageLimitSnapshot {
+                id: "five_hour".to_string(),
+                utilization,
+                resets_at: timestamp,
+                min_utilization: None,
+                max_utilization: None,
+            }],
+        }
+    }
+
+    /// Credentials, settings and history stores holding some state
+    fn populated_stores() -> [MemoryStore; 3] {
+        let credentials = MemoryStore::default();
+        let accounts = HashMap::from([("acc-1".to_string(), account("acc-1"))]);
+        CredentialService::store_accounts(&credentials, &accounts).unwrap();
+        credentials.set_value("version", serde_json::json!(5));
+
+        let settings = MemoryStore::default();
+        let app_settings = AppSettings {
+            refresh_interval: 600,
+            ..AppSettings::default()
+        };
+        settings.set_value("app_settings", serde_json::to_value(&app_settings).unwrap());
+
+        let history = MemoryStore::default();
+        history.set_value(
+            "last_viewed",
+            serde_json::json!({ "acc-1": "2025-01-01T00:00:00Z" }),
+        );
+
+        [credentials, settings, history]
+    }
+
+    fn named(stores: &[MemoryStore; 3]) -> Vec<(&'static str, &MemoryStore)> {
+        SNAPSHOT_STORES.iter().copied().zip(stores.iter()).collect()
+    }
+
+    fn snapshot(id: &str, reason: SnapshotReason) -> Snapshot {
+        let stores = populated_stores();
+        SnapshotService::capture_from(
+            id.to_string(),
+            reason,
+            &named(&stores),
+            Vec::new(),
+            NotificationStateSnapshot::default(),
+        )
+    }
+
+    #[test]
+    fn create_then_restore_round_trips_every_store() {
+        let dir = temp_dir();
+        let compressed = dir.join("history.json.gz");
+        let stores = populated_stores();
+        let history = vec![entry(40.0)];
+        stores[2].set_value(
+            history::HISTORY_KEY,
+            serde_json::to_value(&history).unwrap(),
+        );
+        let notification_state = NotificationStateSnapshot {
+            sent_thresholds: vec![("acc-1".to_string(), "five_hour".to_string(), 75)],
+            ..NotificationStateSnapshot::default()
+        };
+
+        let snapshot = SnapshotService::capture_from(
+            "20250101-120000-000".to_string(),
+            SnapshotReason::Manual,
+            &named(&stores),
+            history.clone(),
+            notification_state.clone(),
+        );
+        assert_eq!(snapshot.info.account_count, 1);
+        assert_eq!(snapshot.info.history_entry_count, 1);
+        SnapshotService::write_snapshot(&dir, &snapshot).unwrap();
+        #[cfg(unix)]
+        assert_eq!(
+            crate::services::FilePermissionService::check(&SnapshotService::path_for(
+                &dir,
+                &snapshot.info.id
+            ))
+            .unwrap()
+            .access,
+            crate::models::FileAccess::OwnerOnly
+        );
+        let before: Vec<_> = stores.iter().map(|s| s.values.borrow().clone()).collect();
+
+        // Change everything after the snapshot
+        stores[0].delete_value("accounts");
+        stores[1].set_value("app_settings", serde_json::json!({ "theme": "dark" }));
+        stores[2].set_value(history::HISTORY_KEY, serde_json::json!([]));
+        stores[2].set_value("retention", serde_json::json!({ "days": 1 }));
+
+        let restored = SnapshotService::read_snapshot(&dir, &snapshot.info.id).unwrap();
+        assert_eq!(
+            serde_json::to_value(&restored).unwrap(),
+            serde_json::to_value(&snapshot).unwrap()
+        );
+        SnapshotService::validate(&restored).unwrap();
+        SnapshotService::apply(&restored, &named(&stores), &compressed).unwrap();
+
+        let after: Vec<_> = stores.iter().map(|s| s.values.borrow().clone()).collect();
+        assert_eq!(after, before);
+        assert_eq!(
+            serde_json::to_value(&restored.history).unwrap(),
+            serde_json::to_value(&history).unwrap()
+        );
+        assert_eq!(restored.notification_state, notification_state);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn tampered_accounts_fail_validation() {
+        let stores = populated_stores();
+        let mut snapshot = SnapshotService::capture_from(
+            "20250101-120000-000".to_string(),
+            SnapshotReason::Manual,
+            &named(&stores),
+            Vec::new(),
+            NotificationStateSnapshot::default(),
+        );
+        snapshot.stores.get_mut(credentials::STORE_FILE).unwrap()["accounts"]["acc-1"]["name"] =
+            serde_json::json!("Renamed");
+
+        assert!(matches!(
+            SnapshotService::validate(&snapshot),
+            Err(AppError::IntegrityViolation(_))
+        ));
+
+        snapshot.stores.remove(credentials::STORE_FILE);
+        assert!(matches!(
+            SnapshotService::validate(&snapshot),
+            Err(AppError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn only_newest_automatic_snapshots_are_kept() {
+        let dir = temp_dir();
+        let ids = [
+            ("20250101-000000-000", SnapshotReason::Manual),
+            ("20250102-000000-000", SnapshotReason::PreMigration),
+            ("20250103-000000-000", SnapshotReason::PreRestore),
+            ("20250104-000000-000", SnapshotReason::PreMigration),
+            ("20250105-000000-000", SnapshotReason::PreMigration),
+        ];
+        for (id, reason) in ids {
+            SnapshotService::write_snapshot(&dir, &snapshot(id, reason)).unwrap();
+        }
+
+        assert_eq!(SnapshotService::prune_automatic(&dir, 2).unwrap(), 2);
+
+        let kept: Vec<String> = SnapshotService::list_in(&dir)
+            .unwrap()
+            .into_iter()
+            .map(|info| info.id)
+            .collect();
+        assert_eq!(
+            kept,
+            vec![
+                "20250105-000000-000",
+                "20250104-000000-000",
+                // Manual snapshots are never pruned
+                "20250101-000000-000",
+            ]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn wipe_deletes_every_bundle() {
+        let dir = temp_dir();
+        let stores = populated_stores();
+        for (id, reason) in [
+            ("20250101-120000-000", SnapshotReason::Manual),
+            ("20250102-120000-000", SnapshotReason::PreRestore),
+        ] {
+            let snapshot = SnapshotService::capture_from(
+                id.to_string(),
+                reason,
+                &named(&stores),
+                Vec::new(),
+                NotificationStateSnapshot::default(),
+            );
+            SnapshotService::write_snapshot(&dir, &snapshot).unwrap();
+        }
+        assert_eq!(SnapshotService::list_in(&dir).unwrap().len(), 2);
+
+        SnapshotService::wipe_dir(&dir).unwrap();
+        assert!(!dir.exists());
+        // Nothing left to delete is fine
+        SnapshotService::wipe_dir(&dir).unwrap();
+    }
+
+    #[test]
+    fn snapshot_ids_cannot_escape_the_directory() {
+        let dir = temp_dir();
+        for id in ["", "../settings", "a/b", "x.json"] {
+            assert!(matches!(
+                SnapshotService::read_snapshot(&dir, id),
+                Err(AppError::InvalidInput(_))
+            ));
+        }
+    }
+}
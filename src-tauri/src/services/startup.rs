@@ -0,0 +1,65 @@
+use crate::models::AppSettings;
+
+/// Something the app does once at launch, decided from settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartupAction {
+    /// Start the background scheduler
+    StartScheduler,
+    /// Fetch every account once without starting the scheduler
+    FetchOnce,
+    /// Hide the main window so only the tray icon shows
+    HideMainWindow,
+}
+
+/// Actions to run at launch for the given settings, in order
+pub fn startup_actions(settings: &AppSettings) -> Vec<StartupAction> {
+    let mut actions = Vec::new();
+
+    if settings.autostart_scheduler {
+        // The scheduler does its own immediate fetch when that is enabled
+        actions.push(StartupAction::StartScheduler);
+    } else if settings.immediate_fetch_on_start {
+        actions.push(StartupAction::FetchOnce);
+    }
+
+    if settings.start_minimized_to_tray {
+        actions.push(StartupAction::HideMainWindow);
+    }
+
+    actions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_settings_start_scheduler_with_window_shown() {
+        assert_eq!(
+            startup_actions(&AppSettings::default()),
+            vec![StartupAction::StartScheduler]
+        );
+    }
+
+    #[test]
+    fn settings_map_to_startup_actions() {
+        let mut settings = AppSettings {
+            autostart_scheduler: false,
+            start_minimized_to_tray: true,
+            ..AppSettings::default()
+        };
+        assert_eq!(
+            startup_actions(&settings),
+            vec![StartupAction::FetchOnce, StartupAction::HideMainWindow]
+        );
+
+        settings.immediate_fetch_on_start = false;
+        assert_eq!(
+            startup_actions(&settings),
+            vec![StartupAction::HideMainWindow]
+        );
+
+        settings.start_minimized_to_tray = false;
+        assert!(startup_actions(&settings).is_empty());
+    }
+}
@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::sync::Mutex as AsyncMutex;
+
+/// Per-file locks that serialize read-modify-write sequences on store files
+///
+/// `tauri_plugin_store` makes each individual get/set atomic, but not a get
+/// followed by a set, so two writers can interleave and drop each other's
+/// changes. Hold the file's lock for the whole sequence.
+#[derive(Default)]
+pub struct StoreLocks {
+    locks: Mutex<HashMap<String, Arc<Mutex<()>>>>,
+}
+
+impl StoreLocks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the lock for a store file, creating it on first use
+    pub fn for_file(&self, file: &str) -> Arc<Mutex<()>> {
+        let mut locks = self.locks.lock().unwrap_or_else(PoisonError::into_inner);
+        locks.entry(file.to_string()).or_default().clone()
+    }
+}
+
+/// Per-account locks held while an OAuth token is refreshed
+///
+/// A refresh token stops working once exchanged, so two fetches must not
+/// refresh the same account at once. Entries are dropped with their account.
+#[derive(Default)]
+pub struct OAuthRefreshLocks {
+    locks: Mutex<HashMap<String, Arc<AsyncMutex<()>>>>,
+}
+
+impl OAuthRefreshLocks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the lock for an account, creating it on first use
+    pub fn for_account(&self, account_id: &str) -> Arc<AsyncMutex<()>> {
+        let mut locks = self.locks.lock().unwrap_or_else(PoisonError::into_inner);
+        locks.entry(account_id.to_string()).or_default().clone()
+    }
+
+    /// Drop a deleted account's lock; a refresh still holding it finishes undisturbed
+    pub fn forget(&self, account_id: &str) {
+        self.locks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(account_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AppError;
+    use crate::models::{Account, ClaudeCredentials, Credentials};
+    use crate::services::credentials::{self, CredentialService};
+    use crate::services::key_value::{persist_unlocked, KeyValueStore};
+    use chrono::Utc;
+    use serde_json::Value as JsonValue;
+    use std::collections::BTreeMap;
+    use std::thread;
+
+    #[tokio::test]
+    async fn oauth_refreshes_are_serialized_per_account() {
+        let locks = OAuthRefreshLocks::new();
+        let first = locks.for_account("acc-oauth-1");
+        let _held = first.lock().await;
+
+        // The same account waits; another account does not
+        assert!(locks.for_account("acc-oauth-1").try_lock().is_err());
+        assert!(locks.for_account("acc-oauth-2").try_lock().is_ok());
+
+        // A deleted account's entry is gone, so the map doesn't grow with every ID seen
+        locks.forget("acc-oauth-1");
+        locks.forget("acc-oauth-2");
+        assert!(locks.locks.lock().unwrap().is_empty());
+    }
+
+    /// Mimics the store plugin: each get or set is atomic, a get followed by a set is not
+    #[derive(Default)]
+    struct SharedStore {
+        values: Mutex<HashMap<String, JsonValue>>,
+    }
+
+    impl KeyValueStore for SharedStore {
+        fn get_value(&self, key: &str) -> Option<JsonValue> {
+            self.values.lock().unwrap().get(key).cloned()
+        }
+
+        fn set_value(&self, key: &str, value: JsonValue) {
+            self.values.lock().unwrap().insert(key.to_string(), value);
+        }
+
+        fn delete_value(&self, key: &str) {
+            self.values.lock().unwrap().remove(key);
+        }
+
+        fn key_list(&self) -> Vec<String> {
+            self.values.lock().unwrap().keys().cloned().collect()
+        }
+
+        fn persist(&self) -> Result<(), AppError> {
+            Ok(())
+        }
+    }
+
+    fn account(id: String) -> Account {
+        Account {
+            name: id.clone(),
+            id,
+            provider: "claude".to_string(),
+            credentials: Credentials::Claude(ClaudeCredentials::default()),
+            created_at: Utc::now(),
+            tags: Vec::new(),
+            display_name: None,
+            extra_headers: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn same_file_returns_same_lock() {
+        let locks = StoreLocks::new();
+        assert!(Arc::ptr_eq(&locks.for_file("a.json"), &locks.for_file("a.json")));
+        assert!(!Arc::ptr_eq(&locks.for_file("a.json"), &locks.for_file("b.json")));
+    }
+
+    #[test]
+    fn concurrent_saves_and_deletes_lose_nothing() {
+        let locks = Arc::new(StoreLocks::new());
+        let store = Arc::new(SharedStore::default());
+
+        // Seed accounts that the delete threads will remove
+        for i in 0..20 {
+            CredentialService::insert_account(&*store, &account(format!("old-{}", i))).unwrap();
+        }
+
+        let mut handles = Vec::new();
+        for i in 0..20 {
+            let (save_locks, save_store) = (locks.clone(), store.clone());
+            handles.push(thread::spawn(move || {
+                let lock = save_locks.for_file(credentials::STORE_FILE);
+                let guard = lock.lock().unwrap();
+                CredentialService::insert_account(&*save_store, &account(format!("new-{}", i)))
+                    .unwrap();
+                persist_unlocked(&*save_store, guard).unwrap();
+            }));
+
+            let (delete_locks, delete_store) = (locks.clone(), store.clone());
+            handles.push(thread::spawn(move || {
+                let lock = delete_locks.for_file(credentials::STORE_FILE);
+                let guard = lock.lock().unwrap();
+                assert!(
+                    CredentialService::remove_account(&*delete_store, &format!("old-{}", i))
+                        .unwrap()
+                );
+                persist_unlocked(&*delete_store, guard).unwrap();
+            }));
+        }
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        // The accounts map still verifies, and holds exactly the saved accounts
+        let accounts = CredentialService::load_accounts(&*store).unwrap();
+        assert_eq!(accounts.len(), 20);
+        assert!((0..20).all(|i| accounts.contains_key(&format!("new-{}", i))));
+    }
+}
@@ -0,0 +1,127 @@
+use crate::error::AppError;
+use chrono::{DateTime, Local, TimeZone, Utc};
+use chrono_tz::Tz;
+use std::fmt::Display;
+
+/// Format used for reset times shown to the user
+const DISPLAY_FORMAT: &str = "%Y-%m-%d %H:%M %Z";
+
+/// Time zone human-readable times are shown in, from `AppSettings.display_timezone`
+///
+/// Only display is affected; timestamps are stored and returned as UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisplayTimezone {
+    /// The system's local time zone
+    Local,
+    Utc,
+    /// A named IANA zone, e.g. "Europe/Berlin"
+    Named(Tz),
+}
+
+impl DisplayTimezone {
+    /// Parse a setting value: "local", "UTC" (both case-insensitive), or an IANA zone name
+    pub fn parse(value: &str) -> Result<Self, AppError> {
+        let value = value.trim();
+        if value.is_empty() || value.eq_ignore_ascii_case("local") {
+            return Ok(Self::Local);
+        }
+        if value.eq_ignore_ascii_case("utc") {
+            return Ok(Self::Utc);
+        }
+
+        value.parse::<Tz>().map(Self::Named).map_err(|_| {
+            AppError::InvalidInput(format!(
+                "Unknown time zone '{}': expected \"local\", \"UTC\" or an IANA name like \"Europe/Berlin\"",
+                value
+            ))
+        })
+    }
+
+    /// Parse a stored setting, falling back to local time if it is no longer valid
+    pub fn from_setting(value: &str) -> Self {
+        Self::parse(value).unwrap_or_else(|e| {
+            log::warn!("{}; showing local times", e);
+            Self::Local
+        })
+    }
+
+    /// Canonical setting value ("local", "UTC" or the zone name)
+    pub fn name(&self) -> String {
+        match self {
+            Self::Local => "local".to_string(),
+            Self::Utc => "UTC".to_string(),
+            Self::Named(tz) => tz.name().to_string(),
+        }
+    }
+
+    /// Format a time in this zone, e.g. "2025-01-15 21:00 JST"
+    pub fn format(&self, at: DateTime<Utc>) -> String {
+        match self {
+            Self::Local => Self::format_in(at, &Local),
+            Self::Utc => Self::format_in(at, &Utc),
+            Self::Named(tz) => Self::format_in(at, tz),
+        }
+    }
+
+    fn format_in<Z: TimeZone>(at: DateTime<Utc>, zone: &Z) -> String
+    where
+        Z::Offset: Display,
+    {
+        at.with_timezone(zone).format(DISPLAY_FORMAT).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_local_utc_and_named_zones() {
+        assert_eq!(DisplayTimezone::parse("").unwrap(), DisplayTimezone::Local);
+        assert_eq!(
+            DisplayTimezone::parse("Local").unwrap(),
+            DisplayTimezone::Local
+        );
+        assert_eq!(DisplayTimezone::parse("utc").unwrap(), DisplayTimezone::Utc);
+        assert_eq!(
+            DisplayTimezone::parse(" Asia/Tokyo ").unwrap(),
+            DisplayTimezone::Named(chrono_tz::Asia::Tokyo)
+        );
+        assert!(matches!(
+            DisplayTimezone::parse("Mars/Olympus_Mons"),
+            Err(AppError::InvalidInput(_))
+        ));
+        assert_eq!(
+            DisplayTimezone::from_setting("Mars/Olympus_Mons"),
+            DisplayTimezone::Local
+        );
+        assert_eq!(
+            DisplayTimezone::parse("Europe/Berlin").unwrap().name(),
+            "Europe/Berlin"
+        );
+    }
+
+    #[test]
+    fn formats_reset_time_in_utc_and_named_zone() {
+        let resets_at = Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap();
+
+        assert_eq!(
+            DisplayTimezone::Utc.format(resets_at),
+            "2025-01-15 12:00 UTC"
+        );
+        assert_eq!(
+            DisplayTimezone::Named(chrono_tz::Asia::Tokyo).format(resets_at),
+            "2025-01-15 21:00 JST"
+        );
+        // Abbreviation and offset follow the zone's DST rules for the date
+        assert_eq!(
+            DisplayTimezone::Named(chrono_tz::America::Los_Angeles).format(resets_at),
+            "2025-01-15 04:00 PST"
+        );
+        let summer = Utc.with_ymd_and_hms(2025, 7, 1, 2, 30, 0).unwrap();
+        assert_eq!(
+            DisplayTimezone::Named(chrono_tz::America::New_York).format(summer),
+            "2025-06-30 22:30 EDT"
+        );
+    }
+}
@@ -0,0 +1,122 @@
+use crate::error::AppError;
+use crate::models::UsageData;
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+/// Updates buffered per subscriber before a slow client starts skipping them
+const CHANNEL_CAPACITY: usize = 16;
+
+/// Fan-out of usage updates to local WebSocket subscribers
+///
+/// Cloning shares the same channel, so the WebSocket server and the
+/// scheduler can each hold one.
+#[derive(Clone)]
+pub struct UsageStream {
+    sender: broadcast::Sender<String>,
+}
+
+/// JSON text frame sent to subscribers
+#[derive(Serialize)]
+struct StreamMessage<'a> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    data: &'a UsageData,
+}
+
+impl Default for UsageStream {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UsageStream {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self { sender }
+    }
+
+    /// Receive every update published from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<String> {
+        self.sender.subscribe()
+    }
+
+    /// Number of connected subscribers
+    pub fn subscriber_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+
+    /// Push a usage update to every subscriber; does nothing when nobody is connected
+    pub fn publish(&self, data: &UsageData) {
+        if self.subscriber_count() == 0 {
+            return;
+        }
+
+        match Self::message(data) {
+            Ok(message) => {
+                let _ = self.sender.send(message);
+            }
+            Err(e) => log::warn!("Failed to serialize usage update for subscribers: {}", e),
+        }
+    }
+
+    /// Serialize a usage update as `{"type": "usage-update", "data": {...}}`
+    pub fn message(data: &UsageData) -> Result<String, AppError> {
+        Ok(serde_json::to_string(&StreamMessage {
+            kind: "usage-update",
+            data,
+        })?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{LimitKind, UsageLimit};
+    use chrono::{TimeZone, Utc};
+
+    fn usage() -> UsageData {
+        let timestamp = Utc.with_ymd_and_hms(2025, 1, 1, 10, 0, 0).unwrap();
+        UsageData {
+            provider: "claude".to_string(),
+            account_id: "acc-1".to_string(),
+            account_name: "Personal".to_string(),
+            timestamp,
+            limits: vec![UsageLimit {
+                id: "five_hour".to_string(),
+                label: "Session".to_string(),
+                utilization: 42.0,
+                resets_at: timestamp,
+                category: None,
+                kind: LimitKind::FiveHour,
+                total: None,
+            }],
+            raw: None,
+            rate_limit: None,
+        }
+    }
+
+    #[test]
+    fn message_wraps_usage_data_with_type() {
+        let json: serde_json::Value =
+            serde_json::from_str(&UsageStream::message(&usage()).unwrap()).unwrap();
+
+        assert_eq!(json["type"], "usage-update");
+        assert_eq!(json["data"]["accountId"], "acc-1");
+        assert_eq!(json["data"]["limits"][0]["utilization"], 42.0);
+    }
+
+    #[test]
+    fn publish_reaches_subscribers() {
+        let stream = UsageStream::new();
+        stream.publish(&usage()); // nobody listening yet
+
+        let mut rx = stream.subscribe();
+        stream.publish(&usage());
+
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            UsageStream::message(&usage()).unwrap()
+        );
+        assert!(rx.try_recv().is_err());
+    }
+}
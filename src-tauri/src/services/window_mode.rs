@@ -0,0 +1,136 @@
+use crate::error::AppError;
+use crate::models::AppSettings;
+use tauri::{AppHandle, Emitter, LogicalSize, Manager};
+
+/// Size of the full dashboard, matching the window in `tauri.conf.json`
+const NORMAL_SIZE: (f64, f64) = (500.0, 800.0);
+const NORMAL_MIN_SIZE: (f64, f64) = (420.0, 600.0);
+
+/// Size of the compact usage-only view
+const MINI_SIZE: (f64, f64) = (320.0, 200.0);
+
+/// A change to make to the main window, decided from settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowAction {
+    /// Keep the window above (or stop keeping it above) other windows
+    SetAlwaysOnTop(bool),
+    /// Shrink to the compact usage-only view
+    EnterMiniMode,
+    /// Restore the full dashboard
+    ExitMiniMode,
+}
+
+/// Window changes to make at launch for the given settings, in order
+///
+/// The window opens in its normal state, so only settings that differ from it
+/// produce an action.
+pub fn window_actions(settings: &AppSettings) -> Vec<WindowAction> {
+    let mut actions = Vec::new();
+
+    if settings.always_on_top {
+        actions.push(WindowAction::SetAlwaysOnTop(true));
+    }
+    if settings.mini_mode {
+        actions.push(WindowAction::EnterMiniMode);
+    }
+
+    actions
+}
+
+/// Window changes to make when saved settings go from `previous` to `settings`
+pub fn window_changes(previous: &AppSettings, settings: &AppSettings) -> Vec<WindowAction> {
+    let mut actions = Vec::new();
+
+    if settings.always_on_top != previous.always_on_top {
+        actions.push(WindowAction::SetAlwaysOnTop(settings.always_on_top));
+    }
+    if settings.mini_mode != previous.mini_mode {
+        actions.push(if settings.mini_mode {
+            WindowAction::EnterMiniMode
+        } else {
+            WindowAction::ExitMiniMode
+        });
+    }
+
+    actions
+}
+
+/// Apply a window action to the main window
+///
+/// Entering or leaving mini mode emits `mini-mode-changed` so the frontend can
+/// switch views.
+pub fn apply_window_action(app: &AppHandle, action: WindowAction) -> Result<(), AppError> {
+    let Some(window) = app.get_webview_window("main") else {
+        return Ok(());
+    };
+
+    match action {
+        WindowAction::SetAlwaysOnTop(enabled) => window.set_always_on_top(enabled)?,
+        WindowAction::EnterMiniMode => {
+            window.set_min_size(Some(LogicalSize::new(MINI_SIZE.0, MINI_SIZE.1)))?;
+            window.set_size(LogicalSize::new(MINI_SIZE.0, MINI_SIZE.1))?;
+            window.emit("mini-mode-changed", true)?;
+        }
+        WindowAction::ExitMiniMode => {
+            window.set_min_size(Some(LogicalSize::new(NORMAL_MIN_SIZE.0, NORMAL_MIN_SIZE.1)))?;
+            window.set_size(LogicalSize::new(NORMAL_SIZE.0, NORMAL_SIZE.1))?;
+            window.emit("mini-mode-changed", false)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_map_to_window_actions() {
+        let mut settings = AppSettings::default();
+        assert!(window_actions(&settings).is_empty());
+
+        settings.always_on_top = true;
+        assert_eq!(
+            window_actions(&settings),
+            vec![WindowAction::SetAlwaysOnTop(true)]
+        );
+
+        settings.mini_mode = true;
+        assert_eq!(
+            window_actions(&settings),
+            vec![
+                WindowAction::SetAlwaysOnTop(true),
+                WindowAction::EnterMiniMode
+            ]
+        );
+
+        settings.always_on_top = false;
+        assert_eq!(window_actions(&settings), vec![WindowAction::EnterMiniMode]);
+    }
+
+    #[test]
+    fn saved_settings_map_to_window_changes() {
+        let previous = AppSettings::default();
+        assert!(window_changes(&previous, &previous).is_empty());
+
+        let mut settings = previous.clone();
+        settings.always_on_top = true;
+        settings.mini_mode = true;
+        assert_eq!(
+            window_changes(&previous, &settings),
+            vec![
+                WindowAction::SetAlwaysOnTop(true),
+                WindowAction::EnterMiniMode
+            ]
+        );
+
+        // Leaving mini mode restores the dashboard; the unchanged setting is left alone
+        let mut next = settings.clone();
+        next.mini_mode = false;
+        assert_eq!(
+            window_changes(&settings, &next),
+            vec![WindowAction::ExitMiniMode]
+        );
+    }
+}
@@ -7,7 +7,6 @@ import { Onboarding } from "@/components/Onboarding";
 import { UpdateChecker } from "@/components/UpdateChecker";
 import { useUsageStore, useSettingsStore } from "@/lib/store";
 import { getSettings, hasAccounts, resumeScheduler } from "@/lib/tauri";
-import { useGlobalShortcut } from "@/hooks/useGlobalShortcut";
 
 function applyTheme(theme: "light" | "dark" | "system" | "pink") {
   const root = document.documentElement;
@@ -27,10 +26,7 @@ function App() {
   const [isAboutOpen, setIsAboutOpen] = useState(false);
   const [showOnboarding, setShowOnboarding] = useState<boolean | null>(null);
   const { setUsage, setError } = useUsageStore();
-  const { settings, setSettings } = useSettingsStore();
-
-  // Register global shortcut to show/hide window
-  useGlobalShortcut(settings?.globalShortcut ?? null);
+  const { setSettings } = useSettingsStore();
 
   // Initialize app - check for credentials and load settings
   useEffect(() => {
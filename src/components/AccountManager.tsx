@@ -1,17 +1,27 @@
 import { useState, useEffect, useCallback } from "react";
-import { Plus, Trash2, Eye, EyeOff, Loader2, Wifi, Check, Edit2, X } from "lucide-react";
+import { Plus, Trash2, Loader2, Wifi, Check, Edit2, X } from "lucide-react";
 import { Button } from "@/components/ui/button";
 import { Input } from "@/components/ui/input";
 import { Label } from "@/components/ui/label";
+import { CredentialFields } from "@/components/CredentialFields";
 import {
   listAccounts,
   saveAccount,
   deleteAccount,
   testAccountConnection,
+  normalizeSessionKey,
+  getCredentialSchema,
+  type Credentials,
   type TestConnectionResult,
 } from "@/lib/tauri";
-import type { Account } from "@/lib/types";
+import type { Account, CredentialField } from "@/lib/types";
 import { useAccountsStore, useUsageStore } from "@/lib/store";
+import { credentialFieldError } from "@/lib/utils";
+
+const CREDENTIAL_HINTS: Record<string, string> = {
+  org_id: "Find this in your Claude.ai URL: claude.ai/settings/organization/[org-id]",
+  session_key: "Find this in browser DevTools: Application → Cookies → sessionKey",
+};
 
 interface AccountManagerProps {
   onAccountsChanged?: () => void;
@@ -26,10 +36,9 @@ export function AccountManager({ onAccountsChanged }: AccountManagerProps) {
   const [deletingId, setDeletingId] = useState<string | null>(null);
 
   // Form state
+  const [schema, setSchema] = useState<CredentialField[]>([]);
   const [formName, setFormName] = useState("");
-  const [formOrgId, setFormOrgId] = useState("");
-  const [formSessionKey, setFormSessionKey] = useState("");
-  const [showSessionKey, setShowSessionKey] = useState(false);
+  const [formValues, setFormValues] = useState<Record<string, string>>({});
   const [isSaving, setIsSaving] = useState(false);
   const [isTesting, setIsTesting] = useState(false);
   const [testResult, setTestResult] = useState<TestConnectionResult | null>(null);
@@ -51,11 +60,18 @@ export function AccountManager({ onAccountsChanged }: AccountManagerProps) {
     loadAccounts();
   }, [loadAccounts]);
 
+  useEffect(() => {
+    getCredentialSchema("claude")
+      .then(setSchema)
+      .catch((err) => console.error("Failed to load credential schema:", err));
+  }, []);
+
+  const isFormFilled =
+    schema.length > 0 && schema.every((field) => formValues[field.key]?.trim());
+
   const resetForm = () => {
     setFormName("");
-    setFormOrgId("");
-    setFormSessionKey("");
-    setShowSessionKey(false);
+    setFormValues({});
     setTestResult(null);
     setError(null);
     setEditingAccount(null);
@@ -71,8 +87,15 @@ export function AccountManager({ onAccountsChanged }: AccountManagerProps) {
   const handleStartEdit = (account: Account) => {
     setEditingAccount(account);
     setFormName(account.name);
-    setFormOrgId(account.credentials.org_id || "");
-    setFormSessionKey(account.credentials.session_key || "");
+    const stored = account.credentials as Record<string, unknown>;
+    setFormValues(
+      Object.fromEntries(
+        schema.map((field) => {
+          const value = stored[field.key];
+          return [field.key, typeof value === "string" ? value : ""];
+        })
+      )
+    );
     setTestResult(null);
     setError(null);
     setIsAddingNew(false);
@@ -82,32 +105,54 @@ export function AccountManager({ onAccountsChanged }: AccountManagerProps) {
     resetForm();
   };
 
+  /** Normalize and check the form, returning the account it describes or an error message */
+  const buildAccount = async (): Promise<Account | string> => {
+    const values = Object.fromEntries(
+      schema.map((field) => [field.key, formValues[field.key]?.trim() ?? ""])
+    );
+    if (values.session_key) {
+      values.session_key = await normalizeSessionKey(values.session_key);
+      setFormValues({ ...formValues, session_key: values.session_key });
+    }
+
+    const invalid =
+      schema.length > 0
+        ? credentialFieldError(schema, values)
+        : "Credential fields failed to load";
+    if (invalid) {
+      return invalid;
+    }
+
+    return {
+      id: editingAccount?.id || crypto.randomUUID(),
+      name: formName.trim() || "Default",
+      provider: "claude",
+      credentials: {
+        ...values,
+        type: "claude",
+        session_keys: editingAccount?.credentials.session_keys,
+      } as Credentials,
+      createdAt: editingAccount?.createdAt || new Date().toISOString(),
+      tags: editingAccount?.tags,
+    };
+  };
+
   const handleTestConnection = async () => {
     setError(null);
     setTestResult(null);
 
-    if (!formOrgId.trim() || !formSessionKey.trim()) {
-      setTestResult({
-        success: false,
-        error_code: "MISSING_FIELDS",
-        error_message: "Please fill in both fields",
-        hint: "Both Organization ID and Session Key are required.",
-      });
-      return;
-    }
-
     setIsTesting(true);
     try {
-      const testAccount: Account = {
-        id: editingAccount?.id || crypto.randomUUID(),
-        name: formName.trim() || "Default",
-        provider: "claude",
-        credentials: {
-          org_id: formOrgId.trim(),
-          session_key: formSessionKey.trim(),
-        },
-        createdAt: editingAccount?.createdAt || new Date().toISOString(),
-      };
+      const testAccount = await buildAccount();
+      if (typeof testAccount === "string") {
+        setTestResult({
+          success: false,
+          error_code: "MISSING_FIELDS",
+          error_message: testAccount,
+          hint: `${schema.map((field) => field.label).join(" and ")} are required.`,
+        });
+        return;
+      }
 
       const result = await testAccountConnection(testAccount);
       setTestResult(result);
@@ -126,23 +171,13 @@ export function AccountManager({ onAccountsChanged }: AccountManagerProps) {
   const handleSave = async () => {
     setError(null);
 
-    if (!formOrgId.trim() || !formSessionKey.trim()) {
-      setError("Both Organization ID and Session Key are required");
-      return;
-    }
-
     setIsSaving(true);
     try {
-      const account: Account = {
-        id: editingAccount?.id || crypto.randomUUID(),
-        name: formName.trim() || "Default",
-        provider: "claude",
-        credentials: {
-          org_id: formOrgId.trim(),
-          session_key: formSessionKey.trim(),
-        },
-        createdAt: editingAccount?.createdAt || new Date().toISOString(),
-      };
+      const account = await buildAccount();
+      if (typeof account === "string") {
+        setError(account);
+        return;
+      }
 
       await saveAccount(account);
       await loadAccounts();
@@ -273,48 +308,13 @@ export function AccountManager({ onAccountsChanged }: AccountManagerProps) {
             </p>
           </div>
 
-          <div className="space-y-2">
-            <Label htmlFor="org-id">Organization ID</Label>
-            <Input
-              id="org-id"
-              placeholder="xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx"
-              value={formOrgId}
-              onChange={(e) => setFormOrgId(e.target.value)}
-            />
-            <p className="text-xs text-muted-foreground">
-              Find this in your Claude.ai URL: claude.ai/settings/organization/[org-id]
-            </p>
-          </div>
-
-          <div className="space-y-2">
-            <Label htmlFor="session-key">Session Key</Label>
-            <div className="relative">
-              <Input
-                id="session-key"
-                type={showSessionKey ? "text" : "password"}
-                placeholder="sk-ant-..."
-                value={formSessionKey}
-                onChange={(e) => setFormSessionKey(e.target.value)}
-                className="pr-10"
-              />
-              <Button
-                type="button"
-                variant="ghost"
-                size="icon"
-                className="absolute right-0 top-0 h-full px-3"
-                onClick={() => setShowSessionKey(!showSessionKey)}
-              >
-                {showSessionKey ? (
-                  <EyeOff className="h-4 w-4" />
-                ) : (
-                  <Eye className="h-4 w-4" />
-                )}
-              </Button>
-            </div>
-            <p className="text-xs text-muted-foreground">
-              Find this in browser DevTools: Application → Cookies → sessionKey
-            </p>
-          </div>
+          <CredentialFields
+            fields={schema}
+            values={formValues}
+            onChange={(key, value) => setFormValues({ ...formValues, [key]: value })}
+            idPrefix="account"
+            hints={CREDENTIAL_HINTS}
+          />
 
           {/* Test Connection Result */}
           {testResult && (
@@ -344,7 +344,7 @@ export function AccountManager({ onAccountsChanged }: AccountManagerProps) {
             <Button
               variant="outline"
               onClick={handleTestConnection}
-              disabled={isTesting || isSaving || !formOrgId.trim() || !formSessionKey.trim()}
+              disabled={isTesting || isSaving || !isFormFilled}
             >
               {isTesting ? (
                 <Loader2 className="h-4 w-4 mr-2 animate-spin" />
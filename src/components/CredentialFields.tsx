@@ -0,0 +1,83 @@
+import { useState, type ReactNode } from "react";
+import { Eye, EyeOff } from "lucide-react";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+import type { CredentialField } from "@/lib/types";
+
+interface CredentialFieldsProps {
+  /** Fields from `getCredentialSchema`, in display order */
+  fields: CredentialField[];
+  values: Record<string, string>;
+  onChange: (key: string, value: string) => void;
+  /** Prefix for input IDs, so two forms on one page don't clash */
+  idPrefix: string;
+  disabled?: boolean;
+  /** Help text shown under a field, keyed by field key */
+  hints?: Record<string, ReactNode>;
+}
+
+/** Inputs for a provider's credentials, rendered from its credential schema */
+export function CredentialFields({
+  fields,
+  values,
+  onChange,
+  idPrefix,
+  disabled,
+  hints,
+}: CredentialFieldsProps) {
+  const [shown, setShown] = useState<Record<string, boolean>>({});
+
+  return (
+    <>
+      {fields.map((field) => {
+        const id = `${idPrefix}-${field.key}`;
+        const value = values[field.key] ?? "";
+        const invalid =
+          !!field.validation &&
+          value.trim() !== "" &&
+          !new RegExp(field.validation).test(value.trim());
+        const visible = !field.isSecret || shown[field.key];
+
+        return (
+          <div key={field.key} className="space-y-2">
+            <Label htmlFor={id}>{field.label}</Label>
+            <div className="relative">
+              <Input
+                id={id}
+                type={visible ? "text" : "password"}
+                placeholder={field.placeholder}
+                value={value}
+                onChange={(e) => onChange(field.key, e.target.value)}
+                className={field.isSecret ? "pr-10" : undefined}
+                aria-invalid={invalid}
+                disabled={disabled}
+              />
+              {field.isSecret && (
+                <Button
+                  type="button"
+                  variant="ghost"
+                  size="icon"
+                  className="absolute right-0 top-0 h-full px-3"
+                  onClick={() => setShown({ ...shown, [field.key]: !shown[field.key] })}
+                  disabled={disabled}
+                >
+                  {visible ? <EyeOff className="h-4 w-4" /> : <Eye className="h-4 w-4" />}
+                </Button>
+              )}
+            </div>
+            {invalid ? (
+              <p className="text-xs text-destructive">
+                Doesn't look like a valid {field.label}
+              </p>
+            ) : (
+              hints?.[field.key] && (
+                <p className="text-xs text-muted-foreground">{hints[field.key]}</p>
+              )
+            )}
+          </div>
+        );
+      })}
+    </>
+  );
+}
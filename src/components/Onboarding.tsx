@@ -1,18 +1,15 @@
-import { useState, useCallback } from "react";
+import { useState, useCallback, useEffect } from "react";
 import {
   ChevronRight,
   ChevronLeft,
   Check,
   ExternalLink,
-  Eye,
-  EyeOff,
   Loader2,
   AlertCircle,
   CheckCircle2,
 } from "lucide-react";
 import { Button } from "@/components/ui/button";
-import { Input } from "@/components/ui/input";
-import { Label } from "@/components/ui/label";
+import { CredentialFields } from "@/components/CredentialFields";
 import {
   Card,
   CardContent,
@@ -23,10 +20,12 @@ import {
 import {
   saveAccount,
   testConnection,
+  getCredentialSchema,
   type Credentials,
   type TestConnectionResult,
 } from "@/lib/tauri";
-import type { Account } from "@/lib/types";
+import type { Account, CredentialField } from "@/lib/types";
+import { credentialFieldError } from "@/lib/utils";
 import { open } from "@tauri-apps/plugin-shell";
 
 interface OnboardingProps {
@@ -39,15 +38,29 @@ const STEPS: Step[] = ["welcome", "guide", "credentials", "complete"];
 
 export function Onboarding({ onComplete }: OnboardingProps) {
   const [currentStep, setCurrentStep] = useState<Step>("welcome");
-  const [orgId, setOrgId] = useState("");
-  const [sessionKey, setSessionKey] = useState("");
-  const [showSessionKey, setShowSessionKey] = useState(false);
+  const [schema, setSchema] = useState<CredentialField[]>([]);
+  const [values, setValues] = useState<Record<string, string>>({});
   const [isTesting, setIsTesting] = useState(false);
   const [testResult, setTestResult] = useState<TestConnectionResult | null>(null);
   const [isSaving, setIsSaving] = useState(false);
 
   const currentIndex = STEPS.indexOf(currentStep);
 
+  useEffect(() => {
+    getCredentialSchema("claude")
+      .then(setSchema)
+      .catch((err) => console.error("Failed to load credential schema:", err));
+  }, []);
+
+  const isFilled = schema.length > 0 && schema.every((field) => values[field.key]?.trim());
+
+  const formCredentials = (): Credentials => {
+    const trimmed = Object.fromEntries(
+      schema.map((field) => [field.key, values[field.key]?.trim() ?? ""])
+    );
+    return { ...trimmed, type: "claude" } as Credentials;
+  };
+
   const goNext = useCallback(() => {
     const nextIndex = currentIndex + 1;
     if (nextIndex < STEPS.length) {
@@ -63,24 +76,22 @@ export function Onboarding({ onComplete }: OnboardingProps) {
   }, [currentIndex]);
 
   const handleTestConnection = async () => {
-    if (!orgId.trim() || !sessionKey.trim()) {
+    const invalid = credentialFieldError(schema, values);
+    if (!isFilled || invalid) {
       setTestResult({
         success: false,
         error_code: "MISSING_FIELDS",
-        error_message: "Please fill in both fields",
-        hint: "Both Organization ID and Session Key are required.",
+        error_message: invalid ?? "Please fill in every field",
+        hint: `${schema.map((field) => field.label).join(" and ")} are required.`,
       });
       return;
     }
+    const credentials = formCredentials();
 
     setIsTesting(true);
     setTestResult(null);
 
     try {
-      const credentials: Credentials = {
-        org_id: orgId.trim(),
-        session_key: sessionKey.trim(),
-      };
       const result = await testConnection("claude", credentials);
       setTestResult(result);
 
@@ -109,16 +120,22 @@ export function Onboarding({ onComplete }: OnboardingProps) {
   };
 
   const handleSaveAndContinue = async () => {
-    if (!orgId.trim() || !sessionKey.trim()) {
+    const invalid = credentialFieldError(schema, values);
+    if (!isFilled || invalid) {
+      if (invalid) {
+        setTestResult({
+          success: false,
+          error_code: "INVALID_FIELD",
+          error_message: invalid,
+          hint: "Check the highlighted field and try again.",
+        });
+      }
       return;
     }
 
+    const credentials = formCredentials();
     setIsSaving(true);
     try {
-      const credentials: Credentials = {
-        org_id: orgId.trim(),
-        session_key: sessionKey.trim(),
-      };
       const account: Account = {
         id: crypto.randomUUID(),
         name: "Default",
@@ -303,51 +320,16 @@ export function Onboarding({ onComplete }: OnboardingProps) {
               </div>
 
               <div className="space-y-4">
-                <div className="space-y-2">
-                  <Label htmlFor="onboard-org-id">Organization ID</Label>
-                  <Input
-                    id="onboard-org-id"
-                    placeholder="xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx"
-                    value={orgId}
-                    onChange={(e) => {
-                      setOrgId(e.target.value);
-                      setTestResult(null);
-                    }}
-                    disabled={isTesting}
-                  />
-                </div>
-
-                <div className="space-y-2">
-                  <Label htmlFor="onboard-session-key">Session Key</Label>
-                  <div className="relative">
-                    <Input
-                      id="onboard-session-key"
-                      type={showSessionKey ? "text" : "password"}
-                      placeholder="sk-ant-sid01-..."
-                      value={sessionKey}
-                      onChange={(e) => {
-                        setSessionKey(e.target.value);
-                        setTestResult(null);
-                      }}
-                      className="pr-10"
-                      disabled={isTesting}
-                    />
-                    <Button
-                      type="button"
-                      variant="ghost"
-                      size="icon"
-                      className="absolute right-0 top-0 h-full px-3"
-                      onClick={() => setShowSessionKey(!showSessionKey)}
-                      disabled={isTesting}
-                    >
-                      {showSessionKey ? (
-                        <EyeOff className="h-4 w-4" />
-                      ) : (
-                        <Eye className="h-4 w-4" />
-                      )}
-                    </Button>
-                  </div>
-                </div>
+                <CredentialFields
+                  fields={schema}
+                  values={values}
+                  onChange={(key, value) => {
+                    setValues({ ...values, [key]: value });
+                    setTestResult(null);
+                  }}
+                  idPrefix="onboard"
+                  disabled={isTesting}
+                />
 
                 {/* Test Connection Result */}
                 {testResult && (
@@ -378,7 +360,7 @@ export function Onboarding({ onComplete }: OnboardingProps) {
 
                 <Button
                   onClick={handleTestConnection}
-                  disabled={isTesting || !orgId.trim() || !sessionKey.trim()}
+                  disabled={isTesting || !isFilled}
                   className="w-full"
                   variant={testResult?.success ? "outline" : "default"}
                 >
@@ -406,7 +388,7 @@ export function Onboarding({ onComplete }: OnboardingProps) {
                 <Button
                   variant="ghost"
                   onClick={handleSaveAndContinue}
-                  disabled={isTesting || isSaving || !orgId.trim() || !sessionKey.trim()}
+                  disabled={isTesting || isSaving || !isFilled}
                 >
                   Skip Test & Save
                 </Button>
@@ -14,10 +14,12 @@ import {
   listProviders,
   sendTestNotification,
   type AppSettings,
+  type ShortcutFailedEvent,
 } from "@/lib/tauri";
 import type { ProviderMetadata, ProviderStatus } from "@/lib/types";
 import { useSettingsStore, useUsageStore } from "@/lib/store";
 import { updateTray } from "@/lib/tray";
+import { useEventListener } from "@/hooks/useEventListener";
 
 interface SettingsProps {
   isOpen: boolean;
@@ -56,6 +58,13 @@ export function Settings({ isOpen, onClose, onCredentialsSaved }: SettingsProps)
   const [launchAtStartup, setLaunchAtStartup] = useState(false);
   const [isTogglingAutostart, setIsTogglingAutostart] = useState(false);
   const [providers, setProviders] = useState<ProviderMetadata[]>([]);
+  const [shortcutError, setShortcutError] = useState<string | null>(null);
+
+  // The backend keeps the previous binding when a new shortcut can't be registered
+  useEventListener<ShortcutFailedEvent>("global-shortcut-failed", (event) => {
+    const active = event.active ? ` ${event.active} is still active.` : "";
+    setShortcutError(`Couldn't register ${event.shortcut}: ${event.error}.${active}`);
+  });
 
   const loadProviders = useCallback(async () => {
     try {
@@ -379,7 +388,10 @@ export function Settings({ isOpen, onClose, onCredentialsSaved }: SettingsProps)
                 <select
                   id="global-shortcut"
                   value={settings?.globalShortcut ?? ""}
-                  onChange={(e) => handleSettingChange("globalShortcut", e.target.value || null)}
+                  onChange={(e) => {
+                    setShortcutError(null);
+                    handleSettingChange("globalShortcut", e.target.value || null);
+                  }}
                   className="w-full h-10 px-3 rounded-md border border-input bg-background text-sm ring-offset-background focus:outline-none focus:ring-2 focus:ring-ring focus:ring-offset-2"
                 >
                   <option value="">None</option>
@@ -392,6 +404,12 @@ export function Settings({ isOpen, onClose, onCredentialsSaved }: SettingsProps)
                 <p className="text-xs text-muted-foreground">
                   Press this shortcut anywhere to show/hide the window
                 </p>
+                {shortcutError && (
+                  <p className="flex items-center gap-1 text-xs text-yellow-500">
+                    <AlertTriangle className="h-3 w-3" />
+                    {shortcutError}
+                  </p>
+                )}
               </div>
 
             </div>
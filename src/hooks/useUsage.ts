@@ -15,6 +15,8 @@ interface UsageUpdateEvent {
   account_id: string;
   data: UsageData | null;
   error: string | null;
+  /** EMA-smoothed utilization by limit ID (only when smoothing is enabled) */
+  smoothedUtilization?: Record<string, number>;
 }
 
 /**
@@ -86,14 +88,20 @@ export function useUsage() {
       }
     }
 
-    // Update tray with worst-case usage across all accounts
+    // Update tray with the primary account, or worst-case usage across all accounts
     if (results.length > 0) {
-      const worstCase = findWorstCaseUsage(results);
+      const worstCase = findTrayUsage(results, settings?.primaryAccountId);
       if (worstCase) {
         await updateTray(worstCase, settings?.trayDisplayLimit ?? "highest");
       }
     }
-  }, [accounts, loadAccounts, refreshAccount, settings?.trayDisplayLimit]);
+  }, [
+    accounts,
+    loadAccounts,
+    refreshAccount,
+    settings?.trayDisplayLimit,
+    settings?.primaryAccountId,
+  ]);
 
   // Force refresh via scheduler (respects rate limiting)
   const forceRefresh = useCallback(async () => {
@@ -125,9 +133,9 @@ export function useUsage() {
         setLastRefresh(account_id, new Date());
         setError(account_id, null);
 
-        // Update tray with worst-case usage across all accounts
+        // Update tray with the primary account, or worst-case usage across all accounts
         const allUsage = getAllUsage();
-        const worstCase = findWorstCaseUsage([...allUsage, data]);
+        const worstCase = findTrayUsage([...allUsage, data], settings?.primaryAccountId);
         if (worstCase) {
           await updateTray(worstCase, settings?.trayDisplayLimit ?? "highest");
         }
@@ -146,6 +154,7 @@ export function useUsage() {
     setLastRefresh,
     getAllUsage,
     settings?.trayDisplayLimit,
+    settings?.primaryAccountId,
   ]);
 
   // Listen for tray refresh events (manual refresh from tray menu)
@@ -167,17 +176,18 @@ export function useUsage() {
     }
   }, [refreshAll]);
 
-  // Update tray when trayDisplayLimit setting changes
+  // Update tray when trayDisplayLimit or the primary account changes
   const trayDisplayLimit = settings?.trayDisplayLimit;
+  const primaryAccountId = settings?.primaryAccountId;
   useEffect(() => {
     const allUsage = getAllUsage();
     if (allUsage.length > 0 && trayDisplayLimit) {
-      const worstCase = findWorstCaseUsage(allUsage);
+      const worstCase = findTrayUsage(allUsage, primaryAccountId);
       if (worstCase) {
         updateTray(worstCase, trayDisplayLimit);
       }
     }
-  }, [trayDisplayLimit, getAllUsage]);
+  }, [trayDisplayLimit, primaryAccountId, getAllUsage]);
 
   return {
     accounts,
@@ -189,9 +199,22 @@ export function useUsage() {
   };
 }
 
+/**
+ * Pick the usage shown in the tray: the primary account's when one is set and
+ * has been fetched, otherwise the worst case across all accounts.
+ */
+function findTrayUsage(
+  usageList: UsageData[],
+  primaryAccountId?: string | null
+): UsageData | null {
+  const primary = primaryAccountId
+    ? usageList.find((u) => u.accountId === primaryAccountId)
+    : undefined;
+  return primary ?? findWorstCaseUsage(usageList);
+}
+
 /**
  * Find the usage data with the highest utilization across all accounts.
- * Used for tray display (worst-case scenario).
  */
 function findWorstCaseUsage(usageList: UsageData[]): UsageData | null {
   if (usageList.length === 0) return null;
@@ -2,6 +2,7 @@ import { describe, expect, it, vi, beforeEach } from 'vitest'
 import { invoke } from '@tauri-apps/api/core'
 import {
   fetchUsageForAccount,
+  cancelFetch,
   validateCredentials,
   getSettings,
   saveSettings,
@@ -24,6 +25,7 @@ import {
   saveAccount,
   deleteAccount,
   testAccountConnection,
+  normalizeSessionKey,
 } from './tauri'
 import type { UsageData, UsageHistoryEntry, HistoryMetadata, RetentionPolicy, UsageStats, Account, Credentials } from './types'
 import type { AppSettings, SchedulerStatus, TestConnectionResult } from './tauri'
@@ -60,6 +62,20 @@ describe('Tauri API Integration', () => {
       expect(mockInvoke).toHaveBeenCalledWith('fetch_usage_for_account', { accountId: 'account-123' })
       expect(result).toEqual(mockUsageData)
     })
+
+    it('passes requestId so the fetch can be canceled', async () => {
+      mockInvoke.mockResolvedValue(true)
+
+      await fetchUsageForAccount('account-123', 'req-1')
+      expect(mockInvoke).toHaveBeenCalledWith('fetch_usage_for_account', {
+        accountId: 'account-123',
+        requestId: 'req-1',
+      })
+
+      const canceled = await cancelFetch('req-1')
+      expect(mockInvoke).toHaveBeenCalledWith('cancel_fetch', { requestId: 'req-1' })
+      expect(canceled).toBe(true)
+    })
   })
 
   // ============================================================================
@@ -188,6 +204,19 @@ describe('Tauri API Integration', () => {
     })
   })
 
+  describe('normalizeSessionKey', () => {
+    it('returns the cleaned key', async () => {
+      mockInvoke.mockResolvedValue('sk-ant-sid01-abc')
+
+      const result = await normalizeSessionKey('  Bearer "sk-ant-sid01-abc" ')
+
+      expect(mockInvoke).toHaveBeenCalledWith('normalize_session_key', {
+        raw: '  Bearer "sk-ant-sid01-abc" ',
+      })
+      expect(result).toBe('sk-ant-sid01-abc')
+    })
+  })
+
   describe('validateCredentials', () => {
     it('validates credentials and returns true', async () => {
       mockInvoke.mockResolvedValue(true)
@@ -297,6 +326,7 @@ describe('Tauri API Integration', () => {
         running: true,
         intervalSecs: 60,
         lastFetch: Date.now(),
+        nextFetch: Math.floor(Date.now() / 1000) + 60,
       }
       mockInvoke.mockResolvedValue(mockStatus)
 
@@ -1,8 +1,11 @@
 import { invoke } from "@tauri-apps/api/core";
 import type {
   UsageData,
+  UsageLimit,
+  RateLimitInfo,
   ProviderId,
   ProviderMetadata,
+  CredentialField,
   UsageHistoryEntry,
   HistoryMetadata,
   HistoryQuery,
@@ -16,8 +19,83 @@ import type {
 export type { Credentials };
 
 // Usage commands
-export async function fetchUsageForAccount(accountId: string): Promise<UsageData> {
-  return invoke<UsageData>("fetch_usage_for_account", { accountId });
+export async function fetchUsageForAccount(
+  accountId: string,
+  requestId?: string
+): Promise<UsageData> {
+  // Only send requestId when cancellation is wanted
+  const args = requestId ? { accountId, requestId } : { accountId };
+  return invoke<UsageData>("fetch_usage_for_account", args);
+}
+
+export async function cancelFetch(requestId: string): Promise<boolean> {
+  return invoke<boolean>("cancel_fetch", { requestId });
+}
+
+export async function getRateLimitInfo(accountId: string): Promise<RateLimitInfo | null> {
+  return invoke<RateLimitInfo | null>("get_rate_limit_info", { accountId });
+}
+
+/** A successful fetch kept (encrypted) in the per-account response cache */
+export interface CachedResponse {
+  fetchedAt: string;
+  usage: UsageData;
+}
+
+/** Recent cached responses for an account, newest first, e.g. to show while offline */
+export async function getCachedResponses(accountId: string): Promise<CachedResponse[]> {
+  return invoke<CachedResponse[]>("get_cached_responses", { accountId });
+}
+
+/** Plan and seat details for an account */
+export interface AccountInfo {
+  /** e.g. "Pro", "Max" or "Team"; null if it couldn't be told */
+  plan: string | null;
+  organizationName: string | null;
+  rateLimitTier: string | null;
+  seatTier: string | null;
+  fetchedAt: string;
+}
+
+/** Account plan details, cached for a day unless `refresh` is set; null if the provider doesn't report them */
+export async function getAccountInfo(
+  accountId: string,
+  refresh?: boolean
+): Promise<AccountInfo | null> {
+  return invoke<AccountInfo | null>("get_account_info", { accountId, refresh });
+}
+
+/** shields.io endpoint badge for a limit; color follows the dashboard's green/amber/red bands */
+export interface BadgeData {
+  schemaVersion: number;
+  label: string;
+  /** Utilization like "62%", or "no data" before the first fetch */
+  message: string;
+  /** Hex without "#", or "lightgrey" when there is no data */
+  color: string;
+}
+
+export async function getBadgeData(accountId: string, limitId: string): Promise<BadgeData> {
+  return invoke<BadgeData>("get_badge_data", { accountId, limitId });
+}
+
+/** IDs of the account's limits that reset within the next `durationSecs`, from cached usage */
+export async function willResetWithin(accountId: string, durationSecs: number): Promise<string[]> {
+  return invoke<string[]>("will_reset_within", { accountId, durationSecs });
+}
+
+export interface UsageSummary {
+  accountId: string;
+  accountName: string;
+  timestamp: string;
+  /** Most-used limit, if the account reported any */
+  highestLimit: UsageLimit | null;
+  limits: UsageLimit[];
+}
+
+/** Cached usage summary; defaults to the primary (or most-used) account */
+export async function getUsageSummary(accountId?: string): Promise<UsageSummary | null> {
+  return invoke<UsageSummary | null>("get_usage_summary", { accountId: accountId ?? null });
 }
 
 export async function validateCredentials(
@@ -42,10 +120,31 @@ export async function testConnection(
   return invoke<TestConnectionResult>("test_connection", { provider, credentials });
 }
 
+export async function previewUsage(
+  provider: ProviderId,
+  credentials: Credentials
+): Promise<UsageData> {
+  return invoke<UsageData>("preview_usage", { provider, credentials });
+}
+
+/** Debug command: fetch with unsaved credentials, optionally recording to scratch history */
+export async function fetchUsageWith(
+  provider: ProviderId,
+  credentials: Credentials,
+  record?: boolean
+): Promise<UsageData> {
+  return invoke<UsageData>("fetch_usage_with", { provider, credentials, record });
+}
+
 export async function listProviders(): Promise<ProviderMetadata[]> {
   return invoke<ProviderMetadata[]>("list_providers");
 }
 
+/** Fields the account form should render for a provider, in display order */
+export async function getCredentialSchema(provider: ProviderId): Promise<CredentialField[]> {
+  return invoke<CredentialField[]>("get_credential_schema", { provider });
+}
+
 // ============================================================================
 // Account commands (Multi-Account Support)
 // ============================================================================
@@ -66,34 +165,426 @@ export async function saveAccount(account: Account): Promise<void> {
   return invoke("save_account", { account });
 }
 
+export type ImportFormat = "json" | "csv";
+
+export interface ImportFailure {
+  /** 1-based data row (the CSV header doesn't count) */
+  row: number;
+  name?: string;
+  error: string;
+}
+
+export interface ImportResult {
+  /** IDs of the accounts created */
+  imported: string[];
+  failures: ImportFailure[];
+}
+
+export async function importAccounts(
+  data: string,
+  format: ImportFormat
+): Promise<ImportResult> {
+  return invoke<ImportResult>("import_accounts", { data, format });
+}
+
+export async function setAccountDisplayName(
+  accountId: string,
+  displayName: string | null
+): Promise<void> {
+  return invoke("set_account_display_name", { accountId, displayName });
+}
+
 export async function deleteAccount(accountId: string): Promise<void> {
   return invoke("delete_account", { accountId });
 }
 
+/**
+ * Delete every account matching all given filters; returns how many were removed.
+ * `archivedOnly` matches accounts tagged "archived". With no filter, `all: true` is required.
+ */
+export async function deleteAccountsWhere(filter: {
+  provider?: string;
+  tag?: string;
+  archivedOnly?: boolean;
+  all?: boolean;
+}): Promise<number> {
+  return invoke<number>("delete_accounts_where", {
+    provider: filter.provider ?? null,
+    tag: filter.tag ?? null,
+    archivedOnly: filter.archivedOnly ?? false,
+    all: filter.all ?? null,
+  });
+}
+
+/** Move the secondary account's history onto the primary, then delete it; returns snapshots moved */
+export async function mergeAccounts(primaryId: string, secondaryId: string): Promise<number> {
+  return invoke("merge_accounts", { primaryId, secondaryId });
+}
+
+/**
+ * Accept a credentials store that fails its integrity check (e.g. edited by hand)
+ * by signing its current contents; a snapshot is taken first. Returns accounts signed.
+ */
+export async function resignCredentialsStore(confirm: boolean): Promise<number> {
+  return invoke("resign_credentials_store", { confirm });
+}
+
+export async function setPrimaryAccount(accountId: string | null): Promise<void> {
+  return invoke("set_primary_account", { accountId });
+}
+
+export async function getPrimaryAccount(): Promise<Account | null> {
+  return invoke<Account | null>("get_primary_account");
+}
+
+export async function setMonthlyBudget(accountId: string, budget: number | null): Promise<void> {
+  return invoke("set_monthly_budget", { accountId, budget });
+}
+
 export async function testAccountConnection(
   account: Account
 ): Promise<TestConnectionResult> {
   return invoke<TestConnectionResult>("test_account_connection", { account });
 }
 
+export type RecheckStatus = "valid" | "expired" | "mismatched" | "error";
+
+export interface RecheckResult {
+  accountId: string;
+  status: RecheckStatus;
+  storedOrgId?: string | null;
+  /** Set when the session key belongs to a different organization */
+  actualOrgId?: string | null;
+  message?: string | null;
+}
+
+/** Re-check a stored account against the live API without saving anything */
+export async function recheckAccount(accountId: string): Promise<RecheckResult> {
+  return invoke<RecheckResult>("recheck_account", { accountId });
+}
+
+export interface AccountValidationResult {
+  accountId: string;
+  accountName: string;
+  provider: string;
+  /** error_code is "TIMEOUT" if the check didn't finish in time */
+  result: TestConnectionResult;
+}
+
+/** Test every stored account's connection (a few at a time), ordered by account name */
+export async function validateAllAccounts(): Promise<AccountValidationResult[]> {
+  return invoke<AccountValidationResult[]>("validate_all_accounts");
+}
+
+export async function normalizeSessionKey(raw: string): Promise<string> {
+  return invoke<string>("normalize_session_key", { raw });
+}
+
+export async function looksLikeClaudeKey(key: string): Promise<boolean> {
+  return invoke<boolean>("looks_like_claude_key", { key });
+}
+
+export interface FieldEncryptionStatus {
+  accountId: string;
+  /** Field path, e.g. "sessionKey" or "sessionKeys[1]" */
+  field: string;
+  encrypted: boolean;
+  /** Encryption scheme prefix (e.g. "enc:v1"), absent for plaintext */
+  scheme?: string;
+  /** Version number from the scheme prefix, absent for plaintext */
+  version?: number;
+}
+
+export interface CryptoInfo {
+  /** Version new values are encrypted under (the N in "enc:vN:") */
+  currentVersion: number;
+  kdf: string;
+  cipher: string;
+  keychainActive: boolean;
+  accounts: AccountCryptoInfo[];
+}
+
+export interface AccountCryptoInfo {
+  accountId: string;
+  /** Distinct versions the account's encrypted fields use, ascending */
+  versions: number[];
+  plaintextFields: number;
+  /** Some field is plaintext or under an older version */
+  needsReencryption: boolean;
+  /** Some field is under a newer version than this build can decrypt */
+  unsupported: boolean;
+}
+
+export async function getCryptoInfo(): Promise<CryptoInfo> {
+  return invoke<CryptoInfo>("get_crypto_info");
+}
+
+export type FileAccess =
+  | "ownerOnly"
+  | "groupReadable"
+  | "worldReadable"
+  | "missing"
+  | "unsupported";
+
+export interface PermissionReport {
+  path: string;
+  /** Permission bits in octal, e.g. "644"; null if missing or unsupported */
+  mode: string | null;
+  access: FileAccess;
+  /** Whether other users can get at the file, so it should be tightened */
+  warning: boolean;
+  message: string;
+}
+
+/** Check whether the credentials file can be read by other users (Unix modes only) */
+export async function checkCredentialFilePermissions(): Promise<PermissionReport> {
+  return invoke<PermissionReport>("check_credential_file_permissions");
+}
+
+/** Restrict the credentials file to its owner (mode 600); a no-op on Windows */
+export async function tightenPermissions(): Promise<PermissionReport> {
+  return invoke<PermissionReport>("tighten_permissions");
+}
+
+/** Microseconds per encrypt+decrypt round trip, key derivation included */
+export interface CryptoBenchResult {
+  iterations: number;
+  kdf: string;
+  averageMicros: number;
+  medianMicros: number;
+  minMicros: number;
+  maxMicros: number;
+}
+
+/** Time encrypt+decrypt round trips (1 to 10,000); debug command */
+export async function benchmarkCrypto(iterations: number): Promise<CryptoBenchResult> {
+  return invoke<CryptoBenchResult>("benchmark_crypto", { iterations });
+}
+
+export async function auditEncryption(): Promise<FieldEncryptionStatus[]> {
+  return invoke<FieldEncryptionStatus[]>("audit_encryption");
+}
+
+export async function reencryptPlaintextFields(): Promise<FieldEncryptionStatus[]> {
+  return invoke<FieldEncryptionStatus[]>("reencrypt_plaintext_fields");
+}
+
+// Diagnostics
+export type DiagnosticStatus = "ok" | "warn" | "fail";
+
+export interface DiagnosticCheck {
+  name: string;
+  status: DiagnosticStatus;
+  message: string;
+}
+
+export interface DiagnosticsReport {
+  generatedAt: string;
+  appVersion: string;
+  overall: DiagnosticStatus;
+  checks: DiagnosticCheck[];
+  summary: string;
+}
+
+export async function runDiagnostics(): Promise<DiagnosticsReport> {
+  return invoke<DiagnosticsReport>("run_diagnostics");
+}
+
+/**
+ * Open debug commands for a support session. Always open in debug builds;
+ * release builds need the passphrase whose digest they were built with, and
+ * repeated wrong guesses make further attempts wait.
+ */
+export async function unlockDebugCommands(passphrase: string): Promise<void> {
+  return invoke("unlock_debug_commands", { passphrase });
+}
+
+export async function lockDebugCommands(): Promise<void> {
+  return invoke("lock_debug_commands");
+}
+
 // Settings commands
 export async function getSettings(): Promise<AppSettings> {
   return invoke<AppSettings>("get_settings");
 }
 
-export async function saveSettings(settings: AppSettings): Promise<void> {
-  return invoke("save_settings", { settings });
+/** Save settings; with `rejectInvalid`, settings with validation errors are refused */
+export async function saveSettings(settings: AppSettings, rejectInvalid?: boolean): Promise<void> {
+  return invoke("save_settings", rejectInvalid === undefined ? { settings } : { settings, rejectInvalid });
+}
+
+export interface SettingsIssue {
+  /** Path to the field, e.g. "notifications.dndStartTime" */
+  field: string;
+  severity: "error" | "warning";
+  message: string;
+}
+
+/** Check settings for malformed DND times, out-of-range thresholds/intervals and similar, without saving */
+export async function validateSettings(settings: AppSettings): Promise<SettingsIssue[]> {
+  return invoke<SettingsIssue[]>("validate_settings", { settings });
+}
+
+/** Keys of a team config that were applied, and those rejected with the reason */
+export interface TeamConfigReport {
+  applied: string[];
+  rejected: SettingsIssue[];
+}
+
+/**
+ * Apply a shared team config (JSON): thresholds, criticalThreshold, resetWarningLeadMinutes,
+ * resetWarningMinPercent, disabledLimitIds and refreshIntervalBounds ({ minSecs, maxSecs })
+ */
+export async function applyTeamConfig(json: string): Promise<TeamConfigReport> {
+  return invoke<TeamConfigReport>("apply_team_config", { json });
 }
 
 export async function sendTestNotification(): Promise<void> {
   return invoke("send_test_notification");
 }
 
+export interface SimulatedNotification {
+  at: string;
+  limitId: string;
+  kind: "threshold" | "reset";
+  threshold?: number;
+  urgency: "normal" | "critical";
+  title: string;
+  body: string;
+}
+
+export async function simulateNotifications(
+  snapshots: UsageData[],
+  thresholds: number[]
+): Promise<SimulatedNotification[]> {
+  return invoke<SimulatedNotification[]>("simulate_notifications", {
+    snapshots,
+    thresholds,
+  });
+}
+
+export interface NotificationRecord {
+  timestamp: string;
+  title: string;
+  body: string;
+  kind: "threshold" | "reset" | "reset_soon" | "budget" | "session_expiry";
+  limitId?: string;
+  /** Held back by DND (only logged when `logSuppressed` is on) */
+  suppressed: boolean;
+}
+
+/** Sent notifications, newest first; omit `limit` for the whole log. */
+export async function getNotificationLog(limit?: number): Promise<NotificationRecord[]> {
+  return invoke<NotificationRecord[]>("get_notification_log", { limit: limit ?? null });
+}
+
+/** Delete the notification log, returning how many records were removed. */
+export async function clearNotificationLog(): Promise<number> {
+  return invoke<number>("clear_notification_log");
+}
+
+/**
+ * Whether the saved DND schedule would mute notifications at `time` ("HH:MM")
+ * on `weekday` (defaults to today)
+ */
+export async function isDndActiveAt(time: string, weekday?: Weekday): Promise<boolean> {
+  return invoke<boolean>("is_dnd_active_at", { time, weekday: weekday ?? null });
+}
+
+/** Limit IDs pinned to the top of the dashboard, in display order */
+export async function getLimitOrder(): Promise<string[]> {
+  return invoke<string[]>("get_limit_order");
+}
+
+/** Alert when a limit has this many units left (needs a limit `total`). Empty removes them. */
+export async function setAbsoluteThresholds(limitId: string, unitsLeft: number[]): Promise<void> {
+  return invoke("set_absolute_thresholds", { limitId, unitsLeft });
+}
+
+/** Pin limits to the top in this order; usage data arrives pre-ordered. Empty unpins all. */
+export async function setLimitOrder(limitIds: string[]): Promise<void> {
+  return invoke("set_limit_order", { limitIds });
+}
+
+/** Keep the window above other windows; saved for the next launch */
+export async function setAlwaysOnTop(enabled: boolean): Promise<void> {
+  return invoke("set_always_on_top", { enabled });
+}
+
+/** Switch to the compact usage-only window (or back); emits `mini-mode-changed` */
+export async function setMiniMode(enabled: boolean): Promise<void> {
+  return invoke("set_mini_mode", { enabled });
+}
+
+/** Set the display time zone ("local", "UTC" or an IANA name); resolves to the saved name */
+export async function setDisplayTimezone(zone: string): Promise<string> {
+  return invoke<string>("set_display_timezone", { zone });
+}
+
+/** Format a UTC timestamp (e.g. resetsAt) in the display time zone */
+export async function formatResetTime(resetsAt: string): Promise<string> {
+  return invoke<string>("format_reset_time", { resetsAt });
+}
+
+/** Directory the stores are read from this session. */
+export async function getDataDir(): Promise<string> {
+  return invoke<string>("get_data_dir");
+}
+
+/** Move the stores to `dir` (null restores the default) from the next launch. */
+export async function setDataDir(dir: string | null): Promise<void> {
+  return invoke("set_data_dir", { dir });
+}
+
+export type NotificationPermissionState =
+  | "granted"
+  | "denied"
+  | "prompt"
+  | "prompt-with-rationale";
+
+/**
+ * Re-check the OS notification permission. When a notification fails because it
+ * was denied, the backend also emits `notification-permission-denied` (once per session).
+ */
+export async function getNotificationPermissionStatus(): Promise<NotificationPermissionState> {
+  return invoke<NotificationPermissionState>("get_notification_permission_status");
+}
+
+export type SnapshotReason = "manual" | "pre-migration" | "pre-restore" | "pre-resign";
+
+export interface SnapshotInfo {
+  id: string;
+  createdAt: string;
+  reason: SnapshotReason;
+  accountCount: number;
+  historyEntryCount: number;
+}
+
+/** Save accounts, settings, history and notification state; returns the snapshot ID */
+export async function createSnapshot(): Promise<string> {
+  return invoke<string>("create_snapshot");
+}
+
+/** Saved snapshots, newest first */
+export async function listSnapshots(): Promise<SnapshotInfo[]> {
+  return invoke<SnapshotInfo[]>("list_snapshots");
+}
+
+/**
+ * Replace the current state with a snapshot. The replaced state is kept as a
+ * `pre-restore` snapshot, and `snapshot-restored` is emitted.
+ */
+export async function restoreSnapshot(id: string): Promise<SnapshotInfo> {
+  return invoke<SnapshotInfo>("restore_snapshot", { id });
+}
+
 // Scheduler commands
 export interface SchedulerStatus {
   running: boolean;
   intervalSecs: number;
   lastFetch: number;
+  /** Unix seconds of the scheduler's next fetch, offline backoff included; 0 while stopped */
+  nextFetch: number;
 }
 
 export async function getSchedulerStatus(): Promise<SchedulerStatus> {
@@ -112,6 +603,18 @@ export async function setRefreshInterval(intervalSecs: number): Promise<void> {
   return invoke("set_refresh_interval", { intervalSecs });
 }
 
+export interface ReconcileReport {
+  intervalSecs: number;
+  running: boolean;
+  /** What was out of step and corrected; empty if nothing was */
+  fixed: string[];
+}
+
+/** Re-apply the settings' refresh interval to the scheduler if it has drifted; never starts or stops it */
+export async function reconcileScheduler(): Promise<ReconcileReport> {
+  return invoke("reconcile_scheduler");
+}
+
 export async function forceRefresh(): Promise<void> {
   return invoke("force_refresh");
 }
@@ -120,16 +623,83 @@ export async function resumeScheduler(): Promise<void> {
   return invoke("resume_scheduler");
 }
 
+/** Debug command: count fake session errors to trigger the pause flow */
+export async function simulateSessionError(
+  accountId: string,
+  count: number
+): Promise<void> {
+  return invoke("simulate_session_error", { accountId, count });
+}
+
+/** Let threshold and reset-warning notifications fire again for a limit (all limits if omitted) */
+export async function resetNotificationState(limitId?: string): Promise<number> {
+  return invoke<number>("reset_notification_state", { limitId: limitId ?? null });
+}
+
+export interface ScheduledFetch {
+  id: string;
+  accountId: string;
+  at: string;
+}
+
+export async function scheduleFetchAt(
+  accountId: string,
+  at: string
+): Promise<ScheduledFetch> {
+  return invoke<ScheduledFetch>("schedule_fetch_at", { accountId, at });
+}
+
+export async function cancelScheduledFetch(id: string): Promise<boolean> {
+  return invoke<boolean>("cancel_scheduled_fetch", { id });
+}
+
+export async function listScheduledFetches(): Promise<ScheduledFetch[]> {
+  return invoke<ScheduledFetch[]>("list_scheduled_fetches");
+}
+
 export interface SessionStatus {
   valid: boolean;
   errorCount: number;
   paused: boolean;
 }
 
+export interface WebSocketInfo {
+  enabled: boolean;
+  /** ws:// URL to connect to; null while the server is disabled */
+  url: string | null;
+  /** Append `?token=...` to the URL when true (always) */
+  requiresToken: boolean;
+  /** Token to append; null until the server has started once */
+  token: string | null;
+  subscribers: number;
+}
+
+export async function getWebSocketInfo(): Promise<WebSocketInfo> {
+  return invoke<WebSocketInfo>("get_websocket_info");
+}
+
 export async function getSessionStatus(): Promise<SessionStatus> {
   return invoke<SessionStatus>("get_session_status");
 }
 
+export type HealthStatus = "healthy" | "degraded" | "failing" | "unknown";
+
+export interface AccountHealth {
+  accountId: string;
+  /** Recent fetch attempts considered (up to the last 20) */
+  attempts: number;
+  successes: number;
+  /** 0-1, null when nothing has been fetched this session */
+  successRate: number | null;
+  lastError: string | null;
+  lastErrorAt: string | null;
+  status: HealthStatus;
+}
+
+export async function getAccountHealth(accountId: string): Promise<AccountHealth> {
+  return invoke<AccountHealth>("get_account_health", { accountId });
+}
+
 // Settings types
 export type TrayDisplayLimit = "highest" | "five_hour" | "seven_day";
 
@@ -137,9 +707,18 @@ export interface AppSettings {
   theme: "light" | "dark" | "system" | "pink";
   language: "en";
   launchAtStartup: boolean;
+  /** Start the background scheduler on launch (default true) */
+  autostartScheduler?: boolean;
+  /** Launch with the main window hidden, leaving only the tray icon */
+  startMinimizedToTray?: boolean;
   refreshMode: "adaptive" | "fixed";
   refreshInterval: 60 | 180 | 300 | 600;
   trayDisplayLimit: TrayDisplayLimit;
+  /**
+   * Shows/hides the window from anywhere; bound by the backend, which emits
+   * `global-shortcut-failed` (ShortcutFailedEvent) and keeps the old binding if it can't register.
+   * saveSettings rejects settings whose shortcut can't be bound
+   */
   globalShortcut: string | null;
   notifications: NotificationSettings;
   providers: ProviderConfig[];
@@ -147,6 +726,55 @@ export interface AppSettings {
   apiServerEnabled: boolean;
   apiServerPort: number;
   apiServerToken: string | null;
+  // WebSocket push of usage updates (for external dashboards)
+  websocketEnabled?: boolean;
+  websocketPort?: number;
+  websocketToken?: string | null;
+  /** EMA alpha (0-1] for smoothing displayed utilization; null shows raw values */
+  smoothingFactor?: number | null;
+  /** Account shown by the tray; null falls back to the most-used account */
+  primaryAccountId?: string | null;
+  /** Store history gzip-compressed (history.json.gz) */
+  compressHistory?: boolean;
+  /** Monthly budget per account ID, in percentage points consumed across all limits */
+  monthlyBudgets?: Record<string, number>;
+  /** Percent of a monthly budget at which `budget-warning` fires */
+  budgetWarningPercent?: number;
+  /** Limit IDs shown first on the dashboard, in this order */
+  limitOrder?: string[];
+  /** Back off and emit `offline`/`online` instead of repeated errors when the API is unreachable */
+  quietWhenOffline?: boolean;
+  /** "local", "UTC", or an IANA zone like "Europe/Berlin" for human-readable times */
+  displayTimezone?: string;
+  /** Most provider requests in flight at once across all accounts (default 4) */
+  maxConcurrentRequests?: number;
+  /** Recent responses cached per account for offline viewing; 0 turns the cache off (default 10, at most 100) */
+  cachedResponsesPerAccount?: number;
+  /** Keep the main window above other windows */
+  alwaysOnTop?: boolean;
+  /** Show the compact usage-only window */
+  miniMode?: boolean;
+  /** Refresh interval range set by a team config; kept as stored by saveSettings */
+  refreshIntervalBounds?: RefreshIntervalBounds | null;
+}
+
+export interface RefreshIntervalBounds {
+  minSecs: number;
+  maxSecs: number;
+}
+
+/** Payload of the `offline` and `online` events */
+export interface ConnectivityEvent {
+  offlineSince: string;
+  /** 0 on `offline` */
+  offlineSecs: number;
+}
+
+export interface ShortcutFailedEvent {
+  shortcut: string;
+  error: string;
+  /** Binding still in effect */
+  active: string | null;
 }
 
 export interface NotificationSettings {
@@ -157,8 +785,47 @@ export interface NotificationSettings {
   dndEnabled: boolean;
   dndStartTime: string | null;
   dndEndTime: string | null;
+  /** Per-weekday DND windows; days without one use dndStartTime/dndEndTime */
+  dndSchedule?: DndSchedule;
+  /** Also email critical threshold and reset alerts */
+  email?: EmailSettings | null;
+  /** Minutes before a reset that the "reset soon" alert may fire (default 60) */
+  resetWarningLeadMinutes?: number;
+  /** Utilization a limit needs for the "reset soon" alert (default 75) */
+  resetWarningMinPercent?: number;
+  /** Per-limit overrides of the two settings above, keyed by limit ID */
+  resetWarningOverrides?: Record<string, ResetWarningOverride>;
+  /** Collect a poll cycle's threshold crossings into one notification */
+  digestMode?: boolean;
+}
+
+export interface ResetWarningOverride {
+  leadMinutes?: number | null;
+  minPercent?: number | null;
+}
+
+export interface EmailSettings {
+  enabled: boolean;
+  smtpHost: string;
+  /** 465 uses implicit TLS; other ports use STARTTLS (default 587) */
+  smtpPort: number;
+  username: string | null;
+  password: string | null;
+  /** Sender address; defaults to username, then recipient */
+  from: string | null;
+  recipient: string;
 }
 
+export interface DndWindow {
+  /** HH:MM; an end before the start runs into the next morning */
+  start: string;
+  end: string;
+}
+
+export type Weekday = "mon" | "tue" | "wed" | "thu" | "fri" | "sat" | "sun";
+
+export type DndSchedule = Partial<Record<Weekday, DndWindow>>;
+
 export interface ProviderConfig {
   id: ProviderId;
   enabled: boolean;
@@ -179,6 +846,28 @@ export async function getHistoryMetadata(): Promise<HistoryMetadata> {
   return invoke<HistoryMetadata>("get_history_metadata");
 }
 
+/** Payload of `quota-changed`: a limit's total changed, e.g. after a plan upgrade */
+export interface QuotaChange {
+  accountId: string;
+  limitId: string;
+  oldTotal: number;
+  newTotal: number;
+  detectedAt: string;
+}
+
+/** Event to mark on history charts */
+export type HistoryAnnotation = { kind: "quotaChanged" } & QuotaChange;
+
+/** Debug command: entries recorded by fetchUsageWith, labeled "scratch", outside real history */
+export async function getScratchHistory(): Promise<UsageHistoryEntry[]> {
+  return invoke<UsageHistoryEntry[]>("get_scratch_history");
+}
+
+/** Annotations for one account (or all), oldest first */
+export async function getHistoryAnnotations(accountId?: string): Promise<HistoryAnnotation[]> {
+  return invoke<HistoryAnnotation[]>("get_history_annotations", { accountId });
+}
+
 export async function getRetentionPolicy(): Promise<RetentionPolicy> {
   return invoke<RetentionPolicy>("get_retention_policy");
 }
@@ -193,6 +882,13 @@ export async function cleanupHistory(): Promise<number> {
   return invoke<number>("cleanup_history");
 }
 
+export async function compactHistory(
+  olderThanDays: number,
+  bucketMinutes: number
+): Promise<number> {
+  return invoke<number>("compact_history", { olderThanDays, bucketMinutes });
+}
+
 export async function getUsageStats(
   provider: ProviderId,
   limitId: string,
@@ -207,12 +903,103 @@ export async function getUsageStats(
   });
 }
 
+export interface DayBucket {
+  /** Local calendar day, YYYY-MM-DD */
+  date: string;
+  /** Highest utilization that day; null when there is no history for the day */
+  peakUtilization: number | null;
+  sampleCount: number;
+}
+
+/** `days` is capped at the history retention window (at most 366) */
+export async function getDailyHeatmap(
+  accountId: string,
+  limitId: string,
+  days: number
+): Promise<DayBucket[]> {
+  return invoke<DayBucket[]>("get_daily_heatmap", { accountId, limitId, days });
+}
+
+export type ScheduleConfidence = "high" | "medium" | "low" | "unknown";
+
+export interface ResetSchedule {
+  accountId: string;
+  limitId: string;
+  /** Distinct reset times seen in history, oldest first */
+  observedResets: string[];
+  /** Median seconds between resets, null when confidence is unknown */
+  intervalSecs: number | null;
+  /** Next few reset times, empty when confidence is unknown */
+  predictedResets: string[];
+  /** predictedResets formatted in the display time zone */
+  predictedResetsDisplay: string[];
+  confidence: ScheduleConfidence;
+}
+
+export async function getResetSchedule(
+  limitId: string,
+  accountId: string
+): Promise<ResetSchedule> {
+  return invoke<ResetSchedule>("get_reset_schedule", { limitId, accountId });
+}
+
+export interface RecoveryForecast {
+  accountId: string;
+  limitId: string;
+  /** Too little recent history; the estimates below are null */
+  insufficientData: boolean;
+  resetsAt: string | null;
+  /** Percentage points per hour over the last week */
+  burnRatePerHour: number | null;
+  /** Seconds from the reset until the limit caps again, null if usage isn't growing */
+  headroomSecs: number | null;
+  recapsAt: string | null;
+  recapsBeforeNextReset: boolean | null;
+}
+
+export async function forecastRecovery(
+  accountId: string,
+  limitId: string
+): Promise<RecoveryForecast> {
+  return invoke<RecoveryForecast>("forecast_recovery", { accountId, limitId });
+}
+
+/** Unicode block sparkline (▁..█) of a limit's last `points` snapshots, oldest first */
+export async function getUsageSparkline(
+  accountId: string,
+  limitId: string,
+  points: number
+): Promise<string> {
+  return invoke<string>("get_usage_sparkline", { accountId, limitId, points });
+}
+
+export type BudgetLevel = "under" | "warning" | "exceeded";
+
+/** Payload of `budget-warning` and `budget-exceeded` */
+export interface BudgetStatus {
+  accountId: string;
+  monthStart: string;
+  /** Percentage points consumed across all limits this month */
+  consumed: number;
+  budget: number;
+  warningPercent: number;
+  level: BudgetLevel;
+}
+
+export async function getBudgetStatus(accountId: string): Promise<BudgetStatus | null> {
+  return invoke<BudgetStatus | null>("get_budget_status", { accountId });
+}
+
 export async function exportHistoryJson(
   query?: HistoryQuery
 ): Promise<string> {
   return invoke<string>("export_history_json", { query });
 }
 
+export async function exportAnonymizedReport(days: number): Promise<string> {
+  return invoke<string>("export_anonymized_report", { days });
+}
+
 export async function exportHistoryCsv(
   query?: HistoryQuery
 ): Promise<string> {
@@ -36,6 +36,7 @@ function normalizeUtilization(utilization: number): number {
   return utilization;
 }
 
+// Badge colors use the same bands (UsageLevel in src-tauri/src/models.rs); keep them in step
 export function getUsageLevel(utilization: number): UsageLevel {
   const normalized = normalizeUtilization(utilization);
   if (normalized < 0.5) return "low";
@@ -4,10 +4,24 @@ export type ProviderId = "claude" | "chatgpt" | "gemini";
 // Account Types (Multi-Account Support)
 // ============================================================================
 
+/**
+ * Provider credentials, tagged with `type`. Claude uses org_id/session_key(s)/oauth,
+ * Codex api_key, and Gemini api_key/project_id. Untagged credentials are read as Claude.
+ */
 export interface Credentials {
+  type?: "claude" | "codex" | "gemini";
   org_id?: string;
   session_key?: string;
+  session_keys?: string[];
   api_key?: string;
+  project_id?: string;
+  oauth?: OAuthCredentials;
+}
+
+export interface OAuthCredentials {
+  access_token: string;
+  refresh_token: string;
+  expires_at: string;
 }
 
 export interface Account {
@@ -16,6 +30,11 @@ export interface Account {
   provider: ProviderId;
   credentials: Credentials;
   createdAt: string;
+  tags?: string[];
+  /** Nickname shown in notifications instead of the account name */
+  displayName?: string;
+  /** Extra HTTP headers sent with this account's API requests (cookie and browser headers are ignored) */
+  extraHeaders?: Record<string, string>;
 }
 
 // ============================================================================
@@ -29,6 +48,17 @@ export interface UsageData {
   timestamp: string;
   limits: UsageLimit[];
   raw?: unknown;
+  /** Request-rate budget from the provider's rate-limit headers */
+  rateLimit?: RateLimitInfo;
+}
+
+export interface RateLimitInfo {
+  /** Requests allowed in the current window */
+  limit?: number;
+  /** Requests left in the current window */
+  remaining?: number;
+  /** When the request budget refills */
+  resetAt?: string;
 }
 
 // ============================================================================
@@ -42,6 +72,8 @@ export interface CredentialField {
   label: string;
   placeholder: string;
   isSecret: boolean;
+  /** Pattern a valid value matches, as a RegExp source */
+  validation?: string | null;
 }
 
 export interface ProviderMetadata {
@@ -58,8 +90,17 @@ export interface UsageLimit {
   utilization: number;
   resetsAt: string;
   category?: string;
+  kind?: LimitKind;
+  /** Size of the limit in its native units, when the provider reports it */
+  total?: number;
 }
 
+export type LimitKind =
+  | { type: "fiveHour" }
+  | { type: "weekly" }
+  | { type: "modelSpecific"; model: string }
+  | { type: "unknown" };
+
 // ============================================================================
 // History Types
 // ============================================================================
@@ -77,6 +118,8 @@ export interface UsageLimitSnapshot {
   id: string;
   utilization: number;
   resetsAt: string;
+  minUtilization?: number;
+  maxUtilization?: number;
 }
 
 export interface HistoryMetadata {
@@ -4,7 +4,9 @@ import {
   getUsageColor,
   getUsageColorHex,
   formatTimeUntil,
+  credentialFieldError,
 } from './utils'
+import type { CredentialField } from './types'
 
 describe('formatPercentage', () => {
   it('rounds to nearest integer', () => {
@@ -93,3 +95,38 @@ describe('formatTimeUntil', () => {
     expect(formatTimeUntil('2025-01-22T12:00:00Z')).toBe('7d 0h 0m')
   })
 })
+
+describe('credentialFieldError', () => {
+  const fields: CredentialField[] = [
+    {
+      key: 'org_id',
+      label: 'Organization ID',
+      placeholder: 'xxxxxxxx',
+      isSecret: false,
+      validation: '^[0-9a-f]{8}$',
+    },
+    {
+      key: 'session_key',
+      label: 'Session Key',
+      placeholder: 'sk-ant-...',
+      isSecret: true,
+      validation: null,
+    },
+  ]
+
+  it('accepts filled-in values that match their patterns', () => {
+    expect(
+      credentialFieldError(fields, { org_id: ' 0123abcd ', session_key: 'anything' })
+    ).toBeNull()
+  })
+
+  it('reports the first missing field', () => {
+    expect(credentialFieldError(fields, { org_id: '0123abcd' })).toBe('Session Key is required')
+  })
+
+  it('reports a value that fails the validation pattern', () => {
+    expect(credentialFieldError(fields, { org_id: 'not-an-id', session_key: 'x' })).toContain(
+      'Organization ID'
+    )
+  })
+})
@@ -41,7 +41,7 @@ export function formatTimeUntil(isoDate: string): string {
   return `${minutes}m`;
 }
 
-import type { UsageData, UsageLimit } from "./types";
+import type { CredentialField, UsageData, UsageLimit } from "./types";
 
 export function formatUsageForClipboard(usage: UsageData): string {
   const lines = [
@@ -66,3 +66,23 @@ export async function copyToClipboard(text: string): Promise<boolean> {
     return false;
   }
 }
+
+/**
+ * Message for the first credential field that is empty or fails its validation
+ * pattern, or null when every field is filled in correctly
+ */
+export function credentialFieldError(
+  fields: CredentialField[],
+  values: Record<string, string>
+): string | null {
+  for (const field of fields) {
+    const value = values[field.key]?.trim() ?? "";
+    if (!value) {
+      return `${field.label} is required`;
+    }
+    if (field.validation && !new RegExp(field.validation).test(value)) {
+      return `${field.label} doesn't look right; expected something like ${field.placeholder}`;
+    }
+  }
+  return null;
+}